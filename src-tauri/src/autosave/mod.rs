@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use crate::AppState;
+use crate::duckdb_core::{self, DatabaseConnection};
+use crate::error::{AppError, ErrorContext};
+
+/// File in the app config directory holding autosave settings and state
+const AUTOSAVE_FILE: &str = "autosave.json";
+
+/// Shortest interval accepted by `set_autosave_interval`
+const MIN_INTERVAL_SECS: u64 = 5;
+
+/// How often the autosave thread wakes up to check the interval and the
+/// open database
+const TICK: Duration = Duration::from_secs(1);
+
+/// Interval in effect; 0 turns autosave off
+static INTERVAL_SECS: AtomicU64 = AtomicU64::new(0);
+
+/// Serializes changes to the settings file, which the autosave thread and
+/// commands both rewrite
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutosaveSettings {
+    pub interval_secs: u64,            // Seconds between checkpoints; 0 turns autosave off
+    pub last_database: Option<String>, // Database file open in the last run
+    pub clean_shutdown: bool,          // Whether the last run closed normally
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 60,
+            last_database: None,
+            clean_shutdown: true,
+        }
+    }
+}
+
+/// Payload of the `database-autosaved` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutosaveEvent {
+    pub path: String,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .context("Failed to locate config directory")?;
+    Ok(dir.join(AUTOSAVE_FILE))
+}
+
+fn load_settings(app: &AppHandle) -> Result<AutosaveSettings, AppError> {
+    let path = settings_path(app)?;
+    if !path.exists() {
+        return Ok(AutosaveSettings::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .context("Failed to read autosave settings")?;
+    serde_json::from_str(&contents).context("Invalid autosave settings file")
+}
+
+fn save_settings(app: &AppHandle, settings: &AutosaveSettings) -> Result<(), AppError> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context("Failed to create config directory")?;
+    }
+
+    let contents = serde_json::to_string_pretty(settings)?;
+    std::fs::write(&path, contents).context("Failed to save autosave settings")
+}
+
+/// Load the settings, apply `change` and save them, with no other change in between
+fn update_settings(app: &AppHandle, change: impl FnOnce(&mut AutosaveSettings)) -> Result<AutosaveSettings, AppError> {
+    let _guard = SETTINGS_LOCK.lock()?;
+    let mut settings = load_settings(app)?;
+    change(&mut settings);
+    save_settings(app, &settings)?;
+    Ok(settings)
+}
+
+/// At startup, reopen the database file that was open when the app last
+/// stopped without closing normally. DuckDB replays its write-ahead log, so
+/// everything committed before the crash comes back. Returns the path
+/// when a database was recovered.
+pub fn recover(app: &AppHandle) -> Result<Option<String>, AppError> {
+    let mut crashed = false;
+    let settings = update_settings(app, |settings| {
+        crashed = !settings.clean_shutdown;
+        settings.clean_shutdown = false;
+    })?;
+    INTERVAL_SECS.store(settings.interval_secs, Ordering::SeqCst);
+
+    let path = match settings.last_database {
+        Some(path) if crashed && Path::new(&path).exists() => path,
+        _ => return Ok(None),
+    };
+
+    let state = app.state::<AppState>();
+    let next = DatabaseConnection::open(&path)
+        .with_context(|| format!("Failed to recover {}", path))?;
+    let mut db = state.db.lock()?;
+    duckdb_core::switch_database(&state, &mut db, next);
+    Ok(Some(path))
+}
+
+/// Record a normal shutdown so the next start doesn't try to recover
+pub fn mark_clean_shutdown(app: &AppHandle) -> Result<(), AppError> {
+    update_settings(app, |settings| settings.clean_shutdown = true).map(|_| ())
+}
+
+/// Checkpoint the open database file every autosave interval, writing the
+/// write-ahead log into the file, and remember which file is open for
+/// `recover`. In-memory databases are left alone. A database busy with a
+/// long write is checkpointed on a later tick instead of waiting for it.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_checkpoint = Instant::now();
+        let mut recorded: Option<String> = None;
+        loop {
+            std::thread::sleep(TICK);
+            let state = app.state::<AppState>();
+            let Ok(db) = state.db.try_lock() else { continue };
+
+            let path = db.database_path().map(str::to_string);
+            if path != recorded && update_settings(&app, |settings| settings.last_database = path.clone()).is_ok() {
+                recorded = path.clone();
+            }
+
+            let interval = INTERVAL_SECS.load(Ordering::SeqCst);
+            let Some(path) = path else { continue };
+            if interval == 0 || last_checkpoint.elapsed() < Duration::from_secs(interval) {
+                continue;
+            }
+            last_checkpoint = Instant::now();
+            match db.get_connection().execute_batch("CHECKPOINT") {
+                Ok(()) => {
+                    let _ = app.emit("database-autosaved", AutosaveEvent { path });
+                }
+                Err(e) => eprintln!("Autosave of {} failed: {}", path, e),
+            }
+        }
+    });
+}
+
+/// The autosave interval and the database file recovery would reopen
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_autosave_settings(app: AppHandle) -> Result<AutosaveSettings, AppError> {
+    load_settings(&app)
+}
+
+/// Checkpoint the open database file every `interval_secs` seconds, or
+/// never with 0. Only applies when working in a database file; the
+/// interval is remembered across restarts.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_autosave_interval(app: AppHandle, interval_secs: u64) -> Result<AutosaveSettings, AppError> {
+    if interval_secs != 0 && interval_secs < MIN_INTERVAL_SECS {
+        return Err(AppError::invalid_input(format!(
+            "Autosave interval must be at least {} seconds, or 0 to turn it off",
+            MIN_INTERVAL_SECS
+        )));
+    }
+
+    let settings = update_settings(&app, |settings| settings.interval_secs = interval_secs)?;
+    INTERVAL_SECS.store(interval_secs, Ordering::SeqCst);
+    Ok(settings)
+}
@@ -0,0 +1,69 @@
+// Headless importer sharing the app's import code, for pipelines such as
+// `some_command | rats-cli import --table foo --db data.duckdb`
+
+use rats_lib::duckdb_core::DatabaseConnection;
+use rats_lib::import::{self, CsvImportOptions, ImportMode};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: rats-cli import --table <name> --db <file.duckdb> \
+[--delimiter <char>] [--no-header] [--encoding <label>] [--append]
+
+Reads CSV from stdin into a table of the database file.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(message) => {
+            println!("{}", message);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<String, String> {
+    match args.first().map(String::as_str) {
+        Some("import") => {}
+        Some("--help") | Some("-h") => return Ok(USAGE.to_string()),
+        _ => return Err(USAGE.to_string()),
+    }
+
+    let mut table_name = None;
+    let mut db_path = None;
+    let mut options = CsvImportOptions::default();
+    let mut mode = ImportMode::Replace;
+
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        let mut value = || rest.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--table" => table_name = Some(value()?),
+            "--db" => db_path = Some(value()?),
+            "--delimiter" => options.delimiter = Some(value()?),
+            "--encoding" => options.encoding = Some(value()?),
+            "--no-header" => options.has_header = Some(false),
+            "--append" => mode = ImportMode::Append,
+            "--help" | "-h" => return Ok(USAGE.to_string()),
+            other => return Err(format!("Unknown option {}\n\n{}", other, USAGE)),
+        }
+    }
+
+    let table_name = table_name.ok_or_else(|| format!("--table is required\n\n{}", USAGE))?;
+    let db_path = db_path.ok_or_else(|| format!("--db is required\n\n{}", USAGE))?;
+
+    let db = DatabaseConnection::open(&db_path)
+        .map_err(|e| format!("Failed to open {}: {}", db_path, e))?;
+    let loaded = import::import_csv_from_reader(
+        db.get_connection(),
+        &mut std::io::stdin().lock(),
+        &table_name,
+        &options,
+        mode,
+    )
+    .map_err(|e| format!("Import failed: {}", e))?;
+
+    Ok(format!("Imported {} rows into {}", loaded.rows_imported, loaded.table_name))
+}
@@ -0,0 +1,555 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use tauri::AppHandle;
+use crate::duckdb_core::{is_list_type, quote_ident, table_exists, INTERNAL_TABLE_PREFIX, ROW_ID_COLUMN};
+use crate::error::{AppError, ErrorContext};
+use duckdb::Result as DuckResult;
+
+/// Internal table holding tags, favorites and groups for user tables. Like
+/// the tables themselves, it is saved with a database file or session and
+/// lost with an unsaved in-memory database.
+pub const ORGANIZATION_TABLE: &str = "_rats_table_organization";
+
+/// Internal table recording which files or URLs each table was last imported
+/// from, with the options and row counts of that import. Kept in the
+/// database like `ORGANIZATION_TABLE`.
+pub const SOURCES_TABLE: &str = "_rats_table_sources";
+
+/// Rows sampled to estimate the average size of variable-width values
+const SIZE_SAMPLE_ROWS: usize = 1000;
+
+/// In-memory size of DuckDB's string header; short strings fit inside it
+const STRING_HEADER_BYTES: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableKind {
+    Table,
+    View,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableEntry {
+    pub name: String,
+    pub kind: TableKind,
+    pub row_count: usize,
+    pub column_count: usize,
+    pub estimated_size: Option<u64>, // Approximate bytes in memory; None for views
+    pub sources: Vec<String>,        // Files or URLs of the last import
+    pub imported_at: Option<String>,
+    pub favorite: bool,
+    pub group: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// One file, sheet or URL an import loaded into a table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSource {
+    pub table_name: String,
+    pub source: String,
+    pub rows_imported: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRecord {
+    pub source: String,
+    pub rows_imported: Option<usize>, // Unknown for imports recorded by older versions
+    pub imported_at: String,
+}
+
+/// Where a table came from: the sources and options of its last import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableMetadata {
+    pub table_name: String,
+    pub sources: Vec<SourceRecord>, // Empty when the table wasn't imported
+    pub imported_at: Option<String>,
+    pub options: Option<Json>, // Import options as given to the import command
+    pub row_count_at_import: Option<usize>,
+    pub row_count: usize, // Rows now, to spot changes since the import
+}
+
+#[derive(Debug, Clone, Default)]
+struct Organization {
+    favorite: bool,
+    group: Option<String>,
+    tags: Vec<String>,
+}
+
+pub fn ensure_organization_table(conn: &duckdb::Connection) -> DuckResult<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            table_name VARCHAR PRIMARY KEY,
+            favorite BOOLEAN NOT NULL DEFAULT false,
+            group_name VARCHAR,
+            tags VARCHAR
+        )",
+        ORGANIZATION_TABLE
+    ))
+}
+
+fn load_organization(conn: &duckdb::Connection, table_name: &str) -> DuckResult<Organization> {
+    ensure_organization_table(conn)?;
+
+    let query = format!(
+        "SELECT favorite, group_name, tags FROM {} WHERE table_name = ?",
+        ORGANIZATION_TABLE
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let mut rows = stmt.query([table_name])?;
+
+    if let Some(row) = rows.next()? {
+        let tags: Option<String> = row.get(2)?;
+        Ok(Organization {
+            favorite: row.get(0)?,
+            group: row.get(1)?,
+            tags: tags
+                .map(|t| t.split(',').map(|s| s.to_string()).collect())
+                .unwrap_or_default(),
+        })
+    } else {
+        Ok(Organization::default())
+    }
+}
+
+fn save_organization(
+    conn: &duckdb::Connection,
+    table_name: &str,
+    organization: &Organization,
+) -> DuckResult<()> {
+    ensure_organization_table(conn)?;
+
+    let tags = if organization.tags.is_empty() {
+        None
+    } else {
+        Some(organization.tags.join(","))
+    };
+
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO {} (table_name, favorite, group_name, tags) VALUES (?, ?, ?, ?)",
+            ORGANIZATION_TABLE
+        ),
+        duckdb::params![table_name, organization.favorite, organization.group, tags],
+    )?;
+    Ok(())
+}
+
+/// Remove organization data for a table (used when the table is dropped)
+pub fn remove_table_organization(conn: &duckdb::Connection, table_name: &str) -> DuckResult<()> {
+    ensure_organization_table(conn)?;
+    conn.execute(
+        &format!("DELETE FROM {} WHERE table_name = ?", ORGANIZATION_TABLE),
+        [table_name],
+    )?;
+    Ok(())
+}
+
+fn ensure_sources_table(conn: &duckdb::Connection) -> DuckResult<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            table_name VARCHAR NOT NULL,
+            source VARCHAR NOT NULL,
+            imported_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+            PRIMARY KEY (table_name, source)
+        );
+        ALTER TABLE {0} ADD COLUMN IF NOT EXISTS rows_imported BIGINT;
+        ALTER TABLE {0} ADD COLUMN IF NOT EXISTS row_count BIGINT;
+        ALTER TABLE {0} ADD COLUMN IF NOT EXISTS options VARCHAR",
+        SOURCES_TABLE
+    ))
+}
+
+/// Remember where imported tables came from, replacing what was recorded
+/// for them by earlier imports. `options` are the import options, kept as
+/// JSON; each table's row count is taken as it is now.
+pub fn record_table_sources(conn: &duckdb::Connection, sources: &[TableSource], options: &Json) -> DuckResult<()> {
+    ensure_sources_table(conn)?;
+
+    let options = options.to_string();
+    let mut cleared: Vec<(&str, usize)> = Vec::new();
+    for source in sources {
+        let row_count = match cleared.iter().find(|(name, _)| *name == source.table_name) {
+            Some((_, row_count)) => *row_count,
+            None => {
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE table_name = ?", SOURCES_TABLE),
+                    [&source.table_name],
+                )?;
+                let row_count: usize = conn.query_row(
+                    &format!("SELECT COUNT(*) FROM {}", quote_ident(&source.table_name)),
+                    [],
+                    |row| row.get(0),
+                )?;
+                cleared.push((&source.table_name, row_count));
+                row_count
+            }
+        };
+        conn.execute(
+            &format!(
+                "INSERT OR REPLACE INTO {} (table_name, source, rows_imported, row_count, options)
+                 VALUES (?, ?, ?, ?, ?)",
+                SOURCES_TABLE
+            ),
+            duckdb::params![
+                source.table_name,
+                source.source,
+                source.rows_imported as u64,
+                row_count as u64,
+                options
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Forget the import sources of a table (used when the table is dropped)
+pub fn remove_table_sources(conn: &duckdb::Connection, table_name: &str) -> DuckResult<()> {
+    ensure_sources_table(conn)?;
+    conn.execute(
+        &format!("DELETE FROM {} WHERE table_name = ?", SOURCES_TABLE),
+        [table_name],
+    )?;
+    Ok(())
+}
+
+fn load_sources(conn: &duckdb::Connection, table_name: &str) -> DuckResult<(Vec<String>, Option<String>)> {
+    ensure_sources_table(conn)?;
+
+    let query = format!(
+        "SELECT source, strftime(imported_at, '%Y-%m-%d %H:%M:%S') FROM {} WHERE table_name = ? ORDER BY source",
+        SOURCES_TABLE
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let entries = stmt
+        .query_map([table_name], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let imported_at = entries.iter().map(|(_, at)| at.clone()).max();
+    Ok((entries.into_iter().map(|(source, _)| source).collect(), imported_at))
+}
+
+/// Sources, options and row counts recorded by the last import of a table
+pub fn table_metadata(conn: &duckdb::Connection, table_name: &str) -> DuckResult<TableMetadata> {
+    ensure_sources_table(conn)?;
+
+    let query = format!(
+        "SELECT source, rows_imported, strftime(imported_at, '%Y-%m-%d %H:%M:%S'), row_count, options
+         FROM {} WHERE table_name = ? ORDER BY source",
+        SOURCES_TABLE
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let mut sources = Vec::new();
+    let mut row_count_at_import = None;
+    let mut options = None;
+    let mut rows = stmt.query([table_name])?;
+    while let Some(row) = rows.next()? {
+        sources.push(SourceRecord {
+            source: row.get(0)?,
+            rows_imported: row.get(1)?,
+            imported_at: row.get(2)?,
+        });
+        row_count_at_import = row_count_at_import.or(row.get(3)?);
+        let recorded: Option<String> = row.get(4)?;
+        options = options.or(recorded.and_then(|o| serde_json::from_str(&o).ok()));
+    }
+
+    let row_count: usize = conn.query_row(
+        &format!("SELECT COUNT(*) FROM {}", quote_ident(table_name)),
+        [],
+        |row| row.get(0),
+    )?;
+
+    Ok(TableMetadata {
+        table_name: table_name.to_string(),
+        imported_at: sources.iter().map(|s| s.imported_at.clone()).max(),
+        sources,
+        options,
+        row_count_at_import,
+        row_count,
+    })
+}
+
+/// Bytes per value of fixed-width types; `None` for variable-width ones
+fn fixed_width(data_type: &str) -> Option<u64> {
+    let upper = data_type.to_uppercase();
+    match upper.as_str() {
+        "BOOLEAN" | "TINYINT" | "UTINYINT" => Some(1),
+        "SMALLINT" | "USMALLINT" => Some(2),
+        "INTEGER" | "UINTEGER" | "FLOAT" | "DATE" => Some(4),
+        "BIGINT" | "UBIGINT" | "DOUBLE" | "TIME" => Some(8),
+        "HUGEINT" | "UHUGEINT" | "UUID" | "INTERVAL" => Some(16),
+        _ if is_list_type(&upper) => None,
+        _ if upper.starts_with("TIMESTAMP") => Some(8),
+        _ if upper.starts_with("DECIMAL") => Some(16),
+        _ => None,
+    }
+}
+
+/// Rough in-memory size of a table: fixed-width columns by their type, and
+/// variable-width ones by the average length of the first rows
+fn estimate_table_size(
+    conn: &duckdb::Connection,
+    table_name: &str,
+    columns: &[(String, String)],
+    row_count: usize,
+) -> DuckResult<u64> {
+    let mut row_bytes = 0;
+    let mut variable = Vec::new();
+    for (name, data_type) in columns {
+        match fixed_width(data_type) {
+            Some(width) => row_bytes += width,
+            None => {
+                row_bytes += STRING_HEADER_BYTES;
+                variable.push(format!(
+                    "COALESCE(avg(strlen(CAST({} AS VARCHAR))), 0)",
+                    quote_ident(name)
+                ));
+            }
+        }
+    }
+
+    let average_variable: f64 = if variable.is_empty() {
+        0.0
+    } else {
+        conn.query_row(
+            &format!(
+                "SELECT CAST({} AS DOUBLE) FROM (SELECT * FROM {} LIMIT {})",
+                variable.join(" + "),
+                quote_ident(table_name),
+                SIZE_SAMPLE_ROWS
+            ),
+            [],
+            |row| row.get(0),
+        )?
+    };
+
+    Ok(((row_bytes as f64 + average_variable) * row_count as f64) as u64)
+}
+
+/// Name and type of each column of a table or view
+fn describe_columns(conn: &duckdb::Connection, table_name: &str) -> DuckResult<Vec<(String, String)>> {
+    let mut describe = conn.prepare(&format!("DESCRIBE {}", quote_ident(table_name)))?;
+    let columns = describe
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns)
+}
+
+/// Rough in-memory size of a table with `row_count` rows, estimated the
+/// same way as for the catalog
+pub fn estimated_table_size(conn: &duckdb::Connection, table_name: &str, row_count: usize) -> DuckResult<u64> {
+    let columns = describe_columns(conn, table_name)?;
+    estimate_table_size(conn, table_name, &columns, row_count)
+}
+
+fn table_kind(conn: &duckdb::Connection, table_name: &str) -> DuckResult<TableKind> {
+    let table_type: String = conn.query_row(
+        "SELECT table_type FROM information_schema.tables
+         WHERE table_catalog = current_database() AND table_name = ?",
+        [table_name],
+        |row| row.get(0),
+    )?;
+    Ok(if table_type == "VIEW" { TableKind::View } else { TableKind::Table })
+}
+
+/// The part of a catalog entry that only changes with the table's data
+#[derive(Debug, Clone)]
+struct TableShape {
+    kind: TableKind,
+    row_count: usize,
+    column_count: usize,
+    estimated_size: Option<u64>,
+}
+
+fn table_shape(conn: &duckdb::Connection, table_name: &str) -> DuckResult<TableShape> {
+    let kind = table_kind(conn, table_name)?;
+    let row_count: usize = conn.query_row(
+        &format!("SELECT COUNT(*) FROM {}", quote_ident(table_name)),
+        [],
+        |row| row.get(0),
+    )?;
+
+    let columns = describe_columns(conn, table_name)?;
+    let estimated_size = match kind {
+        TableKind::Table => Some(estimate_table_size(conn, table_name, &columns, row_count)?),
+        TableKind::View => None,
+    };
+
+    // The hidden row id column takes memory but isn't one of the user's columns
+    let column_count = columns.iter().filter(|(name, _)| name != ROW_ID_COLUMN).count();
+    Ok(TableShape { kind, row_count, column_count, estimated_size })
+}
+
+fn entry_with_shape(conn: &duckdb::Connection, table_name: &str, shape: TableShape) -> DuckResult<TableEntry> {
+    let organization = load_organization(conn, table_name)?;
+    let (sources, imported_at) = load_sources(conn, table_name)?;
+
+    Ok(TableEntry {
+        name: table_name.to_string(),
+        kind: shape.kind,
+        row_count: shape.row_count,
+        column_count: shape.column_count,
+        estimated_size: shape.estimated_size,
+        sources,
+        imported_at,
+        favorite: organization.favorite,
+        group: organization.group,
+        tags: organization.tags,
+    })
+}
+
+/// Load one catalog entry with its size, provenance and organization data
+pub fn table_entry(conn: &duckdb::Connection, table_name: &str) -> DuckResult<TableEntry> {
+    entry_with_shape(conn, table_name, table_shape(conn, table_name)?)
+}
+
+fn update_organization<F>(
+    conn: &duckdb::Connection,
+    table_name: &str,
+    update: F,
+) -> Result<TableEntry, AppError>
+where
+    F: FnOnce(&mut Organization),
+{
+    if !table_exists(conn, table_name)? {
+        return Err(AppError::not_found(format!("Table {} does not exist", table_name)));
+    }
+
+    let mut organization = load_organization(conn, table_name)?;
+    update(&mut organization);
+    save_organization(conn, table_name, &organization)
+        .context("Failed to save table organization")?;
+
+    table_entry(conn, table_name).map_err(AppError::from)
+}
+
+/// List user tables and views with row and column counts, estimated size,
+/// import sources, tags, favorite flag and group. Favorites come first,
+/// then entries are ordered by group and name.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_tables(
+    app: AppHandle,
+) -> Result<Vec<TableEntry>, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT table_name, table_type FROM information_schema.tables
+                 WHERE table_catalog = current_database() AND table_type IN ('BASE TABLE', 'VIEW')
+                 ORDER BY table_name",
+            )?;
+        let tables: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut entries = Vec::new();
+        for (table_name, table_type) in tables {
+            if table_name.starts_with(INTERNAL_TABLE_PREFIX) {
+                continue;
+            }
+            // Counting and sizing every table is the slow part, so table
+            // shapes are cached; views are recomputed since their sources
+            // can change underneath them
+            let shape = if table_type == "VIEW" {
+                table_shape(conn, &table_name)
+            } else {
+                state.query_cache.get_or_run(
+                    conn,
+                    &format!("SELECT COUNT(*) FROM {}; DESCRIBE {0}", quote_ident(&table_name)),
+                    || table_shape(conn, &table_name),
+                )
+            };
+            let entry = shape.and_then(|shape| entry_with_shape(conn, &table_name, shape));
+            entries.push(entry?);
+        }
+
+        entries.sort_by(|a, b| {
+            b.favorite
+                .cmp(&a.favorite)
+                .then_with(|| a.group.cmp(&b.group))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(entries)
+    })
+    .await
+}
+
+/// Replace the tags of a table
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_table_tags(
+    app: AppHandle,
+    table_name: String,
+    tags: Vec<String>,
+) -> Result<TableEntry, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+
+        let mut tags: Vec<String> = tags
+            .iter()
+            .map(|t| t.trim().replace(',', " "))
+            .filter(|t| !t.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+
+        update_organization(db.get_connection(), &table_name, |org| org.tags = tags)
+    })
+    .await
+}
+
+/// Mark or unmark a table as favorite
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_table_favorite(
+    app: AppHandle,
+    table_name: String,
+    favorite: bool,
+) -> Result<TableEntry, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+
+        update_organization(db.get_connection(), &table_name, |org| org.favorite = favorite)
+    })
+    .await
+}
+
+/// Move a table into a group (folder), or out of any group when `None`
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_table_group(
+    app: AppHandle,
+    table_name: String,
+    group: Option<String>,
+) -> Result<TableEntry, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+
+        let group = group
+            .map(|g| g.trim().to_string())
+            .filter(|g| !g.is_empty());
+
+        update_organization(db.get_connection(), &table_name, |org| org.group = group)
+    })
+    .await
+}
+
+/// Where a table came from: the files or URLs of its last import, when it
+/// ran, the options it used and the row count right after it
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_table_metadata(
+    app: AppHandle,
+    table_name: String,
+) -> Result<TableMetadata, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        if !table_exists(conn, &table_name)? {
+            return Err(AppError::not_found(format!("Table {} does not exist", table_name)));
+        }
+
+        table_metadata(conn, &table_name).map_err(AppError::from)
+    })
+    .await
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use duckdb::Result as DuckResult;
+use crate::error::{AppError, ErrorContext};
+
+/// Internal table holding table and column descriptions. Table-level entries
+/// use an empty column name. Like the tables it describes, it is saved with
+/// a database file or session and lost with an unsaved in-memory database.
+pub const DICTIONARY_TABLE: &str = "_rats_data_dictionary";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnMetadata {
+    pub column_name: String,
+    pub description: Option<String>,
+    pub unit: Option<String>,
+    pub tags: Vec<String>, // Semantic tags, e.g. "pii", "currency", "identifier"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataDictionary {
+    pub table_name: String,
+    pub description: Option<String>,
+    pub columns: Vec<ColumnMetadata>,
+}
+
+impl DataDictionary {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.columns.is_empty()
+    }
+
+    pub fn column(&self, column_name: &str) -> Option<&ColumnMetadata> {
+        self.columns.iter().find(|c| c.column_name == column_name)
+    }
+}
+
+pub fn ensure_dictionary_table(conn: &duckdb::Connection) -> DuckResult<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            table_name VARCHAR NOT NULL,
+            column_name VARCHAR NOT NULL,
+            description VARCHAR,
+            unit VARCHAR,
+            tags VARCHAR,
+            PRIMARY KEY (table_name, column_name)
+        )",
+        DICTIONARY_TABLE
+    ))
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = tags
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Load the data dictionary for a table (empty if nothing was recorded)
+pub fn load_dictionary(conn: &duckdb::Connection, table_name: &str) -> DuckResult<DataDictionary> {
+    ensure_dictionary_table(conn)?;
+
+    let query = format!(
+        "SELECT column_name, description, unit, tags FROM {} WHERE table_name = ? ORDER BY column_name",
+        DICTIONARY_TABLE
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let entries = stmt
+        .query_map([table_name], |row| {
+            let tags: Option<String> = row.get(3)?;
+            Ok(ColumnMetadata {
+                column_name: row.get(0)?,
+                description: row.get(1)?,
+                unit: row.get(2)?,
+                tags: tags
+                    .map(|t| t.split(',').map(|s| s.to_string()).collect())
+                    .unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut description = None;
+    let mut columns = Vec::new();
+    for entry in entries {
+        if entry.column_name.is_empty() {
+            description = entry.description;
+        } else {
+            columns.push(entry);
+        }
+    }
+
+    Ok(DataDictionary {
+        table_name: table_name.to_string(),
+        description,
+        columns,
+    })
+}
+
+/// Remove all dictionary entries for a table (used when the table is dropped)
+pub fn remove_table_metadata(conn: &duckdb::Connection, table_name: &str) -> DuckResult<()> {
+    ensure_dictionary_table(conn)?;
+    conn.execute(
+        &format!("DELETE FROM {} WHERE table_name = ?", DICTIONARY_TABLE),
+        [table_name],
+    )?;
+    Ok(())
+}
+
+/// Give `target` the same descriptions as `source`, e.g. for a copied table
+pub fn copy_table_metadata(conn: &duckdb::Connection, source: &str, target: &str) -> DuckResult<()> {
+    ensure_dictionary_table(conn)?;
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO {table} (table_name, column_name, description, unit, tags)
+             SELECT ?, column_name, description, unit, tags FROM {table} WHERE table_name = ?",
+            table = DICTIONARY_TABLE
+        ),
+        [target, source],
+    )?;
+    Ok(())
+}
+
+/// Remove the entry for one column, e.g. when the column is dropped
+pub fn remove_column_metadata(conn: &duckdb::Connection, table_name: &str, column_name: &str) -> DuckResult<()> {
+    ensure_dictionary_table(conn)?;
+    conn.execute(
+        &format!("DELETE FROM {} WHERE table_name = ? AND column_name = ?", DICTIONARY_TABLE),
+        [table_name, column_name],
+    )?;
+    Ok(())
+}
+
+/// Move a column's entry to its new name after a rename
+pub fn rename_column_metadata(conn: &duckdb::Connection, table_name: &str, from: &str, to: &str) -> DuckResult<()> {
+    ensure_dictionary_table(conn)?;
+    conn.execute(
+        &format!(
+            "UPDATE {} SET column_name = ? WHERE table_name = ? AND column_name = ?",
+            DICTIONARY_TABLE
+        ),
+        [to, table_name, from],
+    )?;
+    Ok(())
+}
+
+fn upsert_entry(
+    conn: &duckdb::Connection,
+    table_name: &str,
+    column_name: &str,
+    description: Option<&str>,
+    unit: Option<&str>,
+    tags: &[String],
+) -> DuckResult<()> {
+    ensure_dictionary_table(conn)?;
+
+    let tags = normalize_tags(tags);
+    let tags = if tags.is_empty() { None } else { Some(tags.join(",")) };
+
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO {} (table_name, column_name, description, unit, tags) VALUES (?, ?, ?, ?, ?)",
+            DICTIONARY_TABLE
+        ),
+        duckdb::params![table_name, column_name, description, unit, tags],
+    )?;
+    Ok(())
+}
+
+/// Get the data dictionary for a table
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_data_dictionary(
+    app: AppHandle,
+    table_name: String,
+) -> Result<DataDictionary, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+
+        load_dictionary(db.get_connection(), &table_name)
+            .context("Failed to load data dictionary")
+    })
+    .await
+}
+
+/// Set or clear the description of a table
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_table_description(
+    app: AppHandle,
+    table_name: String,
+    description: Option<String>,
+) -> Result<DataDictionary, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        upsert_entry(conn, &table_name, "", description.as_deref(), None, &[])
+            .context("Failed to save table description")?;
+
+        load_dictionary(conn, &table_name)
+            .context("Failed to load data dictionary")
+    })
+    .await
+}
+
+/// Create or update description, unit and semantic tags for a column
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_column_metadata(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    description: Option<String>,
+    unit: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<DataDictionary, AppError> {
+    if column_name.is_empty() {
+        return Err(AppError::invalid_input("Column name cannot be empty"));
+    }
+
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        upsert_entry(
+            conn,
+            &table_name,
+            &column_name,
+            description.as_deref(),
+            unit.as_deref(),
+            &tags.unwrap_or_default(),
+        )
+        .context("Failed to save column metadata")?;
+
+        load_dictionary(conn, &table_name)
+            .context("Failed to load data dictionary")
+    })
+    .await
+}
+
+/// Delete metadata for one column, or for the whole table when no column is given
+#[tauri::command(rename_all = "camelCase")]
+pub async fn delete_column_metadata(
+    app: AppHandle,
+    table_name: String,
+    column_name: Option<String>,
+) -> Result<DataDictionary, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        match column_name {
+            Some(column_name) => {
+                remove_column_metadata(conn, &table_name, &column_name)
+                    .context("Failed to delete column metadata")?;
+            }
+            None => {
+                remove_table_metadata(conn, &table_name)
+                    .context("Failed to delete table metadata")?;
+            }
+        }
+
+        load_dictionary(conn, &table_name)
+            .context("Failed to load data dictionary")
+    })
+    .await
+}
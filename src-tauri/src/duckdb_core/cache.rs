@@ -0,0 +1,121 @@
+use duckdb::{Connection, Result as DuckResult};
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Results kept before the least recently used one is evicted
+const CAPACITY: usize = 256;
+
+/// Small metadata results (table info, column lists, counts) keyed on the
+/// normalized text of the query that produced them, so the grid asking for
+/// them on every refresh doesn't rerun the queries. Entries are tied to a
+/// fingerprint of the catalog (every table's identity, columns and stored
+/// size), so DDL and inserts anywhere invalidate them; statements that
+/// change rows in place, like UPDATE and DELETE, have to `clear` the cache.
+#[derive(Default)]
+pub struct QueryCache {
+    entries: Mutex<Entries>,
+}
+
+type Key = (TypeId, String);
+
+#[derive(Default)]
+struct Entries {
+    fingerprint: String,
+    values: HashMap<Key, Box<dyn Any + Send + Sync>>,
+    recent: VecDeque<Key>, // Least recently used first
+}
+
+impl Entries {
+    fn touch(&mut self, key: &Key) {
+        if let Some(position) = self.recent.iter().position(|k| k == key) {
+            self.recent.remove(position);
+        }
+        self.recent.push_back(key.clone());
+    }
+
+    fn insert(&mut self, key: Key, value: Box<dyn Any + Send + Sync>) {
+        self.touch(&key);
+        self.values.insert(key, value);
+        while self.recent.len() > CAPACITY {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.values.remove(&oldest);
+            }
+        }
+    }
+
+    fn reset(&mut self, fingerprint: String) {
+        self.fingerprint = fingerprint;
+        self.values.clear();
+        self.recent.clear();
+    }
+}
+
+impl QueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached result of `sql`, or the result of `run` (which should run
+    /// `sql`), cached for next time. Only meant for small results.
+    pub fn get_or_run<T, F>(&self, conn: &Connection, sql: &str, run: F) -> DuckResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> DuckResult<T>,
+    {
+        let key = (TypeId::of::<T>(), normalize(sql));
+        let fingerprint = catalog_fingerprint(conn)?;
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.fingerprint != fingerprint {
+                entries.reset(fingerprint.clone());
+            }
+            let cached = entries.values.get(&key).and_then(|value| value.downcast_ref::<T>()).cloned();
+            if let Some(value) = cached {
+                entries.touch(&key);
+                return Ok(value);
+            }
+        }
+
+        let value = run()?;
+        if let Ok(mut entries) = self.entries.lock() {
+            // Something else may have seen a newer catalog in the meantime
+            if entries.fingerprint == fingerprint {
+                entries.insert(key, Box::new(value.clone()));
+            }
+        }
+        Ok(value)
+    }
+
+    /// Forget all results, e.g. after SQL that may have changed rows
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.reset(String::new());
+        }
+    }
+}
+
+/// Query text with runs of whitespace collapsed and any trailing semicolon
+/// dropped, so formatting differences hit the same entry
+fn normalize(sql: &str) -> String {
+    sql.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(';')
+        .trim_end()
+        .to_string()
+}
+
+/// Changes whenever a table or view is created, dropped, renamed or
+/// altered, or rows are appended to a table
+fn catalog_fingerprint(conn: &Connection) -> DuckResult<String> {
+    conn.query_row(
+        "SELECT COALESCE((SELECT string_agg(
+                    concat_ws(':', database_name, table_name, table_oid, column_count, estimated_size), ','
+                    ORDER BY table_oid)
+                FROM duckdb_tables()), '')
+            || '|' || COALESCE((SELECT string_agg(concat_ws(':', database_name, view_name, view_oid), ',' ORDER BY view_oid)
+                FROM duckdb_views() WHERE NOT internal), '')",
+        [],
+        |row| row.get(0),
+    )
+}
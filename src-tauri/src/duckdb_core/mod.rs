@@ -1,7 +1,38 @@
-use duckdb::{Connection, Result as DuckResult};
+use duckdb::arrow::array::{Array, AsArray, RecordBatch};
+use duckdb::arrow::datatypes::{Int64Type, SchemaRef};
+use duckdb::arrow::ipc::writer::StreamWriter;
+use duckdb::types::Value;
+use duckdb::{params, params_from_iter, Connection, Result as DuckResult};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
 use crate::AppState;
+use crate::error::{AppError, ErrorContext, ErrorKind};
+
+mod cache;
+mod plan;
+mod script;
+mod values;
+
+pub use cache::QueryCache;
+pub use values::{json_value, value_encoding};
+use values::arrow_json_value;
+
+/// Prefix for app-managed metadata tables that are hidden from the catalog
+pub const INTERNAL_TABLE_PREFIX: &str = "_rats_";
+
+/// Prefix of the temporary tables holding query results kept by `execute_sql`
+pub const RESULT_TABLE_PREFIX: &str = "_rats_result_";
+
+/// Hidden column giving each row of an imported table an id that survives
+/// paging, filtering, sorting and deletes. Left out of query results (which
+/// return it as `row_ids`), table info, statistics and exports.
+pub const ROW_ID_COLUMN: &str = "_rats_rowid";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColumnInfo {
@@ -15,143 +46,803 @@ pub struct TableInfo {
     pub row_count: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+    pub region: Option<String>,
+    pub endpoint: Option<String>,  // Custom endpoint for S3-compatible stores (MinIO, R2, ...)
+    pub url_style: Option<String>, // "vhost" or "path"
+    pub use_ssl: Option<bool>,
+}
+
+/// How numbers that don't fit a JavaScript double are sent to the frontend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberMode {
+    /// 64-bit integers as JSON numbers; beyond 2^53 they lose digits when parsed
+    #[default]
+    Json,
+    /// 64-bit integers as strings of digits, like decimals and 128-bit integers
+    Exact,
+}
+
+/// How a column's values are encoded in JSON rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueEncoding {
+    Native,        // The JSON value as is
+    IntegerString, // An integer as a string of digits; parse with BigInt
+    DecimalString, // A decimal as a string, e.g. "-12.340"; parse with a decimal library
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResult {
     pub columns: Vec<String>,
+    pub column_types: Vec<String>, // DuckDB type of each column, e.g. "DECIMAL(18,3)"
+    pub encodings: Vec<ValueEncoding>, // How each column's values are encoded
     pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_ids: Option<Vec<i64>>, // Stable id of each row when the rows come from a table that has them
     pub total_rows: usize,
+    pub next_rowid: Option<i64>, // `query_data` keyset pages: pass as `afterRowid` for the next page
 }
 
-pub struct DatabaseConnection {
-    conn: Connection,
+/// Outcome of one statement run by `execute_sql`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StatementResult {
+    /// SELECT-like statements; `total_rows` counts every row even when only
+    /// the first `max_rows` are returned. `result_id` is set when the full
+    /// result was kept for `export_query_result`.
+    Query { sql: String, result: QueryResult, result_id: Option<String> },
+    /// DDL and DML; `rows_affected` is 0 for DDL
+    Command { sql: String, rows_affected: usize },
 }
 
-impl DatabaseConnection {
-    pub fn new() -> DuckResult<Self> {
-        let conn = Connection::open_in_memory()?;
+/// A slice of a streamed query's rows, emitted as `query-chunk` events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryChunk {
+    pub query_id: String,
+    pub columns: Option<Vec<String>>,          // Set on the first chunk only
+    pub column_types: Option<Vec<String>>,     // Set on the first chunk only
+    pub encodings: Option<Vec<ValueEncoding>>, // Set on the first chunk only
+    pub offset: usize,                         // Index of the chunk's first row
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_ids: Option<Vec<i64>>,             // Stable row ids, as in `QueryResult`
+    pub done: bool,                            // Last chunk; no more events for this query
+}
 
-        // Configure DuckDB for performance
-        conn.execute_batch(
-            "SET memory_limit='4GB';
-             SET threads=4;"
-        )?;
+/// What `explain_query` found out about a statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub plan: String,                 // DuckDB's rendered physical plan
+    pub operators: Vec<PlanOperator>, // Analyzed operators, depth first; empty without ANALYZE
+    pub total_time_ms: Option<f64>,   // Set with ANALYZE
+}
+
+/// One operator of an analyzed plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanOperator {
+    pub name: String,               // e.g. "HASH_JOIN", "FILTER", "TABLE_SCAN"
+    pub depth: usize,               // 0 for the topmost operator
+    pub time_ms: f64,
+    pub rows: u64,                  // Rows the operator produced
+    pub details: serde_json::Value, // DuckDB's extra info, e.g. join conditions or filters
+}
+
+/// Whether a table or view with this name exists in the workspace database
+/// (attached databases aside)
+pub fn table_exists(conn: &Connection, table_name: &str) -> DuckResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM information_schema.tables
+         WHERE table_catalog = current_database() AND table_name = ?",
+        [table_name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Whether a table has the hidden row id column
+pub fn has_row_ids(conn: &Connection, table_name: &str) -> DuckResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM information_schema.columns
+         WHERE table_catalog = current_database() AND table_name = ? AND column_name = ?",
+        [table_name, ROW_ID_COLUMN],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Highest row id handed out per table, kept so the ids of deleted rows
+/// aren't handed out again
+const ROW_ID_MARKS_TABLE: &str = "_rats_row_id_marks";
+
+/// Give every row of a table a stable id: add the hidden row id column if
+/// it is missing, then number rows without an id (all of them the first
+/// time; appended or inserted ones later) after the highest id the table
+/// has ever had. Ids are never reused. Views are left alone.
+pub fn ensure_row_ids(conn: &Connection, table_name: &str) -> DuckResult<()> {
+    let is_table: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM information_schema.tables
+         WHERE table_catalog = current_database() AND table_name = ? AND table_type = 'BASE TABLE'",
+        [table_name],
+        |row| row.get(0),
+    )?;
+    if !is_table {
+        return Ok(());
+    }
+
+    let table = quote_ident(table_name);
+    let column = quote_ident(ROW_ID_COLUMN);
+    let marks = quote_ident(ROW_ID_MARKS_TABLE);
+    if !has_row_ids(conn, table_name)? {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} BIGINT", table, column))?;
+    }
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (table_name VARCHAR PRIMARY KEY, highest BIGINT NOT NULL)",
+        marks
+    ))?;
+    let highest: i64 = conn.query_row(
+        &format!(
+            "SELECT GREATEST(
+                (SELECT COALESCE(MAX({}), 0) FROM {}),
+                (SELECT COALESCE(MAX(highest), 0) FROM {} WHERE table_name = ?))",
+            column, table, marks
+        ),
+        [table_name],
+        |row| row.get(0),
+    )?;
+    // DuckDB's own rowid is unique within the table, so adding it keeps new ids apart
+    conn.execute(
+        &format!("UPDATE {} SET {} = ? + rowid + 1 WHERE {} IS NULL", table, column, column),
+        [highest],
+    )?;
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO {} SELECT ?, GREATEST(?, COALESCE(MAX({}), 0)) FROM {}",
+            marks, column, table
+        ),
+        params![table_name, highest],
+    )?;
+    Ok(())
+}
+
+/// Scratch table `rebuild_table` fills before putting it in place
+const REBUILD_TABLE: &str = "_rats_rebuild";
+
+/// Replace a table's rows with those `select` returns, in its order, e.g.
+/// to sort the table. `select` must give the table's columns by name. The
+/// table is recreated from its own `CREATE TABLE` statement and indexes,
+/// so defaults and constraints stay; run it in a transaction.
+pub fn rebuild_table(conn: &Connection, table_name: &str, select: &str) -> Result<(), AppError> {
+    let failed = |e: duckdb::Error| AppError::from(e).context(format!("Failed to rebuild {}", table_name));
+    let ddl: String = conn
+        .query_row(
+            "SELECT sql FROM duckdb_tables() WHERE database_name = current_database() AND table_name = ?",
+            [table_name],
+            |row| row.get(0),
+        )
+        .map_err(failed)?;
+    let mut stmt = conn
+        .prepare("SELECT sql FROM duckdb_indexes() WHERE database_name = current_database() AND table_name = ?")
+        .map_err(failed)?;
+    let indexes = stmt
+        .query_map([table_name], |row| row.get::<_, Option<String>>(0))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(failed)?;
+
+    // DuckDB writes the name quoted only when it has to
+    let definition = [quote_ident(table_name), table_name.to_string()]
+        .iter()
+        .find_map(|name| ddl.strip_prefix(&format!("CREATE TABLE {}(", name)))
+        .ok_or_else(|| AppError::internal(format!("Failed to rebuild {}: unexpected table definition", table_name)))?
+        .trim_end()
+        .trim_end_matches(';');
+    let scratch = quote_ident(REBUILD_TABLE);
+    let table = quote_ident(table_name);
+    conn.execute_batch(&format!(
+        "DROP TABLE IF EXISTS {scratch};
+         CREATE TABLE {scratch}({definition};
+         INSERT INTO {scratch} BY NAME {select};
+         DROP TABLE {table};
+         ALTER TABLE {scratch} RENAME TO {table};",
+        scratch = scratch,
+        definition = definition,
+        select = select,
+        table = table
+    ))
+    .map_err(failed)?;
+    for index in indexes.into_iter().flatten() {
+        conn.execute_batch(&index).map_err(failed)?;
+    }
+    Ok(())
+}
 
-        Ok(Self { conn })
+/// FROM clause source for a table or view without the hidden row id column
+pub fn visible_source(conn: &Connection, table_name: &str) -> DuckResult<String> {
+    let table = quote_ident(table_name);
+    Ok(if has_row_ids(conn, table_name)? {
+        format!("(SELECT * EXCLUDE ({}) FROM {}) AS {}", quote_ident(ROW_ID_COLUMN), table, table)
+    } else {
+        table
+    })
+}
+
+/// Take the hidden row id column out of a result, returning its values as
+/// `row_ids`
+fn split_row_ids(result: &mut QueryResult) {
+    let Some(index) = result.columns.iter().position(|c| c == ROW_ID_COLUMN) else {
+        return;
+    };
+    result.columns.remove(index);
+    if result.column_types.len() > index {
+        result.column_types.remove(index);
+        result.encodings.remove(index);
+    }
+    let row_ids = result.rows.iter_mut().map(|row| row_id(&row.remove(index))).collect();
+    result.row_ids = Some(row_ids);
+}
+
+/// A row id from its JSON value; a string in exact number mode
+fn row_id(value: &serde_json::Value) -> i64 {
+    match value {
+        serde_json::Value::String(s) => s.parse().unwrap_or_default(),
+        other => other.as_i64().unwrap_or_default(),
+    }
+}
+
+/// Name of the temporary table holding the kept query result `result_id`
+pub fn result_table(result_id: &str) -> String {
+    format!("{}{}", RESULT_TABLE_PREFIX, result_id)
+}
+
+/// Whether the query result `result_id` is still kept. Kept results are
+/// temporary tables of the writer connection, so check on that one.
+pub fn result_exists(conn: &Connection, result_id: &str) -> DuckResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM duckdb_tables() WHERE temporary AND table_name = ?",
+        [result_table(result_id)],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Run multi-step changes (create a copy, drop, rename) as one transaction:
+/// committed when `work` succeeds and rolled back when any step fails, so a
+/// table is never left half-replaced. Inside a transaction the user opened
+/// with `execute_sql`, `work` joins it and the user decides its outcome.
+pub fn in_transaction<T, E>(conn: &Connection, work: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where
+    E: From<duckdb::Error>,
+{
+    if conn.execute_batch("BEGIN TRANSACTION").is_err() {
+        return work();
     }
 
+    match work() {
+        Ok(value) => match conn.execute_batch("COMMIT") {
+            Ok(()) => Ok(value),
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e.into())
+            }
+        },
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(e)
+        }
+    }
+}
+
+/// Directory of extension files shipped with the app, used when the
+/// extension repository can't be reached
+static BUNDLED_EXTENSIONS: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set once at startup to the app's bundled `extensions` resource directory
+pub fn set_bundled_extension_dir(dir: PathBuf) {
+    let _ = BUNDLED_EXTENSIONS.set(dir);
+}
+
+/// The bundled `<name>.duckdb_extension` file, if the app ships one
+pub fn bundled_extension(name: &str) -> Option<PathBuf> {
+    BUNDLED_EXTENSIONS
+        .get()
+        .map(|dir| dir.join(format!("{}.duckdb_extension", name)))
+        .filter(|path| path.exists())
+}
+
+/// Install an extension from `repository` (DuckDB's core repository by
+/// default), falling back to the copy bundled with the app when offline
+pub fn install_extension(conn: &Connection, name: &str, repository: Option<&str>) -> DuckResult<()> {
+    let from = repository.map(|r| format!(" FROM {}", r)).unwrap_or_default();
+    conn.execute_batch(&format!("INSTALL {}{}", name, from)).or_else(|e| match bundled_extension(name) {
+        Some(path) => conn.execute_batch(&format!("INSTALL {}", sql_literal(&path.to_string_lossy()))),
+        None => Err(e),
+    })
+}
+
+/// Load a DuckDB extension, installing it first if it is not available locally
+pub fn load_extension(conn: &Connection, name: &str) -> DuckResult<()> {
+    if conn.execute_batch(&format!("LOAD {}", name)).is_ok() {
+        return Ok(());
+    }
+    install_extension(conn, name, None)?;
+    conn.execute_batch(&format!("LOAD {}", name))
+}
+
+/// Name of the in-memory DuckDB secret holding S3 credentials
+const S3_SECRET_NAME: &str = "rats_s3";
+
+/// Quote a string as a SQL literal
+pub fn sql_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Quote a table, view or column name for use in SQL, so names with spaces,
+/// mixed case or quotes work and can't inject SQL
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Whether `sql` is one statement, with no top-level semicolon starting
+/// another, e.g. to check SQL built around an expression the user typed
+pub fn is_single_statement(sql: &str) -> bool {
+    script::split_statements(sql).len() == 1
+}
+
+/// Whether `data_type` is a LIST (`INTEGER[]`) or ARRAY (`INTEGER[3]`) type,
+/// whose name starts with its element type
+pub fn is_list_type(data_type: &str) -> bool {
+    data_type.trim_end().ends_with(']')
+}
+
+/// Whether `data_type` is a plain SQL type name such as `VARCHAR`, `DATE`
+/// or `DECIMAL(18, 2)`, and so safe to splice into SQL
+pub fn is_type_name(data_type: &str) -> bool {
+    !data_type.trim().is_empty()
+        && data_type
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '_' | '(' | ')' | ',' | '[' | ']'))
+}
+
+/// Load an extension published in the DuckDB community repository
+pub fn load_community_extension(conn: &Connection, name: &str) -> DuckResult<()> {
+    if conn.execute_batch(&format!("LOAD {}", name)).is_ok() {
+        return Ok(());
+    }
+    install_extension(conn, name, Some("community"))?;
+    conn.execute_batch(&format!("LOAD {}", name))
+}
+
+/// Create a temporary secret holding S3 credentials, limited to URLs under
+/// `scope` when given. Temporary secrets live only in memory.
+pub(crate) fn create_s3_secret(
+    conn: &Connection,
+    name: &str,
+    credentials: &S3Credentials,
+    scope: Option<&str>,
+) -> DuckResult<()> {
+    let mut options = vec![
+        "TYPE S3".to_string(),
+        format!("KEY_ID {}", sql_literal(&credentials.access_key_id)),
+        format!("SECRET {}", sql_literal(&credentials.secret_access_key)),
+    ];
+    if let Some(token) = &credentials.session_token {
+        options.push(format!("SESSION_TOKEN {}", sql_literal(token)));
+    }
+    if let Some(region) = &credentials.region {
+        options.push(format!("REGION {}", sql_literal(region)));
+    }
+    if let Some(endpoint) = &credentials.endpoint {
+        options.push(format!("ENDPOINT {}", sql_literal(endpoint)));
+    }
+    if let Some(url_style) = &credentials.url_style {
+        options.push(format!("URL_STYLE {}", sql_literal(url_style)));
+    }
+    if let Some(use_ssl) = credentials.use_ssl {
+        options.push(format!("USE_SSL {}", use_ssl));
+    }
+    if let Some(scope) = scope {
+        options.push(format!("SCOPE {}", sql_literal(scope)));
+    }
+
+    conn.execute_batch(&format!(
+        "CREATE OR REPLACE TEMPORARY SECRET {} ({})",
+        name,
+        options.join(", ")
+    ))
+}
+
+/// Interrupt handle for the open connection, kept outside the database mutex
+/// so running statements can be stopped. Replaced whenever another database
+/// is opened.
+pub struct ConnectionInterrupt(Mutex<Arc<duckdb::InterruptHandle>>);
+
+impl ConnectionInterrupt {
+    pub fn new(conn: &Connection) -> Self {
+        Self(Mutex::new(conn.interrupt_handle()))
+    }
+
+    pub fn interrupt(&self) {
+        if let Ok(handle) = self.0.lock() {
+            handle.interrupt();
+        }
+    }
+
+    fn replace(&self, conn: &Connection) {
+        if let Ok(mut handle) = self.0.lock() {
+            *handle = conn.interrupt_handle();
+        }
+    }
+}
+
+/// Idle reader connections kept around between read-only commands
+const MAX_IDLE_READERS: usize = 4;
+
+/// Extra connections to the open database for read-only commands (paging,
+/// statistics, previews), so they run alongside each other and alongside
+/// whatever holds the main connection. Writes keep going through `db` and
+/// stay serialized. Readers are cloned from the main connection and
+/// dropped when another database is opened.
+pub struct ReaderPool {
+    idle: Mutex<Vec<Connection>>,
+    seed: Mutex<Option<Connection>>, // Clone of the main connection new readers are cloned from
+    generation: AtomicUsize,         // Bumped when the open database changes
+}
+
+/// A reader connection, returned to the pool when dropped
+pub struct Reader<'a> {
+    pool: &'a ReaderPool,
+    conn: Option<Connection>,
+    generation: usize,
+}
+
+impl ReaderPool {
+    pub fn new(conn: &Connection) -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            seed: Mutex::new(conn.try_clone().ok()),
+            generation: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take an idle reader, or clone a new one. Clones come from a handle of
+    /// their own, so getting a reader never waits on the main connection.
+    pub fn get(&self) -> Result<Reader<'_>, AppError> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let idle = self.idle.lock()?.pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => self
+                .seed
+                .lock()?
+                .as_ref()
+                .ok_or_else(|| AppError::invalid_input("No database is open to read from"))?
+                .try_clone()
+                .context("Failed to open reader connection")?,
+        };
+        Ok(Reader { pool: self, conn: Some(conn), generation })
+    }
+
+    /// Drop idle readers of the previous database and clone new ones from `conn`
+    fn reset(&self, conn: &Connection) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.clear();
+        }
+        if let Ok(mut seed) = self.seed.lock() {
+            *seed = conn.try_clone().ok();
+        }
+    }
+}
+
+impl Reader<'_> {
     pub fn get_connection(&self) -> &Connection {
-        &self.conn
+        self.conn.as_ref().expect("reader connection is present until dropped")
+    }
+}
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else { return };
+        if self.generation != self.pool.generation.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Ok(mut idle) = self.pool.idle.lock() {
+            if idle.len() < MAX_IDLE_READERS {
+                idle.push(conn);
+            }
+        }
     }
+}
+
+/// Start of the error returned when a query runs past its timeout, so the
+/// frontend can tell timeouts from other failures
+pub const QUERY_TIMED_OUT: &str = "Query timed out";
+
+/// Timeout in seconds as given by the frontend; none or 0 means no limit
+pub fn query_timeout(timeout_secs: Option<u64>) -> Option<Duration> {
+    timeout_secs.filter(|secs| *secs > 0).map(Duration::from_secs)
+}
+
+/// Tracks running queries by the ID the frontend gave them, so
+/// `cancel_query` only stops the query it was asked to
+#[derive(Default)]
+pub struct QueryControl {
+    running: Mutex<HashMap<String, RunningQuery>>,
+}
+
+struct RunningQuery {
+    cancelled: bool,
+    interrupt: Arc<duckdb::InterruptHandle>, // Of the connection the query runs on
+}
+
+/// Marks a query as running until dropped
+pub struct QueryGuard<'a> {
+    control: &'a QueryControl,
+    id: Option<String>,
+    deadline: Option<Arc<Deadline>>,
+}
 
-    pub fn execute_query(&self, query: &str) -> DuckResult<QueryResult> {
-        // First, get column information using DESCRIBE
-        let describe_query = format!("DESCRIBE {}", query);
-        let mut describe_stmt = self.conn.prepare(&describe_query)?;
-        let mut describe_rows = describe_stmt.query([])?;
+/// Shared with the watchdog thread that interrupts a query once it runs
+/// past its timeout
+struct Deadline {
+    timeout: Duration,
+    finished: Mutex<bool>,
+    done: Condvar,
+    expired: AtomicBool,
+}
 
-        let mut columns = Vec::new();
-        while let Some(row) = describe_rows.next()? {
-            let col_name: String = row.get(0)?;
-            columns.push(col_name);
+impl QueryControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a query running on `conn`; queries without an ID can't be
+    /// cancelled
+    pub fn begin(&self, id: Option<String>, conn: &Connection) -> QueryGuard<'_> {
+        if let (Some(id), Ok(mut running)) = (&id, self.running.lock()) {
+            running.insert(
+                id.clone(),
+                RunningQuery { cancelled: false, interrupt: conn.interrupt_handle() },
+            );
         }
+        QueryGuard { control: self, id, deadline: None }
+    }
 
-        let column_count = columns.len();
+    /// Interrupt a query; returns false when no query with this ID is running
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.running.lock() {
+            Ok(mut running) => match running.get_mut(id) {
+                Some(query) => {
+                    query.cancelled = true;
+                    query.interrupt.interrupt();
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
 
-        // Extract table name from query to get actual total row count
-        // Assumes query is like "SELECT * FROM table_name LIMIT x OFFSET y"
-        let total_rows: usize = if let Some(table_name) = Self::extract_table_name(query) {
-            let count_query = format!("SELECT COUNT(*) FROM {}", table_name);
-            match self.conn.query_row(&count_query, [], |row| row.get(0)) {
-                Ok(count) => count,
-                Err(_) => 0, // Fallback if count fails
+impl QueryGuard<'_> {
+    /// Interrupt the query on `conn` if it is still running after `timeout`
+    pub fn with_timeout(mut self, conn: &Connection, timeout: Option<Duration>) -> Self {
+        let Some(timeout) = timeout else { return self };
+        let deadline = Arc::new(Deadline {
+            timeout,
+            finished: Mutex::new(false),
+            done: Condvar::new(),
+            expired: AtomicBool::new(false),
+        });
+        let watched = deadline.clone();
+        let interrupt = conn.interrupt_handle();
+        std::thread::spawn(move || {
+            let Ok(finished) = watched.finished.lock() else { return };
+            if let Ok((finished, _)) = watched.done.wait_timeout_while(finished, timeout, |finished| !*finished) {
+                if !*finished {
+                    watched.expired.store(true, Ordering::SeqCst);
+                    interrupt.interrupt();
+                }
             }
+        });
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        self.deadline.as_ref().is_some_and(|deadline| deadline.expired.load(Ordering::SeqCst))
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        match (&self.id, self.control.running.lock()) {
+            (Some(id), Ok(running)) => running.get(id).map(|query| query.cancelled).unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// The error reported for a failed query: timed out, cancelled, or
+    /// `context: error`
+    pub fn error(&self, context: &str, error: impl Into<AppError>) -> AppError {
+        if let Some(deadline) = self.deadline.as_ref().filter(|_| self.is_timed_out()) {
+            AppError::new(
+                ErrorKind::Timeout,
+                format!("{} after {} seconds", QUERY_TIMED_OUT, deadline.timeout.as_secs()),
+            )
+        } else if self.is_cancelled() {
+            AppError::cancelled("Query cancelled")
         } else {
-            0 // Fallback if we can't parse table name
-        };
+            error.into().context(context)
+        }
+    }
+}
 
-        // Now execute the actual data query
-        let mut stmt = self.conn.prepare(query)?;
-        let mut rows_result = stmt.query([])?;
-        let mut collected_rows = Vec::new();
-
-        while let Some(row) = rows_result.next()? {
-            let mut row_data = Vec::new();
-            for i in 0..column_count {
-                let value: serde_json::Value = match row.get_ref(i)? {
-                    duckdb::types::ValueRef::Null => serde_json::Value::Null,
-                    duckdb::types::ValueRef::Boolean(b) => serde_json::Value::Bool(b),
-                    duckdb::types::ValueRef::TinyInt(i) => serde_json::Value::Number(i.into()),
-                    duckdb::types::ValueRef::SmallInt(i) => serde_json::Value::Number(i.into()),
-                    duckdb::types::ValueRef::Int(i) => serde_json::Value::Number(i.into()),
-                    duckdb::types::ValueRef::BigInt(i) => serde_json::Value::Number(i.into()),
-                    duckdb::types::ValueRef::Float(f) => {
-                        serde_json::Number::from_f64(f as f64)
-                            .map(serde_json::Value::Number)
-                            .unwrap_or(serde_json::Value::Null)
-                    },
-                    duckdb::types::ValueRef::Double(f) => {
-                        serde_json::Number::from_f64(f)
-                            .map(serde_json::Value::Number)
-                            .unwrap_or(serde_json::Value::Null)
-                    },
-                    duckdb::types::ValueRef::Text(s) => {
-                        serde_json::Value::String(String::from_utf8_lossy(s).to_string())
-                    },
-                    _ => serde_json::Value::String(format!("{:?}", row.get_ref(i)?)),
-                };
-                row_data.push(value);
+impl Drop for QueryGuard<'_> {
+    fn drop(&mut self) {
+        if let (Some(id), Ok(mut running)) = (&self.id, self.control.running.lock()) {
+            running.remove(id);
+        }
+        if let Some(deadline) = &self.deadline {
+            if let Ok(mut finished) = deadline.finished.lock() {
+                *finished = true;
             }
-            collected_rows.push(row_data);
+            deadline.done.notify_one();
         }
+    }
+}
 
-        Ok(QueryResult {
-            columns,
-            rows: collected_rows,
-            total_rows,
-        })
+/// Table row counts from earlier COUNT(*)s, so paging through a big table
+/// doesn't count it again for every page. A count is reused while the
+/// table's catalog entry and stored size are unchanged; views are always
+/// counted.
+#[derive(Default)]
+pub struct RowCounts {
+    counts: Mutex<HashMap<String, CachedCount>>,
+}
+
+struct CachedCount {
+    oid: i64,
+    estimated_size: i64,
+    rows: usize,
+}
+
+impl RowCounts {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn extract_table_name(query: &str) -> Option<String> {
-        // Simple parser for "SELECT ... FROM table_name ..."
-        let query_upper = query.to_uppercase();
-        if let Some(from_pos) = query_upper.find(" FROM ") {
-            let after_from = &query[(from_pos + 6)..]; // " FROM " is 6 chars
-            // Take everything up to the next space, LIMIT, WHERE, ORDER, etc.
-            let table_part: String = after_from
-                .split_whitespace()
-                .next()?
-                .to_string();
-            Some(table_part)
-        } else {
-            None
+    pub fn count(&self, conn: &Connection, table_name: &str) -> DuckResult<usize> {
+        let mut stmt = conn.prepare(
+            "SELECT table_oid, estimated_size FROM duckdb_tables()
+             WHERE database_name = current_database() AND schema_name = 'main' AND table_name = ?",
+        )?;
+        let version = stmt
+            .query_map([table_name], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?
+            .next()
+            .transpose()?;
+
+        if let (Some((oid, estimated_size)), Ok(counts)) = (version, self.counts.lock()) {
+            if let Some(cached) = counts.get(table_name) {
+                if cached.oid == oid && cached.estimated_size == estimated_size {
+                    return Ok(cached.rows);
+                }
+            }
         }
+
+        let rows: usize = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {}", quote_ident(table_name)),
+            [],
+            |row| row.get(0),
+        )?;
+        if let (Some((oid, estimated_size)), Ok(mut counts)) = (version, self.counts.lock()) {
+            counts.insert(table_name.to_string(), CachedCount { oid, estimated_size, rows });
+        }
+        Ok(rows)
     }
 
-    pub fn get_table_info_internal(&self, table_name: &str) -> DuckResult<TableInfo> {
-        // Get column information
-        let query = format!("PRAGMA table_info('{}')", table_name);
-        let mut stmt = self.conn.prepare(&query)?;
+    /// Forget all counts, e.g. after SQL that may have deleted rows
+    pub fn clear(&self) {
+        if let Ok(mut counts) = self.counts.lock() {
+            counts.clear();
+        }
+    }
+}
 
-        let mut columns = Vec::new();
-        let rows = stmt.query_map([], |row| {
-            Ok(ColumnInfo {
-                name: row.get(1)?,
-                data_type: row.get(2)?,
-            })
-        })?;
+/// File in the app config directory holding the DuckDB resource settings
+const DATABASE_SETTINGS_FILE: &str = "database_settings.json";
 
-        for row in rows {
-            columns.push(row?);
+/// Resources DuckDB may use. Applied to every database the app opens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseSettings {
+    pub memory_limit: String, // DuckDB size like "4GB" or "512MB"
+    pub threads: usize,
+    pub temp_directory: Option<String>, // Where spilled data goes; DuckDB's default when unset
+    pub spill_to_disk: bool, // Off fails queries that outgrow memory_limit instead of spilling
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            memory_limit: "4GB".to_string(),
+            threads: 4,
+            temp_directory: None,
+            spill_to_disk: true,
         }
+    }
+}
+
+pub struct DatabaseConnection {
+    conn: Connection,
+    path: Option<String>,
+    settings: DatabaseSettings,
+}
+
+impl DatabaseConnection {
+    pub fn new() -> DuckResult<Self> {
+        let conn = Connection::open_in_memory()?;
+        let settings = DatabaseSettings::default();
+        apply_settings(&conn, &settings)?;
+
+        Ok(Self { conn, path: None, settings })
+    }
 
-        // Get row count
-        let count_query = format!("SELECT COUNT(*) FROM {}", table_name);
-        let row_count: usize = self.conn.query_row(&count_query, [], |row| row.get(0))?;
+    /// Open (or create) a database file, e.g. for the headless CLI
+    pub fn open(path: &str) -> DuckResult<Self> {
+        let conn = Connection::open(path)?;
+        let settings = DatabaseSettings::default();
+        apply_settings(&conn, &settings)?;
 
-        Ok(TableInfo { columns, row_count })
+        Ok(Self { conn, path: Some(path.to_string()), settings })
+    }
+
+    pub fn get_connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn settings(&self) -> &DatabaseSettings {
+        &self.settings
+    }
+
+    /// Change the resource settings. Nothing changes when DuckDB rejects
+    /// one of them.
+    pub fn configure(&mut self, settings: DatabaseSettings) -> DuckResult<()> {
+        if let Err(e) = apply_settings(&self.conn, &settings) {
+            let _ = apply_settings(&self.conn, &self.settings);
+            return Err(e);
+        }
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// "memory" for the default in-memory database, "file" when backed by a path
+    pub fn database_mode(&self) -> &'static str {
+        if self.path.is_some() { "file" } else { "memory" }
+    }
+
+    pub fn database_path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Register S3 credentials as a temporary DuckDB secret. Temporary
+    /// secrets live only in memory and are never written to disk.
+    pub fn set_s3_credentials(&self, credentials: &S3Credentials) -> DuckResult<()> {
+        load_extension(&self.conn, "httpfs")?;
+        create_s3_secret(&self.conn, S3_SECRET_NAME, credentials, None)
+    }
+
+    pub fn clear_s3_credentials(&self) -> DuckResult<()> {
+        self.conn.execute_batch(&format!("DROP TEMPORARY SECRET IF EXISTS {}", S3_SECRET_NAME))
     }
 
     pub fn cleanup(&self) -> DuckResult<()> {
+        // A database file keeps its tables for the next session
+        if self.path.is_some() {
+            return self.conn.execute_batch("CHECKPOINT");
+        }
+
         // Get all tables
         let mut stmt = self.conn.prepare("SHOW TABLES")?;
         let table_names: Vec<String> = stmt.query_map([], |row| row.get(0))?
@@ -159,7 +850,7 @@ impl DatabaseConnection {
 
         // Drop all tables
         for table_name in table_names {
-            let _ = self.conn.execute(&format!("DROP TABLE IF EXISTS {}", table_name), []);
+            let _ = self.conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&table_name)), []);
         }
 
         // Flush any pending operations
@@ -169,46 +860,1172 @@ impl DatabaseConnection {
     }
 }
 
+fn apply_settings(conn: &Connection, settings: &DatabaseSettings) -> DuckResult<()> {
+    let temp_directory = match (&settings.temp_directory, settings.spill_to_disk) {
+        // An empty temp directory turns spilling off
+        (_, false) => "SET temp_directory = ''".to_string(),
+        (Some(dir), true) => format!("SET temp_directory = {}", sql_literal(dir)),
+        (None, true) => "RESET temp_directory".to_string(),
+    };
+    conn.execute_batch(&format!(
+        "SET memory_limit = {}; SET threads = {}; {};",
+        sql_literal(&settings.memory_limit),
+        settings.threads,
+        temp_directory
+    ))
+}
+
+/// Columns and row count of a table
+pub fn table_info(conn: &Connection, table_name: &str) -> DuckResult<TableInfo> {
+    // Get column information
+    let query = format!("PRAGMA table_info({})", sql_literal(table_name));
+    let mut stmt = conn.prepare(&query)?;
+
+    let mut columns = Vec::new();
+    let rows = stmt.query_map([], |row| {
+        Ok(ColumnInfo {
+            name: row.get(1)?,
+            data_type: row.get(2)?,
+        })
+    })?;
+
+    for row in rows {
+        let column = row?;
+        if column.name != ROW_ID_COLUMN {
+            columns.push(column);
+        }
+    }
+
+    // Get row count
+    let count_query = format!("SELECT COUNT(*) FROM {}", quote_ident(table_name));
+    let row_count: usize = conn.query_row(&count_query, [], |row| row.get(0))?;
+
+    Ok(TableInfo { columns, row_count })
+}
+
+/// Run a SELECT with `params` bound to its `?` placeholders. `total_rows`
+/// counts the rows of `count_from` (a quoted table name or subquery), so
+/// pages report the size of the whole table.
+pub fn execute_query(
+    conn: &Connection,
+    query: &str,
+    params: &[Value],
+    count_from: &str,
+    number_mode: NumberMode,
+) -> DuckResult<QueryResult> {
+    let total_rows: usize = conn
+        .query_row(&format!("SELECT COUNT(*) FROM {}", count_from), [], |row| row.get(0))
+        .unwrap_or(0); // Fallback if count fails
+
+    let result = run_query(conn, query, params, usize::MAX, number_mode)?;
+    Ok(QueryResult { total_rows, ..result })
+}
+
+/// Page through a table. Give `query_id` to be able to stop the query with
+/// `cancel_query`, and `timeout_secs` to stop it when it runs too long.
+/// Deep offsets get slow on big tables; page through a table (not a view)
+/// with `after_rowid` instead, passing the previous page's `next_rowid`
+/// (start with -1). `total_rows` comes from the cached `count_rows`. With
+/// `number_mode` "exact", BIGINT values come back as strings too.
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
 pub async fn query_data(
-    state: State<'_, AppState>,
+    app: AppHandle,
+    table_name: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    query_id: Option<String>,
+    timeout_secs: Option<u64>,
+    after_rowid: Option<i64>,
+    number_mode: Option<NumberMode>,
+) -> Result<QueryResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
+        let guard = state.queries.begin(query_id, conn).with_timeout(conn, query_timeout(timeout_secs));
+
+        let limit = limit.unwrap_or(5000); // Increased default for better virtual scroll performance
+        let offset = offset.unwrap_or(0);
+        let number_mode = number_mode.unwrap_or_default();
+
+        let started = Instant::now();
+        let table = quote_ident(&table_name);
+        let result = state.row_counts.count(conn, &table_name).and_then(|total_rows| {
+            let result = match after_rowid {
+                Some(after) => keyset_page(conn, &table_name, after, limit, number_mode)?,
+                None => run_query(
+                    conn,
+                    &format!("SELECT * FROM {} LIMIT {} OFFSET {}", table, limit, offset),
+                    &[],
+                    usize::MAX,
+                    number_mode,
+                )?,
+            };
+            Ok(QueryResult { total_rows, ..result })
+        });
+        state.metrics.record(
+            "query_data",
+            started,
+            result.as_ref().ok().map(|r| r.total_rows),
+            result.is_ok(),
+        );
+
+        result.map_err(|e| guard.error("Query error", e))
+    })
+    .await
+}
+
+/// Up to `limit` rows after row id `after`, in row id order. Keys on the
+/// hidden row id column, which (unlike DuckDB's rowid) stays with each row
+/// through deletes and rebuilds; tables without it fall back to the rowid.
+fn keyset_page(
+    conn: &Connection,
+    table_name: &str,
+    after: i64,
+    limit: usize,
+    number_mode: NumberMode,
+) -> DuckResult<QueryResult> {
+    let table = quote_ident(table_name);
+    if has_row_ids(conn, table_name)? {
+        let result = run_query(
+            conn,
+            &format!(
+                "SELECT * FROM {} WHERE {id} > ? ORDER BY {id} LIMIT {}",
+                table,
+                limit,
+                id = quote_ident(ROW_ID_COLUMN)
+            ),
+            &[Value::BigInt(after)],
+            usize::MAX,
+            number_mode,
+        )?;
+        let next_rowid = result.row_ids.as_ref().and_then(|ids| ids.last().copied());
+        return Ok(QueryResult { next_rowid: next_rowid.or(Some(after)), ..result });
+    }
+
+    let mut result = run_query(
+        conn,
+        &format!("SELECT rowid, * FROM {} WHERE rowid > ? ORDER BY rowid LIMIT {}", table, limit),
+        &[Value::BigInt(after)],
+        usize::MAX,
+        number_mode,
+    )?;
+
+    let next_rowid = result.rows.last().and_then(|row| row.first()).map(row_id);
+    result.columns.remove(0);
+    result.column_types.remove(0);
+    result.encodings.remove(0);
+    for row in &mut result.rows {
+        row.remove(0);
+    }
+    Ok(QueryResult { next_rowid: next_rowid.or(Some(after)), ..result })
+}
+
+/// Number of rows in a table or view. Table counts are cached until the
+/// table changes, so calling this for every page is cheap.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn count_rows(
+    app: AppHandle,
+    table_name: String,
+) -> Result<usize, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        state
+            .row_counts
+            .count(reader.get_connection(), &table_name)
+            .with_context(|| format!("Failed to count {}", table_name))
+    })
+    .await
+}
+
+/// Schema metadata key carrying the table's total row count in `query_arrow`
+/// responses
+const ARROW_TOTAL_ROWS_KEY: &str = "rats.total_rows";
+
+/// Page through a table like `query_data`, but answer with the rows as an
+/// Arrow IPC stream instead of JSON. The frontend gets an `ArrayBuffer` it
+/// can read with apache-arrow's `tableFromIPC`; the table's total row count
+/// is in the schema metadata under `rats.total_rows`. Much cheaper than JSON
+/// for wide pages, and values keep their DuckDB types.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn query_arrow(
+    app: AppHandle,
     table_name: String,
     limit: Option<usize>,
     offset: Option<usize>,
-) -> Result<QueryResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    query_id: Option<String>,
+) -> Result<tauri::ipc::Response, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
+        let guard = state.queries.begin(query_id, conn);
+
+        let limit = limit.unwrap_or(5000);
+        let offset = offset.unwrap_or(0);
+        let started = Instant::now();
+
+        let total_rows = state
+            .row_counts
+            .count(conn, &table_name)
+            .map_err(|e| guard.error("Query error", e))?;
+
+        let mut stmt = arrow_page_query(conn, &table_name, limit, offset)
+            .and_then(|sql| conn.prepare(&sql))
+            .map_err(|e| guard.error("Query error", e))?;
+        let batches = stmt.query_arrow([]).map_err(|e| guard.error("Query error", e))?;
 
-    let limit = limit.unwrap_or(5000); // Increased default for better virtual scroll performance
-    let offset = offset.unwrap_or(0);
+        let arrow_schema = batches.get_schema();
+        let mut metadata = arrow_schema.metadata().clone();
+        metadata.insert(ARROW_TOTAL_ROWS_KEY.to_string(), total_rows.to_string());
+        let schema = Arc::new(arrow_schema.as_ref().clone().with_metadata(metadata));
+
+        let mut writer = StreamWriter::try_new(Vec::new(), &schema)
+            .map_err(|e| AppError::internal(format!("Failed to encode Arrow data: {}", e)))?;
+        for batch in batches {
+            batch
+                .with_schema(schema.clone())
+                .and_then(|batch| writer.write(&batch))
+                .map_err(|e| AppError::internal(format!("Failed to encode Arrow data: {}", e)))?;
+        }
+        // The batch iterator ends quietly when the query is interrupted
+        if guard.is_cancelled() {
+            return Err(AppError::cancelled("Query cancelled"));
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| AppError::internal(format!("Failed to encode Arrow data: {}", e)))?;
 
-    let query = format!("SELECT * FROM {} LIMIT {} OFFSET {}", table_name, limit, offset);
+        state.metrics.record("query_arrow", started, Some(total_rows), true);
+        Ok(tauri::ipc::Response::new(bytes))
+    })
+    .await
+}
 
-    db.execute_query(&query)
-        .map_err(|e| format!("Query error: {}", e))
+/// The `query_arrow` page query, leaving out the hidden row id column
+fn arrow_page_query(conn: &Connection, table_name: &str, limit: usize, offset: usize) -> DuckResult<String> {
+    Ok(format!("SELECT * FROM {} LIMIT {} OFFSET {}", visible_source(conn, table_name)?, limit, offset))
 }
 
 #[tauri::command(rename_all = "camelCase")]
 pub async fn get_table_info(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
-) -> Result<TableInfo, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<TableInfo, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
 
-    db.get_table_info_internal(&table_name)
-        .map_err(|e| format!("Failed to get table info: {}", e))
+        // Cached under the column query; the row count goes stale with it
+        state
+            .query_cache
+            .get_or_run(conn, &format!("PRAGMA table_info({})", sql_literal(&table_name)), || {
+                table_info(conn, &table_name)
+            })
+            .context("Failed to get table info")
+    })
+    .await
 }
 
+/// Drop a table along with its data dictionary, catalog entries, import
+/// checkpoint and undo steps. The app's own tables can't be dropped.
 #[tauri::command(rename_all = "camelCase")]
 pub async fn drop_table(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
-) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
+) -> Result<String, AppError> {
+    if table_name.trim().to_lowercase().starts_with(INTERNAL_TABLE_PREFIX) {
+        return Err(AppError::invalid_input(format!(
+            "Table names starting with {} are reserved",
+            INTERNAL_TABLE_PREFIX
+        )));
+    }
+    let checkpoints = crate::import::resume::checkpoint_store(&app);
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let query = format!("DROP TABLE IF EXISTS {}", quote_ident(&table_name));
+        conn.execute(&query, [])
+            .context("Failed to drop table")?;
+
+        let _ = crate::dictionary::remove_table_metadata(conn, &table_name);
+        let _ = crate::catalog::remove_table_organization(conn, &table_name);
+        let _ = crate::catalog::remove_table_sources(conn, &table_name);
+        state.undo.forget_table(conn, &table_name);
+        if let Ok(store) = &checkpoints {
+            let _ = crate::import::resume::remove_checkpoint(store, &table_name);
+        }
+
+        Ok(format!("Table {} dropped successfully", table_name))
+    })
+    .await
+}
+
+/// Copy a table's rows and data dictionary into a new table, e.g. to keep a
+/// snapshot before reordering, retyping or deduplicating it
+#[tauri::command(rename_all = "camelCase")]
+pub async fn duplicate_table(
+    app: AppHandle,
+    source: String,
+    target: String,
+) -> Result<TableInfo, AppError> {
+    let target = target.trim().to_string();
+    if target.is_empty() {
+        return Err(AppError::invalid_input("Table name cannot be empty"));
+    }
+    if target.starts_with(INTERNAL_TABLE_PREFIX) {
+        return Err(AppError::invalid_input(format!(
+            "Table names starting with {} are reserved",
+            INTERNAL_TABLE_PREFIX
+        )));
+    }
+
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let started = Instant::now();
+
+        if !table_exists(conn, &source)? {
+            return Err(AppError::not_found(format!("Table {} not found", source)));
+        }
+        if table_exists(conn, &target)? {
+            return Err(AppError::invalid_input(format!("Table {} already exists", target)));
+        }
+
+        let copied = conn.execute(
+            &format!("CREATE TABLE {} AS SELECT * FROM {}", quote_ident(&target), quote_ident(&source)),
+            [],
+        );
+        state.metrics.record("duplicate_table", started, copied.as_ref().ok().copied(), copied.is_ok());
+        copied.with_context(|| format!("Failed to copy {}", source))?;
+
+        let _ = crate::dictionary::copy_table_metadata(conn, &source, &target);
+        table_info(conn, &target).map_err(AppError::from)
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_s3_credentials(
+    app: AppHandle,
+    credentials: S3Credentials,
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+
+        db.set_s3_credentials(&credentials)
+            .context("Failed to set S3 credentials")?;
+
+        Ok("S3 credentials configured".to_string())
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn clear_s3_credentials(
+    app: AppHandle,
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
 
-    let query = format!("DROP TABLE IF EXISTS {}", table_name);
-    conn.execute(&query, [])
-        .map_err(|e| format!("Failed to drop table: {}", e))?;
+        db.clear_s3_credentials()
+            .context("Failed to clear S3 credentials")?;
+
+        Ok("S3 credentials cleared".to_string())
+    })
+    .await
+}
+
+/// Alias a new database file is attached under while `save_database_as`
+/// copies into it
+const SAVE_AS_ALIAS: &str = "rats_save_as";
+
+/// Added to the target path for the file `save_database_as` copies into
+/// before moving it into place
+const SAVE_AS_SUFFIX: &str = ".saving";
+
+/// Swap the open database for `next`. Writes against the old one have
+/// finished, since callers hold the database lock; readers still running
+/// there finish and are then dropped.
+pub(crate) fn switch_database(state: &AppState, db: &mut DatabaseConnection, next: DatabaseConnection) {
+    // Undo steps refer to the old database's tables; metrics recorded so
+    // far belong in it too
+    state.undo.clear(&db.conn);
+    state.metrics.flush(&db.conn);
+    if db.path.is_some() {
+        let _ = db.conn.execute_batch("CHECKPOINT");
+    }
+    state.interrupt.replace(next.get_connection());
+    state.readers.reset(next.get_connection());
+    state.row_counts.clear();
+    state.query_cache.clear();
+    let settings = db.settings.clone();
+    *db = next;
+    state.undo.clear(&db.conn);
+    if let Err(e) = db.configure(settings) {
+        eprintln!("Failed to apply database settings: {}", e);
+    }
+}
+
+/// Open a DuckDB database file, creating it if it doesn't exist, and work in
+/// it from now on so tables survive restarts. The current in-memory tables
+/// are discarded; save them with `save_database_as` first. S3 credentials
+/// need to be set again.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn open_database(
+    app: AppHandle,
+    path: String,
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let mut db = state.db.lock()?;
+        if db.database_path() == Some(path.as_str()) {
+            return Ok(format!("{} is already open", path));
+        }
+
+        let next = DatabaseConnection::open(&path)
+            .with_context(|| format!("Failed to open {}", path))?;
+        switch_database(&state, &mut db, next);
+
+        Ok(format!("Opened database {}", path))
+    })
+    .await
+}
+
+/// Copy every table and view into a new database file at `path`, replacing
+/// any file already there, and continue working in that file
+#[tauri::command(rename_all = "camelCase")]
+pub async fn save_database_as(
+    app: AppHandle,
+    path: String,
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let mut db = state.db.lock()?;
+        if db.database_path() == Some(path.as_str()) {
+            db.get_connection()
+                .execute_batch("CHECKPOINT")
+                .with_context(|| format!("Failed to save {}", path))?;
+            return Ok(format!("Saved database {}", path));
+        }
+
+        // Copied into a file beside the target first, so a failed copy
+        // leaves any database already at `path` as it was
+        let partial = format!("{}{}", path, SAVE_AS_SUFFIX);
+        let remove_partial = || {
+            for file in [partial.clone(), format!("{}.wal", partial)] {
+                if std::path::Path::new(&file).exists() {
+                    let _ = std::fs::remove_file(&file);
+                }
+            }
+        };
+        remove_partial();
+
+        let conn = db.get_connection();
+        let current: String = conn
+            .query_row("SELECT current_database()", [], |row| row.get(0))?;
+        conn.execute_batch(&format!("ATTACH {} AS {}", sql_literal(&partial), SAVE_AS_ALIAS))
+            .with_context(|| format!("Failed to create {}", path))?;
+        let copied = conn.execute_batch(&format!(
+            "COPY FROM DATABASE {} TO {}",
+            quote_ident(&current),
+            SAVE_AS_ALIAS
+        ));
+        let detached = conn.execute_batch(&format!("DETACH DATABASE IF EXISTS {}", SAVE_AS_ALIAS));
+        if let Err(e) = copied.and(detached) {
+            remove_partial();
+            return Err(AppError::from(e).context(format!("Failed to save {}", path)));
+        }
+
+        if let Err(e) = std::fs::rename(&partial, &path) {
+            remove_partial();
+            return Err(AppError::from(e).context(format!("Failed to replace {}", path)));
+        }
+        // The replaced database's log would otherwise be replayed into the new file
+        let wal = format!("{}.wal", path);
+        if std::path::Path::new(&wal).exists() {
+            std::fs::remove_file(&wal).with_context(|| format!("Failed to replace {}", wal))?;
+        }
+
+        let next = DatabaseConnection::open(&path)
+            .with_context(|| format!("Saved {} but failed to open it", path))?;
+        switch_database(&state, &mut db, next);
+
+        Ok(format!("Saved database as {}", path))
+    })
+    .await
+}
+
+/// Go back to a fresh in-memory database, closing any open database file
+#[tauri::command(rename_all = "camelCase")]
+pub async fn new_in_memory_database(
+    app: AppHandle,
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let mut db = state.db.lock()?;
+        let next = DatabaseConnection::new()
+            .context("Failed to create database")?;
+        switch_database(&state, &mut db, next);
+
+        Ok("Started a new in-memory database".to_string())
+    })
+    .await
+}
+
+/// Prefix of the aliases the app attaches databases under internally
+const INTERNAL_ALIAS_PREFIX: &str = "rats_";
+
+/// A database file attached next to the workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachedDatabase {
+    pub alias: String, // Query its tables as alias.table_name
+    pub path: String,
+    pub kind: String, // "duckdb" or "sqlite"
+    pub read_only: bool,
+    pub tables: Vec<String>,
+}
+
+/// Whether `file` is a "sqlite" or "duckdb" database, from the magic bytes
+/// at the start of the file (`.db` is used by both). Files too short to
+/// tell, e.g. ones an attach will create, go by their extension.
+fn database_kind(file: &Path) -> &'static str {
+    let mut header = [0u8; 16];
+    if std::fs::File::open(file).and_then(|mut f| f.read_exact(&mut header)).is_ok() {
+        if header.starts_with(b"SQLite format 3\0") {
+            return "sqlite";
+        }
+        // DuckDB files carry "DUCK" after an 8-byte checksum
+        if &header[8..12] == b"DUCK" {
+            return "duckdb";
+        }
+    }
+    match file.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("sqlite" | "sqlite3" | "db") => "sqlite",
+        _ => "duckdb",
+    }
+}
+
+/// Attach a `.duckdb` or SQLite file under `alias` (the file name by
+/// default) so queries can join against its tables without importing them.
+/// Files are attached read-only unless `read_only` is false. Attachments
+/// last until they are detached or another database is opened.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn attach_database(
+    app: AppHandle,
+    path: String,
+    alias: Option<String>,
+    read_only: Option<bool>,
+) -> Result<AttachedDatabase, AppError> {
+    let file = std::path::Path::new(&path);
+    let alias = match alias.map(|a| a.trim().to_string()) {
+        Some(alias) if !alias.is_empty() => alias,
+        _ => file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(|stem| stem.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect::<String>())
+            .filter(|stem| !stem.is_empty())
+            .ok_or_else(|| AppError::invalid_input(format!("Cannot name a database after {}; give an alias", path)))?,
+    };
+    if alias.starts_with(INTERNAL_ALIAS_PREFIX) {
+        return Err(AppError::invalid_input(format!("Aliases starting with {} are reserved", INTERNAL_ALIAS_PREFIX)));
+    }
+    let kind = database_kind(file);
+    let read_only = read_only.unwrap_or(true);
+
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let mut options = Vec::new();
+        if kind == "sqlite" {
+            load_extension(conn, "sqlite").context("Failed to load sqlite extension")?;
+            options.push("TYPE SQLITE");
+        }
+        if read_only {
+            options.push("READ_ONLY");
+        }
+        let options = if options.is_empty() { String::new() } else { format!(" ({})", options.join(", ")) };
+        conn.execute_batch(&format!("ATTACH {} AS {}{}", sql_literal(&path), quote_ident(&alias), options))
+            .with_context(|| format!("Failed to attach {}", path))?;
+
+        let mut stmt = conn
+            .prepare("SELECT table_name FROM information_schema.tables WHERE table_catalog = ? ORDER BY table_name")?;
+        let tables = stmt
+            .query_map([&alias], |row| row.get(0))
+            .and_then(|rows| rows.collect::<DuckResult<Vec<String>>>())?;
+
+        Ok(AttachedDatabase { alias, path, kind: kind.to_string(), read_only, tables })
+    })
+    .await
+}
+
+/// Detach a database attached with `attach_database`
+#[tauri::command(rename_all = "camelCase")]
+pub async fn detach_database(
+    app: AppHandle,
+    alias: String,
+) -> Result<String, AppError> {
+    if alias.starts_with(INTERNAL_ALIAS_PREFIX) {
+        return Err(AppError::not_found(format!("{} is not an attached database", alias)));
+    }
 
-    Ok(format!("Table {} dropped successfully", table_name))
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let current: String = conn
+            .query_row("SELECT current_database()", [], |row| row.get(0))?;
+        if alias == current {
+            return Err(AppError::invalid_input("The workspace database cannot be detached"));
+        }
+
+        conn.execute_batch(&format!("DETACH DATABASE {}", quote_ident(&alias)))
+            .with_context(|| format!("Failed to detach {}", alias))?;
+        Ok(format!("Detached {}", alias))
+    })
+    .await
+}
+
+fn database_settings_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .context("Failed to locate config directory")?;
+    Ok(dir.join(DATABASE_SETTINGS_FILE))
+}
+
+fn load_database_settings(app: &AppHandle) -> Result<DatabaseSettings, AppError> {
+    let path = database_settings_path(app)?;
+    if !path.exists() {
+        return Ok(DatabaseSettings::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .context("Failed to read database settings")?;
+    serde_json::from_str(&contents).context("Invalid database settings file")
+}
+
+fn save_database_settings(app: &AppHandle, settings: &DatabaseSettings) -> Result<(), AppError> {
+    let path = database_settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context("Failed to create config directory")?;
+    }
+
+    let contents = serde_json::to_string_pretty(settings)?;
+    std::fs::write(&path, contents).context("Failed to save database settings")
+}
+
+/// Apply the saved resource settings at startup
+pub fn restore_database_settings(app: &AppHandle) -> Result<DatabaseSettings, AppError> {
+    let settings = load_database_settings(app)?;
+    let state = app.state::<AppState>();
+    let mut db = state.db.lock()?;
+    db.configure(settings.clone())
+        .context("Failed to apply database settings")?;
+    Ok(settings)
+}
+
+/// The memory limit, thread count and spill settings in effect
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_database_settings(app: AppHandle) -> Result<DatabaseSettings, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        Ok(db.settings().clone())
+    })
+    .await
+}
+
+/// Change how much memory and how many threads DuckDB may use and where it
+/// spills data that doesn't fit. The settings take effect immediately, carry
+/// over to databases opened later and are remembered across restarts.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_database_settings(
+    app: AppHandle,
+    settings: DatabaseSettings,
+) -> Result<DatabaseSettings, AppError> {
+    let memory_limit = settings.memory_limit.trim().to_string();
+    if memory_limit.is_empty() {
+        return Err(AppError::invalid_input("Memory limit cannot be empty"));
+    }
+    if settings.threads == 0 {
+        return Err(AppError::invalid_input("At least one thread is required"));
+    }
+    let temp_directory = settings
+        .temp_directory
+        .map(|dir| dir.trim().to_string())
+        .filter(|dir| !dir.is_empty());
+    let settings = DatabaseSettings { memory_limit, temp_directory, ..settings };
+
+    let applied = settings.clone();
+    crate::run_blocking(app.clone(), move |state| {
+        let mut db = state.db.lock()?;
+        db.configure(applied)
+            .context("Failed to apply database settings")
+    })
+    .await?;
+
+    save_database_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+/// Rows `execute_sql` returns per query unless told otherwise
+const DEFAULT_SQL_MAX_ROWS: usize = 5000;
+
+/// DuckDB type names of a query's result columns
+fn describe_types(conn: &Connection, sql: &str, params: &[Value]) -> DuckResult<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("DESCRIBE {}", sql))?;
+    let types = stmt
+        .query_map(params_from_iter(params), |row| row.get(1))?
+        .collect::<DuckResult<Vec<String>>>()?;
+    Ok(types)
+}
+
+/// Run a SELECT-like statement with `params` bound, keeping the first
+/// `max_rows` rows
+fn run_query(
+    conn: &Connection,
+    sql: &str,
+    params: &[Value],
+    max_rows: usize,
+    number_mode: NumberMode,
+) -> DuckResult<QueryResult> {
+    let described = describe_types(conn, sql, params);
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query(params_from_iter(params))?;
+    let columns = rows.as_ref().map(|stmt| stmt.column_names()).unwrap_or_default();
+    // PRAGMA, SHOW and friends can't be described; fall back to Arrow type names
+    let column_types = match described {
+        Ok(types) if types.len() == columns.len() => types,
+        _ => rows
+            .as_ref()
+            .map(|stmt| (0..columns.len()).map(|i| stmt.column_type(i).to_string()).collect())
+            .unwrap_or_default(),
+    };
+    let encodings = column_types.iter().map(|t| value_encoding(t, number_mode)).collect();
+
+    let mut collected_rows = Vec::new();
+    let mut total_rows = 0;
+    while let Some(row) = rows.next()? {
+        if collected_rows.len() < max_rows {
+            let row_data = (0..columns.len())
+                .map(|i| row.get_ref(i).map(|value| json_value(value, number_mode)))
+                .collect::<DuckResult<Vec<_>>>()?;
+            collected_rows.push(row_data);
+        }
+        total_rows += 1;
+    }
+
+    let mut result = QueryResult {
+        columns,
+        column_types,
+        encodings,
+        rows: collected_rows,
+        row_ids: None,
+        total_rows,
+        next_rowid: None,
+    };
+    split_row_ids(&mut result);
+    Ok(result)
+}
+
+/// Run user-written SQL, one or more statements separated by semicolons.
+/// Queries return their rows (the first `max_rows`, 5000 by default); other
+/// statements return how many rows they changed. Statements run in order
+/// and stop at the first error; the ones before it stay applied. Give
+/// `query_id` to be able to stop the script with `cancel_query`. With
+/// `timeout_secs` the script is interrupted once it runs that long and fails
+/// with an error starting "Query timed out". With `read_only`, scripts
+/// containing anything but queries are refused before they run, and the
+/// queries run in a transaction that is rolled back afterwards. With
+/// `number_mode` "exact", BIGINT values come back as strings too. With
+/// `keep_results`, each query's full result is kept so it can be exported
+/// with `export_query_result` without running the query again; the result
+/// is identified by `query_id`, or `query_id.N` for the Nth statement of a
+/// longer script. Kept results last until released with
+/// `release_query_result`, replaced, or the database is closed.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_sql(
+    app: AppHandle,
+    sql: String,
+    max_rows: Option<usize>,
+    query_id: Option<String>,
+    timeout_secs: Option<u64>,
+    read_only: Option<bool>,
+    number_mode: Option<NumberMode>,
+    keep_results: Option<bool>,
+) -> Result<Vec<StatementResult>, AppError> {
+    let statements = script::split_statements(&sql);
+    if statements.is_empty() {
+        return Err(AppError::invalid_input("No SQL to run"));
+    }
+
+    let keep_id = match (keep_results.unwrap_or(false), &query_id) {
+        (false, _) => None,
+        (true, None) => return Err(AppError::invalid_input("Give a queryId to keep query results")),
+        (true, Some(_)) if read_only.unwrap_or(false) => {
+            return Err(AppError::invalid_input("Query results can't be kept in read-only mode"))
+        }
+        (true, Some(query_id)) => Some(query_id.clone()),
+    };
+
+    let read_only = read_only.unwrap_or(false);
+    if read_only {
+        if let Some(statement) = statements.iter().find(|s| !script::is_read_only(s)) {
+            return Err(AppError::invalid_input(
+                "Read-only mode only runs queries; this statement could change the database",
+            )
+            .with_sql(statement.clone()));
+        }
+    }
+
+    crate::run_blocking(app, move |state| {
+        let statement_count = statements.len();
+        let max_rows = max_rows.unwrap_or(DEFAULT_SQL_MAX_ROWS);
+        let number_mode = number_mode.unwrap_or_default();
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let guard = state.queries.begin(query_id, conn).with_timeout(conn, query_timeout(timeout_secs));
+        let started = Instant::now();
+
+        // Anything a query manages to change despite the check is rolled back.
+        // Inside a transaction the user already opened, only the check applies.
+        let rollback = read_only && conn.execute_batch("BEGIN TRANSACTION").is_ok();
+
+        let mut results = Vec::with_capacity(statements.len());
+        let mut failure = None;
+        for (idx, statement) in statements.into_iter().enumerate() {
+            let result_id = keep_id.as_ref().filter(|_| script::is_query(&statement)).map(|id| {
+                if statement_count == 1 {
+                    id.clone()
+                } else {
+                    format!("{}.{}", id, idx + 1)
+                }
+            });
+            let result = if let Some(result_id) = result_id {
+                // The query runs once into the kept table, and the rows shown come from there
+                let table = quote_ident(&result_table(&result_id));
+                conn.execute_batch(&format!("CREATE OR REPLACE TEMP TABLE {} AS {}", table, statement))
+                    .and_then(|_| run_query(conn, &format!("SELECT * FROM {}", table), &[], max_rows, number_mode))
+                    .map(|result| StatementResult::Query {
+                        sql: statement.clone(),
+                        result,
+                        result_id: Some(result_id),
+                    })
+            } else if script::returns_rows(&statement) {
+                run_query(conn, &statement, &[], max_rows, number_mode)
+                    .map(|result| StatementResult::Query { sql: statement.clone(), result, result_id: None })
+            } else {
+                conn.execute(&statement, [])
+                    .map(|rows_affected| StatementResult::Command { sql: statement.clone(), rows_affected })
+            };
+            match result {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    let error = if statement_count == 1 {
+                        guard.error("Query error", e)
+                    } else {
+                        guard.error(&format!("Statement {} of {} failed", idx + 1, statement_count), e)
+                    };
+                    failure = Some(error.with_sql(statement));
+                    break;
+                }
+            }
+        }
+        if rollback {
+            let _ = conn.execute_batch("ROLLBACK");
+        }
+
+        // Deletes keep a table's stored size, so cached counts and results can't tell
+        if results.iter().any(|result| matches!(result, StatementResult::Command { .. })) {
+            state.row_counts.clear();
+            state.query_cache.clear();
+        }
+
+        let rows = results
+            .iter()
+            .map(|result| match result {
+                StatementResult::Query { result, .. } => result.total_rows,
+                StatementResult::Command { rows_affected, .. } => *rows_affected,
+            })
+            .sum();
+        state.metrics.record("execute_sql", started, Some(rows), failure.is_none());
+
+        // The error names the statement that failed
+        Ok(match failure {
+            Some(error) => Err(error),
+            None => Ok(results),
+        })
+    })
+    .await?
+}
+
+/// DuckDB's physical plan for a statement, without running it
+fn explain_plan(conn: &Connection, statement: &str) -> DuckResult<String> {
+    let mut stmt = conn.prepare(&format!("EXPLAIN {}", statement))?;
+    let parts = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<DuckResult<Vec<_>>>()?;
+    Ok(parts.join("\n"))
+}
+
+/// Run a statement under `EXPLAIN ANALYZE` and return DuckDB's JSON profile
+fn analyze_plan(conn: &Connection, statement: &str) -> DuckResult<String> {
+    conn.execute_batch("PRAGMA enable_profiling = 'json'")?;
+    let profile = conn.query_row(&format!("EXPLAIN ANALYZE {}", statement), [], |row| row.get(1));
+    let _ = conn.execute_batch("PRAGMA disable_profiling");
+    profile
+}
+
+/// Show how DuckDB runs a single statement. With `analyze` the statement is
+/// actually run, and each operator's time and row count are reported, to see
+/// which join or filter makes a query slow. Analyzing a statement that
+/// changes data applies the change. Stop it with `cancel_query`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn explain_query(
+    app: AppHandle,
+    query: String,
+    analyze: bool,
+    query_id: Option<String>,
+) -> Result<QueryPlan, AppError> {
+    let statements = script::split_statements(&query);
+    let statement = match statements.as_slice() {
+        [statement] => statement.clone(),
+        _ => return Err(AppError::invalid_input("explain_query explains a single statement")),
+    };
+
+    let sql = statement.clone();
+    crate::run_blocking(app, move |state| {
+        // Only analyzing a data change needs the writer connection
+        let db;
+        let reader;
+        let conn = if analyze && !script::returns_rows(&statement) {
+            db = state.db.lock()?;
+            db.get_connection()
+        } else {
+            reader = state.readers.get()?;
+            reader.get_connection()
+        };
+        let guard = state.queries.begin(query_id, conn);
+        let started = Instant::now();
+
+        let result = explain_plan(conn, &statement)
+            .map_err(|e| guard.error("Failed to explain query", e))
+            .and_then(|plan| {
+                if !analyze {
+                    return Ok(QueryPlan { plan, operators: Vec::new(), total_time_ms: None });
+                }
+                let profile = analyze_plan(conn, &statement)
+                    .map_err(|e| guard.error("Failed to analyze query", e))?;
+                let profile: serde_json::Value = serde_json::from_str(&profile)
+                    .context("Unreadable query profile")?;
+                let (operators, total_time_ms) = plan::profile_operators(&profile);
+                Ok(QueryPlan { plan, operators, total_time_ms })
+            });
+
+        state.metrics.record("explain_query", started, None, result.is_ok());
+        result
+    })
+    .await
+    .map_err(|e| e.with_sql(sql))
+}
+
+/// Rows per `query-chunk` event unless told otherwise
+const DEFAULT_STREAM_CHUNK_ROWS: usize = 10_000;
+
+/// Run a single SELECT once and send its rows to the frontend as
+/// `query-chunk` events of `chunk_size` rows, tagged with `query_id`, so
+/// large results are never held in memory all at once. Returns the total
+/// row count after the last chunk; stop it early with `cancel_query`. With
+/// `number_mode` "exact", BIGINT values come back as strings too.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn query_stream(
+    app: AppHandle,
+    window: tauri::Window,
+    sql: String,
+    chunk_size: Option<usize>,
+    query_id: String,
+    number_mode: Option<NumberMode>,
+) -> Result<usize, AppError> {
+    let statements = script::split_statements(&sql);
+    match statements.as_slice() {
+        [statement] if script::returns_rows(statement) => {}
+        _ => return Err(AppError::invalid_input("query_stream runs a single SELECT statement")),
+    }
+
+    crate::run_blocking(app, move |state| {
+        let chunk_size = chunk_size.unwrap_or(DEFAULT_STREAM_CHUNK_ROWS).max(1);
+        let number_mode = number_mode.unwrap_or_default();
+
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
+        let guard = state.queries.begin(Some(query_id.clone()), conn);
+        let started = Instant::now();
+
+        let statement = &statements[0];
+        let mut column_types = describe_types(conn, statement, &[]).ok();
+        let mut stmt = conn.prepare(statement).map_err(|e| guard.error("Query error", e))?;
+        let (schema, batches): (SchemaRef, Box<dyn Iterator<Item = RecordBatch>>) = if script::is_query(statement) {
+            // Take the schema from the query without rows, then stream the
+            // result batch by batch instead of materializing it
+            let schema = conn
+                .prepare(&format!("SELECT * FROM ({}) LIMIT 0", statement))
+                .and_then(|mut empty| empty.query_arrow([]).map(|batches| batches.get_schema()))
+                .map_err(|e| guard.error("Query error", e))?;
+            let batches = stmt.stream_arrow([], schema.clone()).map_err(|e| guard.error("Query error", e))?;
+            (schema, Box::new(batches))
+        } else {
+            // SHOW, PRAGMA and friends return few rows and can't be a subquery
+            let batches = stmt.query_arrow([]).map_err(|e| guard.error("Query error", e))?;
+            (batches.get_schema(), Box::new(batches))
+        };
+        let mut columns: Vec<String> = schema.fields().iter().map(|field| field.name().clone()).collect();
+
+        // The hidden row id column is sent as `row_ids` instead
+        let row_id_index = columns.iter().position(|c| c == ROW_ID_COLUMN);
+        let value_columns: Vec<usize> = (0..columns.len()).filter(|i| Some(*i) != row_id_index).collect();
+        if let Some(index) = row_id_index {
+            columns.remove(index);
+            if let Some(types) = column_types.as_mut().filter(|types| types.len() > index) {
+                types.remove(index);
+            }
+        }
+        let mut encodings = column_types
+            .as_ref()
+            .map(|types| types.iter().map(|t| value_encoding(t, number_mode)).collect());
+
+        let mut columns = Some(columns);
+        let mut offset = 0;
+        let mut send = |rows: Vec<Vec<serde_json::Value>>, ids: Vec<i64>, done: bool| {
+            let sent = rows.len();
+            let _ = window.emit(
+                "query-chunk",
+                QueryChunk {
+                    query_id: query_id.clone(),
+                    columns: columns.take(),
+                    column_types: column_types.take(),
+                    encodings: encodings.take(),
+                    offset,
+                    rows,
+                    row_ids: row_id_index.map(|_| ids),
+                    done,
+                },
+            );
+            offset += sent;
+        };
+
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let mut chunk_ids = Vec::new();
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                chunk.push(
+                    value_columns
+                        .iter()
+                        .map(|&i| arrow_json_value(batch.column(i).as_ref(), row, number_mode))
+                        .collect(),
+                );
+                if let Some(index) = row_id_index {
+                    let ids = batch.column(index).as_primitive_opt::<Int64Type>();
+                    chunk_ids.push(ids.filter(|ids| ids.is_valid(row)).map(|ids| ids.value(row)).unwrap_or_default());
+                }
+                if chunk.len() == chunk_size {
+                    let rows = std::mem::replace(&mut chunk, Vec::with_capacity(chunk_size));
+                    send(rows, std::mem::take(&mut chunk_ids), false);
+                }
+            }
+        }
+        // The batch iterator ends quietly when the query is interrupted
+        if guard.is_cancelled() {
+            return Err(AppError::cancelled("Query cancelled"));
+        }
+        send(chunk, chunk_ids, true);
+
+        state.metrics.record("query_stream", started, Some(offset), true);
+        Ok(offset)
+    })
+    .await
+    .map_err(|e| e.with_sql(sql))
+}
+
+/// Stop the query started with `query_id`. Returns false when it isn't
+/// running (it finished, or never started).
+#[tauri::command(rename_all = "camelCase")]
+pub async fn cancel_query(
+    state: State<'_, AppState>,
+    query_id: String,
+) -> Result<bool, AppError> {
+    Ok(state.queries.cancel(&query_id))
+}
+
+/// A query result kept by `execute_sql` with `keep_results`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeptResult {
+    pub result_id: String,
+    pub row_count: usize,
+    pub column_count: usize,
+}
+
+/// Query results kept for export, by result id
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_query_results(app: AppHandle) -> Result<Vec<KeptResult>, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let mut stmt = db
+            .get_connection()
+            .prepare(
+                "SELECT table_name, estimated_size, column_count FROM duckdb_tables()
+                 WHERE temporary AND starts_with(table_name, ?)
+                 ORDER BY table_name",
+            )?;
+        let results = stmt
+            .query_map([RESULT_TABLE_PREFIX], |row| {
+                let name: String = row.get(0)?;
+                Ok(KeptResult {
+                    result_id: name[RESULT_TABLE_PREFIX.len()..].to_string(),
+                    row_count: row.get::<_, i64>(1)?.max(0) as usize,
+                    column_count: row.get::<_, i64>(2)?.max(0) as usize,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(results)
+    })
+    .await
+}
+
+/// Free the memory held by a kept query result. Returns false when no
+/// result with that id is kept.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn release_query_result(app: AppHandle, result_id: String) -> Result<bool, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        if !result_exists(conn, &result_id)? {
+            return Ok(false);
+        }
+        conn.execute_batch(&format!("DROP TABLE temp.main.{}", quote_ident(&result_table(&result_id))))
+            .context("Failed to release query result")?;
+        Ok(true)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrow_pages_leave_out_row_ids() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t AS SELECT i AS a, 'row ' || i AS b FROM range(10) r(i)").unwrap();
+        // Editing a table gives its rows ids
+        ensure_row_ids(&conn, "t").unwrap();
+        assert!(has_row_ids(&conn, "t").unwrap());
+
+        let mut stmt = conn.prepare(&arrow_page_query(&conn, "t", 4, 8).unwrap()).unwrap();
+        let batches = stmt.query_arrow([]).unwrap();
+        let schema = batches.get_schema();
+        let columns: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+        assert_eq!(columns, ["a", "b"]);
+        assert_eq!(batches.map(|batch| batch.num_rows()).sum::<usize>(), 2);
+    }
 }
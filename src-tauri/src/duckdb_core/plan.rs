@@ -0,0 +1,46 @@
+use serde_json::Value as Json;
+use super::PlanOperator;
+
+/// Flatten the JSON profile DuckDB writes for `EXPLAIN ANALYZE` into its
+/// operators, depth first, plus the total query time in milliseconds. Key
+/// names changed in DuckDB 1.1 (`operator_type` for `name` and so on), so
+/// both spellings are read.
+pub(super) fn profile_operators(profile: &Json) -> (Vec<PlanOperator>, Option<f64>) {
+    let mut operators = Vec::new();
+    for child in children(profile) {
+        collect(child, 0, &mut operators);
+    }
+    let total = field(profile, &["latency", "timing", "result"])
+        .and_then(Json::as_f64)
+        .map(|seconds| seconds * 1000.0);
+    (operators, total)
+}
+
+fn collect(node: &Json, depth: usize, operators: &mut Vec<PlanOperator>) {
+    let name = field(node, &["operator_type", "operator_name", "name"])
+        .and_then(Json::as_str)
+        .unwrap_or("UNKNOWN");
+    operators.push(PlanOperator {
+        name: name.to_string(),
+        depth,
+        time_ms: field(node, &["operator_timing", "timing"])
+            .and_then(Json::as_f64)
+            .unwrap_or(0.0)
+            * 1000.0,
+        rows: field(node, &["operator_cardinality", "cardinality"])
+            .and_then(Json::as_u64)
+            .unwrap_or(0),
+        details: field(node, &["extra_info", "extra-info"]).cloned().unwrap_or(Json::Null),
+    });
+    for child in children(node) {
+        collect(child, depth + 1, operators);
+    }
+}
+
+fn children(node: &Json) -> impl Iterator<Item = &Json> {
+    node.get("children").and_then(Json::as_array).into_iter().flatten()
+}
+
+fn field<'a>(node: &'a Json, keys: &[&str]) -> Option<&'a Json> {
+    keys.iter().find_map(|key| node.get(*key))
+}
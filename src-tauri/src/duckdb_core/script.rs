@@ -0,0 +1,245 @@
+/// Split a SQL script into statements at top-level semicolons, leaving
+/// semicolons inside quotes, `$$` strings and comments alone. Empty
+/// statements are dropped.
+pub(super) fn split_statements(script: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = script.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                current.push(c);
+                // A doubled quote is an escaped quote and just continues the string
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == c {
+                        if chars.peek() == Some(&c) {
+                            current.push(chars.next().unwrap_or(c));
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'$') => {
+                current.push_str("$$");
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if previous == '$' && next == '$' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                current.push(c);
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                current.push(c);
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            ';' => {
+                if !strip_comments(&current).trim().is_empty() {
+                    statements.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !strip_comments(&current).trim().is_empty() {
+        statements.push(current.trim().to_string());
+    }
+
+    statements
+}
+
+/// The statement with leading comments removed
+fn strip_comments(statement: &str) -> &str {
+    let mut rest = statement.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map(|(_, after)| after).unwrap_or("").trim_start();
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map(|(_, after)| after).unwrap_or("").trim_start();
+        } else {
+            return rest;
+        }
+    }
+}
+
+/// The statement's first keyword, uppercased
+fn first_keyword(statement: &str) -> String {
+    strip_comments(statement)
+        .trim_start_matches(|c: char| c == '(' || c.is_whitespace())
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Whether a statement produces a result set (SELECT and friends) rather than
+/// changing the database
+pub(super) fn returns_rows(statement: &str) -> bool {
+    is_query(statement)
+        || matches!(
+            first_keyword(statement).as_str(),
+            "SHOW" | "DESCRIBE" | "DESC" | "SUMMARIZE" | "EXPLAIN" | "PRAGMA" | "CALL"
+        )
+}
+
+/// Whether a statement is a query that can be the body of
+/// `CREATE TABLE ... AS`
+pub(super) fn is_query(statement: &str) -> bool {
+    matches!(first_keyword(statement).as_str(), "SELECT" | "WITH" | "VALUES" | "FROM" | "TABLE")
+}
+
+/// Words that start or make up statements changing data, schema, settings
+/// or files
+const WRITE_KEYWORDS: [&str; 25] = [
+    "INSERT", "UPDATE", "DELETE", "MERGE", "UPSERT", "CREATE", "DROP", "ALTER", "TRUNCATE", "COPY",
+    "ATTACH", "DETACH", "INSTALL", "LOAD", "EXPORT", "IMPORT", "VACUUM", "CHECKPOINT", "SET", "RESET",
+    "USE", "CALL", "BEGIN", "COMMIT", "ROLLBACK",
+];
+
+/// PRAGMAs that only report on the database
+const READ_PRAGMAS: [&str; 8] = [
+    "TABLE_INFO", "SHOW", "SHOW_TABLES", "SHOW_TABLES_EXPANDED", "DATABASE_LIST", "DATABASE_SIZE",
+    "STORAGE_INFO", "VERSION",
+];
+
+/// Uppercased words of a statement outside strings, quoted names and
+/// comments
+fn bare_words(statement: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut chars = statement.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c.to_ascii_uppercase());
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        match c {
+            // A doubled quote ends the string and starts it again, which is fine here
+            '\'' | '"' => {
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'$') => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '$' && next == '$' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => {}
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Whether a statement only reads: a query or a reporting PRAGMA, with no
+/// write keyword anywhere in it, so a CTE or `EXPLAIN ANALYZE` can't wrap a
+/// change. Errs on the side of refusing.
+pub(super) fn is_read_only(statement: &str) -> bool {
+    let words = bare_words(statement);
+    let allowed = match words.first().map(String::as_str) {
+        Some("PRAGMA") => words.get(1).is_some_and(|name| READ_PRAGMAS.contains(&name.as_str())),
+        Some(keyword) => matches!(
+            keyword,
+            "SELECT" | "WITH" | "VALUES" | "FROM" | "TABLE" | "SHOW" | "DESCRIBE" | "DESC" | "SUMMARIZE"
+                | "EXPLAIN"
+        ),
+        None => false,
+    };
+    allowed && !words.iter().any(|word| WRITE_KEYWORDS.contains(&word.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_top_level_semicolons() {
+        assert_eq!(
+            split_statements("SELECT 'a;b', 'it''s; fine'; SELECT \"x;y\" FROM t"),
+            ["SELECT 'a;b', 'it''s; fine'", "SELECT \"x;y\" FROM t"]
+        );
+        assert_eq!(split_statements("SELECT $$a;b$$; SELECT 2"), ["SELECT $$a;b$$", "SELECT 2"]);
+        assert_eq!(
+            split_statements("-- one; two\nSELECT 1; /* a;b */ SELECT 2"),
+            ["-- one; two\nSELECT 1", "/* a;b */ SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn drops_empty_and_comment_only_statements() {
+        assert_eq!(split_statements(";; \n ; SELECT 1;;"), ["SELECT 1"]);
+        assert_eq!(split_statements("SELECT 1; -- the end"), ["SELECT 1"]);
+        assert_eq!(split_statements("SELECT 1; /* done */ ;"), ["SELECT 1"]);
+        assert!(split_statements("  ").is_empty());
+    }
+
+    #[test]
+    fn allows_only_reads() {
+        assert!(is_read_only("SELECT * FROM t"));
+        assert!(is_read_only("-- note\n(SELECT 1)"));
+        assert!(is_read_only("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(is_read_only("SELECT 'DROP TABLE t', \"update\" FROM t -- delete"));
+        assert!(is_read_only("PRAGMA table_info('t')"));
+
+        assert!(!is_read_only("WITH x AS (DELETE FROM t RETURNING *) SELECT * FROM x"));
+        assert!(!is_read_only("EXPLAIN ANALYZE INSERT INTO t VALUES (1)"));
+        assert!(!is_read_only("SELECT * FROM t; DROP TABLE t"));
+        assert!(!is_read_only("PRAGMA threads = 1"));
+        assert!(!is_read_only("PRAGMA enable_checkpoint_on_shutdown"));
+        assert!(!is_read_only("CREATE TABLE u AS SELECT * FROM t"));
+        assert!(!is_read_only(""));
+    }
+}
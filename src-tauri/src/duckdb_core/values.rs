@@ -0,0 +1,245 @@
+use duckdb::arrow::array::{Array, AsArray};
+use duckdb::arrow::datatypes::*;
+use duckdb::arrow::util::display::{ArrayFormatter, FormatOptions};
+use duckdb::types::{Value, ValueRef};
+use serde_json::{json, Map, Number, Value as Json};
+use super::{NumberMode, ValueEncoding};
+
+/// How values of a column of `data_type` come out of `json_value`
+pub fn value_encoding(data_type: &str, mode: NumberMode) -> ValueEncoding {
+    let upper = data_type.to_uppercase();
+    match upper.as_str() {
+        "HUGEINT" | "UHUGEINT" => ValueEncoding::IntegerString,
+        "BIGINT" | "UBIGINT" if mode == NumberMode::Exact => ValueEncoding::IntegerString,
+        _ if upper.starts_with("DECIMAL") && !super::is_list_type(&upper) => ValueEncoding::DecimalString,
+        _ => ValueEncoding::Native,
+    }
+}
+
+/// Convert a DuckDB value to typed JSON for the frontend. Dates, times and
+/// timestamps become ISO 8601 strings; decimals and 128-bit integers become
+/// strings so no precision is lost in JavaScript, and so do 64-bit integers
+/// in `Exact` mode; lists, arrays, structs and maps become JSON arrays and
+/// objects.
+pub fn json_value(value: ValueRef, mode: NumberMode) -> Json {
+    match value {
+        ValueRef::Null => Json::Null,
+        ValueRef::Boolean(b) => Json::Bool(b),
+        ValueRef::TinyInt(i) => Json::Number(i.into()),
+        ValueRef::SmallInt(i) => Json::Number(i.into()),
+        ValueRef::Int(i) => Json::Number(i.into()),
+        ValueRef::BigInt(i) => wide_integer(i, mode),
+        ValueRef::UTinyInt(i) => Json::Number(i.into()),
+        ValueRef::USmallInt(i) => Json::Number(i.into()),
+        ValueRef::UInt(i) => Json::Number(i.into()),
+        ValueRef::UBigInt(i) => wide_integer(i, mode),
+        ValueRef::Float(f) => float(f as f64),
+        ValueRef::Double(f) => float(f),
+        ValueRef::Text(s) => Json::String(String::from_utf8_lossy(s).to_string()),
+        // Nested and less common types go through the owned value
+        other => owned_json(other.to_owned(), mode),
+    }
+}
+
+fn owned_json(value: Value, mode: NumberMode) -> Json {
+    match value {
+        Value::Null => Json::Null,
+        Value::Boolean(b) => Json::Bool(b),
+        Value::TinyInt(i) => Json::Number(i.into()),
+        Value::SmallInt(i) => Json::Number(i.into()),
+        Value::Int(i) => Json::Number(i.into()),
+        Value::BigInt(i) => wide_integer(i, mode),
+        Value::HugeInt(i) => Json::String(i.to_string()),
+        Value::UTinyInt(i) => Json::Number(i.into()),
+        Value::USmallInt(i) => Json::Number(i.into()),
+        Value::UInt(i) => Json::Number(i.into()),
+        Value::UBigInt(i) => wide_integer(i, mode),
+        Value::Float(f) => float(f as f64),
+        Value::Double(f) => float(f),
+        Value::Decimal(d) => Json::String(d.to_string()),
+        Value::Text(s) | Value::Enum(s) => Json::String(s),
+        Value::Blob(bytes) => Json::String(hex(&bytes)),
+        Value::Date32(days) => Json::String(date(days as i64)),
+        Value::Time64(unit, t) => Json::String(time_of_day(unit.to_micros(t))),
+        Value::Timestamp(unit, t) => Json::String(timestamp(unit.to_micros(t))),
+        Value::Interval { months, days, nanos } => json!({ "months": months, "days": days, "nanos": nanos }),
+        Value::List(items) | Value::Array(items) => {
+            Json::Array(items.into_iter().map(|item| owned_json(item, mode)).collect())
+        }
+        Value::Struct(fields) => Json::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), owned_json(value.clone(), mode)))
+                .collect::<Map<_, _>>(),
+        ),
+        // Map keys can be any type, so keep maps as key/value pairs
+        Value::Map(entries) => Json::Array(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    json!({ "key": owned_json(key.clone(), mode), "value": owned_json(value.clone(), mode) })
+                })
+                .collect(),
+        ),
+        Value::Union(value) => owned_json(*value, mode),
+    }
+}
+
+/// Convert a value of an Arrow column to JSON the same way as `json_value`,
+/// for results streamed as record batches
+pub fn arrow_json_value(array: &dyn Array, row: usize, mode: NumberMode) -> Json {
+    if array.is_null(row) {
+        return Json::Null;
+    }
+    match array.data_type() {
+        DataType::Null => Json::Null,
+        DataType::Boolean => Json::Bool(array.as_boolean().value(row)),
+        DataType::Int8 => Json::Number(array.as_primitive::<Int8Type>().value(row).into()),
+        DataType::Int16 => Json::Number(array.as_primitive::<Int16Type>().value(row).into()),
+        DataType::Int32 => Json::Number(array.as_primitive::<Int32Type>().value(row).into()),
+        DataType::Int64 => wide_integer(array.as_primitive::<Int64Type>().value(row), mode),
+        DataType::UInt8 => Json::Number(array.as_primitive::<UInt8Type>().value(row).into()),
+        DataType::UInt16 => Json::Number(array.as_primitive::<UInt16Type>().value(row).into()),
+        DataType::UInt32 => Json::Number(array.as_primitive::<UInt32Type>().value(row).into()),
+        DataType::UInt64 => wide_integer(array.as_primitive::<UInt64Type>().value(row), mode),
+        DataType::Float16 => float(array.as_primitive::<Float16Type>().value(row).to_f64()),
+        DataType::Float32 => float(array.as_primitive::<Float32Type>().value(row) as f64),
+        DataType::Float64 => float(array.as_primitive::<Float64Type>().value(row)),
+        // DuckDB hands 128-bit integers over as DECIMAL(38, 0)
+        DataType::Decimal128(..) => Json::String(array.as_primitive::<Decimal128Type>().value_as_string(row)),
+        DataType::Decimal256(..) => Json::String(array.as_primitive::<Decimal256Type>().value_as_string(row)),
+        DataType::Utf8 => Json::String(array.as_string::<i32>().value(row).to_string()),
+        DataType::LargeUtf8 => Json::String(array.as_string::<i64>().value(row).to_string()),
+        DataType::Binary => Json::String(hex(array.as_binary::<i32>().value(row))),
+        DataType::LargeBinary => Json::String(hex(array.as_binary::<i64>().value(row))),
+        DataType::FixedSizeBinary(_) => Json::String(hex(array.as_fixed_size_binary().value(row))),
+        DataType::Date32 => Json::String(date(array.as_primitive::<Date32Type>().value(row) as i64)),
+        DataType::Date64 => Json::String(date(array.as_primitive::<Date64Type>().value(row).div_euclid(86_400_000))),
+        DataType::Time32(TimeUnit::Second) => {
+            Json::String(time_of_day(array.as_primitive::<Time32SecondType>().value(row) as i64 * 1_000_000))
+        }
+        DataType::Time32(_) => {
+            Json::String(time_of_day(array.as_primitive::<Time32MillisecondType>().value(row) as i64 * 1_000))
+        }
+        DataType::Time64(TimeUnit::Nanosecond) => {
+            Json::String(time_of_day(array.as_primitive::<Time64NanosecondType>().value(row) / 1_000))
+        }
+        DataType::Time64(_) => Json::String(time_of_day(array.as_primitive::<Time64MicrosecondType>().value(row))),
+        DataType::Timestamp(unit, _) => {
+            let micros = match unit {
+                TimeUnit::Second => array.as_primitive::<TimestampSecondType>().value(row) * 1_000_000,
+                TimeUnit::Millisecond => array.as_primitive::<TimestampMillisecondType>().value(row) * 1_000,
+                TimeUnit::Microsecond => array.as_primitive::<TimestampMicrosecondType>().value(row),
+                TimeUnit::Nanosecond => array.as_primitive::<TimestampNanosecondType>().value(row) / 1_000,
+            };
+            Json::String(timestamp(micros))
+        }
+        DataType::Interval(IntervalUnit::MonthDayNano) => {
+            let interval = array.as_primitive::<IntervalMonthDayNanoType>().value(row);
+            json!({ "months": interval.months, "days": interval.days, "nanos": interval.nanoseconds })
+        }
+        DataType::List(_) => items(array.as_list::<i32>().value(row).as_ref(), mode),
+        DataType::LargeList(_) => items(array.as_list::<i64>().value(row).as_ref(), mode),
+        DataType::FixedSizeList(..) => items(array.as_fixed_size_list().value(row).as_ref(), mode),
+        DataType::Struct(_) => {
+            let fields = array.as_struct();
+            Json::Object(
+                fields
+                    .column_names()
+                    .into_iter()
+                    .zip(fields.columns())
+                    .map(|(name, column)| (name.to_string(), arrow_json_value(column.as_ref(), row, mode)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        // Map keys can be any type, so keep maps as key/value pairs
+        DataType::Map(..) => {
+            let entries = array.as_map().value(row);
+            Json::Array(
+                (0..entries.len())
+                    .map(|i| {
+                        json!({
+                            "key": arrow_json_value(entries.column(0).as_ref(), i, mode),
+                            "value": arrow_json_value(entries.column(1).as_ref(), i, mode),
+                        })
+                    })
+                    .collect(),
+            )
+        }
+        // Enums arrive as dictionaries
+        DataType::Dictionary(..) => {
+            let dictionary = array.as_any_dictionary();
+            match arrow_json_value(dictionary.keys(), row, NumberMode::Json).as_u64() {
+                Some(key) => arrow_json_value(dictionary.values().as_ref(), key as usize, mode),
+                None => Json::Null,
+            }
+        }
+        // Anything rarer goes out the way Arrow prints it
+        _ => ArrayFormatter::try_new(array, &FormatOptions::default())
+            .map(|formatter| Json::String(formatter.value(row).to_string()))
+            .unwrap_or(Json::Null),
+    }
+}
+
+/// Values of a list as a JSON array
+fn items(list: &dyn Array, mode: NumberMode) -> Json {
+    Json::Array((0..list.len()).map(|i| arrow_json_value(list, i, mode)).collect())
+}
+
+/// 64-bit integers as numbers, or as strings of digits in `Exact` mode
+fn wide_integer<T: Into<Number> + ToString>(i: T, mode: NumberMode) -> Json {
+    match mode {
+        NumberMode::Json => Json::Number(i.into()),
+        NumberMode::Exact => Json::String(i.to_string()),
+    }
+}
+
+/// NaN and infinity have no JSON number, so they become null
+fn float(f: f64) -> Json {
+    Number::from_f64(f).map(Json::Number).unwrap_or(Json::Null)
+}
+
+/// Blobs as `\x`-prefixed hex, like DuckDB prints them
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("\\x");
+    for byte in bytes {
+        out.push_str(&format!("{:02X}", byte));
+    }
+    out
+}
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Days since 1970-01-01 as `YYYY-MM-DD` in the proleptic Gregorian calendar
+fn date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Microseconds since midnight as `HH:MM:SS`, with fractional seconds when set
+fn time_of_day(micros: i64) -> String {
+    let seconds = micros / 1_000_000;
+    let fraction = micros % 1_000_000;
+    let clock = format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if fraction == 0 {
+        clock
+    } else {
+        format!("{}.{:06}", clock, fraction)
+    }
+}
+
+/// Microseconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SS[.ffffff]`
+fn timestamp(micros: i64) -> String {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    format!("{}T{}", date(days), time_of_day(micros.rem_euclid(MICROS_PER_DAY)))
+}
+
@@ -1,6 +1,18 @@
+use duckdb::params_from_iter;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use serde_json::{Map, Value as Json};
+use tauri::AppHandle;
+use crate::duckdb_core::{
+    ensure_row_ids, is_list_type, is_single_statement, is_type_name, quote_ident, rebuild_table, sql_literal,
+    table_exists, table_info, TableInfo, INTERNAL_TABLE_PREFIX, ROW_ID_COLUMN,
+};
+use crate::error::{AppError, ErrorContext};
 use crate::AppState;
+use crate::statistics::{bind_value, build_where_clause, sql_value, FilterCondition};
+
+mod undo;
+
+pub use undo::{UndoEntry, UndoJournal, UndoStack};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReorderResult {
@@ -8,6 +20,152 @@ pub struct ReorderResult {
     pub message: String,
 }
 
+/// Outcome of `insert_rows` and `delete_rows`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowChangeResult {
+    pub rows_affected: usize,
+    pub row_ids: Vec<i64>, // Ids of the inserted rows; empty for deletes
+    pub message: String,
+}
+
+/// What `cast_column` does when some values don't convert
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CastOnError {
+    #[default]
+    Abort, // Leave the column as it is and report the failures
+    Null,  // Convert anyway, leaving NULL where a value doesn't convert
+}
+
+/// Outcome of `cast_column`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastResult {
+    pub converted: bool,              // False when aborted because values failed
+    pub failed_count: usize,          // Non-empty values that don't convert
+    pub failed_samples: Vec<String>,  // A few of them, for the message in the UI
+    pub review_table: Option<String>, // Table the failing rows were written to
+    pub message: String,
+}
+
+/// How `find_replace` reads its pattern
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Literal,
+    Regex, // RE2 syntax; the replacement can use \1 for groups
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnMatches {
+    pub column: String,
+    pub rows: usize,    // Rows with at least one match in this column
+    pub matches: usize, // Occurrences, counting several in one value
+}
+
+/// A value before and after replacement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacePreview {
+    pub row_id: i64,
+    pub column: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Outcome of `find_replace`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindReplaceResult {
+    pub columns: Vec<ColumnMatches>, // Columns with matches only
+    pub match_count: usize,
+    pub replaced_cells: usize, // 0 for a dry run
+    pub preview: Vec<ReplacePreview>,
+    pub dry_run: bool,
+    pub message: String,
+}
+
+/// Cleanup applied by `transform_column`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextTransform {
+    Trim,
+    Upper,
+    Lower,
+    TitleCase,          // First letter of each space-separated word upper case, the rest lower
+    CollapseWhitespace, // Runs of spaces, tabs and newlines to one space, trimmed
+    StripNonNumeric,    // Keep digits, '.' and '-'
+    NormalizeUnicode,   // NFC, so composed and decomposed accents compare equal
+}
+
+impl TextTransform {
+    /// SQL computing the transformed value of `column`
+    fn sql(self, column: &str) -> String {
+        match self {
+            TextTransform::Trim => format!("trim({})", column),
+            TextTransform::Upper => format!("upper({})", column),
+            TextTransform::Lower => format!("lower({})", column),
+            TextTransform::TitleCase => format!(
+                "array_to_string(list_transform(string_split(lower({}), ' '), w -> upper(w[1]) || w[2:]), ' ')",
+                column
+            ),
+            TextTransform::CollapseWhitespace => format!("trim(regexp_replace({}, '\\s+', ' ', 'g'))", column),
+            TextTransform::StripNonNumeric => format!("regexp_replace({}, '[^0-9.-]', '', 'g')", column),
+            TextTransform::NormalizeUnicode => format!("nfc_normalize({})", column),
+        }
+    }
+}
+
+/// Outcome of `transform_column`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformResult {
+    pub changed_count: usize,
+    pub message: String,
+}
+
+/// How `fill_nulls` picks the value for a NULL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillStrategy {
+    Constant, // The given value
+    Forward,  // The last non-NULL value above, in table order
+    Backward, // The next non-NULL value below
+    Mean,
+    Median,
+    Mode, // Most common value
+}
+
+/// Outcome of `fill_nulls`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillResult {
+    pub filled_count: usize,
+    pub remaining_nulls: usize, // E.g. leading NULLs with nothing above to fill forward from
+    pub message: String,
+}
+
+/// Which of a set of duplicate rows `deduplicate` keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeepRow {
+    #[default]
+    First, // In table order
+    Last,
+}
+
+/// Outcome of `deduplicate`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeduplicateResult {
+    pub removed_count: usize,
+    pub duplicates_table: Option<String>, // Where the removed rows were saved
+    pub message: String,
+}
+
+/// Outcome of `split_column`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitResult {
+    pub table: TableInfo,
+    pub overflow_rows: usize, // Rows with more parts than new columns; the extra parts are left out
+    pub message: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SortColumn {
     pub column: String,
@@ -16,15 +174,23 @@ pub struct SortColumn {
 
 #[tauri::command(rename_all = "camelCase")]
 pub async fn reorder_rows(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
     sort_columns: Vec<SortColumn>,
-) -> Result<ReorderResult, String> {
+) -> Result<ReorderResult, AppError> {
     if sort_columns.is_empty() {
-        return Err("No sort columns specified".to_string());
+        return Err(AppError::invalid_input("No sort columns specified"));
     }
 
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    crate::run_blocking(app, move |state| run_reorder_rows(&state, table_name, sort_columns)).await
+}
+
+fn run_reorder_rows(
+    state: &AppState,
+    table_name: String,
+    sort_columns: Vec<SortColumn>,
+) -> Result<ReorderResult, AppError> {
+    let db = state.db.lock()?;
     let conn = db.get_connection();
 
     // Build ORDER BY clause
@@ -32,39 +198,1636 @@ pub async fn reorder_rows(
         .iter()
         .map(|sc| {
             let direction = if sc.ascending { "ASC" } else { "DESC" };
-            format!("\"{}\" {}", sc.column, direction)
+            format!("{} {}", quote_ident(&sc.column), direction)
         })
         .collect();
 
     let order_by_clause = order_by_parts.join(", ");
 
-    // Create a new table with sorted data using DuckDB
-    let temp_table = format!("{}_sorted_temp", table_name);
+    // Rebuild the table sorted; undo keeps the order it had
+    state.undo.record(conn, &table_name, "reorder_rows", |recorder| {
+        recorder.save_order()?;
+        rebuild_table(
+            conn,
+            &table_name,
+            &format!("SELECT * FROM {} ORDER BY {}", quote_ident(&table_name), order_by_clause),
+        )
+    })?;
+
+    Ok(ReorderResult {
+        success: true,
+        message: format!("Rows reordered by {} column(s)", sort_columns.len()),
+    })
+}
+
+/// Highest row id in use in a table that has row ids
+fn highest_row_id(conn: &duckdb::Connection, table: &str) -> duckdb::Result<i64> {
+    conn.query_row(
+        &format!("SELECT COALESCE(MAX({}), 0) FROM {}", quote_ident(ROW_ID_COLUMN), table),
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Insert rows given as column name to value maps; columns left out are
+/// NULL (or their default). Rows are added at the end, or before the row
+/// now at 0-based `position` in the table's order. Returns the new rows'
+/// ids.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn insert_rows(
+    app: AppHandle,
+    table_name: String,
+    rows: Vec<Map<String, Json>>,
+    position: Option<usize>,
+) -> Result<RowChangeResult, AppError> {
+    if rows.is_empty() {
+        return Err(AppError::invalid_input("No rows to insert"));
+    }
+
+    crate::run_blocking(app, move |state| run_insert_rows(&state, table_name, rows, position)).await
+}
+
+fn run_insert_rows(
+    state: &AppState,
+    table_name: String,
+    rows: Vec<Map<String, Json>>,
+    position: Option<usize>,
+) -> Result<RowChangeResult, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    let table = quote_ident(&table_name);
+
+    let inserted = state.undo.record(conn, &table_name, "insert_rows", |recorder| {
+        let mut insert = || {
+            let highest = highest_row_id(conn, &table)?;
+
+            for row in &rows {
+                // Ids are assigned below, never taken from the caller
+                let values: Vec<(&String, &Json)> = row.iter().filter(|(name, _)| *name != ROW_ID_COLUMN).collect();
+                if values.is_empty() {
+                    conn.execute(&format!("INSERT INTO {} DEFAULT VALUES", table), [])?;
+                    continue;
+                }
+                let columns = values.iter().map(|(name, _)| quote_ident(name)).collect::<Vec<_>>().join(", ");
+                let placeholders = vec!["?"; values.len()].join(", ");
+                conn.execute(
+                    &format!("INSERT INTO {} ({}) VALUES ({})", table, columns, placeholders),
+                    params_from_iter(values.iter().map(|(_, value)| bind_value(value))),
+                )?;
+            }
+            ensure_row_ids(conn, &table_name)?;
+
+            if let Some(position) = position {
+                // Rebuild the table with the new rows (appended last, so the
+                // existing rows keep their positions) moved before `position`
+                rebuild_table(
+                    conn,
+                    &table_name,
+                    &format!(
+                        "SELECT * EXCLUDE (_rats_new, _rats_pos) FROM (
+                            SELECT *, {id} > {highest} AS _rats_new,
+                                   CASE WHEN {id} > {highest} THEN {position}
+                                        ELSE row_number() OVER (ORDER BY rowid) - 1 END AS _rats_pos
+                            FROM {})
+                         ORDER BY _rats_pos, _rats_new DESC, {id}",
+                        table,
+                        id = quote_ident(ROW_ID_COLUMN),
+                        highest = highest,
+                        position = position
+                    ),
+                )?;
+            }
+
+            let new_rows = format!("WHERE {} > ?", quote_ident(ROW_ID_COLUMN));
+            recorder.added_rows(&new_rows, [highest])?;
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {id} FROM {} {} ORDER BY {id}",
+                table,
+                new_rows,
+                id = quote_ident(ROW_ID_COLUMN)
+            ))?;
+            let ids = stmt
+                .query_map([highest], |row| row.get(0))?
+                .collect::<Result<Vec<i64>, _>>()?;
+            Ok::<_, AppError>(ids)
+        };
+        insert().with_context(|| format!("Failed to insert rows into {}", table_name))
+    })?;
+
+    state.row_counts.clear();
+    state.query_cache.clear();
+
+    Ok(RowChangeResult {
+        rows_affected: inserted.len(),
+        message: format!("Inserted {} row(s) into {}", inserted.len(), table_name),
+        row_ids: inserted,
+    })
+}
+
+/// Delete rows by id (`row_ids` from `query_data`) or all rows matching
+/// `conditions`. Give exactly one of the two.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn delete_rows(
+    app: AppHandle,
+    table_name: String,
+    row_ids: Option<Vec<i64>>,
+    conditions: Option<Vec<FilterCondition>>,
+) -> Result<RowChangeResult, AppError> {
+    crate::run_blocking(app, move |state| run_delete_rows(&state, table_name, row_ids, conditions)).await
+}
+
+fn run_delete_rows(
+    state: &AppState,
+    table_name: String,
+    row_ids: Option<Vec<i64>>,
+    conditions: Option<Vec<FilterCondition>>,
+) -> Result<RowChangeResult, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+
+    let mut params = Vec::new();
+    let where_clause = match (&row_ids, &conditions) {
+        (Some(ids), None) if ids.is_empty() => return Err(AppError::invalid_input("No rows selected")),
+        (Some(ids), None) => {
+            params.extend(ids.iter().map(|id| duckdb::types::Value::BigInt(*id)));
+            format!("WHERE {} IN ({})", quote_ident(ROW_ID_COLUMN), vec!["?"; ids.len()].join(", "))
+        }
+        // Deleting everything by accident is too easy with an empty filter
+        (None, Some(conditions)) if conditions.is_empty() => {
+            return Err(AppError::invalid_input("Give at least one condition to delete rows"))
+        }
+        (None, Some(conditions)) => build_where_clause(conditions, |value| {
+            params.push(bind_value(value));
+            "?".to_string()
+        })?,
+        _ => return Err(AppError::invalid_input("Delete either rows by id or rows matching conditions")),
+    };
+
+    let deleted = state.undo.record(conn, &table_name, "delete_rows", |recorder| {
+        recorder
+            .delete_rows(&where_clause, params_from_iter(params))
+            .with_context(|| format!("Failed to delete rows from {}", table_name))
+    })?;
+
+    // Deletes keep a table's stored size, so cached counts can't tell
+    state.row_counts.clear();
+    state.query_cache.clear();
+
+    Ok(RowChangeResult {
+        rows_affected: deleted,
+        row_ids: Vec::new(),
+        message: format!("Deleted {} row(s) from {}", deleted, table_name),
+    })
+}
+
+/// A trimmed column name, rejecting empty names and the `_rats_` prefix the
+/// app keeps for its own columns, like the hidden row id
+fn user_column_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Column name cannot be empty"));
+    }
+    if name.starts_with(INTERNAL_TABLE_PREFIX) {
+        return Err(AppError::invalid_input(format!(
+            "Column names starting with {} are reserved",
+            INTERNAL_TABLE_PREFIX
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Add a column of `data_type` (e.g. `VARCHAR` or `DECIMAL(18, 2)`). Existing
+/// rows get `default_value`, which also applies to rows inserted later, or
+/// NULL without one. Returns the table's new layout.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn add_column(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    data_type: String,
+    default_value: Option<Json>,
+) -> Result<TableInfo, AppError> {
+    let column_name = user_column_name(&column_name)?;
+    if !is_type_name(&data_type) {
+        return Err(AppError::invalid_input(format!("Invalid column type: {}", data_type)));
+    }
+
+    crate::run_blocking(app, move |state| {
+        run_add_column(&state, table_name, column_name, data_type, default_value)
+    })
+    .await
+}
+
+fn run_add_column(
+    state: &AppState,
+    table_name: String,
+    column_name: String,
+    data_type: String,
+    default_value: Option<Json>,
+) -> Result<TableInfo, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+
+    let default_clause = match &default_value {
+        Some(value) if !value.is_null() => format!(" DEFAULT CAST({} AS {})", sql_value(value), data_type),
+        _ => String::new(),
+    };
+    state.undo.record(conn, &table_name, "add_column", |recorder| {
+        // DuckDB won't prepare ADD COLUMN with a DEFAULT, so run it as a batch
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}{}",
+            quote_ident(&table_name),
+            quote_ident(&column_name),
+            data_type,
+            default_clause
+        ))
+        .and_then(|_| recorder.added_default_column(&column_name))
+        .with_context(|| format!("Failed to add column {}", column_name))
+    })?;
+
+    state.query_cache.clear();
+    table_info(conn, &table_name).map_err(AppError::from)
+}
+
+/// Drop a column and its data dictionary entry. Returns the table's new
+/// layout.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn drop_column(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+) -> Result<TableInfo, AppError> {
+    let column_name = user_column_name(&column_name)?;
+
+    crate::run_blocking(app, move |state| run_drop_column(&state, table_name, column_name)).await
+}
+
+fn run_drop_column(state: &AppState, table_name: String, column_name: String) -> Result<TableInfo, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+
+    state.undo.record(conn, &table_name, "drop_column", |recorder| {
+        recorder
+            .save_columns(std::slice::from_ref(&column_name))
+            .and_then(|_| {
+                conn.execute(
+                    &format!("ALTER TABLE {} DROP COLUMN {}", quote_ident(&table_name), quote_ident(&column_name)),
+                    [],
+                )
+            })
+            .with_context(|| format!("Failed to drop column {}", column_name))
+    })?;
+
+    let _ = crate::dictionary::remove_column_metadata(conn, &table_name, &column_name);
+    state.query_cache.clear();
+    table_info(conn, &table_name).map_err(AppError::from)
+}
+
+/// Rename a column, carrying its data dictionary entry over. Returns the
+/// table's new layout.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn rename_column(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    new_name: String,
+) -> Result<TableInfo, AppError> {
+    let column_name = user_column_name(&column_name)?;
+    let new_name = user_column_name(&new_name)?;
+
+    crate::run_blocking(app, move |state| run_rename_column(&state, table_name, column_name, new_name)).await
+}
+
+fn run_rename_column(
+    state: &AppState,
+    table_name: String,
+    column_name: String,
+    new_name: String,
+) -> Result<TableInfo, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+
+    state.undo.record(conn, &table_name, "rename_column", |recorder| {
+        recorder.renamed_column(&column_name, &new_name);
+        conn.execute(
+            &format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {}",
+                quote_ident(&table_name),
+                quote_ident(&column_name),
+                quote_ident(&new_name)
+            ),
+            [],
+        )
+        .with_context(|| format!("Failed to rename column {}", column_name))
+    })?;
 
-    // Drop temp table if exists
-    let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", temp_table), []);
+    let _ = crate::dictionary::rename_column_metadata(conn, &table_name, &column_name, &new_name);
+    // A rename keeps the column count and size the cache checks
+    state.query_cache.clear();
+    table_info(conn, &table_name).map_err(AppError::from)
+}
+
+/// Failing values shown in a `cast_column` result
+const CAST_FAILURE_SAMPLES: usize = 10;
+
+/// Declared type of a column, or None when the table or column is missing
+fn column_type(conn: &duckdb::Connection, table_name: &str, column_name: &str) -> duckdb::Result<Option<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT data_type FROM information_schema.columns
+         WHERE table_catalog = current_database() AND table_name = ? AND column_name = ?",
+    )?;
+    let mut rows = stmt.query([table_name, column_name])?;
+    rows.next()?.map(|row| row.get(0)).transpose()
+}
+
+/// Convert a column to `target_type` in place. Values that don't convert
+/// are counted first; with `on_error` `abort` (the default) any failure
+/// leaves the column unchanged, with `null` they become NULL. Text columns
+/// treat blank values as NULL rather than failures, which suits columns
+/// imported as all-VARCHAR from Excel. With `review_table`, the failing
+/// rows' ids and values are written to that table as `row_id, value`, to
+/// look over afterwards; an existing table of that name is only replaced
+/// with `replace_review_table`. Undoing the conversion drops it again.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn cast_column(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    target_type: String,
+    on_error: Option<CastOnError>,
+    review_table: Option<String>,
+    replace_review_table: Option<bool>,
+) -> Result<CastResult, AppError> {
+    let column_name = user_column_name(&column_name)?;
+    if !is_type_name(&target_type) {
+        return Err(AppError::invalid_input(format!("Invalid column type: {}", target_type)));
+    }
+    let review_table = review_table.map(|name| name.trim().to_string());
+    match &review_table {
+        Some(name) if name.is_empty() => return Err(AppError::invalid_input("Table name cannot be empty")),
+        Some(name) if name.starts_with(INTERNAL_TABLE_PREFIX) => {
+            return Err(AppError::invalid_input(format!(
+                "Table names starting with {} are reserved",
+                INTERNAL_TABLE_PREFIX
+            )))
+        }
+        // DuckDB matches table names case-insensitively
+        Some(name) if name.to_lowercase() == table_name.to_lowercase() => {
+            return Err(AppError::invalid_input("The review table can't be the table being converted"))
+        }
+        _ => {}
+    }
+    let on_error = on_error.unwrap_or_default();
+    let replace_review_table = replace_review_table.unwrap_or(false);
+
+    crate::run_blocking(app, move |state| {
+        run_cast_column(&state, table_name, column_name, target_type, on_error, review_table, replace_review_table)
+    })
+    .await
+}
+
+fn run_cast_column(
+    state: &AppState,
+    table_name: String,
+    column_name: String,
+    target_type: String,
+    on_error: CastOnError,
+    review_table: Option<String>,
+    replace_review_table: bool,
+) -> Result<CastResult, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    if let Some(review) = &review_table {
+        if !replace_review_table && table_exists(conn, review)? {
+            return Err(AppError::invalid_input(format!(
+                "Table {} already exists; pass replaceReviewTable to replace it",
+                review
+            )));
+        }
+    }
+    let table = quote_ident(&table_name);
+    let column = quote_ident(&column_name);
+
+    let source_type = column_type(conn, &table_name, &column_name)?
+        .ok_or_else(|| AppError::not_found(format!("Column {} not found in {}", column_name, table_name)))?;
+    let value = if source_type == "VARCHAR" {
+        format!("NULLIF(trim({}), '')", column)
+    } else {
+        column.clone()
+    };
+    let converted_value = format!("TRY_CAST({} AS {})", value, target_type);
+    let failed = format!("{} IS NOT NULL AND {} IS NULL", value, converted_value);
+
+    // Rows get ids as the change is recorded, which the review table
+    // points back at
+    let result = state.undo.record(conn, &table_name, "cast_column", |recorder| {
+        let mut cast = || {
+            let failed_count: usize =
+                conn.query_row(&format!("SELECT COUNT(*) FROM {} WHERE {}", table, failed), [], |row| row.get(0))?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT DISTINCT CAST({} AS VARCHAR) FROM {} WHERE {} LIMIT {}",
+                column, table, failed, CAST_FAILURE_SAMPLES
+            ))?;
+            let failed_samples = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+
+            if let Some(review) = &review_table {
+                recorder.save_table(review)?;
+                conn.execute(
+                    &format!(
+                        "CREATE OR REPLACE TABLE {} AS
+                         SELECT {id} AS row_id, {} AS value FROM {} WHERE {} ORDER BY {id}",
+                        quote_ident(review),
+                        column,
+                        table,
+                        failed,
+                        id = quote_ident(ROW_ID_COLUMN)
+                    ),
+                    [],
+                )?;
+            }
+
+            let converted = failed_count == 0 || on_error == CastOnError::Null;
+            if converted {
+                recorder.save_values(std::slice::from_ref(&column_name), &format!("WHERE {} IS NOT NULL", column))?;
+                conn.execute(
+                    &format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}",
+                        table, column, target_type, converted_value
+                    ),
+                    [],
+                )?;
+            } else {
+                recorder.skip();
+            }
+
+            let message = match (converted, failed_count) {
+                (true, 0) => format!("Converted {} to {}", column_name, target_type),
+                (true, n) => format!("Converted {} to {}; {} value(s) set to NULL", column_name, target_type, n),
+                (false, n) => format!(
+                    "{} value(s) in {} don't convert to {}; column left unchanged",
+                    n, column_name, target_type
+                ),
+            };
+            Ok::<_, AppError>(CastResult {
+                converted,
+                failed_count,
+                failed_samples,
+                review_table: review_table.clone(),
+                message,
+            })
+        };
+        cast().with_context(|| format!("Failed to convert {}", column_name))
+    })?;
+
+    state.query_cache.clear();
+    Ok(result)
+}
+
+/// Values shown in a `find_replace` preview
+const REPLACE_PREVIEW_ROWS: usize = 100;
+
+/// Text columns of a table, the hidden row id excluded
+fn text_columns(conn: &duckdb::Connection, table_name: &str) -> duckdb::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT column_name FROM information_schema.columns
+         WHERE table_catalog = current_database() AND table_name = ? AND data_type = 'VARCHAR'
+           AND column_name <> ?
+         ORDER BY ordinal_position",
+    )?;
+    let columns = stmt
+        .query_map([table_name, ROW_ID_COLUMN], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns)
+}
+
+/// Escape text so RE2 matches it literally
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Find `pattern` in text columns (all of them unless `columns` are given)
+/// and replace every occurrence with `replacement`. Counts matches per
+/// column and previews the first changes by row id; with `dry_run` nothing
+/// is changed.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn find_replace(
+    app: AppHandle,
+    table_name: String,
+    columns: Option<Vec<String>>,
+    pattern: String,
+    replacement: String,
+    mode: Option<MatchMode>,
+    case_sensitive: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<FindReplaceResult, AppError> {
+    if pattern.is_empty() {
+        return Err(AppError::invalid_input("Search pattern cannot be empty"));
+    }
+    let dry_run = dry_run.unwrap_or(false);
+
+    // Literal text goes through the same regex functions, escaped
+    let (regex, replacement) = match mode.unwrap_or_default() {
+        MatchMode::Literal => (regex_escape(&pattern), replacement.replace('\\', "\\\\")),
+        MatchMode::Regex => (pattern, replacement),
+    };
+    let options = if case_sensitive.unwrap_or(true) { "c" } else { "i" };
+    let regex = sql_literal(&regex);
+    let replacement = sql_literal(&replacement);
+
+    crate::run_blocking(app, move |state| {
+        run_find_replace(&state, table_name, columns, regex, replacement, options, dry_run)
+    })
+    .await
+}
+
+fn run_find_replace(
+    state: &AppState,
+    table_name: String,
+    columns: Option<Vec<String>>,
+    regex: String,
+    replacement: String,
+    options: &str,
+    dry_run: bool,
+) -> Result<FindReplaceResult, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    let table = quote_ident(&table_name);
+
+    let text = text_columns(conn, &table_name)?;
+    let columns = match columns {
+        Some(columns) if columns.is_empty() => return Err(AppError::invalid_input("No columns selected")),
+        Some(columns) => {
+            if let Some(column) = columns.iter().find(|c| !text.contains(c)) {
+                return Err(AppError::invalid_input(format!(
+                    "Find and replace can only search text columns; {} is not one",
+                    column
+                )));
+            }
+            columns
+        }
+        None => text,
+    };
+    ensure_row_ids(conn, &table_name)?;
+
+    let found = |column: &str| format!("regexp_matches({}, {}, '{}')", column, regex, options);
+    let search = || {
+        let mut matches = Vec::new();
+        let mut preview = Vec::new();
+        for column_name in &columns {
+            let column = quote_ident(column_name);
+            let matched = found(&column);
+            let replaced = format!("regexp_replace({}, {}, {}, 'g{}')", column, regex, replacement, options);
+
+            let (rows, count): (usize, Option<i64>) = conn.query_row(
+                &format!(
+                    "SELECT COUNT(*), SUM(len(regexp_extract_all({}, {}, 0, '{}'))) FROM {} WHERE {}",
+                    column, regex, options, table, matched
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            if rows == 0 {
+                continue;
+            }
+            matches.push(ColumnMatches {
+                column: column_name.clone(),
+                rows,
+                matches: count.unwrap_or(0).max(0) as usize,
+            });
+
+            let remaining = REPLACE_PREVIEW_ROWS.saturating_sub(preview.len());
+            if remaining > 0 {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {id}, {}, {} FROM {} WHERE {} ORDER BY {id} LIMIT {}",
+                    column,
+                    replaced,
+                    table,
+                    matched,
+                    remaining,
+                    id = quote_ident(ROW_ID_COLUMN)
+                ))?;
+                let rows = stmt.query_map([], |row| {
+                    Ok(ReplacePreview {
+                        row_id: row.get(0)?,
+                        column: column_name.clone(),
+                        before: row.get(1)?,
+                        after: row.get(2)?,
+                    })
+                })?;
+                for row in rows {
+                    preview.push(row?);
+                }
+            }
+        }
+
+        Ok::<_, duckdb::Error>((matches, preview))
+    };
+    let (matches, preview) = search().with_context(|| format!("Failed to search {}", table_name))?;
+
+    let mut replaced_cells = 0;
+    if !dry_run && !matches.is_empty() {
+        let changed: Vec<String> = matches.iter().map(|c| c.column.clone()).collect();
+        let filter = changed.iter().map(|c| found(&quote_ident(c))).collect::<Vec<_>>().join(" OR ");
+        replaced_cells = state.undo.record(conn, &table_name, "find_replace", |recorder| {
+            let mut replace = || {
+                recorder.save_values(&changed, &format!("WHERE {}", filter))?;
+                let mut replaced_cells = 0;
+                for column in &changed {
+                    let quoted = quote_ident(column);
+                    replaced_cells += conn.execute(
+                        &format!(
+                            "UPDATE {} SET {col} = regexp_replace({col}, {}, {}, 'g{}') WHERE {}",
+                            table,
+                            regex,
+                            replacement,
+                            options,
+                            found(&quoted),
+                            col = quoted
+                        ),
+                        [],
+                    )?;
+                }
+                Ok::<_, duckdb::Error>(replaced_cells)
+            };
+            replace().with_context(|| format!("Failed to find and replace in {}", table_name))
+        })?;
+    }
+
+    let match_count = matches.iter().map(|c| c.matches).sum();
+    let message = if dry_run {
+        format!("{} match(es) in {} column(s)", match_count, matches.len())
+    } else {
+        state.row_counts.clear();
+        state.query_cache.clear();
+        format!("Replaced {} match(es) in {} value(s)", match_count, replaced_cells)
+    };
+    Ok(FindReplaceResult {
+        columns: matches,
+        match_count,
+        replaced_cells,
+        preview,
+        dry_run,
+        message,
+    })
+}
+
+/// Result type of `expression` evaluated over a table's rows. Anything but
+/// a single expression is rejected, since it's spliced into SQL.
+fn expression_type(conn: &duckdb::Connection, table_name: &str, expression: &str) -> Result<String, AppError> {
+    let select = format!("SELECT ({}) AS value FROM {}", expression, quote_ident(table_name));
+    if expression.trim().is_empty() || !is_single_statement(&select) {
+        return Err(AppError::invalid_input(format!("Invalid expression: {}", expression)));
+    }
+
+    let mut stmt = conn
+        .prepare(&format!("DESCRIBE {}", select))
+        .with_context(|| format!("Invalid expression {}", expression))?;
+    let types = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Invalid expression {}", expression))?;
+    match types.as_slice() {
+        [data_type] if data_type != "NULL" => Ok(data_type.clone()),
+        _ => Err(AppError::invalid_input(format!(
+            "Invalid expression {}: expected a single value per row",
+            expression
+        ))),
+    }
+}
+
+/// Add a column computed from `expression` for each row, e.g.
+/// `price * quantity` or `concat(first, ' ', last)`, typed as the
+/// expression's result. Values are computed once and stored: DuckDB can't
+/// add generated columns to an existing table, so later edits to the
+/// inputs don't update them. Returns the table's new layout.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn add_computed_column(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    expression: String,
+) -> Result<TableInfo, AppError> {
+    let column_name = user_column_name(&column_name)?;
 
-    // Create sorted temp table
-    let create_query = format!(
-        "CREATE TABLE {} AS SELECT * FROM {} ORDER BY {}",
-        temp_table, table_name, order_by_clause
+    crate::run_blocking(app, move |state| run_add_computed_column(&state, table_name, column_name, expression)).await
+}
+
+fn run_add_computed_column(
+    state: &AppState,
+    table_name: String,
+    column_name: String,
+    expression: String,
+) -> Result<TableInfo, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    let table = quote_ident(&table_name);
+    let column = quote_ident(&column_name);
+
+    let data_type = expression_type(conn, &table_name, &expression)?;
+    state.undo.record(conn, &table_name, "add_computed_column", |recorder| {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, data_type), [])
+            .and_then(|_| conn.execute(&format!("UPDATE {} SET {} = ({})", table, column, expression), []))
+            .and_then(|_| recorder.added_columns(std::slice::from_ref(&column_name)))
+            .with_context(|| format!("Failed to add column {}", column_name))
+    })?;
+
+    state.query_cache.clear();
+    table_info(conn, &table_name).map_err(AppError::from)
+}
+
+/// Split a column into new text columns at each `separator`, read as
+/// literal text or (with `mode` `regex`) a regular expression. Part *n*
+/// goes to the *n*th of `new_columns`; rows with fewer parts get NULL in the
+/// rest, and parts beyond the last new column are left out and counted.
+/// Parts are trimmed, with empty ones becoming NULL, unless `trim` is
+/// false. The original column is dropped unless `keep_original` is true.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn split_column(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    separator: String,
+    mode: Option<MatchMode>,
+    new_columns: Vec<String>,
+    keep_original: Option<bool>,
+    trim: Option<bool>,
+) -> Result<SplitResult, AppError> {
+    let column_name = user_column_name(&column_name)?;
+    if separator.is_empty() {
+        return Err(AppError::invalid_input("Separator cannot be empty"));
+    }
+    let new_columns = new_columns.iter().map(|name| user_column_name(name)).collect::<Result<Vec<_>, _>>()?;
+    if new_columns.is_empty() {
+        return Err(AppError::invalid_input("No new column names given"));
+    }
+    for (i, name) in new_columns.iter().enumerate() {
+        if new_columns[..i].contains(name) {
+            return Err(AppError::invalid_input(format!("Column name {} is given twice", name)));
+        }
+    }
+    let keep_original = keep_original.unwrap_or(false);
+    let trim = trim.unwrap_or(true);
+
+    crate::run_blocking(app, move |state| {
+        run_split_column(&state, table_name, column_name, separator, mode, new_columns, keep_original, trim)
+    })
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_split_column(
+    state: &AppState,
+    table_name: String,
+    column_name: String,
+    separator: String,
+    mode: Option<MatchMode>,
+    new_columns: Vec<String>,
+    keep_original: bool,
+    trim: bool,
+) -> Result<SplitResult, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    let table = quote_ident(&table_name);
+    let column = quote_ident(&column_name);
+
+    let source = format!("CAST({} AS VARCHAR)", column);
+    let parts = match mode.unwrap_or_default() {
+        MatchMode::Literal => format!("string_split({}, {})", source, sql_literal(&separator)),
+        MatchMode::Regex => format!("string_split_regex({}, {})", source, sql_literal(&separator)),
+    };
+    let part = |n: usize| {
+        if trim {
+            format!("NULLIF(trim({}[{}]), '')", parts, n)
+        } else {
+            format!("{}[{}]", parts, n)
+        }
+    };
+
+    let overflow_rows = state.undo.record(conn, &table_name, "split_column", |recorder| {
+        let mut split = || {
+            let overflow_rows: usize = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE len({}) > {}", table, parts, new_columns.len()),
+                [],
+                |row| row.get(0),
+            )?;
+            for (i, name) in new_columns.iter().enumerate() {
+                add_filled_column(conn, &table, name, &part(i + 1))?;
+            }
+            recorder.added_columns(&new_columns)?;
+            if !keep_original {
+                recorder.save_columns(std::slice::from_ref(&column_name))?;
+                conn.execute(&format!("ALTER TABLE {} DROP COLUMN {}", table, column), [])?;
+            }
+            Ok::<_, duckdb::Error>(overflow_rows)
+        };
+        split().with_context(|| format!("Failed to split {}", column_name))
+    })?;
+
+    if !keep_original {
+        let _ = crate::dictionary::remove_column_metadata(conn, &table_name, &column_name);
+    }
+    state.query_cache.clear();
+
+    let mut message = format!("Split {} into {} column(s)", column_name, new_columns.len());
+    if overflow_rows > 0 {
+        message.push_str(&format!("; {} row(s) had more parts than columns", overflow_rows));
+    }
+    Ok(SplitResult {
+        table: table_info(conn, &table_name)?,
+        overflow_rows,
+        message,
+    })
+}
+
+/// Add a text column set to `value`. It's filled by the ALTER rather than
+/// an UPDATE, as DuckDB won't alter a table (e.g. to drop the source
+/// columns) after updating it in the same transaction.
+fn add_filled_column(conn: &duckdb::Connection, table: &str, name: &str, value: &str) -> duckdb::Result<()> {
+    let column = quote_ident(name);
+    conn.execute_batch(&format!(
+        "ALTER TABLE {table} ADD COLUMN {column} VARCHAR;
+         ALTER TABLE {table} ALTER COLUMN {column} TYPE VARCHAR USING {value};",
+        table = table,
+        column = column,
+        value = value
+    ))
+}
+
+/// Join columns into a new text column with `separator` between values,
+/// e.g. first and last name into a full name. NULL and empty values are
+/// skipped, so no doubled separators; rows with none get NULL. With
+/// `drop_sources` the joined columns are dropped. Returns the table's new
+/// layout.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn merge_columns(
+    app: AppHandle,
+    table_name: String,
+    columns: Vec<String>,
+    separator: String,
+    new_column: String,
+    drop_sources: Option<bool>,
+) -> Result<TableInfo, AppError> {
+    let columns = columns.iter().map(|name| user_column_name(name)).collect::<Result<Vec<_>, _>>()?;
+    if columns.len() < 2 {
+        return Err(AppError::invalid_input("Give at least two columns to merge"));
+    }
+    let new_column = user_column_name(&new_column)?;
+    let drop_sources = drop_sources.unwrap_or(false);
+
+    crate::run_blocking(app, move |state| {
+        run_merge_columns(&state, table_name, columns, separator, new_column, drop_sources)
+    })
+    .await
+}
+
+fn run_merge_columns(
+    state: &AppState,
+    table_name: String,
+    columns: Vec<String>,
+    separator: String,
+    new_column: String,
+    drop_sources: bool,
+) -> Result<TableInfo, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    let table = quote_ident(&table_name);
+
+    let values: Vec<String> = columns
+        .iter()
+        .map(|name| format!("NULLIF(CAST({} AS VARCHAR), '')", quote_ident(name)))
+        .collect();
+    let merged = format!("NULLIF(concat_ws({}, {}), '')", sql_literal(&separator), values.join(", "));
+
+    state.undo.record(conn, &table_name, "merge_columns", |recorder| {
+        let mut merge = || {
+            add_filled_column(conn, &table, &new_column, &merged)?;
+            recorder.added_columns(std::slice::from_ref(&new_column))?;
+            if drop_sources {
+                recorder.save_columns(&columns)?;
+                for name in &columns {
+                    conn.execute(&format!("ALTER TABLE {} DROP COLUMN {}", table, quote_ident(name)), [])?;
+                }
+            }
+            Ok::<_, duckdb::Error>(())
+        };
+        merge().with_context(|| format!("Failed to merge columns into {}", new_column))
+    })?;
+
+    if drop_sources {
+        for name in &columns {
+            let _ = crate::dictionary::remove_column_metadata(conn, &table_name, name);
+        }
+    }
+    state.query_cache.clear();
+    table_info(conn, &table_name).map_err(AppError::from)
+}
+
+/// Clean up a text column in place with one of the `TextTransform`s.
+/// Returns how many values changed.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn transform_column(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    transform: TextTransform,
+) -> Result<TransformResult, AppError> {
+    let column_name = user_column_name(&column_name)?;
+
+    crate::run_blocking(app, move |state| run_transform_column(&state, table_name, column_name, transform)).await
+}
+
+fn run_transform_column(
+    state: &AppState,
+    table_name: String,
+    column_name: String,
+    transform: TextTransform,
+) -> Result<TransformResult, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    let column = quote_ident(&column_name);
+
+    let source_type = column_type(conn, &table_name, &column_name)?
+        .ok_or_else(|| AppError::not_found(format!("Column {} not found in {}", column_name, table_name)))?;
+    if source_type != "VARCHAR" {
+        return Err(AppError::invalid_input(format!(
+            "Transforms can only change text columns; {} is {}",
+            column_name, source_type
+        )));
+    }
+
+    let transformed = transform.sql(&column);
+    let changing = format!("WHERE {} IS DISTINCT FROM {}", column, transformed);
+    let changed_count = state.undo.record(conn, &table_name, "transform_column", |recorder| {
+        let changed = recorder
+            .save_values(std::slice::from_ref(&column_name), &changing)
+            .and_then(|_| {
+                conn.execute(
+                    &format!("UPDATE {} SET {} = {} {}", quote_ident(&table_name), column, transformed, changing),
+                    [],
+                )
+            })
+            .with_context(|| format!("Failed to transform {}", column_name))?;
+        if changed == 0 {
+            recorder.skip();
+        }
+        Ok(changed)
+    })?;
+
+    state.query_cache.clear();
+    Ok(TransformResult {
+        changed_count,
+        message: format!("Changed {} value(s) in {}", changed_count, column_name),
+    })
+}
+
+/// Fill a column's NULLs by `strategy`. Forward and backward fill follow
+/// the table's row order; `value` is required for `constant` and cast to
+/// the column's type. Mean and median need a numeric column.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn fill_nulls(
+    app: AppHandle,
+    table_name: String,
+    column_name: String,
+    strategy: FillStrategy,
+    value: Option<Json>,
+) -> Result<FillResult, AppError> {
+    let column_name = user_column_name(&column_name)?;
+    let constant = match (strategy, value) {
+        (FillStrategy::Constant, Some(value)) if !value.is_null() => Some(sql_value(&value)),
+        (FillStrategy::Constant, _) => return Err(AppError::invalid_input("Give a value to fill NULLs with")),
+        _ => None,
+    };
+
+    crate::run_blocking(app, move |state| run_fill_nulls(&state, table_name, column_name, strategy, constant)).await
+}
+
+fn run_fill_nulls(
+    state: &AppState,
+    table_name: String,
+    column_name: String,
+    strategy: FillStrategy,
+    constant: Option<String>,
+) -> Result<FillResult, AppError> {
+    let db = state.db.lock().map_err(|e| AppError::internal(e.to_string()))?;
+    let conn = db.get_connection();
+    let table = quote_ident(&table_name);
+    let column = quote_ident(&column_name);
+    let id = quote_ident(ROW_ID_COLUMN);
+
+    let data_type = column_type(conn, &table_name, &column_name)?
+        .ok_or_else(|| AppError::not_found(format!("Column {} not found in {}", column_name, table_name)))?;
+    let upper = data_type.to_uppercase();
+    let integer = matches!(
+        upper.as_str(),
+        "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" | "HUGEINT" | "UTINYINT" | "USMALLINT" | "UINTEGER"
+            | "UBIGINT" | "UHUGEINT"
     );
-    conn.execute(&create_query, [])
-        .map_err(|e| format!("Failed to create sorted table: {}", e))?;
+    let numeric = integer
+        || matches!(upper.as_str(), "FLOAT" | "DOUBLE")
+        || (upper.starts_with("DECIMAL") && !is_list_type(&upper));
+    if matches!(strategy, FillStrategy::Mean | FillStrategy::Median) && !numeric {
+        return Err(AppError::invalid_input(format!(
+            "Mean and median can only fill numeric columns; {} is {}",
+            column_name, data_type
+        )));
+    }
 
-    // Drop original table
-    conn.execute(&format!("DROP TABLE {}", table_name), [])
-        .map_err(|e| format!("Failed to drop original table: {}", e))?;
+    // Window fills look values up by row id; rows get ids as the change
+    // is recorded
+    let window = match strategy {
+        FillStrategy::Forward => Some(format!(
+            "last_value({} IGNORE NULLS) OVER (ORDER BY rowid ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW)",
+            column
+        )),
+        FillStrategy::Backward => Some(format!(
+            "first_value({} IGNORE NULLS) OVER (ORDER BY rowid ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING)",
+            column
+        )),
+        _ => None,
+    };
+    let sql = match &window {
+        Some(window) => format!(
+            "UPDATE {table} SET {col} = filled.fill_value
+             FROM (SELECT {id} AS fill_id, {} AS fill_value FROM {table}) filled
+             WHERE {table}.{id} = filled.fill_id AND {table}.{col} IS NULL AND filled.fill_value IS NOT NULL",
+            window,
+            table = table,
+            col = column,
+            id = id
+        ),
+        None => {
+            // Integer columns get the mean or median rounded to a whole number
+            let average = |function: &str| {
+                let value = format!("(SELECT {}({}) FROM {})", function, column, table);
+                let value = if integer { format!("round({})", value) } else { value };
+                format!("CAST({} AS {})", value, data_type)
+            };
+            let fill = match strategy {
+                FillStrategy::Mean => average("avg"),
+                FillStrategy::Median => average("median"),
+                FillStrategy::Mode => format!("(SELECT mode({}) FROM {})", column, table),
+                _ => format!("CAST({} AS {})", constant.as_deref().unwrap_or("NULL"), data_type),
+            };
+            format!("UPDATE {} SET {col} = {} WHERE {col} IS NULL", table, fill, col = column)
+        }
+    };
 
-    // Rename temp table to original name
-    conn.execute(
-        &format!("ALTER TABLE {} RENAME TO {}", temp_table, table_name),
-        [],
-    )
-    .map_err(|e| format!("Failed to rename table: {}", e))?;
+    let filled_count = state.undo.record(conn, &table_name, "fill_nulls", |recorder| {
+        let filled = recorder
+            .save_values(std::slice::from_ref(&column_name), &format!("WHERE {} IS NULL", column))
+            .and_then(|_| conn.execute(&sql, []))
+            .with_context(|| format!("Failed to fill NULLs in {}", column_name))?;
+        if filled == 0 {
+            recorder.skip();
+        }
+        Ok(filled)
+    })?;
 
-    Ok(ReorderResult {
-        success: true,
-        message: format!("Rows reordered by {} column(s)", sort_columns.len()),
+    let remaining_nulls: usize =
+        conn.query_row(&format!("SELECT COUNT(*) FROM {} WHERE {} IS NULL", table, column), [], |row| row.get(0))?;
+    state.query_cache.clear();
+
+    let mut message = format!("Filled {} NULL(s) in {}", filled_count, column_name);
+    if remaining_nulls > 0 {
+        message.push_str(&format!("; {} left with nothing to fill from", remaining_nulls));
+    }
+    Ok(FillResult { filled_count, remaining_nulls, message })
+}
+
+/// Remove duplicate rows: rows equal in every column, or in `key_columns`
+/// when given, keeping the first or last of each set in table order. With
+/// `save_duplicates`, the removed rows are written to `<table>_duplicates`
+/// first; an existing table of that name is only replaced with
+/// `replace_duplicates`. Undo drops the saved rows again.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn deduplicate(
+    app: AppHandle,
+    table_name: String,
+    key_columns: Option<Vec<String>>,
+    keep: Option<KeepRow>,
+    save_duplicates: Option<bool>,
+    replace_duplicates: Option<bool>,
+) -> Result<DeduplicateResult, AppError> {
+    let key_columns = match key_columns {
+        Some(columns) if columns.is_empty() => return Err(AppError::invalid_input("No key columns selected")),
+        Some(columns) => Some(columns.iter().map(|name| user_column_name(name)).collect::<Result<Vec<_>, _>>()?),
+        None => None,
+    };
+    let keep = keep.unwrap_or_default();
+    let duplicates_table = save_duplicates.unwrap_or(false).then(|| format!("{}_duplicates", table_name));
+    let replace_duplicates = replace_duplicates.unwrap_or(false);
+
+    crate::run_blocking(app, move |state| {
+        run_deduplicate(&state, table_name, key_columns, keep, duplicates_table, replace_duplicates)
+    })
+    .await
+}
+
+fn run_deduplicate(
+    state: &AppState,
+    table_name: String,
+    key_columns: Option<Vec<String>>,
+    keep: KeepRow,
+    duplicates_table: Option<String>,
+    replace_duplicates: bool,
+) -> Result<DeduplicateResult, AppError> {
+    let db = state.db.lock()?;
+    let conn = db.get_connection();
+    if let Some(duplicates_table) = &duplicates_table {
+        if !replace_duplicates && table_exists(conn, duplicates_table)? {
+            return Err(AppError::invalid_input(format!(
+                "Table {} already exists; pass replaceDuplicates to replace it",
+                duplicates_table
+            )));
+        }
+    }
+    let table = quote_ident(&table_name);
+    let id = quote_ident(ROW_ID_COLUMN);
+    let direction = match keep {
+        KeepRow::First => "ASC",
+        KeepRow::Last => "DESC",
+    };
+
+    let key_columns = match key_columns {
+        Some(columns) => columns,
+        None => table_info(conn, &table_name)
+            .context("Failed to get table info")?
+            .columns
+            .into_iter()
+            .map(|c| c.name)
+            .collect(),
+    };
+    let partition = key_columns.iter().map(|c| quote_ident(c)).collect::<Vec<_>>().join(", ");
+
+    let duplicates = format!(
+        "SELECT {id} FROM (
+            SELECT {id}, row_number() OVER (PARTITION BY {} ORDER BY rowid {}) AS copy_number FROM {}
+         ) WHERE copy_number > 1",
+        partition,
+        direction,
+        table,
+        id = id
+    );
+
+    let removed_count = state.undo.record(conn, &table_name, "deduplicate", |recorder| {
+        let mut remove = || {
+            if let Some(duplicates_table) = &duplicates_table {
+                recorder.save_table(duplicates_table)?;
+                conn.execute(
+                    &format!(
+                        "CREATE OR REPLACE TABLE {} AS
+                         SELECT * EXCLUDE ({id}) FROM {} WHERE {id} IN ({}) ORDER BY rowid",
+                        quote_ident(duplicates_table),
+                        table,
+                        duplicates,
+                        id = id
+                    ),
+                    [],
+                )?;
+            }
+            recorder.delete_rows(&format!("WHERE {} IN ({})", id, duplicates), [])
+        };
+        let removed = remove().with_context(|| format!("Failed to remove duplicates from {}", table_name))?;
+        if removed == 0 {
+            recorder.skip();
+        }
+        Ok(removed)
+    })?;
+
+    state.row_counts.clear();
+    state.query_cache.clear();
+
+    let mut message = format!("Removed {} duplicate row(s) from {}", removed_count, table_name);
+    if let Some(duplicates_table) = &duplicates_table {
+        message.push_str(&format!("; saved them to {}", duplicates_table));
+    }
+    Ok(DeduplicateResult {
+        removed_count,
+        duplicates_table,
+        message,
     })
 }
+
+/// Undo the latest editor change, restoring the table as it was before it.
+/// Returns the step undone.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn undo(app: AppHandle) -> Result<UndoEntry, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let entry = state.undo.undo(db.get_connection())?;
+        state.row_counts.clear();
+        state.query_cache.clear();
+        Ok(entry)
+    })
+    .await
+}
+
+/// Redo the latest undone change. Returns the step redone.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn redo(app: AppHandle) -> Result<UndoEntry, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let entry = state.undo.redo(db.get_connection())?;
+        state.row_counts.clear();
+        state.query_cache.clear();
+        Ok(entry)
+    })
+    .await
+}
+
+/// The changes that can be undone and redone, most recent first
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_undo_stack(app: AppHandle) -> Result<UndoStack, AppError> {
+    crate::run_blocking(app, move |state| state.undo.stack()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// App state over an in-memory database set up by `sql`
+    fn state(sql: &str) -> AppState {
+        let state = AppState::new().unwrap();
+        state.db.lock().unwrap().get_connection().execute_batch(sql).unwrap();
+        state
+    }
+
+    /// A column's values in table order, as text
+    fn column(state: &AppState, table: &str, column: &str) -> Vec<String> {
+        let db = state.db.lock().unwrap();
+        let mut stmt = db
+            .get_connection()
+            .prepare(&format!(
+                "SELECT COALESCE(CAST({} AS VARCHAR), 'NULL') FROM {} ORDER BY rowid",
+                quote_ident(column),
+                quote_ident(table)
+            ))
+            .unwrap();
+        let values = stmt.query_map([], |row| row.get(0)).unwrap();
+        values.collect::<Result<_, _>>().unwrap()
+    }
+
+    fn column_names(info: &TableInfo) -> Vec<&str> {
+        info.columns.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    fn layout(state: &AppState, table: &str) -> Vec<String> {
+        let db = state.db.lock().unwrap();
+        let info = table_info(db.get_connection(), table).unwrap();
+        info.columns.into_iter().map(|c| c.name).collect()
+    }
+
+    fn undo(state: &AppState) {
+        state.undo.undo(state.db.lock().unwrap().get_connection()).unwrap();
+    }
+
+    fn redo(state: &AppState) {
+        state.undo.redo(state.db.lock().unwrap().get_connection()).unwrap();
+    }
+
+    fn row(value: Json) -> Map<String, Json> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn reorders_rows() {
+        let state = state("CREATE TABLE t (a INTEGER); INSERT INTO t VALUES (2), (3), (1);");
+        let sort = vec![SortColumn { column: "a".into(), ascending: false }];
+
+        run_reorder_rows(&state, "t".into(), sort).unwrap();
+        assert_eq!(column(&state, "t", "a"), ["3", "2", "1"]);
+        undo(&state);
+        assert_eq!(column(&state, "t", "a"), ["2", "3", "1"]);
+        redo(&state);
+        assert_eq!(column(&state, "t", "a"), ["3", "2", "1"]);
+    }
+
+    #[test]
+    fn inserts_rows_at_a_position() {
+        let state = state(
+            "CREATE TABLE t (a INTEGER, b VARCHAR DEFAULT 'x');
+             INSERT INTO t VALUES (1, 'one'), (2, 'two'), (3, 'three');",
+        );
+
+        let result = run_insert_rows(&state, "t".into(), vec![row(json!({ "a": 10 }))], Some(1)).unwrap();
+        assert_eq!(result.rows_affected, 1);
+        assert_eq!(column(&state, "t", "a"), ["1", "10", "2", "3"]);
+        assert_eq!(column(&state, "t", "b"), ["one", "x", "two", "three"]);
+
+        let rows = vec![row(json!({ "a": 20, "b": "twenty" })), row(json!({}))];
+        let appended = run_insert_rows(&state, "t".into(), rows, None).unwrap();
+        assert_eq!(column(&state, "t", "a"), ["1", "10", "2", "3", "20", "NULL"]);
+        assert!(appended.row_ids.iter().all(|id| *id > result.row_ids[0]));
+
+        undo(&state);
+        undo(&state);
+        assert_eq!(column(&state, "t", "a"), ["1", "2", "3"]);
+        redo(&state);
+        assert_eq!(column(&state, "t", "a"), ["1", "10", "2", "3"]);
+    }
+
+    #[test]
+    fn moving_rows_keeps_the_table_definition() {
+        let state = state(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name VARCHAR NOT NULL DEFAULT 'unnamed' CHECK (name <> ''));
+             CREATE INDEX t_name ON t (name);
+             CREATE TABLE t_inserted_temp (kept INTEGER);
+             INSERT INTO t VALUES (2, 'b'), (1, 'a');",
+        );
+        let definition = |state: &AppState| {
+            let db = state.db.lock().unwrap();
+            let conn = db.get_connection();
+            let sql = |query: &str| conn.query_row(query, [], |row| row.get::<_, String>(0)).unwrap();
+            (
+                sql("SELECT sql FROM duckdb_tables() WHERE table_name = 't'"),
+                sql("SELECT sql FROM duckdb_indexes() WHERE table_name = 't'"),
+            )
+        };
+
+        run_insert_rows(&state, "t".into(), vec![row(json!({ "id": 3 }))], Some(0)).unwrap();
+        let before = definition(&state);
+        run_reorder_rows(&state, "t".into(), vec![SortColumn { column: "id".into(), ascending: true }]).unwrap();
+        assert_eq!(definition(&state), before);
+        assert!(before.0.contains("PRIMARY KEY") && before.0.contains("CHECK"));
+        assert_eq!(column(&state, "t", "name"), ["a", "b", "unnamed"]);
+        assert!(run_insert_rows(&state, "t".into(), vec![row(json!({ "id": 1 }))], Some(0)).is_err());
+        assert!(run_insert_rows(&state, "t".into(), vec![row(json!({ "id": 4, "name": "" }))], Some(0)).is_err());
+        assert_eq!(layout(&state, "t_inserted_temp"), ["kept"]);
+
+        undo(&state);
+        undo(&state);
+        assert_eq!(column(&state, "t", "id"), ["2", "1"]);
+        assert_eq!(definition(&state), before);
+    }
+
+    #[test]
+    fn deletes_rows_by_id_or_condition() {
+        let state = state("CREATE TABLE t (a INTEGER); INSERT INTO t SELECT * FROM range(1, 7);");
+        run_insert_rows(&state, "t".into(), vec![row(json!({ "a": 7 }))], None).unwrap();
+        let ids: Vec<i64> = {
+            let db = state.db.lock().unwrap();
+            let mut stmt = db.get_connection().prepare("SELECT _rats_rowid FROM t WHERE a IN (2, 7)").unwrap();
+            let ids = stmt.query_map([], |row| row.get(0)).unwrap();
+            ids.collect::<Result<_, _>>().unwrap()
+        };
+
+        let deleted = run_delete_rows(&state, "t".into(), Some(ids), None).unwrap();
+        assert_eq!(deleted.rows_affected, 2);
+        let conditions = vec![FilterCondition { column: "a".into(), operator: ">".into(), value: json!(4) }];
+        let deleted = run_delete_rows(&state, "t".into(), None, Some(conditions)).unwrap();
+        assert_eq!(deleted.rows_affected, 2);
+        assert_eq!(column(&state, "t", "a"), ["1", "3", "4"]);
+
+        assert!(run_delete_rows(&state, "t".into(), None, Some(Vec::new())).is_err());
+        assert!(run_delete_rows(&state, "t".into(), None, None).is_err());
+
+        undo(&state);
+        undo(&state);
+        assert_eq!(column(&state, "t", "a"), ["1", "2", "3", "4", "5", "6", "7"]);
+    }
+
+    #[test]
+    fn adds_drops_and_renames_columns() {
+        let state = state("CREATE TABLE t (a INTEGER); INSERT INTO t VALUES (1), (2);");
+
+        let info = run_add_column(&state, "t".into(), "flag".into(), "BOOLEAN".into(), Some(json!(true))).unwrap();
+        assert_eq!(column_names(&info), ["a", "flag"]);
+        assert_eq!(column(&state, "t", "flag"), ["true", "true"]);
+        undo(&state);
+        assert_eq!(layout(&state, "t"), ["a"]);
+        redo(&state);
+        assert_eq!(column(&state, "t", "flag"), ["true", "true"]);
+
+        let info = run_rename_column(&state, "t".into(), "a".into(), "id".into()).unwrap();
+        assert_eq!(column_names(&info), ["id", "flag"]);
+        let info = run_drop_column(&state, "t".into(), "id".into()).unwrap();
+        assert_eq!(column_names(&info), ["flag"]);
+
+        undo(&state);
+        undo(&state);
+        assert_eq!(layout(&state, "t"), ["flag", "a"]);
+        assert_eq!(column(&state, "t", "a"), ["1", "2"]);
+    }
+
+    #[test]
+    fn casts_columns() {
+        let state = state("CREATE TABLE t (v VARCHAR); INSERT INTO t VALUES ('1'), (' 2 '), (''), ('x'), (NULL);");
+
+        let cast = |on_error, review: Option<&str>, replace| {
+            let review = review.map(str::to_string);
+            run_cast_column(&state, "t".into(), "v".into(), "INTEGER".into(), on_error, review, replace)
+        };
+
+        let aborted = cast(CastOnError::Abort, Some("bad"), false).unwrap();
+        assert!(!aborted.converted);
+        assert_eq!(aborted.failed_count, 1);
+        assert_eq!(aborted.failed_samples, ["x"]);
+        assert_eq!(column(&state, "bad", "value"), ["x"]);
+        assert_eq!(column(&state, "t", "v"), ["1", " 2 ", "", "x", "NULL"]);
+        assert!(state.undo.stack().unwrap().undo.is_empty());
+
+        // An existing review table is only replaced when asked, and undo
+        // brings it back
+        state.db.lock().unwrap().get_connection().execute_batch("INSERT INTO bad VALUES (0, 'old')").unwrap();
+        assert!(cast(CastOnError::Null, Some("bad"), false).unwrap_err().message.contains("already exists"));
+        assert_eq!(column(&state, "t", "v"), ["1", " 2 ", "", "x", "NULL"]);
+
+        let converted = cast(CastOnError::Null, Some("bad"), true).unwrap();
+        assert!(converted.converted);
+        assert_eq!(column(&state, "t", "v"), ["1", "2", "NULL", "NULL", "NULL"]);
+        assert_eq!(column(&state, "bad", "value"), ["x"]);
+
+        undo(&state);
+        assert_eq!(column(&state, "t", "v"), ["1", " 2 ", "", "x", "NULL"]);
+        assert_eq!(column(&state, "bad", "value"), ["x", "old"]);
+        let db = state.db.lock().unwrap();
+        assert_eq!(column_type(db.get_connection(), "t", "v").unwrap().as_deref(), Some("VARCHAR"));
+    }
+
+    #[test]
+    fn finds_and_replaces() {
+        let state = state(
+            "CREATE TABLE t (name VARCHAR, n INTEGER);
+             INSERT INTO t VALUES ('apple pie', 1), ('Apple', 2), ('banana', 3);",
+        );
+        let literal = |text: &str| sql_literal(&regex_escape(text));
+
+        let found =
+            run_find_replace(&state, "t".into(), None, literal("apple"), sql_literal("pear"), "c", true).unwrap();
+        assert_eq!(found.match_count, 1);
+        assert_eq!(found.replaced_cells, 0);
+        assert_eq!(found.preview[0].after, "pear pie");
+        assert_eq!(column(&state, "t", "name"), ["apple pie", "Apple", "banana"]);
+
+        let replaced =
+            run_find_replace(&state, "t".into(), None, literal("apple"), sql_literal("pear"), "i", false).unwrap();
+        assert_eq!(replaced.replaced_cells, 2);
+        assert_eq!(column(&state, "t", "name"), ["pear pie", "pear", "banana"]);
+
+        run_find_replace(&state, "t".into(), None, sql_literal("a(n)"), sql_literal("o\\1"), "c", false).unwrap();
+        assert_eq!(column(&state, "t", "name"), ["pear pie", "pear", "bonona"]);
+
+        let numbers = Some(vec!["n".to_string()]);
+        assert!(run_find_replace(&state, "t".into(), numbers, literal("1"), sql_literal("2"), "c", false).is_err());
+
+        undo(&state);
+        undo(&state);
+        assert_eq!(column(&state, "t", "name"), ["apple pie", "Apple", "banana"]);
+    }
+
+    #[test]
+    fn adds_computed_columns() {
+        let state = state("CREATE TABLE t (price DOUBLE, qty INTEGER); INSERT INTO t VALUES (2.5, 2), (1.0, 3);");
+
+        let info = run_add_computed_column(&state, "t".into(), "total".into(), "price * qty".into()).unwrap();
+        assert_eq!(info.columns[2].data_type, "DOUBLE");
+        assert_eq!(column(&state, "t", "total"), ["5.0", "3.0"]);
+        assert!(run_add_computed_column(&state, "t".into(), "x".into(), "1; DROP TABLE t".into()).is_err());
+
+        undo(&state);
+        assert_eq!(layout(&state, "t"), ["price", "qty"]);
+    }
+
+    #[test]
+    fn splits_columns() {
+        let state = state(
+            "CREATE TABLE t (name VARCHAR);
+             INSERT INTO t VALUES ('Lovelace, Ada'), ('Turing,Alan,M'), (NULL), ('Hopper');",
+        );
+        let columns = vec!["last".to_string(), "first".to_string()];
+
+        let result = run_split_column(&state, "t".into(), "name".into(), ",".into(), None, columns, false, true);
+        let result = result.unwrap();
+        assert_eq!(result.overflow_rows, 1);
+        assert_eq!(column_names(&result.table), ["last", "first"]);
+        assert_eq!(column(&state, "t", "last"), ["Lovelace", "Turing", "NULL", "Hopper"]);
+        assert_eq!(column(&state, "t", "first"), ["Ada", "Alan", "NULL", "NULL"]);
+
+        undo(&state);
+        assert_eq!(layout(&state, "t"), ["name"]);
+        assert_eq!(column(&state, "t", "name"), ["Lovelace, Ada", "Turing,Alan,M", "NULL", "Hopper"]);
+    }
+
+    #[test]
+    fn merges_columns() {
+        let state = state(
+            "CREATE TABLE t (first VARCHAR, last VARCHAR, age INTEGER);
+             INSERT INTO t VALUES ('Ada', 'Lovelace', 36), ('Alan', NULL, 41), ('', '', NULL);",
+        );
+        let sources = vec!["first".to_string(), "last".to_string()];
+
+        let info = run_merge_columns(&state, "t".into(), sources, " ".into(), "name".into(), true).unwrap();
+        assert_eq!(column_names(&info), ["age", "name"]);
+        assert_eq!(column(&state, "t", "name"), ["Ada Lovelace", "Alan", "NULL"]);
+
+        undo(&state);
+        assert_eq!(layout(&state, "t"), ["age", "first", "last"]);
+        assert_eq!(column(&state, "t", "last"), ["Lovelace", "NULL", ""]);
+    }
+
+    #[test]
+    fn transforms_text_columns() {
+        let state = state(
+            "CREATE TABLE t (v VARCHAR, n INTEGER);
+             INSERT INTO t VALUES ('  a  b ', 1), ('hello world', 2);",
+        );
+
+        let result = run_transform_column(&state, "t".into(), "v".into(), TextTransform::CollapseWhitespace).unwrap();
+        assert_eq!(result.changed_count, 1);
+        let result = run_transform_column(&state, "t".into(), "v".into(), TextTransform::TitleCase).unwrap();
+        assert_eq!(result.changed_count, 2);
+        assert_eq!(column(&state, "t", "v"), ["A B", "Hello World"]);
+        assert!(run_transform_column(&state, "t".into(), "n".into(), TextTransform::Trim).is_err());
+
+        undo(&state);
+        undo(&state);
+        assert_eq!(column(&state, "t", "v"), ["  a  b ", "hello world"]);
+    }
+
+    #[test]
+    fn fills_nulls() {
+        let state = state("CREATE TABLE t (n INTEGER); INSERT INTO t VALUES (NULL), (1), (NULL), (3), (NULL);");
+        let fill = |strategy, constant| run_fill_nulls(&state, "t".into(), "n".into(), strategy, constant).unwrap();
+
+        let forward = fill(FillStrategy::Forward, None);
+        assert_eq!((forward.filled_count, forward.remaining_nulls), (2, 1));
+        assert_eq!(column(&state, "t", "n"), ["NULL", "1", "1", "3", "3"]);
+        undo(&state);
+
+        fill(FillStrategy::Backward, None);
+        assert_eq!(column(&state, "t", "n"), ["1", "1", "3", "3", "NULL"]);
+        undo(&state);
+
+        fill(FillStrategy::Median, None);
+        assert_eq!(column(&state, "t", "n"), ["2", "1", "2", "3", "2"]);
+        undo(&state);
+
+        // The mean of 1 and 4 rounds to 3 in an INTEGER column
+        state.db.lock().unwrap().get_connection().execute_batch("UPDATE t SET n = 4 WHERE n = 3").unwrap();
+        fill(FillStrategy::Mean, None);
+        assert_eq!(column(&state, "t", "n"), ["3", "1", "3", "4", "3"]);
+        undo(&state);
+        state.db.lock().unwrap().get_connection().execute_batch("UPDATE t SET n = 3 WHERE n = 4").unwrap();
+
+        fill(FillStrategy::Constant, Some(sql_value(&json!(7))));
+        assert_eq!(column(&state, "t", "n"), ["7", "1", "7", "3", "7"]);
+        undo(&state);
+        assert_eq!(column(&state, "t", "n"), ["NULL", "1", "NULL", "3", "NULL"]);
+
+        let nothing = run_fill_nulls(&state, "t".into(), "n".into(), FillStrategy::Forward, None);
+        assert!(nothing.is_ok());
+
+        state.db.lock().unwrap().get_connection().execute_batch("ALTER TABLE t ADD COLUMN s VARCHAR").unwrap();
+        let text = run_fill_nulls(&state, "t".into(), "s".into(), FillStrategy::Mean, None).unwrap_err();
+        assert_eq!(text.kind, crate::error::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn deduplicates_rows() {
+        let state = state(
+            "CREATE TABLE t (k INTEGER, v VARCHAR);
+             INSERT INTO t VALUES (1, 'a'), (2, 'b'), (1, 'a'), (1, 'c'), (2, 'b');",
+        );
+
+        let result = run_deduplicate(&state, "t".into(), None, KeepRow::First, None, false).unwrap();
+        assert_eq!(result.removed_count, 2);
+        assert_eq!(column(&state, "t", "v"), ["a", "b", "c"]);
+        undo(&state);
+
+        let keys = Some(vec!["k".to_string()]);
+        let dedup = |replace| {
+            run_deduplicate(&state, "t".into(), keys.clone(), KeepRow::Last, Some("t_duplicates".into()), replace)
+        };
+        let result = dedup(false).unwrap();
+        assert_eq!(result.removed_count, 3);
+        assert_eq!(column(&state, "t", "v"), ["c", "b"]);
+        assert_eq!(column(&state, "t_duplicates", "v"), ["a", "b", "a"]);
+
+        undo(&state);
+        assert_eq!(column(&state, "t", "v"), ["a", "b", "a", "c", "b"]);
+        let db = state.db.lock().unwrap();
+        assert!(!table_exists(db.get_connection(), "t_duplicates").unwrap());
+        db.get_connection().execute_batch("CREATE TABLE t_duplicates AS SELECT 'kept' AS v").unwrap();
+        drop(db);
+
+        assert!(dedup(false).is_err());
+        assert_eq!(column(&state, "t", "v"), ["a", "b", "a", "c", "b"]);
+        dedup(true).unwrap();
+        assert_eq!(column(&state, "t_duplicates", "v"), ["a", "b", "a"]);
+        undo(&state);
+        assert_eq!(column(&state, "t_duplicates", "v"), ["kept"]);
+    }
+}
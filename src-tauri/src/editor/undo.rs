@@ -0,0 +1,901 @@
+use duckdb::{Connection, Params, Result as DuckResult};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use crate::duckdb_core::{ensure_row_ids, in_transaction, quote_ident, rebuild_table, table_exists, ROW_ID_COLUMN};
+use crate::error::{AppError, ErrorContext};
+
+/// Prefix of the hidden tables holding the data undo and redo need
+const UNDO_TABLE_PREFIX: &str = "_rats_undo_";
+
+/// Column of row copies holding each row's 0-based position in the table
+const POSITION_COLUMN: &str = "_rats_pos";
+
+/// Undo steps kept; the oldest step's copies are dropped beyond this
+const CAPACITY: usize = 20;
+
+/// One change that can be undone or redone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoEntry {
+    pub id: u64,
+    pub operation: String, // Command that made the change, e.g. "delete_rows"
+    pub table_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoStack {
+    pub undo: Vec<UndoEntry>, // Most recent first
+    pub redo: Vec<UndoEntry>, // Next to redo first
+}
+
+/// A column as declared, to add it back
+struct ColumnDef {
+    name: String,
+    data_type: String,
+    default: Option<String>,
+    nullable: bool,
+}
+
+/// A column whose values a change set, with its type either side of it
+struct ValueColumn {
+    name: String,
+    type_before: String,
+    type_after: String,
+}
+
+/// Part of a change, with the hidden copies needed to reverse and repeat it
+enum Change {
+    /// Rows taken out of and put into the table, copied whole with their
+    /// positions
+    Rows { removed: Option<String>, added: Option<String> },
+    /// Values set in existing rows, copied by row id before and after
+    Values { columns: Vec<ValueColumn>, before: String, after: String },
+    /// Columns added (or dropped), with their values by row id unless
+    /// adding them back fills in their defaults
+    Columns { columns: Vec<ColumnDef>, copy: Option<String>, added: bool },
+    RenameColumn { from: String, to: String },
+    /// Row order, as row ids and positions before and after
+    Order { before: String, after: String },
+    /// Another table the change created or replaced, copied whole before
+    /// (unless it didn't exist) and after
+    Table { name: String, before: Option<String>, after: String },
+}
+
+impl Change {
+    fn copies(&self) -> Vec<&str> {
+        match self {
+            Change::Rows { removed, added } => removed.iter().chain(added).map(String::as_str).collect(),
+            Change::Values { before, after, .. } | Change::Order { before, after } => vec![before, after],
+            Change::Columns { copy, .. } => copy.iter().map(String::as_str).collect(),
+            Change::RenameColumn { .. } => Vec::new(),
+            Change::Table { before, after, .. } => before.iter().chain([after]).map(String::as_str).collect(),
+        }
+    }
+
+    /// Repeat the change (`forward`) or reverse it
+    fn apply(&self, conn: &Connection, table_name: &str, forward: bool) -> Result<(), AppError> {
+        let table = quote_ident(table_name);
+        let id = quote_ident(ROW_ID_COLUMN);
+        match self {
+            Change::Rows { removed, added } => {
+                let (take, put) = if forward { (removed, added) } else { (added, removed) };
+                if let Some(take) = take {
+                    conn.execute_batch(&format!(
+                        "DELETE FROM {} WHERE {id} IN (SELECT {id} FROM {})",
+                        table,
+                        quote_ident(take),
+                        id = id
+                    ))?;
+                }
+                if let Some(put) = put {
+                    restore_rows(conn, table_name, put)?;
+                }
+            }
+            Change::Values { columns, before, after } => {
+                let copy = quote_ident(if forward { after } else { before });
+                for column in columns.iter().filter(|c| c.type_before != c.type_after) {
+                    let data_type = if forward { &column.type_after } else { &column.type_before };
+                    let name = quote_ident(&column.name);
+                    conn.execute_batch(&format!(
+                        "ALTER TABLE {} ALTER COLUMN {name} TYPE {ty} USING TRY_CAST({name} AS {ty})",
+                        table,
+                        name = name,
+                        ty = data_type
+                    ))?;
+                }
+                fill_from_copy(conn, &table, columns.iter().map(|c| c.name.as_str()), &copy)?;
+            }
+            Change::Columns { columns, copy, added } => {
+                if forward == *added {
+                    for column in columns {
+                        let default = column.default.as_ref().map(|d| format!(" DEFAULT {}", d)).unwrap_or_default();
+                        conn.execute_batch(&format!(
+                            "ALTER TABLE {} ADD COLUMN {} {}{}",
+                            table,
+                            quote_ident(&column.name),
+                            column.data_type,
+                            default
+                        ))?;
+                    }
+                    // Rebuilt rather than updated, as DuckDB won't alter
+                    // the table again in this transaction after an UPDATE
+                    if let Some(copy) = copy {
+                        let copy = quote_ident(copy);
+                        let values: Vec<String> = columns
+                            .iter()
+                            .map(|c| format!("{copy}.{name} AS {name}", copy = copy, name = quote_ident(&c.name)))
+                            .collect();
+                        rebuild_table(
+                            conn,
+                            table_name,
+                            &format!(
+                                "SELECT {table}.* REPLACE ({}) FROM {table} LEFT JOIN {copy} USING ({id})
+                                 ORDER BY {table}.rowid",
+                                values.join(", "),
+                                table = table,
+                                copy = copy,
+                                id = id
+                            ),
+                        )?;
+                    }
+                    for column in columns.iter().filter(|c| !c.nullable) {
+                        conn.execute_batch(&format!(
+                            "ALTER TABLE {} ALTER COLUMN {} SET NOT NULL",
+                            table,
+                            quote_ident(&column.name)
+                        ))?;
+                    }
+                } else {
+                    for column in columns {
+                        let name = quote_ident(&column.name);
+                        conn.execute_batch(&format!("ALTER TABLE {} DROP COLUMN {}", table, name))?;
+                    }
+                }
+            }
+            Change::RenameColumn { from, to } => {
+                let (from, to) = if forward { (from, to) } else { (to, from) };
+                conn.execute_batch(&format!(
+                    "ALTER TABLE {} RENAME COLUMN {} TO {}",
+                    table,
+                    quote_ident(from),
+                    quote_ident(to)
+                ))?;
+                let _ = crate::dictionary::rename_column_metadata(conn, table_name, from, to);
+            }
+            Change::Order { before, after } => {
+                let copy = quote_ident(if forward { after } else { before });
+                rebuild_table(
+                    conn,
+                    table_name,
+                    &format!(
+                        "SELECT {table}.* FROM {table} LEFT JOIN {copy} USING ({id})
+                         ORDER BY {copy}.{pos} NULLS LAST, {table}.rowid",
+                        table = table,
+                        copy = copy,
+                        id = id,
+                        pos = quote_ident(POSITION_COLUMN)
+                    ),
+                )?;
+            }
+            Change::Table { name, before, after } => {
+                // Redoing the creation of a new table fails rather than
+                // replace one made since
+                let (replace, copy) = if forward { (before.is_some(), Some(after)) } else { (true, before.as_ref()) };
+                if replace {
+                    conn.execute_batch(&format!("DROP TABLE IF EXISTS {}", quote_ident(name)))?;
+                }
+                if let Some(copy) = copy {
+                    conn.execute_batch(&format!(
+                        "CREATE TABLE {} AS SELECT * FROM {} ORDER BY rowid",
+                        quote_ident(name),
+                        quote_ident(copy)
+                    ))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Set `columns` of the rows in `copy` to the values copied there
+fn fill_from_copy<'a>(
+    conn: &Connection,
+    table: &str,
+    columns: impl Iterator<Item = &'a str>,
+    copy: &str,
+) -> DuckResult<()> {
+    let assignments: Vec<String> = columns
+        .map(|name| format!("{} = {}.{}", quote_ident(name), copy, quote_ident(name)))
+        .collect();
+    conn.execute_batch(&format!(
+        "UPDATE {table} SET {} FROM {copy} WHERE {table}.{id} = {copy}.{id}",
+        assignments.join(", "),
+        table = table,
+        copy = copy,
+        id = quote_ident(ROW_ID_COLUMN)
+    ))
+}
+
+/// Put rows copied with their positions back at those positions
+fn restore_rows(conn: &Connection, table_name: &str, copy: &str) -> Result<(), AppError> {
+    let table = quote_ident(table_name);
+    let copy = quote_ident(copy);
+    let pos = quote_ident(POSITION_COLUMN);
+    let (first, rows): (Option<i64>, i64) = conn
+        .query_row(
+            &format!("SELECT (SELECT MIN({}) FROM {}), (SELECT COUNT(*) FROM {})", pos, copy, table),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+    // Rows going after every row in the table can just be appended
+    if first.unwrap_or(rows) >= rows {
+        return conn
+            .execute_batch(&format!(
+                "INSERT INTO {} BY NAME SELECT * EXCLUDE ({pos}) FROM {} ORDER BY {pos}",
+                table,
+                copy,
+                pos = pos
+            ))
+            .map_err(AppError::from);
+    }
+
+    // The nth copied row has n fewer copied rows ahead of it, so it goes
+    // before the table row at its position less n
+    rebuild_table(
+        conn,
+        table_name,
+        &format!(
+            "SELECT * EXCLUDE (_rats_slot, _rats_existing, {pos}) FROM (
+                SELECT *, row_number() OVER (ORDER BY rowid) - 1 AS _rats_slot, true AS _rats_existing FROM {}
+                UNION ALL BY NAME
+                SELECT *, {pos} - row_number() OVER (ORDER BY {pos}) + 1 AS _rats_slot, false AS _rats_existing FROM {}
+             ) ORDER BY _rats_slot, _rats_existing, {pos}",
+            table,
+            copy,
+            pos = pos
+        ),
+    )
+}
+
+/// Copy the row ids in table order
+fn copy_order(conn: &Connection, table_name: &str, copy: &str) -> DuckResult<()> {
+    conn.execute_batch(&format!(
+        "CREATE TEMP TABLE {} AS SELECT {}, row_number() OVER (ORDER BY rowid) - 1 AS {} FROM {}",
+        quote_ident(copy),
+        quote_ident(ROW_ID_COLUMN),
+        quote_ident(POSITION_COLUMN),
+        quote_ident(table_name)
+    ))
+}
+
+fn column_defs(conn: &Connection, table_name: &str, names: &[String]) -> DuckResult<Vec<ColumnDef>> {
+    let mut stmt = conn.prepare(
+        "SELECT data_type, column_default, is_nullable FROM duckdb_columns()
+         WHERE database_name = current_database() AND table_name = ? AND column_name = ?",
+    )?;
+    names
+        .iter()
+        .map(|name| {
+            stmt.query_row([table_name, name], |row| {
+                Ok(ColumnDef {
+                    name: name.clone(),
+                    data_type: row.get(0)?,
+                    default: row.get(1)?,
+                    nullable: row.get(2)?,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Records what an editor change does as it's made, keeping copies of just
+/// the rows, values or columns it touches. Hands to the work passed to
+/// `UndoJournal::record`.
+pub struct Recorder<'a> {
+    conn: &'a Connection,
+    table_name: String,
+    id: u64,
+    changes: Vec<Change>,
+    skip: bool,
+}
+
+impl Recorder<'_> {
+    fn copy_name(&self) -> String {
+        let copies: usize = self.changes.iter().map(|change| change.copies().len()).sum();
+        format!("{}{}_{}", UNDO_TABLE_PREFIX, self.id, copies + 1)
+    }
+
+    /// Copy the rows `filter` (a WHERE clause, or empty) picks with their
+    /// positions, into a new hidden table
+    fn copy_rows(&self, filter: &str, params: impl Params) -> DuckResult<String> {
+        let name = self.copy_name();
+        let copy = quote_ident(&name);
+        let rows = format!(
+            "(SELECT *, row_number() OVER (ORDER BY rowid) - 1 AS {} FROM {table}) AS {table}",
+            quote_ident(POSITION_COLUMN),
+            table = quote_ident(&self.table_name)
+        );
+        self.conn.execute_batch(&format!("CREATE TEMP TABLE {} AS SELECT * FROM {} LIMIT 0", copy, rows))?;
+        self.conn.execute(&format!("INSERT INTO {} SELECT * FROM {} {}", copy, rows, filter), params)?;
+        Ok(name)
+    }
+
+    /// Delete the rows `filter` picks, keeping copies to put them back.
+    /// Returns how many were deleted.
+    pub fn delete_rows(&mut self, filter: &str, params: impl Params) -> DuckResult<usize> {
+        let copy = self.copy_rows(filter, params)?;
+        let id = quote_ident(ROW_ID_COLUMN);
+        let deleted = self.conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE {id} IN (SELECT {id} FROM {})",
+                quote_ident(&self.table_name),
+                quote_ident(&copy),
+                id = id
+            ),
+            [],
+        )?;
+        self.changes.push(Change::Rows { removed: Some(copy), added: None });
+        Ok(deleted)
+    }
+
+    /// Note rows just added, which `filter` picks
+    pub fn added_rows(&mut self, filter: &str, params: impl Params) -> DuckResult<()> {
+        let copy = self.copy_rows(filter, params)?;
+        self.changes.push(Change::Rows { removed: None, added: Some(copy) });
+        Ok(())
+    }
+
+    /// Copy the values of `columns` in the rows `filter` picks, before
+    /// changing them (or their type)
+    pub fn save_values(&mut self, columns: &[String], filter: &str) -> DuckResult<()> {
+        let copy = self.copy_name();
+        let names: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+        self.conn.execute_batch(&format!(
+            "CREATE TEMP TABLE {} AS SELECT {}, {} FROM {} {}",
+            quote_ident(&copy),
+            quote_ident(ROW_ID_COLUMN),
+            names.join(", "),
+            quote_ident(&self.table_name),
+            filter
+        ))?;
+        let columns = column_defs(self.conn, &self.table_name, columns)?
+            .into_iter()
+            .map(|def| ValueColumn { name: def.name, type_before: def.data_type.clone(), type_after: def.data_type })
+            .collect();
+        // The values after the change are copied once it's made
+        let after = format!("{}_after", copy);
+        self.changes.push(Change::Values { columns, before: copy, after });
+        Ok(())
+    }
+
+    /// Copy columns about to be dropped, to add them back
+    pub fn save_columns(&mut self, columns: &[String]) -> DuckResult<()> {
+        self.copy_columns(columns, false)
+    }
+
+    /// Note columns just added and filled
+    pub fn added_columns(&mut self, columns: &[String]) -> DuckResult<()> {
+        self.copy_columns(columns, true)
+    }
+
+    /// Note a column just added that holds only its default
+    pub fn added_default_column(&mut self, column: &str) -> DuckResult<()> {
+        let columns = column_defs(self.conn, &self.table_name, &[column.to_string()])?;
+        self.changes.push(Change::Columns { columns, copy: None, added: true });
+        Ok(())
+    }
+
+    fn copy_columns(&mut self, columns: &[String], added: bool) -> DuckResult<()> {
+        let copy = self.copy_name();
+        let names: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+        self.conn.execute_batch(&format!(
+            "CREATE TEMP TABLE {} AS SELECT {}, {} FROM {}",
+            quote_ident(&copy),
+            quote_ident(ROW_ID_COLUMN),
+            names.join(", "),
+            quote_ident(&self.table_name)
+        ))?;
+        let columns = column_defs(self.conn, &self.table_name, columns)?;
+        self.changes.push(Change::Columns { columns, copy: Some(copy), added });
+        Ok(())
+    }
+
+    pub fn renamed_column(&mut self, from: &str, to: &str) {
+        self.changes.push(Change::RenameColumn { from: from.to_string(), to: to.to_string() });
+    }
+
+    /// Copy the row order, before sorting or moving rows
+    pub fn save_order(&mut self) -> DuckResult<()> {
+        let copy = self.copy_name();
+        copy_order(self.conn, &self.table_name, &copy)?;
+        let after = format!("{}_after", copy);
+        self.changes.push(Change::Order { before: copy, after });
+        Ok(())
+    }
+
+    /// Copy `table_name`, if it exists, before the change creates or
+    /// replaces it
+    pub fn save_table(&mut self, table_name: &str) -> DuckResult<()> {
+        let copy = self.copy_name();
+        let before = if table_exists(self.conn, table_name)? {
+            self.conn.execute_batch(&format!(
+                "CREATE TEMP TABLE {} AS SELECT * FROM {} ORDER BY rowid",
+                quote_ident(&copy),
+                quote_ident(table_name)
+            ))?;
+            Some(copy.clone())
+        } else {
+            None
+        };
+        let after = format!("{}_after", copy);
+        self.changes.push(Change::Table { name: table_name.to_string(), before, after });
+        Ok(())
+    }
+
+    /// Keep no undo step, as the change turned out to change nothing
+    pub fn skip(&mut self) {
+        self.skip = true;
+    }
+
+    /// Copy what values and order became, now the change is made
+    fn finish(&mut self) -> DuckResult<()> {
+        let table = quote_ident(&self.table_name);
+        let id = quote_ident(ROW_ID_COLUMN);
+        for change in &mut self.changes {
+            match change {
+                Change::Values { columns, before, after } => {
+                    let names: Vec<String> = columns.iter().map(|c| quote_ident(&c.name)).collect();
+                    self.conn.execute_batch(&format!(
+                        "CREATE TEMP TABLE {} AS SELECT {id}, {} FROM {} WHERE {id} IN (SELECT {id} FROM {})",
+                        quote_ident(after),
+                        names.join(", "),
+                        table,
+                        quote_ident(before),
+                        id = id
+                    ))?;
+                    let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+                    for (column, def) in columns.iter_mut().zip(column_defs(self.conn, &self.table_name, &names)?) {
+                        column.type_after = def.data_type;
+                    }
+                }
+                Change::Order { after, .. } => copy_order(self.conn, &self.table_name, after)?,
+                Change::Table { name, after, .. } => self.conn.execute_batch(&format!(
+                    "CREATE TEMP TABLE {} AS SELECT * FROM {} ORDER BY rowid",
+                    quote_ident(after),
+                    quote_ident(name)
+                ))?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Step {
+    entry: UndoEntry,
+    changes: Vec<Change>, // In the order they were made
+}
+
+impl Step {
+    /// Redo (`forward`) or undo the step's changes as one transaction
+    fn apply(&self, conn: &Connection, forward: bool) -> Result<(), AppError> {
+        let table_name = &self.entry.table_name;
+        if !table_exists(conn, table_name)? {
+            return Err(AppError::not_found(format!("Table {} no longer exists", table_name)));
+        }
+        in_transaction(conn, || {
+            if forward {
+                self.changes.iter().try_for_each(|change| change.apply(conn, table_name, true))?;
+            } else {
+                self.changes.iter().rev().try_for_each(|change| change.apply(conn, table_name, false))?;
+            }
+            Ok::<_, AppError>(())
+        })
+        .with_context(|| format!("Failed to restore {}", table_name))
+    }
+
+    fn drop_copies(&self, conn: &Connection) {
+        for change in &self.changes {
+            drop_copies(conn, change);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Steps {
+    next_id: u64,
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+/// Undo and redo for editor commands. Each command records its change as
+/// it makes it (see `Recorder`): copies of the rows it deletes or inserts,
+/// of the values or columns it overwrites or drops, the row order it
+/// replaces and other tables it writes, plus plain inverses for renames.
+/// Copies live in hidden
+/// temporary tables, so they're never saved with the database or in a
+/// session, and go when the app switches databases.
+#[derive(Default)]
+pub struct UndoJournal {
+    steps: Mutex<Steps>,
+}
+
+fn drop_copy(conn: &Connection, copy: &str) {
+    let _ = conn.execute_batch(&format!("DROP TABLE IF EXISTS {}", quote_ident(copy)));
+}
+
+fn drop_copies(conn: &Connection, change: &Change) {
+    for copy in change.copies() {
+        drop_copy(conn, copy);
+    }
+}
+
+impl UndoJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `work`, which changes `table_name` through the `Recorder` or
+    /// notes what it changed there, and keep the change as an undo step if
+    /// it succeeds. Redo steps are dropped, as the change branches off from
+    /// them. The work and its copies make one transaction.
+    pub fn record<T>(
+        &self,
+        conn: &Connection,
+        table_name: &str,
+        operation: &str,
+        work: impl FnOnce(&mut Recorder) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        let id = {
+            let mut steps = self.steps.lock()?;
+            steps.next_id += 1;
+            steps.next_id
+        };
+        // Copies point back at rows by id
+        ensure_row_ids(conn, table_name)?;
+
+        let mut recorder = Recorder {
+            conn,
+            table_name: table_name.to_string(),
+            id,
+            changes: Vec::new(),
+            skip: false,
+        };
+        let result = in_transaction(conn, || {
+            let value = work(&mut recorder)?;
+            recorder.finish()?;
+            Ok::<_, AppError>(value)
+        });
+
+        let steps = match self.steps.lock() {
+            Ok(steps) if result.is_ok() && !recorder.skip && !recorder.changes.is_empty() => Some(steps),
+            _ => None,
+        };
+        let Some(mut steps) = steps else {
+            for change in &recorder.changes {
+                drop_copies(conn, change);
+            }
+            return result;
+        };
+        for step in steps.redo.drain(..) {
+            step.drop_copies(conn);
+        }
+        steps.undo.push(Step {
+            entry: UndoEntry { id, operation: operation.to_string(), table_name: table_name.to_string() },
+            changes: recorder.changes,
+        });
+        while steps.undo.len() > CAPACITY {
+            steps.undo.remove(0).drop_copies(conn);
+        }
+        result
+    }
+
+    /// Undo the latest step, returning it
+    pub fn undo(&self, conn: &Connection) -> Result<UndoEntry, AppError> {
+        let mut steps = self.steps.lock()?;
+        let steps = &mut *steps;
+        Self::step(conn, &mut steps.undo, &mut steps.redo, false)?
+            .ok_or_else(|| AppError::invalid_input("Nothing to undo"))
+    }
+
+    /// Redo the latest undone step, returning it
+    pub fn redo(&self, conn: &Connection) -> Result<UndoEntry, AppError> {
+        let mut steps = self.steps.lock()?;
+        let steps = &mut *steps;
+        Self::step(conn, &mut steps.redo, &mut steps.undo, true)?
+            .ok_or_else(|| AppError::invalid_input("Nothing to redo"))
+    }
+
+    /// Apply the last step in `from` and move it to `to`
+    fn step(
+        conn: &Connection,
+        from: &mut Vec<Step>,
+        to: &mut Vec<Step>,
+        forward: bool,
+    ) -> Result<Option<UndoEntry>, AppError> {
+        let Some(step) = from.pop() else { return Ok(None) };
+        if let Err(e) = step.apply(conn, forward) {
+            from.push(step);
+            return Err(e);
+        }
+        let entry = step.entry.clone();
+        to.push(step);
+        Ok(Some(entry))
+    }
+
+    pub fn stack(&self) -> Result<UndoStack, AppError> {
+        let steps = self.steps.lock()?;
+        Ok(UndoStack {
+            undo: steps.undo.iter().rev().map(|step| step.entry.clone()).collect(),
+            redo: steps.redo.iter().rev().map(|step| step.entry.clone()).collect(),
+        })
+    }
+
+    /// Forget the steps that changed `table_name` and drop their copies,
+    /// once the table itself is dropped
+    pub fn forget_table(&self, conn: &Connection, table_name: &str) {
+        if let Ok(mut steps) = self.steps.lock() {
+            let steps = &mut *steps;
+            for list in [&mut steps.undo, &mut steps.redo] {
+                list.retain(|step| {
+                    // DuckDB matches table names case-insensitively
+                    let kept = step.entry.table_name.to_lowercase() != table_name.to_lowercase();
+                    if !kept {
+                        step.drop_copies(conn);
+                    }
+                    kept
+                });
+            }
+        }
+    }
+
+    /// Forget every step and drop the copies, including any that older
+    /// versions kept in the database file
+    pub fn clear(&self, conn: &Connection) {
+        if let Ok(mut steps) = self.steps.lock() {
+            steps.undo.clear();
+            steps.redo.clear();
+        }
+        if let Ok(copies) = undo_copies(conn) {
+            for copy in copies {
+                drop_copy(conn, &copy);
+            }
+        }
+    }
+}
+
+/// Every undo copy, temporary or in the open database
+fn undo_copies(conn: &Connection) -> DuckResult<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT table_name FROM duckdb_tables()
+         WHERE (temporary OR database_name = current_database()) AND starts_with(table_name, ?)",
+    )?;
+    let copies = stmt
+        .query_map([UNDO_TABLE_PREFIX], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(copies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Table `t` of ten rows, `a` 1 to 10 and `b` its text
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (a INTEGER NOT NULL, b VARCHAR);
+             INSERT INTO t SELECT i, 'row ' || i FROM range(1, 11) r(i);",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// Column `a` in table order
+    fn column_a(conn: &Connection) -> Vec<i32> {
+        let mut stmt = conn.prepare("SELECT a FROM t ORDER BY rowid").unwrap();
+        let values = stmt.query_map([], |row| row.get(0)).unwrap();
+        values.collect::<Result<_, _>>().unwrap()
+    }
+
+    fn text(conn: &Connection, a: i32) -> Option<String> {
+        conn.query_row("SELECT b FROM t WHERE a = ?", [a], |row| row.get(0)).unwrap()
+    }
+
+    fn delete(journal: &UndoJournal, conn: &Connection, filter: &str) -> usize {
+        journal
+            .record(conn, "t", "delete_rows", |recorder| {
+                recorder.delete_rows(filter, []).map_err(AppError::from)
+            })
+            .unwrap()
+    }
+
+    fn set_text(journal: &UndoJournal, conn: &Connection, a: i32, b: &str) {
+        journal
+            .record(conn, "t", "fill_nulls", |recorder| {
+                let filter = format!("WHERE a = {}", a);
+                recorder
+                    .save_values(&["b".to_string()], &filter)
+                    .and_then(|_| conn.execute(&format!("UPDATE t SET b = ? {}", filter), [b]))
+                    .map_err(AppError::from)
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let conn = open();
+        let journal = UndoJournal::new();
+
+        assert_eq!(delete(&journal, &conn, "WHERE a IN (2, 5)"), 2);
+        assert_eq!(delete(&journal, &conn, "WHERE a = 10"), 1);
+        assert_eq!(column_a(&conn), [1, 3, 4, 6, 7, 8, 9]);
+
+        assert_eq!(journal.undo(&conn).unwrap().operation, "delete_rows");
+        assert_eq!(column_a(&conn), [1, 3, 4, 6, 7, 8, 9, 10]);
+        journal.undo(&conn).unwrap();
+        assert_eq!(column_a(&conn), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(text(&conn, 5).as_deref(), Some("row 5"));
+        assert!(journal.undo(&conn).is_err());
+
+        journal.redo(&conn).unwrap();
+        journal.redo(&conn).unwrap();
+        assert_eq!(column_a(&conn), [1, 3, 4, 6, 7, 8, 9]);
+        assert!(journal.redo(&conn).is_err());
+
+        journal.undo(&conn).unwrap();
+        journal.undo(&conn).unwrap();
+        assert_eq!(column_a(&conn), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn undoes_value_changes() {
+        let conn = open();
+        let journal = UndoJournal::new();
+
+        set_text(&journal, &conn, 3, "changed");
+        journal.undo(&conn).unwrap();
+        assert_eq!(text(&conn, 3).as_deref(), Some("row 3"));
+        journal.redo(&conn).unwrap();
+        assert_eq!(text(&conn, 3).as_deref(), Some("changed"));
+    }
+
+    #[test]
+    fn keeps_the_latest_steps_up_to_capacity() {
+        let conn = open();
+        let journal = UndoJournal::new();
+
+        for i in 0..CAPACITY + 5 {
+            set_text(&journal, &conn, 1, &format!("v{}", i));
+        }
+        assert_eq!(journal.stack().unwrap().undo.len(), CAPACITY);
+        // Each step keeps the values before and after
+        let copies = undo_copies(&conn).unwrap();
+        assert_eq!(copies.len(), CAPACITY * 2);
+        assert!(!copies.iter().any(|copy| copy.starts_with(&format!("{}1_", UNDO_TABLE_PREFIX))));
+
+        for _ in 0..CAPACITY {
+            journal.undo(&conn).unwrap();
+        }
+        assert_eq!(text(&conn, 1).as_deref(), Some("v4"));
+        assert!(journal.undo(&conn).is_err());
+    }
+
+    #[test]
+    fn new_change_drops_redo_steps() {
+        let conn = open();
+        let journal = UndoJournal::new();
+
+        set_text(&journal, &conn, 1, "first");
+        set_text(&journal, &conn, 1, "second");
+        journal.undo(&conn).unwrap();
+        assert_eq!(journal.stack().unwrap().redo.len(), 1);
+        assert_eq!(undo_copies(&conn).unwrap().len(), 4);
+
+        delete(&journal, &conn, "WHERE a = 2");
+        let stack = journal.stack().unwrap();
+        assert!(stack.redo.is_empty());
+        assert_eq!(stack.undo.len(), 2);
+        // The undone step's two copies are gone; the delete keeps one
+        assert_eq!(undo_copies(&conn).unwrap().len(), 3);
+        assert!(journal.redo(&conn).is_err());
+    }
+
+    #[test]
+    fn brings_dropped_columns_back() {
+        let conn = open();
+        let journal = UndoJournal::new();
+
+        journal
+            .record(&conn, "t", "drop_column", |recorder| {
+                recorder
+                    .save_columns(&["a".to_string()])
+                    .and_then(|_| conn.execute_batch("ALTER TABLE t DROP COLUMN a"))
+                    .map_err(AppError::from)
+            })
+            .unwrap();
+        journal.undo(&conn).unwrap();
+
+        assert_eq!(column_a(&conn), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(text(&conn, 7).as_deref(), Some("row 7"));
+        let nullable: bool = conn
+            .query_row(
+                "SELECT is_nullable FROM duckdb_columns() WHERE table_name = 't' AND column_name = 'a'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!nullable);
+    }
+
+    #[test]
+    fn forgets_a_dropped_tables_steps() {
+        let conn = open();
+        conn.execute_batch("CREATE TABLE u AS SELECT * FROM t").unwrap();
+        let journal = UndoJournal::new();
+        delete(&journal, &conn, "WHERE a = 1");
+        journal
+            .record(&conn, "u", "delete_rows", |recorder| {
+                recorder.delete_rows("WHERE a = 2", []).map_err(AppError::from)
+            })
+            .unwrap();
+        delete(&journal, &conn, "WHERE a = 3");
+        journal.undo(&conn).unwrap();
+
+        conn.execute_batch("DROP TABLE t").unwrap();
+        journal.forget_table(&conn, "T");
+        let stack = journal.stack().unwrap();
+        assert_eq!(stack.undo.len(), 1);
+        assert_eq!(stack.undo[0].table_name, "u");
+        assert!(stack.redo.is_empty());
+        assert_eq!(undo_copies(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn failed_change_keeps_no_step() {
+        let conn = open();
+        let journal = UndoJournal::new();
+
+        let result = journal.record(&conn, "t", "delete_rows", |recorder| {
+            recorder.delete_rows("WHERE a > 5", [])?;
+            Err::<(), _>(AppError::internal("failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(column_a(&conn).len(), 10);
+        assert!(journal.stack().unwrap().undo.is_empty());
+        assert!(undo_copies(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn copies_stay_out_of_exports() {
+        let conn = open();
+        let journal = UndoJournal::new();
+        delete(&journal, &conn, "WHERE a = 1");
+
+        let directory = std::env::temp_dir().join(format!("rats-undo-export-{}", std::process::id()));
+        conn.execute_batch(&format!("EXPORT DATABASE '{}' (FORMAT PARQUET)", directory.display())).unwrap();
+        let exported: Vec<String> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        let schema = std::fs::read_to_string(directory.join("schema.sql")).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(!exported.iter().any(|name| name.starts_with(UNDO_TABLE_PREFIX)));
+        assert!(!schema.contains(UNDO_TABLE_PREFIX));
+        journal.undo(&conn).unwrap();
+        assert_eq!(column_a(&conn).len(), 10);
+    }
+
+    #[test]
+    fn clear_drops_every_copy() {
+        let conn = open();
+        let journal = UndoJournal::new();
+        delete(&journal, &conn, "WHERE a = 1");
+        set_text(&journal, &conn, 2, "changed");
+
+        journal.clear(&conn);
+        assert!(undo_copies(&conn).unwrap().is_empty());
+        assert!(journal.undo(&conn).is_err());
+    }
+}
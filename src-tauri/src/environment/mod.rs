@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+use duckdb::Result as DuckResult;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub debug_build: bool,
+    pub duckdb_version: String,
+    pub database_mode: String,
+    pub database_path: Option<String>,
+    pub memory_limit: String,
+    pub threads: i64,
+    pub available_cpus: usize,
+    pub loaded_extensions: Vec<String>,
+    pub features: HashMap<String, bool>,
+}
+
+/// Report runtime environment and capabilities so the frontend can toggle
+/// UI features and bug reports carry the essentials
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_environment_info(
+    app: AppHandle,
+) -> Result<EnvironmentInfo, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let duckdb_version: String = conn
+            .query_row("SELECT version()", [], |row| row.get(0))?;
+
+        let memory_limit: String = conn
+            .query_row("SELECT current_setting('memory_limit')", [], |row| row.get(0))?;
+
+        let threads: i64 = conn
+            .query_row("SELECT current_setting('threads')", [], |row| row.get(0))?;
+
+        let loaded_extensions = loaded_extensions(conn)?;
+
+        let mut features = HashMap::new();
+        features.insert("metrics_enabled".to_string(), state.metrics.is_enabled());
+        features.insert("excel_import".to_string(), true);
+        features.insert("excel_export".to_string(), true);
+        features.insert("xml_import".to_string(), true);
+        features.insert("orc_import".to_string(), true);
+        features.insert("remote_export".to_string(), true);
+        features.insert(
+            "json_import".to_string(),
+            loaded_extensions.iter().any(|e| e == "json"),
+        );
+        features.insert(
+            "parquet".to_string(),
+            loaded_extensions.iter().any(|e| e == "parquet"),
+        );
+
+        Ok(EnvironmentInfo {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            debug_build: cfg!(debug_assertions),
+            duckdb_version,
+            database_mode: db.database_mode().to_string(),
+            database_path: db.database_path().map(|p| p.to_string()),
+            memory_limit,
+            threads,
+            available_cpus: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            loaded_extensions,
+            features,
+        })
+    })
+    .await
+}
+
+fn loaded_extensions(conn: &duckdb::Connection) -> DuckResult<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT extension_name FROM duckdb_extensions() WHERE loaded ORDER BY extension_name",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(names)
+}
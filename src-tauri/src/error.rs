@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+/// What went wrong, so the frontend can tell mistakes the user can fix
+/// from failures worth retrying or reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    InvalidInput, // Bad arguments, e.g. an empty name
+    NotFound,     // Missing table, column, file or saved item
+    InvalidQuery, // SQL that doesn't parse or bind
+    Conversion,   // A value that doesn't fit its type
+    Constraint,   // A change that breaks a key or NOT NULL constraint
+    Cancelled,
+    Timeout,
+    OutOfMemory,
+    Io, // Files, URLs and remote storage
+    Internal,
+}
+
+impl ErrorKind {
+    /// Errors the user can fix by changing what they asked for
+    pub fn is_user_error(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::InvalidInput
+                | ErrorKind::NotFound
+                | ErrorKind::InvalidQuery
+                | ErrorKind::Conversion
+                | ErrorKind::Constraint
+        )
+    }
+
+    /// Kind of a DuckDB error, from the error type its message starts with
+    /// ("Catalog Error: ...")
+    fn from_duckdb(message: &str) -> Self {
+        let Some((error_type, detail)) = message.split_once(" Error: ") else {
+            return ErrorKind::Internal;
+        };
+        let missing = detail.contains("does not exist") || detail.contains(" not found");
+        match error_type {
+            "INTERRUPT" => ErrorKind::Cancelled,
+            "Out of Memory" => ErrorKind::OutOfMemory,
+            "Catalog" | "Binder" if missing => ErrorKind::NotFound,
+            "Catalog" if detail.contains("already exists") => ErrorKind::InvalidInput,
+            "Parser" | "Syntax" | "Binder" | "Catalog" => ErrorKind::InvalidQuery,
+            "Conversion" => ErrorKind::Conversion,
+            "Constraint" => ErrorKind::Constraint,
+            "Invalid Input" => ErrorKind::InvalidInput,
+            "IO" | "HTTP" => ErrorKind::Io,
+            _ => ErrorKind::Internal,
+        }
+    }
+
+    fn hint(self) -> Option<&'static str> {
+        match self {
+            ErrorKind::NotFound => Some("Check the name; list_tables shows the tables that exist"),
+            ErrorKind::InvalidQuery => Some("Check the SQL syntax and the table and column names"),
+            ErrorKind::Conversion => Some("A value doesn't fit the column type; try TRY_CAST or a wider type"),
+            ErrorKind::Constraint => Some("The change would break a primary key, unique or NOT NULL constraint"),
+            ErrorKind::Timeout => Some("Allow a longer timeout or narrow the query"),
+            ErrorKind::OutOfMemory => {
+                Some("Raise the memory limit or turn on spilling to disk with set_database_settings")
+            }
+            ErrorKind::Io => Some("Check the path or URL and that it can be read or written"),
+            _ => None,
+        }
+    }
+}
+
+/// The error every command returns, serialized to the frontend as
+/// `{ kind, message, sql, hint }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub sql: Option<String>, // The statement that failed, for SQL the user wrote
+    pub hint: Option<String>,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            sql: None,
+            hint: kind.hint().map(str::to_string),
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Cancelled, message)
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Io, message)
+    }
+
+    /// Attach the SQL that failed
+    pub fn with_sql(self, sql: impl Into<String>) -> Self {
+        Self { sql: Some(sql.into()), ..self }
+    }
+
+    /// Put what was being done in front of the message, keeping the kind:
+    /// "Failed to drop column a: ..."
+    pub fn context(self, context: impl std::fmt::Display) -> Self {
+        Self { message: format!("{}: {}", context, self.message), ..self }
+    }
+}
+
+/// `context` for results whose error converts into an `AppError`
+pub trait ErrorContext<T> {
+    fn context(self, context: &str) -> Result<T, AppError>;
+    /// Like `context`, building the text only on failure
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T, AppError>;
+}
+
+impl<T, E: Into<AppError>> ErrorContext<T> for Result<T, E> {
+    fn context(self, context: &str) -> Result<T, AppError> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T, AppError> {
+        self.map_err(|e| e.into().context(context()))
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<duckdb::Error> for AppError {
+    fn from(error: duckdb::Error) -> Self {
+        let message = error.to_string();
+        Self::new(ErrorKind::from_duckdb(&message), message)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        Self::io(error.to_string())
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for AppError {
+    fn from(error: std::sync::PoisonError<T>) -> Self {
+        Self::internal(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::internal(error.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(error: tauri::Error) -> Self {
+        Self::internal(error.to_string())
+    }
+}
@@ -0,0 +1,113 @@
+use encoding_rs::{EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use crate::error::AppError;
+
+const BUFFER_BYTES: usize = 64 * 1024;
+
+/// Resolve an encoding label such as `windows-1252` or `shift_jis`
+pub(super) fn output_encoding(label: &str) -> Result<&'static Encoding, AppError> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| AppError::invalid_input(format!("Unknown encoding: {}", label)))
+}
+
+/// Rewrite the UTF-8 CSV DuckDB produced at `input` into `output` in the
+/// requested encoding, optionally with CRLF record endings and a byte order
+/// mark (for UTF-8 and UTF-16). Line breaks inside quoted values are left
+/// alone. Characters the encoding can't represent are written as `?`;
+/// returns how many were.
+pub(super) fn rewrite_csv(
+    input: &Path,
+    output: &Path,
+    encoding: &'static Encoding,
+    crlf: bool,
+    bom: bool,
+    quote: char,
+) -> std::io::Result<usize> {
+    let mut reader = File::open(input)?;
+    let mut writer = BufWriter::new(File::create(output)?);
+    // encoding_rs only decodes UTF-16 (its encoders write UTF-8), so UTF-16 is encoded here
+    let utf16: Option<fn(u16) -> [u8; 2]> = if encoding == UTF_16LE {
+        Some(u16::to_le_bytes)
+    } else if encoding == UTF_16BE {
+        Some(u16::to_be_bytes)
+    } else {
+        None
+    };
+    match utf16 {
+        Some(to_bytes) if bom => writer.write_all(&to_bytes(0xFEFF))?,
+        None if bom && encoding == UTF_8 => writer.write_all(b"\xEF\xBB\xBF")?,
+        _ => {}
+    }
+
+    let mut decoder = UTF_8.new_decoder_without_bom_handling();
+    let mut encoder = encoding.new_encoder();
+    let mut buffer = vec![0u8; BUFFER_BYTES];
+    let mut encoded = vec![0u8; BUFFER_BYTES];
+    let mut decoded = String::with_capacity(BUFFER_BYTES * 3);
+    let mut text = String::with_capacity(BUFFER_BYTES * 3);
+    let mut in_quotes = false;
+    let mut previous = '\0';
+    let mut replaced = 0;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        let last = read == 0;
+
+        let mut consumed = 0;
+        loop {
+            let (result, used, _) = decoder.decode_to_string(&buffer[consumed..read], &mut decoded, last);
+            consumed += used;
+
+            text.clear();
+            for c in decoded.chars() {
+                if c == quote {
+                    in_quotes = !in_quotes;
+                } else if crlf && c == '\n' && !in_quotes && previous != '\r' {
+                    text.push('\r');
+                }
+                text.push(c);
+                previous = c;
+            }
+            decoded.clear();
+
+            if let Some(to_bytes) = utf16 {
+                let mut units = [0u16; 2];
+                for c in text.chars() {
+                    for unit in c.encode_utf16(&mut units) {
+                        writer.write_all(&to_bytes(*unit))?;
+                    }
+                }
+                text.clear();
+            }
+            let mut remaining = text.as_str();
+            loop {
+                let (result, read, written) =
+                    encoder.encode_from_utf8_without_replacement(remaining, &mut encoded, last);
+                writer.write_all(&encoded[..written])?;
+                remaining = &remaining[read..];
+                match result {
+                    EncoderResult::InputEmpty => break,
+                    EncoderResult::OutputFull => {}
+                    EncoderResult::Unmappable(_) => {
+                        replaced += 1;
+                        writer.write_all(b"?")?;
+                    }
+                }
+            }
+
+            if result == encoding_rs::CoderResult::InputEmpty {
+                break;
+            }
+            decoded.reserve(BUFFER_BYTES * 3);
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    writer.flush()?;
+    Ok(replaced)
+}
@@ -1,7 +1,24 @@
+use duckdb::arrow::datatypes::Schema;
+use duckdb::arrow::ipc::writer::FileWriter;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tauri::State;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 use crate::AppState;
+use crate::duckdb_core::{self, is_list_type, quote_ident, ROW_ID_COLUMN};
+use crate::error::{AppError, ErrorContext};
+use crate::import::ColumnSelection;
+
+mod encoding;
+mod sidecar;
+mod sink;
+mod workbook;
+
+pub use sink::{ExportDestination, SftpDestination};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportResult {
@@ -26,162 +43,2042 @@ pub enum ExportError {
     Custom(String),
 }
 
-/// Export table to CSV
+/// Progress of a running export, emitted as `export-progress` events
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub rows_written: usize,
+    pub total_rows: Option<usize>,
+    pub bytes_written: Option<u64>,
+    pub eta_secs: Option<f64>,
+    pub status: String,
+}
+
+impl ExportProgress {
+    /// Add an ETA from the share of the work done since `started`
+    fn with_eta(mut self, started: Instant, done: u64, total: u64) -> Self {
+        if done > 0 && total > done {
+            let elapsed = started.elapsed().as_secs_f64();
+            self.eta_secs = Some((total - done) as f64 * elapsed / done as f64);
+        }
+        self
+    }
+}
+
+/// Tracks the running export so `cancel_export` can stop it
+#[derive(Debug, Default)]
+pub struct ExportControl {
+    active: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+/// Marks an export as running until dropped
+pub struct ExportGuard<'a> {
+    control: &'a ExportControl,
+}
+
+impl ExportControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin(&self) -> ExportGuard<'_> {
+        self.cancelled.store(false, Ordering::SeqCst);
+        self.active.store(true, Ordering::SeqCst);
+        ExportGuard { control: self }
+    }
+
+    /// Request cancellation; returns false when no export is running
+    pub fn cancel(&self) -> bool {
+        if !self.active.load(Ordering::SeqCst) {
+            return false;
+        }
+        self.cancelled.store(true, Ordering::SeqCst);
+        true
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for ExportGuard<'_> {
+    fn drop(&mut self) {
+        self.control.active.store(false, Ordering::SeqCst);
+    }
+}
+
+/// An export file written next to its destination and renamed over it by
+/// `persist` once complete, so a failed export never touches a file the user
+/// already had there. Dropped without persisting, the staged file is removed.
+struct StagedFile {
+    staged: PathBuf,
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl StagedFile {
+    fn new(path: &Path) -> Self {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        // Prefixed rather than suffixed, keeping the extension DuckDB reads
+        // the format and compression from
+        let staged = path.with_file_name(format!(".rats-{}-{}", std::process::id(), name));
+        Self { staged, path: path.to_path_buf(), persisted: false }
+    }
+
+    /// Where to write the export
+    fn staged_path(&self) -> &Path {
+        &self.staged
+    }
+
+    /// Move the finished file into place, replacing any file there
+    fn persist(mut self) -> Result<(), AppError> {
+        std::fs::rename(&self.staged, &self.path)
+            .with_context(|| format!("Failed to replace {}", self.path.display()))?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.staged);
+        }
+    }
+}
+
+/// Emit the final `cancelled` event when the export was stopped; otherwise
+/// just report the error. Partly written output goes with its `StagedFile`.
+fn export_failure(control: &ExportControl, window: &tauri::Window, error: AppError) -> AppError {
+    if control.is_cancelled() {
+        let _ = window.emit("export-progress", ExportProgress {
+            status: "cancelled".to_string(),
+            ..Default::default()
+        });
+        return AppError::cancelled("Export cancelled");
+    }
+    error
+}
+
+/// Rows written between Excel progress events
+const EXCEL_PROGRESS_ROWS: usize = 10_000;
+
+/// How often the output file is checked while DuckDB writes a CSV
+const CSV_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Rows written to a scratch file to estimate the size of a CSV export
+const CSV_SIZE_SAMPLE_ROWS: usize = 1_000;
+
+/// Estimate the finished size of a CSV export by writing a sample of its
+/// rows with the same options. Returns `None` when that isn't possible.
+fn estimate_csv_bytes(
+    conn: &duckdb::Connection,
+    select: &str,
+    copy_options: &str,
+    total_rows: usize,
+) -> Option<u64> {
+    if total_rows == 0 {
+        return None;
+    }
+    let sample_path = std::env::temp_dir().join(format!("rats_export_sample_{}.csv", std::process::id()));
+    let sample_rows = conn
+        .execute(
+            &format!(
+                "COPY ({} LIMIT {}) TO '{}' ({})",
+                select,
+                CSV_SIZE_SAMPLE_ROWS,
+                sample_path.to_str()?.replace('\'', "''"),
+                copy_options
+            ),
+            [],
+        )
+        .ok()?;
+    let sample_bytes = std::fs::metadata(&sample_path).ok()?.len();
+    let _ = std::fs::remove_file(&sample_path);
+    if sample_rows == 0 {
+        return None;
+    }
+    Some(sample_bytes * total_rows as u64 / sample_rows as u64)
+}
+
+/// Run a CSV `COPY` while a second thread reports the bytes written so far,
+/// estimating rows and time left from a sample of the output
+fn copy_csv_with_progress(
+    conn: &duckdb::Connection,
+    window: &tauri::Window,
+    select: &str,
+    path: &Path,
+    copy_options: &str,
+    total_rows: usize,
+) -> Result<(), AppError> {
+    let path_str = path.to_str().ok_or_else(|| AppError::invalid_input("Invalid path"))?;
+    let estimated_bytes = estimate_csv_bytes(conn, select, copy_options, total_rows);
+    let started = Instant::now();
+    let finished = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            while !finished.load(Ordering::SeqCst) {
+                std::thread::sleep(CSV_PROGRESS_INTERVAL);
+                let Ok(metadata) = std::fs::metadata(path) else {
+                    continue;
+                };
+                let bytes_written = metadata.len();
+                let progress = ExportProgress {
+                    rows_written: estimated_bytes
+                        .filter(|total| *total > 0)
+                        .map(|total| (total_rows as u64 * bytes_written.min(total) / total) as usize)
+                        .unwrap_or(0),
+                    total_rows: Some(total_rows),
+                    bytes_written: Some(bytes_written),
+                    eta_secs: None,
+                    status: format!("Writing CSV... {} bytes", bytes_written),
+                };
+                let progress = match estimated_bytes {
+                    Some(total) => progress.with_eta(started, bytes_written, total),
+                    None => progress,
+                };
+                let _ = window.emit("export-progress", progress);
+            }
+        });
+
+        let result = conn
+            .execute(
+                &format!("COPY ({}) TO '{}' ({})", select, path_str.replace('\'', "''"), copy_options),
+                [],
+            )
+            .map(|_| ())
+            .context("Export error");
+        finished.store(true, Ordering::SeqCst);
+        result
+    })?;
+
+    let _ = window.emit("export-progress", ExportProgress {
+        rows_written: total_rows,
+        total_rows: Some(total_rows),
+        bytes_written: std::fs::metadata(path).ok().map(|m| m.len()),
+        eta_secs: None,
+        status: "Export complete!".to_string(),
+    });
+    Ok(())
+}
+
+/// Compression applied by DuckDB while writing a CSV file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl CsvCompression {
+    /// File extension for parts of a split export
+    fn extension(self) -> &'static str {
+        match self {
+            CsvCompression::None => "csv",
+            CsvCompression::Gzip => "csv.gz",
+            CsvCompression::Zstd => "csv.zst",
+        }
+    }
+}
+
+/// Which values a CSV export wraps in quotes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvQuoting {
+    #[default]
+    Minimal, // Only values containing the delimiter, a quote or a line break
+    All,
+    NonNumeric, // Every value of a non-numeric column
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+/// Formatting for CSV exports
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvExportOptions {
+    pub delimiter: Option<String>, // Defaults to ","; "\t" for TSV
+    pub quote: Option<String>,     // Defaults to '"'
+    pub quoting: CsvQuoting,
+    pub null_value: Option<String>,       // Written for NULLs; empty by default
+    pub date_format: Option<String>,      // strftime format, e.g. "%d.%m.%Y"
+    pub timestamp_format: Option<String>, // strftime format, e.g. "%d.%m.%Y %H:%M"
+    pub line_ending: LineEnding,
+    pub encoding: Option<String>, // e.g. "windows-1252"; UTF-8 when unset
+    pub bom: bool,                // Start UTF-8 or UTF-16 output with a byte order mark
+}
+
+impl CsvExportOptions {
+    /// Whether DuckDB's UTF-8, LF output has to be rewritten afterwards
+    fn needs_rewrite(&self) -> bool {
+        self.line_ending == LineEnding::Crlf
+            || self.bom
+            || self
+                .encoding
+                .as_deref()
+                .is_some_and(|label| encoding::output_encoding(label).ok() != Some(encoding_rs::UTF_8))
+    }
+}
+
+/// Single-character CSV option as a SQL literal body, accepting `\t` for tab
+fn csv_char_option(name: &str, value: &Option<String>) -> Result<Option<String>, AppError> {
+    match value {
+        None => Ok(None),
+        Some(v) if v == "\\t" || v == "\t" => Ok(Some("\\t".to_string())),
+        Some(v) if v.chars().count() == 1 => Ok(Some(v.replace('\'', "''"))),
+        Some(v) => Err(AppError::invalid_input(format!("CSV {} must be a single character, got '{}'", name, v))),
+    }
+}
+
+/// Limits for splitting a CSV export into several files; set either or both
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SplitOptions {
+    pub max_rows: Option<usize>,
+    pub max_megabytes: Option<f64>,
+}
+
+/// Scratch table a split export is materialized into, so every part is a
+/// range of the same rows in a fixed order
+const SPLIT_TABLE: &str = "_rats_export_split";
+
+/// Write the rows in `SPLIT_TABLE` as `part-0001.csv`, `part-0002.csv`, ...
+/// in `dir`, each part a `rowid` range. Sized parts start from a sampled row
+/// width and shrink any part that comes out too large, so every file stays
+/// under the limit. Each file is staged in `parts` and only moved into place
+/// once every part is written.
+#[allow(clippy::too_many_arguments)]
+fn copy_csv_parts(
+    conn: &duckdb::Connection,
+    window: &tauri::Window,
+    control: &ExportControl,
+    dir: &Path,
+    compression: CsvCompression,
+    copy_options: &str,
+    total_rows: usize,
+    split: &SplitOptions,
+    parts: &mut Vec<StagedFile>,
+) -> Result<(), AppError> {
+    let max_bytes = split.max_megabytes.map(|mb| (mb * 1024.0 * 1024.0) as u64);
+    if split.max_rows.is_none() && max_bytes.is_none() {
+        return Err(AppError::invalid_input("Give a row or size limit to split the export on"));
+    }
+    if split.max_rows == Some(0) || max_bytes == Some(0) {
+        return Err(AppError::invalid_input("Split limits must be greater than zero"));
+    }
+    let max_rows = split.max_rows.unwrap_or(usize::MAX);
+    let select = format!("SELECT * FROM {} ORDER BY rowid", SPLIT_TABLE);
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut part_rows = match max_bytes {
+        Some(max_bytes) => estimate_csv_bytes(conn, &select, copy_options, total_rows)
+            .map(|total| (max_bytes as f64 * total_rows as f64 / total.max(1) as f64 * 0.9) as usize)
+            .unwrap_or(CSV_SIZE_SAMPLE_ROWS),
+        None => max_rows,
+    }
+    .clamp(1, max_rows);
+
+    let started = Instant::now();
+    let mut offset = 0;
+    let mut bytes_written = 0;
+    while offset < total_rows || parts.is_empty() {
+        if control.is_cancelled() {
+            return Err(AppError::cancelled("Export cancelled"));
+        }
+        let part = StagedFile::new(&dir.join(format!("part-{:04}.{}", parts.len() + 1, compression.extension())));
+        let part_path = part.staged_path().to_path_buf();
+        parts.push(part);
+        let path_str = part_path.to_str().ok_or_else(|| AppError::invalid_input("Invalid path"))?.replace('\'', "''");
+
+        let part_bytes = loop {
+            conn.execute(
+                &format!(
+                    "COPY (SELECT * FROM {} WHERE rowid >= {} AND rowid < {} ORDER BY rowid) TO '{}' ({})",
+                    SPLIT_TABLE,
+                    offset,
+                    offset.saturating_add(part_rows),
+                    path_str,
+                    copy_options
+                ),
+                [],
+            )
+            .context("Export error")?;
+            let part_bytes = std::fs::metadata(&part_path)?.len();
+
+            match max_bytes {
+                Some(max_bytes) if part_bytes > max_bytes => {
+                    if part_rows == 1 {
+                        return Err(AppError::invalid_input(format!(
+                            "Row {} alone is larger than the {} MB split size",
+                            offset + 1,
+                            split.max_megabytes.unwrap_or_default()
+                        )));
+                    }
+                    // Too big: retry with proportionally fewer rows
+                    part_rows = ((part_rows as f64 * max_bytes as f64 / part_bytes as f64 * 0.9) as usize).max(1);
+                }
+                _ => break part_bytes,
+            }
+        };
+
+        let written = part_rows.min(total_rows - offset);
+        offset += written;
+        bytes_written += part_bytes;
+
+        // Grow back towards the size limit when rows came out narrower
+        if let Some(max_bytes) = max_bytes {
+            if written > 0 && part_bytes > 0 {
+                part_rows = ((written as f64 * max_bytes as f64 / part_bytes as f64 * 0.9) as usize).clamp(1, max_rows);
+            }
+        }
+
+        let _ = window.emit(
+            "export-progress",
+            ExportProgress {
+                rows_written: offset,
+                total_rows: Some(total_rows),
+                bytes_written: Some(bytes_written),
+                eta_secs: None,
+                status: format!("Wrote part {} ({} rows)", parts.len(), written),
+            }
+            .with_eta(started, offset as u64, total_rows as u64),
+        );
+    }
+    Ok(())
+}
+
+/// Write a CSV export to `path`, or to numbered part files in the `path`
+/// directory when `split` is set. Returns a description of the output.
+#[allow(clippy::too_many_arguments)]
+fn write_csv_export(
+    conn: &duckdb::Connection,
+    window: &tauri::Window,
+    control: &ExportControl,
+    select: &str,
+    path: &Path,
+    include_header: bool,
+    compression: CsvCompression,
+    csv: &CsvExportOptions,
+    total_rows: usize,
+    split: Option<&SplitOptions>,
+) -> Result<String, AppError> {
+    let force_quote = force_quote_columns(conn, select, csv.quoting)?;
+    let copy_options = csv_copy_options(include_header, compression, csv, force_quote.as_deref())?;
+    let _guard = control.begin();
+
+    if csv.needs_rewrite() {
+        if split.is_some() || compression != CsvCompression::None {
+            return Err(AppError::invalid_input(
+                "Encoding, CRLF and byte order mark options can't be combined with compression or split files",
+            ));
+        }
+        let encoding = match &csv.encoding {
+            Some(label) => encoding::output_encoding(label)?,
+            None => encoding_rs::UTF_8,
+        };
+        let quote = csv.quote.as_ref().and_then(|q| q.chars().next()).unwrap_or('"');
+
+        // DuckDB writes UTF-8 with LF endings, so rewrite its output
+        let raw = std::env::temp_dir().join(format!("rats_export_{}.csv", std::process::id()));
+        let output = StagedFile::new(path);
+        let rewritten = copy_csv_with_progress(conn, window, select, &raw, &copy_options, total_rows).and_then(|_| {
+            let crlf = csv.line_ending == LineEnding::Crlf;
+            encoding::rewrite_csv(&raw, output.staged_path(), encoding, crlf, csv.bom, quote)
+                .with_context(|| format!("Failed to write {}", path.display()))
+        });
+        let _ = std::fs::remove_file(&raw);
+        let replaced = rewritten.map_err(|e| export_failure(control, window, e))?;
+        output.persist()?;
+
+        return Ok(if replaced > 0 {
+            format!(
+                "{} CSV ({} characters with no {} equivalent were written as '?')",
+                encoding.name(),
+                replaced,
+                encoding.name()
+            )
+        } else {
+            format!("{} CSV", encoding.name())
+        });
+    }
+
+    match split {
+        Some(split) => {
+            // Run the query once; paging it per part would re-run it each time with no fixed order
+            conn.execute_batch(&format!("CREATE OR REPLACE TEMP TABLE {} AS {}", SPLIT_TABLE, select))
+                .map_err(|e| export_failure(control, window, AppError::from(e).context("Export error")))?;
+            let total_rows = conn
+                .query_row(&format!("SELECT count(*) FROM {}", SPLIT_TABLE), [], |row| row.get::<_, i64>(0))
+                .map(|count| count as usize)
+                .unwrap_or(total_rows);
+            let mut parts = Vec::new();
+            let written = copy_csv_parts(
+                conn, window, control, path, compression, &copy_options, total_rows, split, &mut parts,
+            );
+            let _ = conn.execute_batch(&format!("DROP TABLE IF EXISTS {}", SPLIT_TABLE));
+            written.map_err(|e| export_failure(control, window, e))?;
+            let count = parts.len();
+            for part in parts {
+                part.persist()?;
+            }
+            Ok(format!("{} CSV files", count))
+        }
+        None => {
+            let output = StagedFile::new(path);
+            copy_csv_with_progress(conn, window, select, output.staged_path(), &copy_options, total_rows)
+                .map_err(|e| export_failure(control, window, e))?;
+            output.persist()?;
+            Ok("CSV".to_string())
+        }
+    }
+}
+
+/// Options list for `COPY ... TO ... (FORMAT CSV, ...)`. `force_quote` is
+/// `*` or a column list for `FORCE_QUOTE`.
+fn csv_copy_options(
+    include_header: bool,
+    compression: CsvCompression,
+    csv: &CsvExportOptions,
+    force_quote: Option<&str>,
+) -> Result<String, AppError> {
+    let literal = |value: &str| format!("'{}'", value.replace('\'', "''"));
+
+    let mut options = vec!["FORMAT CSV".to_string()];
+    if include_header {
+        options.push("HEADER".to_string());
+    }
+    match compression {
+        CsvCompression::None => {}
+        CsvCompression::Gzip => options.push("COMPRESSION gzip".to_string()),
+        CsvCompression::Zstd => options.push("COMPRESSION zstd".to_string()),
+    }
+    if let Some(delimiter) = csv_char_option("delimiter", &csv.delimiter)? {
+        options.push(format!("DELIMITER '{}'", delimiter));
+    }
+    if let Some(quote) = csv_char_option("quote", &csv.quote)? {
+        options.push(format!("QUOTE '{}'", quote));
+    }
+    if let Some(null_value) = &csv.null_value {
+        options.push(format!("NULL {}", literal(null_value)));
+    }
+    if let Some(date_format) = &csv.date_format {
+        options.push(format!("DATEFORMAT {}", literal(date_format)));
+    }
+    if let Some(timestamp_format) = &csv.timestamp_format {
+        options.push(format!("TIMESTAMPFORMAT {}", literal(timestamp_format)));
+    }
+    if let Some(force_quote) = force_quote {
+        options.push(format!("FORCE_QUOTE {}", force_quote));
+    }
+    Ok(options.join(", "))
+}
+
+/// `FORCE_QUOTE` argument for the quoting mode, or `None` for minimal quoting
+fn force_quote_columns(
+    conn: &duckdb::Connection,
+    select: &str,
+    quoting: CsvQuoting,
+) -> Result<Option<String>, AppError> {
+    match quoting {
+        CsvQuoting::Minimal => Ok(None),
+        CsvQuoting::All => Ok(Some("*".to_string())),
+        CsvQuoting::NonNumeric => {
+            let mut describe = conn
+                .prepare(&format!("DESCRIBE {}", select))
+                .context("Query error")?;
+            let columns = describe
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+                .context("Query error")?
+                .collect::<Result<Vec<_>, _>>()
+                .context("Query error")?;
+            let quoted: Vec<String> = columns
+                .into_iter()
+                .filter(|(_, data_type)| !cell_kind(data_type).0.is_number())
+                .map(|(name, _)| quote_ident(&name))
+                .collect();
+            Ok((!quoted.is_empty()).then(|| format!("({})", quoted.join(", "))))
+        }
+    }
+}
+
+/// Rows of a table or view to export. Leave everything unset to export all
+/// rows; otherwise give either a range or the selected rows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RowSelection {
+    pub offset: Option<usize>, // First row of a range, 0-based
+    pub limit: Option<usize>,
+    pub row_ids: Option<Vec<i64>>, // Hidden row ids of the selected rows, as in `QueryResult::row_ids`
+}
+
+/// FROM clause source for the selected rows of `table_name`, without the
+/// hidden row id column
+fn export_source(
+    conn: &duckdb::Connection,
+    table_name: &str,
+    selection: Option<&RowSelection>,
+) -> Result<String, AppError> {
+    let table = duckdb_core::visible_source(conn, table_name)?;
+    let Some(selection) = selection else {
+        return Ok(table);
+    };
+
+    match &selection.row_ids {
+        Some(_) if selection.offset.is_some() || selection.limit.is_some() => {
+            Err(AppError::invalid_input("Export either a row range or selected rows, not both"))
+        }
+        Some(ids) if ids.is_empty() => Err(AppError::invalid_input("No rows selected")),
+        Some(_) if !duckdb_core::has_row_ids(conn, table_name)? => {
+            Err(AppError::invalid_input(format!(
+                "{} has no row ids to select rows by; export a row range instead",
+                table_name
+            )))
+        }
+        Some(ids) => {
+            let list = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ");
+            Ok(format!(
+                "(SELECT * EXCLUDE ({id}) FROM {table} WHERE {id} IN ({list}) ORDER BY rowid) AS selected_rows",
+                id = quote_ident(ROW_ID_COLUMN),
+                table = quote_ident(table_name),
+                list = list
+            ))
+        }
+        None if selection.offset.is_none() && selection.limit.is_none() => Ok(table),
+        None => Ok(format!(
+            "(SELECT * FROM {}{} OFFSET {}) AS selected_rows",
+            table,
+            selection.limit.map(|l| format!(" LIMIT {}", l)).unwrap_or_default(),
+            selection.offset.unwrap_or(0)
+        )),
+    }
+}
+
+/// FROM clause source for the results of `query`, without the hidden row id
+/// column a `SELECT *` from a table brings along
+fn query_source(conn: &duckdb::Connection, query: &str) -> Result<String, AppError> {
+    let source = format!("({}) AS query_result", query);
+    let mut describe = conn
+        .prepare(&format!("DESCRIBE SELECT * FROM {}", source))
+        .context("Query error")?;
+    let has_row_ids = describe
+        .query_map([], |row| row.get::<_, String>(0))
+        .context("Query error")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Query error")?
+        .iter()
+        .any(|name| name == ROW_ID_COLUMN);
+    Ok(if has_row_ids {
+        format!("(SELECT * EXCLUDE ({}) FROM {}) AS query_result", quote_ident(ROW_ID_COLUMN), source)
+    } else {
+        source
+    })
+}
+
+/// Narrow `source` to `columns`, in the given order and under their new
+/// header names. Without a list every column is exported as is.
+fn select_columns(source: String, columns: Option<&[ColumnSelection]>) -> Result<String, AppError> {
+    let Some(columns) = columns else {
+        return Ok(source);
+    };
+    if columns.is_empty() {
+        return Err(AppError::invalid_input("No columns selected"));
+    }
+
+    let mut headers = HashSet::new();
+    for column in columns {
+        if !headers.insert(column.target_name().to_lowercase()) {
+            return Err(AppError::invalid_input(format!("Duplicate column header: {}", column.target_name())));
+        }
+    }
+    let select_list = columns
+        .iter()
+        .map(|c| format!("{} AS {}", quote_ident(&c.source), quote_ident(c.target_name())))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(format!("(SELECT {} FROM {}) AS selected_columns", select_list, source))
+}
+
+/// Export a table or view to CSV, optionally only a range or selection of
+/// rows. `compression` writes gzip or zstd compressed output; with `split`,
+/// `file_path` is a directory that receives `part-0001.csv`, ... files.
+/// `options` sets the delimiter, quoting, NULL text, date formats, line
+/// endings and encoding. `columns` picks, orders and renames the columns.
+/// `write_metadata` adds a `.meta.json` sidecar describing the export.
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_to_csv(
-    state: State<'_, AppState>,
+    app: AppHandle,
+    window: tauri::Window,
     table_name: String,
     file_path: String,
     include_header: Option<bool>,
-) -> Result<ExportResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
-
-    let path = PathBuf::from(&file_path);
-    let include_header = include_header.unwrap_or(true);
-
-    // Use DuckDB's COPY TO for efficient CSV export
-    let header_option = if include_header { "HEADER" } else { "" };
-
-    let copy_query = format!(
-        "COPY {} TO '{}' (FORMAT CSV, {})",
-        table_name,
-        path.to_str().ok_or("Invalid path")?,
-        header_option
-    );
-
-    conn.execute(&copy_query, [])
-        .map_err(|e| format!("Export error: {}", e))?;
-
-    // Get row count
-    let count_query = format!("SELECT COUNT(*) FROM {}", table_name);
-    let rows_exported: usize = conn
-        .query_row(&count_query, [], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
-
-    Ok(ExportResult {
-        success: true,
-        message: format!("Successfully exported {} rows to CSV", rows_exported),
-        file_path: file_path.clone(),
-        rows_exported,
+    selection: Option<RowSelection>,
+    compression: Option<CsvCompression>,
+    split: Option<SplitOptions>,
+    options: Option<CsvExportOptions>,
+    columns: Option<Vec<ColumnSelection>>,
+    write_metadata: Option<bool>,
+) -> Result<ExportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let path = PathBuf::from(&file_path);
+        let include_header = include_header.unwrap_or(true);
+        let source = select_columns(export_source(conn, &table_name, selection.as_ref())?, columns.as_deref())?;
+
+        // Get row count
+        let count_query = format!("SELECT COUNT(*) FROM {}", source);
+        let rows_exported: usize = conn
+            .query_row(&count_query, [], |row| row.get(0))?;
+
+        // Use DuckDB's COPY TO for efficient CSV export
+        let output = write_csv_export(
+            conn,
+            &window,
+            &state.exports,
+            &format!("SELECT * FROM {}", source),
+            &path,
+            include_header,
+            compression.unwrap_or_default(),
+            &options.unwrap_or_default(),
+            rows_exported,
+            split.as_ref(),
+        )?;
+        if write_metadata.unwrap_or(false) {
+            sidecar::write_sidecar(conn, &source, &path, rows_exported, sidecar::ExportProvenance {
+                table_name: Some(&table_name),
+                query: None,
+                format: "csv",
+                selection: selection.as_ref(),
+                columns: columns.as_deref(),
+            })?;
+        }
+
+        state.metrics.record("export_to_csv", started, Some(rows_exported), true);
+
+        Ok(ExportResult {
+            success: true,
+            message: format!("Successfully exported {} rows to {}", rows_exported, output),
+            file_path: file_path.clone(),
+            rows_exported,
+        })
     })
+    .await
+}
+
+/// Excel's row limit, including the header row
+const EXCEL_MAX_ROWS: usize = 1_048_576;
+
+/// Widest column auto-fit will produce, in characters
+const MAX_COLUMN_WIDTH: usize = 60;
+
+/// Formatting for Excel exports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcelExportOptions {
+    pub date_format: String,     // Excel number format for DATE columns
+    pub datetime_format: String, // Excel number format for TIMESTAMP columns
+    pub number_format: Option<String>, // Overrides the per-type default for numeric columns
+    pub freeze_header: bool,
+    pub autofilter: bool,
+    pub auto_fit: bool, // Size columns to their longest value
+}
+
+impl Default for ExcelExportOptions {
+    fn default() -> Self {
+        Self {
+            date_format: "yyyy-mm-dd".to_string(),
+            datetime_format: "yyyy-mm-dd hh:mm:ss".to_string(),
+            number_format: None,
+            freeze_header: true,
+            autofilter: false,
+            auto_fit: true,
+        }
+    }
+}
+
+/// What `export_to_excel` does with the file at `file_path`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExcelExportMode {
+    #[default]
+    Replace, // Write a new workbook, overwriting any existing file
+    AddSheet,   // Add the data as a new sheet of an existing workbook
+    AppendRows, // Add the rows below the data of an existing sheet
+}
+
+/// How a column is written to Excel
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellKind {
+    Number,
+    ExactNumber, // May have more digits than Excel keeps; those are written as text
+    Date,
+    DateTime,
+    Boolean,
+    Text,
+}
+
+impl CellKind {
+    fn is_number(self) -> bool {
+        matches!(self, CellKind::Number | CellKind::ExactNumber)
+    }
+}
+
+/// Significant digits Excel stores; it silently rounds anything longer
+const EXCEL_DIGITS: usize = 15;
+
+/// Classify a DuckDB type and pick the default Excel number format for it
+fn cell_kind(data_type: &str) -> (CellKind, Option<String>) {
+    let upper = data_type.to_uppercase();
+    match upper.as_str() {
+        "TINYINT" | "SMALLINT" | "INTEGER" | "UTINYINT" | "USMALLINT" | "UINTEGER" => {
+            (CellKind::Number, Some("0".to_string()))
+        }
+        "BIGINT" | "HUGEINT" | "UBIGINT" | "UHUGEINT" => (CellKind::ExactNumber, Some("0".to_string())),
+        "FLOAT" | "DOUBLE" => (CellKind::Number, None),
+        "DATE" => (CellKind::Date, None),
+        "BOOLEAN" => (CellKind::Boolean, None),
+        _ if is_list_type(&upper) => (CellKind::Text, None),
+        _ if upper.starts_with("DECIMAL") => {
+            // DECIMAL(p,s) keeps its scale, e.g. s = 2 -> "0.00"
+            let scale = upper
+                .trim_end_matches(')')
+                .rsplit(',')
+                .next()
+                .and_then(|s| s.trim().parse::<usize>().ok())
+                .filter(|_| upper.contains(','))
+                .unwrap_or(0);
+            let format = if scale == 0 {
+                "0".to_string()
+            } else {
+                format!("0.{}", "0".repeat(scale))
+            };
+            // Plain DECIMAL is DECIMAL(18,3)
+            let precision = upper
+                .split_once('(')
+                .and_then(|(_, args)| args.trim_end_matches(')').split(',').next())
+                .and_then(|p| p.trim().parse::<usize>().ok())
+                .unwrap_or(18);
+            let kind = if precision > EXCEL_DIGITS { CellKind::ExactNumber } else { CellKind::Number };
+            (kind, Some(format))
+        }
+        _ if upper.starts_with("TIMESTAMP") => (CellKind::DateTime, None),
+        _ => (CellKind::Text, None),
+    }
+}
+
+/// SELECT expression giving each kind a value the writer can read back
+fn cell_select(column: &str, kind: CellKind) -> String {
+    let column = quote_ident(column);
+    match kind {
+        CellKind::Number => format!("CAST({} AS DOUBLE)", column),
+        CellKind::DateTime => format!("CAST(CAST({} AS TIMESTAMP) AS VARCHAR)", column),
+        CellKind::Boolean => column,
+        CellKind::ExactNumber | CellKind::Date | CellKind::Text => format!("CAST({} AS VARCHAR)", column),
+    }
+}
+
+/// A number's text as an Excel number when Excel can hold every digit of
+/// it; `None` when it would be rounded and has to be written as text
+fn excel_number(text: &str) -> Option<f64> {
+    let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0');
+    if significant.len() > EXCEL_DIGITS {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Parse DuckDB's `YYYY-MM-DD[ HH:MM:SS[.ffffff]]` text into an Excel
+/// datetime. Excel can't show dates before 1900, so those return `None`.
+fn excel_datetime(text: &str) -> Option<xlsxwriter::DateTime> {
+    let (date, time) = text.split_once(' ').unwrap_or((text, "00:00:00"));
+    let mut date_parts = date.splitn(3, '-').map(|p| p.parse::<i16>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+    if year < 1900 {
+        return None;
+    }
+
+    let mut time_parts = time.splitn(3, ':');
+    let hour = time_parts.next()?.parse::<i8>().ok()?;
+    let minute = time_parts.next()?.parse::<i8>().ok()?;
+    let second = time_parts.next()?.parse::<f64>().ok()?;
+
+    Some(xlsxwriter::DateTime::new(year, month as i8, day as i8, hour, minute, second))
 }
 
-/// Export table to Excel
+/// Export a table or view to Excel with typed cells: numbers, real date and
+/// datetime cells, and booleans keep their type, with a bold header row.
+/// `selection` limits the export to a range or selection of rows. With
+/// `mode`, the data goes into an existing workbook instead, leaving its other
+/// sheets, charts and formulas untouched. `columns` picks, orders and renames
+/// the columns; `write_metadata` adds a `.meta.json` sidecar.
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_to_excel(
-    state: State<'_, AppState>,
+    app: AppHandle,
+    window: tauri::Window,
     table_name: String,
     file_path: String,
     sheet_name: Option<String>,
-) -> Result<ExportResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    include_dictionary: Option<bool>,
+    options: Option<ExcelExportOptions>,
+    selection: Option<RowSelection>,
+    mode: Option<ExcelExportMode>,
+    columns: Option<Vec<ColumnSelection>>,
+    write_metadata: Option<bool>,
+) -> Result<ExportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
 
-    let sheet_name = sheet_name.unwrap_or_else(|| "Data".to_string());
+        let sheet_name = sheet_name.unwrap_or_else(|| "Data".to_string());
+        let options = options.unwrap_or_default();
+        let source = select_columns(export_source(conn, &table_name, selection.as_ref())?, columns.as_deref())?;
+        let provenance = || sidecar::ExportProvenance {
+            table_name: Some(&table_name),
+            query: None,
+            format: "xlsx",
+            selection: selection.as_ref(),
+            columns: columns.as_deref(),
+        };
 
-    // Query all data
-    let query = format!("SELECT * FROM {}", table_name);
-    let result = db.execute_query(&query)
-        .map_err(|e| format!("Query error: {}", e))?;
+        let row_count: usize = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", source), [], |row| row.get(0))
+            .context("Query error")?;
+        if row_count >= EXCEL_MAX_ROWS {
+            return Err(AppError::invalid_input(format!(
+                "{} has {} rows; Excel sheets hold at most {}",
+                table_name,
+                row_count,
+                EXCEL_MAX_ROWS - 1
+            )));
+        }
 
-    // Create Excel workbook
-    let workbook = xlsxwriter::Workbook::new(&file_path)
-        .map_err(|e| format!("Failed to create workbook: {}", e))?;
+        let mut describe = conn
+            .prepare(&format!("DESCRIBE SELECT * FROM {}", source))
+            .context("Query error")?;
+        let cell_columns: Vec<(String, CellKind, Option<String>)> = describe
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .context("Query error")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Query error")?
+            .into_iter()
+            .map(|(name, data_type)| {
+                let (kind, number_format) = cell_kind(&data_type);
+                (name, kind, number_format)
+            })
+            .collect();
+        let column_names: Vec<String> = cell_columns.iter().map(|(name, _, _)| name.clone()).collect();
 
-    let mut worksheet = workbook.add_worksheet(Some(&sheet_name))
-        .map_err(|e| format!("Failed to add worksheet: {}", e))?;
+        let edit = match mode.unwrap_or_default() {
+            ExcelExportMode::Replace => None,
+            ExcelExportMode::AddSheet => Some(workbook::WorkbookEdit::AddSheet),
+            ExcelExportMode::AppendRows => Some(workbook::WorkbookEdit::AppendRows),
+        };
+        if let Some(edit) = edit {
+            if include_dictionary.unwrap_or(false) {
+                return Err(AppError::invalid_input(
+                    "The data dictionary sheet is only written when replacing the workbook",
+                ));
+            }
+            let _guard = state.exports.begin();
+            let rows_written = export_into_workbook(
+                conn,
+                &state.exports,
+                &source,
+                &cell_columns,
+                Path::new(&file_path),
+                &sheet_name,
+                edit,
+                &options,
+            )?;
+            if write_metadata.unwrap_or(false) {
+                sidecar::write_sidecar(conn, &source, Path::new(&file_path), rows_written, provenance())?;
+            }
+            state.metrics.record("export_to_excel", started, Some(rows_written), true);
 
-    // Write headers
-    for (col_idx, col_name) in result.columns.iter().enumerate() {
-        worksheet
-            .write_string(0, col_idx as u16, col_name, None)
-            .map_err(|e| format!("Failed to write header: {}", e))?;
-    }
+            return Ok(ExportResult {
+                success: true,
+                message: format!("Successfully exported {} rows to sheet {}", rows_written, sheet_name),
+                file_path,
+                rows_exported: rows_written,
+            });
+        }
 
-    // Write data rows
-    for (row_idx, row_data) in result.rows.iter().enumerate() {
-        for (col_idx, cell_value) in row_data.iter().enumerate() {
-            let excel_row = (row_idx + 1) as u32;
-            let excel_col = col_idx as u16;
+        // Create Excel workbook next to the destination; declared after the
+        // staged file so an early return drops (and writes) it first
+        let path = PathBuf::from(&file_path);
+        let output = StagedFile::new(&path);
+        let staged = output.staged_path().to_str().ok_or_else(|| AppError::invalid_input("Invalid path"))?;
+        let workbook = xlsxwriter::Workbook::new(staged)
+            .map_err(|e| AppError::io(format!("Failed to create workbook: {}", e)))?;
 
-            match cell_value {
-                serde_json::Value::Null => {
-                    worksheet.write_blank(excel_row, excel_col, None)
-                        .map_err(|e| format!("Failed to write cell: {}", e))?;
+        let mut worksheet = workbook.add_worksheet(Some(&sheet_name))
+            .map_err(|e| AppError::internal(format!("Failed to add worksheet: {}", e)))?;
+
+        let write_err = |e: xlsxwriter::XlsxError| AppError::internal(format!("Failed to write cell: {}", e));
+
+        let mut header_format = xlsxwriter::Format::new();
+        header_format.set_bold();
+        let mut date_format = xlsxwriter::Format::new();
+        date_format.set_num_format(&options.date_format);
+        let mut datetime_format = xlsxwriter::Format::new();
+        datetime_format.set_num_format(&options.datetime_format);
+        let number_formats: Vec<Option<xlsxwriter::Format>> = cell_columns
+            .iter()
+            .map(|(_, kind, default_format)| {
+                let format = options.number_format.as_ref().or(default_format.as_ref());
+                match (kind, format) {
+                    (CellKind::Number | CellKind::ExactNumber, Some(format)) => {
+                        let mut number_format = xlsxwriter::Format::new();
+                        number_format.set_num_format(format);
+                        Some(number_format)
+                    }
+                    _ => None,
                 }
-                serde_json::Value::Bool(b) => {
-                    worksheet.write_boolean(excel_row, excel_col, *b, None)
-                        .map_err(|e| format!("Failed to write cell: {}", e))?;
+            })
+            .collect();
+
+        // Track the longest rendered value per column for auto-fit
+        let mut widths: Vec<usize> = column_names.iter().map(|name| name.chars().count()).collect();
+
+        // Write headers
+        for (col_idx, col_name) in column_names.iter().enumerate() {
+            worksheet
+                .write_string(0, col_idx as u16, col_name, Some(&header_format))
+                .map_err(|e| AppError::internal(format!("Failed to write header: {}", e)))?;
+        }
+
+        // Stream data rows, reading each column back in the shape its kind needs
+        let select_list = cell_columns
+            .iter()
+            .map(|(name, kind, _)| cell_select(name, *kind))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = conn
+            .prepare(&format!("SELECT {} FROM {}", select_list, source))
+            .context("Query error")?;
+        let mut rows = stmt.query([]).context("Query error")?;
+
+        let _guard = state.exports.begin();
+
+        let mut rows_written = 0;
+        let mut stopped = None;
+        loop {
+            if state.exports.is_cancelled() {
+                stopped = Some(AppError::cancelled("Export cancelled"));
+                break;
+            }
+            let row = match rows.next() {
+                Ok(Some(row)) => row,
+                Ok(None) => break,
+                Err(e) => {
+                    stopped = Some(AppError::from(e).context("Query error"));
+                    break;
                 }
-                serde_json::Value::Number(n) => {
-                    if let Some(f) = n.as_f64() {
-                        worksheet.write_number(excel_row, excel_col, f, None)
-                            .map_err(|e| format!("Failed to write cell: {}", e))?;
-                    } else if let Some(i) = n.as_i64() {
-                        worksheet.write_number(excel_row, excel_col, i as f64, None)
-                            .map_err(|e| format!("Failed to write cell: {}", e))?;
+            };
+            let excel_row = (rows_written + 1) as u32;
+            for (col_idx, (_, kind, _)) in cell_columns.iter().enumerate() {
+                let excel_col = col_idx as u16;
+                let read_err = |e: duckdb::Error| AppError::from(e).context("Failed to read value");
+
+                let rendered_width = match kind {
+                    CellKind::Number => match row.get::<_, Option<f64>>(col_idx).map_err(read_err)? {
+                        Some(value) => {
+                            worksheet
+                                .write_number(excel_row, excel_col, value, number_formats[col_idx].as_ref())
+                                .map_err(write_err)?;
+                            value.to_string().len()
+                        }
+                        None => 0,
+                    },
+                    CellKind::ExactNumber => match row.get::<_, Option<String>>(col_idx).map_err(read_err)? {
+                        Some(text) => {
+                            match excel_number(&text) {
+                                Some(value) => worksheet
+                                    .write_number(excel_row, excel_col, value, number_formats[col_idx].as_ref())
+                                    .map_err(write_err)?,
+                                None => worksheet.write_string(excel_row, excel_col, &text, None).map_err(write_err)?,
+                            }
+                            text.chars().count()
+                        }
+                        None => 0,
+                    },
+                    CellKind::Boolean => match row.get::<_, Option<bool>>(col_idx).map_err(read_err)? {
+                        Some(value) => {
+                            worksheet.write_boolean(excel_row, excel_col, value, None).map_err(write_err)?;
+                            5
+                        }
+                        None => 0,
+                    },
+                    CellKind::Date | CellKind::DateTime | CellKind::Text => {
+                        match row.get::<_, Option<String>>(col_idx).map_err(read_err)? {
+                            Some(text) => {
+                                let datetime = match kind {
+                                    CellKind::Date => excel_datetime(&text).map(|d| (d, &date_format, &options.date_format)),
+                                    CellKind::DateTime => excel_datetime(&text).map(|d| (d, &datetime_format, &options.datetime_format)),
+                                    _ => None,
+                                };
+                                match datetime {
+                                    Some((datetime, format, pattern)) => {
+                                        worksheet
+                                            .write_datetime(excel_row, excel_col, &datetime, Some(format))
+                                            .map_err(write_err)?;
+                                        pattern.chars().count()
+                                    }
+                                    None => {
+                                        worksheet.write_string(excel_row, excel_col, &text, None).map_err(write_err)?;
+                                        text.chars().count()
+                                    }
+                                }
+                            }
+                            None => 0,
+                        }
                     }
+                };
+                widths[col_idx] = widths[col_idx].max(rendered_width);
+            }
+            rows_written += 1;
+
+            if rows_written % EXCEL_PROGRESS_ROWS == 0 {
+                let _ = window.emit(
+                    "export-progress",
+                    ExportProgress {
+                        rows_written,
+                        total_rows: Some(row_count),
+                        bytes_written: None,
+                        eta_secs: None,
+                        status: format!("Writing Excel rows... {} of {}", rows_written, row_count),
+                    }
+                    .with_eta(started, rows_written as u64, row_count as u64),
+                );
+            }
+        }
+        if let Some(error) = stopped {
+            // Dropping the workbook writes it out, so do that before removing it
+            drop(workbook);
+            drop(output);
+            return Err(export_failure(&state.exports, &window, error));
+        }
+
+        if options.auto_fit {
+            for (col_idx, width) in widths.iter().enumerate() {
+                let width = (*width).min(MAX_COLUMN_WIDTH) + 2;
+                worksheet
+                    .set_column(col_idx as u16, col_idx as u16, width as f64, None)
+                    .map_err(|e| AppError::internal(format!("Failed to size column: {}", e)))?;
+            }
+        }
+        if options.freeze_header {
+            worksheet.freeze_panes(1, 0);
+        }
+        if options.autofilter && !column_names.is_empty() {
+            worksheet
+                .autofilter(0, 0, rows_written as u32, (column_names.len() - 1) as u16)
+                .map_err(|e| AppError::internal(format!("Failed to add autofilter: {}", e)))?;
+        }
+
+        // Optionally document the columns on a README sheet
+        if include_dictionary.unwrap_or(false) {
+            let dictionary = crate::dictionary::load_dictionary(conn, &table_name)
+                .context("Failed to load data dictionary")?;
+
+            if !dictionary.is_empty() {
+                // Renamed columns are documented under their original name
+                let dictionary_columns: Vec<(String, String)> = match &columns {
+                    Some(columns) => columns
+                        .iter()
+                        .map(|c| (c.target_name().to_string(), c.source.clone()))
+                        .collect(),
+                    None => column_names.iter().map(|name| (name.clone(), name.clone())).collect(),
+                };
+                write_dictionary_sheet(&workbook, &dictionary, &dictionary_columns)?;
+            }
+        }
+
+        // Saving compresses the whole workbook, so this step can't be cancelled
+        let _ = window.emit("export-progress", ExportProgress {
+            rows_written,
+            total_rows: Some(row_count),
+            status: "Saving workbook...".to_string(),
+            ..Default::default()
+        });
+        workbook.close()
+            .map_err(|e| AppError::io(format!("Failed to save workbook: {}", e)))?;
+        output.persist()?;
+        if write_metadata.unwrap_or(false) {
+            sidecar::write_sidecar(conn, &source, &path, rows_written, provenance())?;
+        }
+
+        let _ = window.emit("export-progress", ExportProgress {
+            rows_written,
+            total_rows: Some(row_count),
+            bytes_written: std::fs::metadata(&path).ok().map(|m| m.len()),
+            eta_secs: None,
+            status: "Export complete!".to_string(),
+        });
+
+        state.metrics.record(
+            "export_to_excel",
+            started,
+            Some(rows_written),
+            true,
+        );
+
+        Ok(ExportResult {
+            success: true,
+            message: format!("Successfully exported {} rows to Excel", rows_written),
+            file_path,
+            rows_exported: rows_written,
+        })
+    })
+    .await
+}
+
+/// Read the rows of `source` as typed cells and write them into the existing
+/// workbook at `path`. Returns the number of rows written.
+#[allow(clippy::too_many_arguments)]
+fn export_into_workbook(
+    conn: &duckdb::Connection,
+    control: &ExportControl,
+    source: &str,
+    columns: &[(String, CellKind, Option<String>)],
+    path: &Path,
+    sheet_name: &str,
+    edit: workbook::WorkbookEdit,
+    options: &ExcelExportOptions,
+) -> Result<usize, AppError> {
+    let select_list = columns
+        .iter()
+        .map(|(name, kind, _)| cell_select(name, *kind))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM {}", select_list, source))
+        .context("Query error")?;
+    let mut rows = stmt.query([]).context("Query error")?;
+
+    let mut cells = Vec::new();
+    while let Some(row) = rows.next().context("Query error")? {
+        if control.is_cancelled() {
+            return Err(AppError::cancelled("Export cancelled"));
+        }
+        let read_err = |e: duckdb::Error| AppError::from(e).context("Failed to read value");
+        let row_cells = columns
+            .iter()
+            .enumerate()
+            .map(|(col_idx, (_, kind, _))| {
+                Ok(match kind {
+                    CellKind::Number => row
+                        .get::<_, Option<f64>>(col_idx)
+                        .map_err(read_err)?
+                        .map_or(workbook::XlsxCell::Empty, workbook::XlsxCell::Number),
+                    CellKind::ExactNumber => match row.get::<_, Option<String>>(col_idx).map_err(read_err)? {
+                        None => workbook::XlsxCell::Empty,
+                        Some(text) => match excel_number(&text) {
+                            Some(value) => workbook::XlsxCell::Number(value),
+                            None => workbook::XlsxCell::Text(text),
+                        },
+                    },
+                    CellKind::Boolean => row
+                        .get::<_, Option<bool>>(col_idx)
+                        .map_err(read_err)?
+                        .map_or(workbook::XlsxCell::Empty, workbook::XlsxCell::Boolean),
+                    CellKind::Date | CellKind::DateTime | CellKind::Text => {
+                        match row.get::<_, Option<String>>(col_idx).map_err(read_err)? {
+                            None => workbook::XlsxCell::Empty,
+                            Some(text) => match (kind, workbook::excel_serial(&text)) {
+                                (CellKind::Date, Some(serial)) => workbook::XlsxCell::Date(serial),
+                                (CellKind::DateTime, Some(serial)) => workbook::XlsxCell::DateTime(serial),
+                                _ => workbook::XlsxCell::Text(text),
+                            },
+                        }
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        cells.push(row_cells);
+    }
+
+    let header: Vec<String> = columns.iter().map(|(name, _, _)| name.clone()).collect();
+    workbook::write_into_workbook(
+        path,
+        sheet_name,
+        edit,
+        &header,
+        &cells,
+        &options.date_format,
+        &options.datetime_format,
+    )?;
+    Ok(cells.len())
+}
+
+/// Write a README sheet describing the table and its columns, given as
+/// pairs of exported header and dictionary column name
+fn write_dictionary_sheet(
+    workbook: &xlsxwriter::Workbook,
+    dictionary: &crate::dictionary::DataDictionary,
+    columns: &[(String, String)],
+) -> Result<(), AppError> {
+    let mut sheet = workbook.add_worksheet(Some("README"))
+        .map_err(|e| AppError::internal(format!("Failed to add worksheet: {}", e)))?;
+
+    let write_err = |e: xlsxwriter::XlsxError| AppError::internal(format!("Failed to write cell: {}", e));
+
+    sheet.write_string(0, 0, "Table", None).map_err(write_err)?;
+    sheet.write_string(0, 1, &dictionary.table_name, None).map_err(write_err)?;
+    sheet.write_string(1, 0, "Description", None).map_err(write_err)?;
+    sheet
+        .write_string(1, 1, dictionary.description.as_deref().unwrap_or(""), None)
+        .map_err(write_err)?;
+
+    let header_row = 3;
+    for (col_idx, header) in ["Column", "Description", "Unit", "Tags"].iter().enumerate() {
+        sheet.write_string(header_row, col_idx as u16, header, None).map_err(write_err)?;
+    }
+
+    for (idx, (header, column)) in columns.iter().enumerate() {
+        let row = header_row + 1 + idx as u32;
+        let metadata = dictionary.column(column);
+
+        sheet.write_string(row, 0, header, None).map_err(write_err)?;
+        if let Some(metadata) = metadata {
+            sheet
+                .write_string(row, 1, metadata.description.as_deref().unwrap_or(""), None)
+                .map_err(write_err)?;
+            sheet
+                .write_string(row, 2, metadata.unit.as_deref().unwrap_or(""), None)
+                .map_err(write_err)?;
+            sheet
+                .write_string(row, 3, &metadata.tags.join(", "), None)
+                .map_err(write_err)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// File format for `export_to_destination`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DestinationFormat {
+    Csv,
+    Parquet,
+}
+
+/// Export a table or query as CSV or Parquet to a local file, an `s3://` URI
+/// or an SFTP server. Credentials in `destination` apply to this export only.
+/// `columns` picks, orders and renames the columns. `write_metadata` adds a
+/// `.meta.json` sidecar, for local files only.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_to_destination(
+    app: AppHandle,
+    table_name: Option<String>,
+    query: Option<String>,
+    format: DestinationFormat,
+    destination: ExportDestination,
+    include_header: Option<bool>,
+    compression: Option<CsvCompression>,
+    columns: Option<Vec<ColumnSelection>>,
+    write_metadata: Option<bool>,
+) -> Result<ExportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let sidecar_path = match (&destination, write_metadata.unwrap_or(false)) {
+            (_, false) => None,
+            (ExportDestination::Local { file_path }, true) => Some(PathBuf::from(file_path)),
+            (_, true) => return Err(AppError::invalid_input("Metadata sidecars can only be written for local files")),
+        };
+        let compression = compression.unwrap_or_default();
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let source = match (&table_name, &query) {
+            (Some(table_name), None) => export_source(conn, table_name, None)?,
+            (None, Some(query)) => query_source(conn, query)?,
+            _ => return Err(AppError::invalid_input("Export either a table or a query")),
+        };
+        let source = select_columns(source, columns.as_deref())?;
+
+        let rows_exported: usize = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", source), [], |row| row.get(0))
+            .context("Query error")?;
+
+        let (extension, copy_options) = match format {
+            DestinationFormat::Csv => (
+                compression.extension(),
+                csv_copy_options(include_header.unwrap_or(true), compression, &CsvExportOptions::default(), None)?,
+            ),
+            // Parquet compresses internally; gzip or zstd replace its default codec
+            DestinationFormat::Parquet => (
+                "parquet",
+                match compression {
+                    CsvCompression::None => "FORMAT PARQUET".to_string(),
+                    CsvCompression::Gzip => "FORMAT PARQUET, COMPRESSION gzip".to_string(),
+                    CsvCompression::Zstd => "FORMAT PARQUET, COMPRESSION zstd".to_string(),
+                },
+            ),
+        };
+
+        let sink = sink::open_sink(conn, &destination, extension)?;
+        let copied = conn.execute(
+            &format!(
+                "COPY (SELECT * FROM {}) TO '{}' ({})",
+                source,
+                sink.target().replace('\'', "''"),
+                copy_options
+            ),
+            [],
+        );
+        let delivery = match copied {
+            Ok(_) => sink.finish(conn),
+            Err(e) => {
+                sink.abort(conn);
+                Err(AppError::from(e).context("Export error"))
+            }
+        };
+
+        // Uploads can be slow, so other commands get the database meanwhile
+        drop(db);
+        let location = delivery.and_then(sink::Delivery::complete);
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        state.metrics.record(
+            "export_to_destination",
+            started,
+            location.as_ref().ok().map(|_| rows_exported),
+            location.is_ok(),
+        );
+        let location = location?;
+
+        if let Some(path) = &sidecar_path {
+            sidecar::write_sidecar(conn, &source, path, rows_exported, sidecar::ExportProvenance {
+                table_name: table_name.as_deref(),
+                query: query.as_deref(),
+                format: match format {
+                    DestinationFormat::Csv => "csv",
+                    DestinationFormat::Parquet => "parquet",
+                },
+                selection: None,
+                columns: columns.as_deref(),
+            })?;
+        }
+
+        Ok(ExportResult {
+            success: true,
+            message: format!("Successfully exported {} rows to {}", rows_exported, location),
+            file_path: location,
+            rows_exported,
+        })
+    })
+    .await
+}
+
+/// Field metadata key recording each column's DuckDB type in Arrow exports
+const ARROW_DUCKDB_TYPE_KEY: &str = "duckdb.type";
+
+/// Export a table or view to an Arrow IPC file (`.arrow`, or `.feather`
+/// since Feather v2 is the same format) with DuckDB's own Arrow types, for
+/// lossless hand-off to Python or R. Each field also records its DuckDB type
+/// under the `duckdb.type` metadata key. `columns` picks, orders and renames
+/// the columns; `write_metadata` adds a `.meta.json` sidecar.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_to_arrow(
+    app: AppHandle,
+    window: tauri::Window,
+    table_name: String,
+    file_path: String,
+    selection: Option<RowSelection>,
+    columns: Option<Vec<ColumnSelection>>,
+    write_metadata: Option<bool>,
+) -> Result<ExportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let source = select_columns(export_source(conn, &table_name, selection.as_ref())?, columns.as_deref())?;
+        let total_rows: usize = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", source), [], |row| row.get(0))
+            .context("Query error")?;
+
+        let mut describe = conn
+            .prepare(&format!("DESCRIBE SELECT * FROM {}", source))
+            .context("Query error")?;
+        let duckdb_types: HashMap<String, String> = describe
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .context("Query error")?
+            .collect::<Result<_, _>>()
+            .context("Query error")?;
+
+        let mut stmt = conn
+            .prepare(&format!("SELECT * FROM {}", source))
+            .context("Query error")?;
+        let batches = stmt.query_arrow([]).context("Query error")?;
+
+        let arrow_schema = batches.get_schema();
+        let fields: Vec<_> = arrow_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let mut metadata = field.metadata().clone();
+                if let Some(data_type) = duckdb_types.get(field.name()) {
+                    metadata.insert(ARROW_DUCKDB_TYPE_KEY.to_string(), data_type.clone());
                 }
-                serde_json::Value::String(s) => {
-                    worksheet.write_string(excel_row, excel_col, s, None)
-                        .map_err(|e| format!("Failed to write cell: {}", e))?;
-                }
-                _ => {
-                    worksheet.write_string(excel_row, excel_col, &cell_value.to_string(), None)
-                        .map_err(|e| format!("Failed to write cell: {}", e))?;
+                field.as_ref().clone().with_metadata(metadata)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new_with_metadata(fields, arrow_schema.metadata().clone()));
+
+        let path = PathBuf::from(&file_path);
+        let output = StagedFile::new(&path);
+        let file = std::fs::File::create(output.staged_path())
+            .with_context(|| format!("Failed to create {}", file_path))?;
+        let mut writer = FileWriter::try_new(file, &schema)
+            .map_err(|e| AppError::internal(format!("Failed to write Arrow file: {}", e)))?;
+
+        let _guard = state.exports.begin();
+        let mut rows_written = 0;
+        let mut stopped = None;
+        for batch in batches {
+            if state.exports.is_cancelled() {
+                stopped = Some(AppError::cancelled("Export cancelled"));
+                break;
+            }
+            let written = batch
+                .with_schema(schema.clone())
+                .and_then(|batch| writer.write(&batch).map(|_| batch.num_rows()));
+            match written {
+                Ok(rows) => rows_written += rows,
+                Err(e) => {
+                    stopped = Some(AppError::internal(format!("Failed to write Arrow file: {}", e)));
+                    break;
                 }
             }
+
+            let _ = window.emit(
+                "export-progress",
+                ExportProgress {
+                    rows_written,
+                    total_rows: Some(total_rows),
+                    bytes_written: None,
+                    eta_secs: None,
+                    status: format!("Writing Arrow batches... {} of {} rows", rows_written, total_rows),
+                }
+                .with_eta(started, rows_written as u64, total_rows as u64),
+            );
+        }
+        // The batch iterator ends quietly when the query is interrupted
+        if stopped.is_none() && rows_written != total_rows {
+            let message = format!("Query stopped after {} of {} rows", rows_written, total_rows);
+            stopped = Some(AppError::internal(message));
         }
+        if let Some(error) = stopped {
+            drop(writer);
+            drop(output);
+            return Err(export_failure(&state.exports, &window, error));
+        }
+        writer.finish().map_err(|e| AppError::internal(format!("Failed to write Arrow file: {}", e)))?;
+        drop(writer);
+        output.persist()?;
+        if write_metadata.unwrap_or(false) {
+            sidecar::write_sidecar(conn, &source, &path, rows_written, sidecar::ExportProvenance {
+                table_name: Some(&table_name),
+                query: None,
+                format: "arrow",
+                selection: selection.as_ref(),
+                columns: columns.as_deref(),
+            })?;
+        }
+
+        let _ = window.emit("export-progress", ExportProgress {
+            rows_written,
+            total_rows: Some(total_rows),
+            bytes_written: std::fs::metadata(&path).ok().map(|m| m.len()),
+            eta_secs: None,
+            status: "Export complete!".to_string(),
+        });
+        state.metrics.record("export_to_arrow", started, Some(rows_written), true);
+
+        Ok(ExportResult {
+            success: true,
+            message: format!("Successfully exported {} rows to Arrow", rows_written),
+            file_path,
+            rows_exported: rows_written,
+        })
+    })
+    .await
+}
+
+/// Stop the running export. A CSV `COPY` is interrupted immediately; Excel
+/// exports stop before the next row. The partly written file is removed.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn cancel_export(
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    if !state.exports.cancel() {
+        return Ok(false);
     }
 
-    workbook.close()
-        .map_err(|e| format!("Failed to save workbook: {}", e))?;
+    state.interrupt.interrupt();
+    Ok(true)
+}
+
+/// Alias the target SQLite file is attached under while exporting
+const SQLITE_EXPORT_ALIAS: &str = "rats_sqlite_export";
 
-    Ok(ExportResult {
-        success: true,
-        message: format!("Successfully exported {} rows to Excel", result.total_rows),
-        file_path,
-        rows_exported: result.total_rows,
+/// Write tables into a SQLite database file, creating it if needed. Tables
+/// that already exist in the file are replaced.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_to_sqlite(
+    app: AppHandle,
+    tables: Vec<String>,
+    file_path: String,
+) -> Result<ExportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        if tables.is_empty() {
+            return Err(AppError::invalid_input("No tables selected"));
+        }
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        crate::duckdb_core::load_extension(conn, "sqlite")
+            .context("Failed to load sqlite extension")?;
+
+        let _ = conn.execute_batch(&format!("DETACH DATABASE IF EXISTS {}", SQLITE_EXPORT_ALIAS));
+        conn.execute_batch(&format!(
+            "ATTACH '{}' AS {} (TYPE SQLITE)",
+            file_path.replace('\'', "''"),
+            SQLITE_EXPORT_ALIAS
+        ))
+        .context("Failed to open SQLite file")?;
+
+        let export_result = tables.iter().try_fold(0usize, |total, table_name| {
+            let target = format!("{}.{}", SQLITE_EXPORT_ALIAS, quote_ident(table_name));
+            let source = export_source(conn, table_name, None)?;
+            conn.execute_batch(&format!(
+                "DROP TABLE IF EXISTS {}; CREATE TABLE {} AS SELECT * FROM {};",
+                target, target, source
+            ))
+            .with_context(|| format!("Failed to export {}", table_name))?;
+
+            let rows: usize = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", quote_ident(table_name)), [], |row| row.get(0))?;
+            Ok::<_, AppError>(total + rows)
+        });
+
+        let _ = conn.execute_batch(&format!("DETACH DATABASE IF EXISTS {}", SQLITE_EXPORT_ALIAS));
+
+        state.metrics.record(
+            "export_to_sqlite",
+            started,
+            export_result.as_ref().ok().copied(),
+            export_result.is_ok(),
+        );
+        let rows_exported = export_result?;
+
+        Ok(ExportResult {
+            success: true,
+            message: format!(
+                "Successfully exported {} rows from {} table(s) to SQLite",
+                rows_exported,
+                tables.len()
+            ),
+            file_path,
+            rows_exported,
+        })
     })
+    .await
 }
 
-/// Export query results to CSV
+/// Export query results to CSV, optionally gzip or zstd compressed or split
+/// into part files like `export_to_csv`, with the same column selection and
+/// metadata sidecar
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_query_to_csv(
-    state: State<'_, AppState>,
+    app: AppHandle,
+    window: tauri::Window,
     query: String,
     file_path: String,
     include_header: Option<bool>,
-) -> Result<ExportResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
-
-    let path = PathBuf::from(&file_path);
-    let include_header = include_header.unwrap_or(true);
-
-    // Use DuckDB's COPY TO with query
-    let header_option = if include_header { "HEADER" } else { "" };
-
-    let copy_query = format!(
-        "COPY ({}) TO '{}' (FORMAT CSV, {})",
-        query,
-        path.to_str().ok_or("Invalid path")?,
-        header_option
-    );
-
-    conn.execute(&copy_query, [])
-        .map_err(|e| format!("Export error: {}", e))?;
-
-    // Get result count
-    let count_query = format!("SELECT COUNT(*) FROM ({})", query);
-    let rows_exported: usize = conn
-        .query_row(&count_query, [], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
-
-    Ok(ExportResult {
-        success: true,
-        message: format!("Successfully exported {} rows to CSV", rows_exported),
-        file_path: file_path.clone(),
-        rows_exported,
+    compression: Option<CsvCompression>,
+    split: Option<SplitOptions>,
+    options: Option<CsvExportOptions>,
+    columns: Option<Vec<ColumnSelection>>,
+    write_metadata: Option<bool>,
+) -> Result<ExportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let path = PathBuf::from(&file_path);
+        let include_header = include_header.unwrap_or(true);
+        let source = select_columns(query_source(conn, &query)?, columns.as_deref())?;
+
+        // Get result count
+        let count_query = format!("SELECT COUNT(*) FROM {}", source);
+        let rows_exported: usize = conn
+            .query_row(&count_query, [], |row| row.get(0))?;
+
+        // Use DuckDB's COPY TO with query
+        let output = write_csv_export(
+            conn,
+            &window,
+            &state.exports,
+            &format!("SELECT * FROM {}", source),
+            &path,
+            include_header,
+            compression.unwrap_or_default(),
+            &options.unwrap_or_default(),
+            rows_exported,
+            split.as_ref(),
+        )?;
+        if write_metadata.unwrap_or(false) {
+            sidecar::write_sidecar(conn, &source, &path, rows_exported, sidecar::ExportProvenance {
+                table_name: None,
+                query: Some(&query),
+                format: "csv",
+                selection: None,
+                columns: columns.as_deref(),
+            })?;
+        }
+
+        Ok(ExportResult {
+            success: true,
+            message: format!("Successfully exported {} rows to {}", rows_exported, output),
+            file_path: file_path.clone(),
+            rows_exported,
+        })
+    })
+    .await
+}
+
+/// File format for `export_query_result`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultExportFormat {
+    Csv,
+    Parquet,
+    Excel,
+}
+
+/// Export a query result kept by `execute_sql` with `keep_results`, without
+/// running the query again. `columns` picks, orders and renames the columns.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_query_result(
+    app: AppHandle,
+    window: tauri::Window,
+    result_id: String,
+    format: ResultExportFormat,
+    file_path: String,
+    include_header: Option<bool>,
+    columns: Option<Vec<ColumnSelection>>,
+) -> Result<ExportResult, AppError> {
+    let id = result_id.clone();
+    let kept = crate::run_blocking(app.clone(), move |state| {
+        let db = state.db.lock()?;
+        duckdb_core::result_exists(db.get_connection(), &id).map_err(AppError::from)
+    })
+    .await?;
+    if !kept {
+        return Err(AppError::not_found(format!(
+            "Query result {} not found; run the query again with keepResults",
+            result_id
+        )));
+    }
+
+    // Kept results are temporary tables on the connection exports use
+    let table_name = duckdb_core::result_table(&result_id);
+    match format {
+        ResultExportFormat::Csv => {
+            export_to_csv(app, window, table_name, file_path, include_header, None, None, None, None, columns, None)
+                .await
+        }
+        ResultExportFormat::Excel => {
+            export_to_excel(app, window, table_name, file_path, None, None, None, None, None, columns, None).await
+        }
+        ResultExportFormat::Parquet => {
+            export_to_destination(
+                app,
+                Some(table_name),
+                None,
+                DestinationFormat::Parquet,
+                ExportDestination::Local { file_path },
+                None,
+                None,
+                columns,
+                None,
+            )
+            .await
+        }
+    }
+}
+
+/// Most rows `copy_to_clipboard` will put on the clipboard
+const MAX_CLIPBOARD_ROWS: usize = 100_000;
+
+/// Block of cells to copy, as 0-based grid positions with inclusive ends.
+/// Unset ends run to the last row or column.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CellRange {
+    pub first_row: usize,
+    pub last_row: Option<usize>,
+    pub first_column: usize,
+    pub last_column: Option<usize>,
+}
+
+/// Quote a value the way Excel does when it pastes tab-separated text
+fn tsv_field(value: &str) -> String {
+    if value.contains(['\t', '\n', '\r', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Put a table, query result or cell range on the system clipboard as
+/// tab-separated text, ready to paste into a spreadsheet
+#[tauri::command(rename_all = "camelCase")]
+pub async fn copy_to_clipboard(
+    app: AppHandle,
+    window: tauri::Window,
+    table_name: Option<String>,
+    query: Option<String>,
+    range: Option<CellRange>,
+    include_header: Option<bool>,
+) -> Result<ExportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let range = range.unwrap_or_default();
+        let include_header = include_header.unwrap_or(true);
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let source = match (table_name, query) {
+            (Some(table_name), None) => export_source(conn, &table_name, None)?,
+            (None, Some(query)) => query_source(conn, &query)?,
+            _ => return Err(AppError::invalid_input("Copy either a table or a query")),
+        };
+
+        let mut describe = conn
+            .prepare(&format!("DESCRIBE SELECT * FROM {}", source))
+            .context("Query error")?;
+        let all_columns: Vec<String> = describe
+            .query_map([], |row| row.get::<_, String>(0))
+            .context("Query error")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Query error")?;
+
+        let last_column = range
+            .last_column
+            .unwrap_or(all_columns.len().saturating_sub(1))
+            .min(all_columns.len().saturating_sub(1));
+        if all_columns.is_empty() || range.first_column > last_column {
+            return Err(AppError::invalid_input("No columns in the selected range"));
+        }
+        let columns = &all_columns[range.first_column..=last_column];
+
+        let row_limit = match range.last_row {
+            Some(last_row) if last_row < range.first_row => {
+                return Err(AppError::invalid_input("No rows in the selected range"));
+            }
+            Some(last_row) => last_row - range.first_row + 1,
+            None => MAX_CLIPBOARD_ROWS + 1,
+        };
+        if row_limit > MAX_CLIPBOARD_ROWS {
+            let row_count: usize = conn
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM (SELECT 1 FROM {} LIMIT {} OFFSET {})", source, row_limit, range.first_row),
+                    [],
+                    |row| row.get(0),
+                )
+                .context("Query error")?;
+            if row_count > MAX_CLIPBOARD_ROWS {
+                return Err(AppError::invalid_input(format!(
+                    "{} rows selected; copy at most {} rows or export to a file instead",
+                    row_count, MAX_CLIPBOARD_ROWS
+                )));
+            }
+        }
+
+        let select_list = columns
+            .iter()
+            .map(|name| format!("CAST({} AS VARCHAR)", quote_ident(name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM {} LIMIT {} OFFSET {}",
+                select_list, source, row_limit, range.first_row
+            ))
+            .context("Query error")?;
+        let mut rows = stmt.query([]).context("Query error")?;
+
+        let mut text = String::new();
+        if include_header {
+            let header: Vec<String> = columns.iter().map(|name| tsv_field(name)).collect();
+            text.push_str(&header.join("\t"));
+            text.push('\n');
+        }
+        let mut rows_copied = 0;
+        while let Some(row) = rows.next().context("Query error")? {
+            let fields = (0..columns.len())
+                .map(|idx| {
+                    row.get::<_, Option<String>>(idx)
+                        .map(|value| value.as_deref().map(tsv_field).unwrap_or_default())
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .context("Failed to read value")?;
+            text.push_str(&fields.join("\t"));
+            text.push('\n');
+            rows_copied += 1;
+        }
+
+        window
+            .clipboard()
+            .write_text(text)
+            .map_err(|e| AppError::internal(format!("Failed to write to clipboard: {}", e)))?;
+
+        state.metrics.record("copy_to_clipboard", started, Some(rows_copied), true);
+
+        Ok(ExportResult {
+            success: true,
+            message: format!("Copied {} rows to the clipboard", rows_copied),
+            file_path: String::new(),
+            rows_exported: rows_copied,
+        })
+    })
+    .await
+}
+
+/// Column types of a table, as DDL and as a JSON Schema document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaExport {
+    pub table_name: String,
+    pub ddl: String,
+    pub json_schema: String, // Pretty-printed JSON Schema (draft 2020-12) for one row
+}
+
+/// JSON Schema for values of a DuckDB type. The exact type is kept under
+/// `x-duckdb-type`, since JSON Schema can't tell INTEGER from BIGINT.
+fn json_schema_type(data_type: &str) -> serde_json::Value {
+    let upper = data_type.to_uppercase();
+    let mut schema = match upper.as_str() {
+        "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" | "HUGEINT" | "UTINYINT" | "USMALLINT"
+        | "UINTEGER" | "UBIGINT" | "UHUGEINT" => serde_json::json!({ "type": "integer" }),
+        "FLOAT" | "DOUBLE" => serde_json::json!({ "type": "number" }),
+        "BOOLEAN" => serde_json::json!({ "type": "boolean" }),
+        "DATE" => serde_json::json!({ "type": "string", "format": "date" }),
+        "TIME" => serde_json::json!({ "type": "string", "format": "time" }),
+        "UUID" => serde_json::json!({ "type": "string", "format": "uuid" }),
+        _ if is_list_type(&upper) => serde_json::json!({ "type": "array" }),
+        _ if upper.starts_with("DECIMAL") => serde_json::json!({ "type": "number" }),
+        _ if upper.starts_with("TIMESTAMP") => serde_json::json!({ "type": "string", "format": "date-time" }),
+        _ if upper.starts_with("STRUCT") || upper.starts_with("MAP") => serde_json::json!({ "type": "object" }),
+        _ => serde_json::json!({ "type": "string" }),
+    };
+    schema["x-duckdb-type"] = serde_json::Value::String(data_type.to_string());
+    schema
+}
+
+/// Describe a table's columns as a `CREATE TABLE` statement and a JSON
+/// Schema document, so recipients know the exact types without opening
+/// the data. Column descriptions from the data dictionary are included in
+/// the JSON Schema. Each document is also written to its path when given.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_schema(
+    app: AppHandle,
+    table_name: String,
+    ddl_path: Option<String>,
+    json_schema_path: Option<String>,
+) -> Result<SchemaExport, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let mut describe = conn
+            .prepare(&format!("DESCRIBE {}", quote_ident(&table_name)))
+            .context("Query error")?;
+        let columns: Vec<(String, String, bool)> = describe
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)? == "YES"))
+            })
+            .context("Query error")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Query error")?
+            .into_iter()
+            .filter(|(name, _, _)| name != ROW_ID_COLUMN)
+            .collect();
+        let dictionary = crate::dictionary::load_dictionary(conn, &table_name)
+            .context("Failed to load data dictionary")?;
+
+        let column_lines = columns
+            .iter()
+            .map(|(name, data_type, nullable)| {
+                format!("    {} {}{}", quote_ident(name), data_type, if *nullable { "" } else { " NOT NULL" })
+            })
+            .collect::<Vec<_>>()
+            .join(",\n");
+        let ddl = format!("CREATE TABLE {} (\n{}\n);\n", quote_ident(&table_name), column_lines);
+
+        let mut properties = serde_json::Map::new();
+        for (name, data_type, nullable) in &columns {
+            let mut property = json_schema_type(data_type);
+            if *nullable {
+                let value_type = property["type"].clone();
+                property["type"] = serde_json::json!([value_type, "null"]);
+            }
+            if let Some(description) = dictionary.column(name).and_then(|c| c.description.as_ref()) {
+                property["description"] = serde_json::Value::String(description.clone());
+            }
+            properties.insert(name.clone(), property);
+        }
+        let mut schema = serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": table_name,
+            "type": "object",
+            "properties": properties,
+            "required": columns.iter().filter(|(_, _, nullable)| !nullable).map(|(name, _, _)| name).collect::<Vec<_>>(),
+        });
+        if let Some(description) = &dictionary.description {
+            schema["description"] = serde_json::Value::String(description.clone());
+        }
+        let json_schema = serde_json::to_string_pretty(&schema)?;
+
+        if let Some(path) = &ddl_path {
+            std::fs::write(path, &ddl).with_context(|| format!("Failed to write {}", path))?;
+        }
+        if let Some(path) = &json_schema_path {
+            std::fs::write(path, &json_schema).with_context(|| format!("Failed to write {}", path))?;
+        }
+
+        Ok(SchemaExport {
+            table_name,
+            ddl,
+            json_schema,
+        })
     })
+    .await
 }
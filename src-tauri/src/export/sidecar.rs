@@ -0,0 +1,111 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::RowSelection;
+use crate::error::{AppError, ErrorContext};
+use crate::import::ColumnSelection;
+
+/// How the exported data was produced, as recorded in the sidecar
+pub(super) struct ExportProvenance<'a> {
+    pub table_name: Option<&'a str>,
+    pub query: Option<&'a str>,
+    pub format: &'a str,
+    pub selection: Option<&'a RowSelection>,
+    pub columns: Option<&'a [ColumnSelection]>,
+}
+
+#[derive(Debug, Serialize)]
+struct SidecarColumn {
+    name: String,
+    data_type: String,
+    source_column: Option<String>, // Set when the column was renamed on export
+}
+
+#[derive(Debug, Serialize)]
+struct SidecarFilters<'a> {
+    query: Option<&'a str>,
+    rows: Option<&'a RowSelection>,
+    columns: Option<&'a [ColumnSelection]>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportSidecar<'a> {
+    source_table: Option<&'a str>,
+    file: String,
+    format: &'a str,
+    row_count: usize,
+    columns: Vec<SidecarColumn>,
+    filters: SidecarFilters<'a>,
+    exported_at: String, // UTC, ISO 8601
+    app_version: &'static str,
+}
+
+/// `orders.csv` -> `orders.csv.meta.json`; split exports get one sidecar
+/// next to their directory
+fn sidecar_path(export_path: &Path) -> PathBuf {
+    let mut name = export_path.as_os_str().to_os_string();
+    name.push(".meta.json");
+    PathBuf::from(name)
+}
+
+/// Write the `.meta.json` sidecar for an export of `source` to
+/// `export_path`, recording where the data came from so a shared extract can
+/// be traced back. Returns the sidecar's path.
+pub(super) fn write_sidecar(
+    conn: &duckdb::Connection,
+    source: &str,
+    export_path: &Path,
+    row_count: usize,
+    provenance: ExportProvenance,
+) -> Result<PathBuf, AppError> {
+    let mut describe = conn
+        .prepare(&format!("DESCRIBE SELECT * FROM {}", source))
+        .context("Query error")?;
+    let columns = describe
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .context("Query error")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Query error")?
+        .into_iter()
+        .map(|(name, data_type)| {
+            let source_column = provenance
+                .columns
+                .and_then(|columns| columns.iter().find(|c| c.target_name() == name))
+                .filter(|c| c.source != name)
+                .map(|c| c.source.clone());
+            SidecarColumn { name, data_type, source_column }
+        })
+        .collect();
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let exported_at: String = conn
+        .query_row("SELECT strftime(epoch_ms(?), '%Y-%m-%dT%H:%M:%SZ')", [now_ms], |row| row.get(0))
+        .context("Query error")?;
+
+    let sidecar = ExportSidecar {
+        source_table: provenance.table_name,
+        file: export_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        format: provenance.format,
+        row_count,
+        columns,
+        filters: SidecarFilters {
+            query: provenance.query,
+            rows: provenance.selection,
+            columns: provenance.columns,
+        },
+        exported_at,
+        app_version: env!("CARGO_PKG_VERSION"),
+    };
+
+    let path = sidecar_path(export_path);
+    let contents = serde_json::to_string_pretty(&sidecar)?;
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write metadata sidecar {}", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::duckdb_core::{self, S3Credentials};
+use crate::error::{AppError, ErrorContext};
+use super::StagedFile;
+
+/// Secret holding per-export S3 credentials, scoped to the export URI so it
+/// takes precedence over the session credentials while it exists
+const EXPORT_S3_SECRET: &str = "rats_export_s3";
+
+/// Where an export is delivered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportDestination {
+    Local {
+        file_path: String,
+    },
+    S3 {
+        uri: String, // s3://bucket/key
+        credentials: Option<S3Credentials>, // Defaults to those from `set_s3_credentials`
+    },
+    Sftp(SftpDestination),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SftpDestination {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: String,
+    pub password: Option<String>,
+    pub private_key_path: Option<String>,
+    pub passphrase: Option<String>, // For an encrypted private key
+    pub remote_path: String,
+    pub accept_unknown_host: Option<bool>, // Allow hosts missing from ~/.ssh/known_hosts
+}
+
+/// How long to wait for an SFTP server to accept the connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Somewhere an export can be written. DuckDB's `COPY` writes to `target`,
+/// then `finish` tidies up on the connection and hands back the delivery,
+/// which is completed once the database lock is released.
+pub(super) trait ExportSink {
+    fn target(&self) -> &str;
+    fn finish(self: Box<Self>, conn: &duckdb::Connection) -> Result<Delivery, AppError>;
+    /// Clean up after the `COPY` failed
+    fn abort(self: Box<Self>, conn: &duckdb::Connection);
+}
+
+/// What is left to do once DuckDB has written the export
+pub(super) enum Delivery {
+    Done(String), // Where the export ended up
+    Upload {
+        destination: SftpDestination,
+        local: PathBuf,
+    },
+}
+
+impl Delivery {
+    /// Finish delivering the export and report where it ended up. Doesn't
+    /// touch the database, so slow uploads don't hold it up.
+    pub(super) fn complete(self) -> Result<String, AppError> {
+        match self {
+            Delivery::Done(location) => Ok(location),
+            Delivery::Upload { destination, local } => {
+                let uploaded = upload(&destination, &local);
+                let _ = std::fs::remove_file(&local);
+                uploaded?;
+                Ok(format!("sftp://{}@{}{}", destination.username, destination.host, destination.remote_path))
+            }
+        }
+    }
+}
+
+/// Prepare `destination` for a file with the given extension
+pub(super) fn open_sink(
+    conn: &duckdb::Connection,
+    destination: &ExportDestination,
+    extension: &str,
+) -> Result<Box<dyn ExportSink>, AppError> {
+    match destination {
+        ExportDestination::Local { file_path } => {
+            let output = StagedFile::new(Path::new(file_path));
+            let staged = output
+                .staged_path()
+                .to_str()
+                .ok_or_else(|| AppError::invalid_input("Invalid path"))?
+                .to_string();
+            Ok(Box::new(LocalSink { path: file_path.clone(), staged, output }))
+        }
+        ExportDestination::S3 { uri, credentials } => {
+            if !uri.starts_with("s3://") {
+                return Err(AppError::invalid_input("S3 destinations must be s3:// URIs"));
+            }
+            duckdb_core::load_extension(conn, "httpfs")
+                .context("Failed to load httpfs extension")?;
+            if let Some(credentials) = credentials {
+                duckdb_core::create_s3_secret(conn, EXPORT_S3_SECRET, credentials, Some(uri))
+                    .context("Failed to set S3 credentials")?;
+            }
+            Ok(Box::new(S3Sink {
+                uri: uri.clone(),
+                own_secret: credentials.is_some(),
+            }))
+        }
+        ExportDestination::Sftp(destination) => {
+            let local = std::env::temp_dir().join(format!(
+                "rats_sftp_export_{}.{}",
+                std::process::id(),
+                extension
+            ));
+            let local_str = local.to_str().ok_or_else(|| AppError::internal("Invalid temporary path"))?.to_string();
+            Ok(Box::new(SftpSink {
+                destination: destination.clone(),
+                local,
+                local_str,
+            }))
+        }
+    }
+}
+
+/// DuckDB writes next to the destination, which is only replaced once the
+/// `COPY` succeeds
+struct LocalSink {
+    path: String,
+    staged: String,
+    output: StagedFile,
+}
+
+impl ExportSink for LocalSink {
+    fn target(&self) -> &str {
+        &self.staged
+    }
+
+    fn finish(self: Box<Self>, _conn: &duckdb::Connection) -> Result<Delivery, AppError> {
+        self.output.persist()?;
+        Ok(Delivery::Done(self.path))
+    }
+
+    fn abort(self: Box<Self>, _conn: &duckdb::Connection) {}
+}
+
+/// DuckDB writes straight to the bucket through httpfs
+struct S3Sink {
+    uri: String,
+    own_secret: bool,
+}
+
+impl S3Sink {
+    fn drop_secret(&self, conn: &duckdb::Connection) {
+        if self.own_secret {
+            let _ = conn.execute_batch(&format!("DROP TEMPORARY SECRET IF EXISTS {}", EXPORT_S3_SECRET));
+        }
+    }
+}
+
+impl ExportSink for S3Sink {
+    fn target(&self) -> &str {
+        &self.uri
+    }
+
+    fn finish(self: Box<Self>, conn: &duckdb::Connection) -> Result<Delivery, AppError> {
+        self.drop_secret(conn);
+        Ok(Delivery::Done(self.uri))
+    }
+
+    fn abort(self: Box<Self>, conn: &duckdb::Connection) {
+        self.drop_secret(conn);
+    }
+}
+
+/// DuckDB writes a temporary file that is then uploaded over SFTP
+struct SftpSink {
+    destination: SftpDestination,
+    local: PathBuf,
+    local_str: String,
+}
+
+impl ExportSink for SftpSink {
+    fn target(&self) -> &str {
+        &self.local_str
+    }
+
+    fn finish(self: Box<Self>, _conn: &duckdb::Connection) -> Result<Delivery, AppError> {
+        Ok(Delivery::Upload {
+            destination: self.destination,
+            local: self.local,
+        })
+    }
+
+    fn abort(self: Box<Self>, _conn: &duckdb::Connection) {
+        let _ = std::fs::remove_file(&self.local);
+    }
+}
+
+/// Refuse hosts whose key doesn't match `~/.ssh/known_hosts`, and unknown
+/// hosts unless the destination allows them
+fn check_host_key(session: &ssh2::Session, destination: &SftpDestination, port: u16) -> Result<(), AppError> {
+    let (key, _) = session.host_key().ok_or_else(|| AppError::io("Server sent no host key"))?;
+    let mut known_hosts = session.known_hosts().map_err(|e| AppError::io(e.to_string()))?;
+    if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        let file = PathBuf::from(home).join(".ssh").join("known_hosts");
+        if file.exists() {
+            known_hosts
+                .read_file(&file, ssh2::KnownHostFileKind::OpenSSH)
+                .map_err(|e| AppError::io(format!("Failed to read {}: {}", file.display(), e)))?;
+        }
+    }
+
+    match known_hosts.check_port(&destination.host, port, key) {
+        ssh2::CheckResult::Match => Ok(()),
+        ssh2::CheckResult::NotFound if destination.accept_unknown_host.unwrap_or(false) => Ok(()),
+        ssh2::CheckResult::NotFound => Err(AppError::invalid_input(format!(
+            "{} is not in known_hosts; connect to it once with ssh or allow unknown hosts",
+            destination.host
+        ))),
+        ssh2::CheckResult::Mismatch => Err(AppError::io(format!(
+            "Host key of {} does not match known_hosts",
+            destination.host
+        ))),
+        ssh2::CheckResult::Failure => Err(AppError::io("Failed to check the host key")),
+    }
+}
+
+fn upload(destination: &SftpDestination, local: &Path) -> Result<(), AppError> {
+    let port = destination.port.unwrap_or(22);
+    let sftp_err = |e: ssh2::Error| AppError::io(format!("SFTP error: {}", e));
+
+    let connect_err =
+        |e: std::io::Error| AppError::from(e).context(format!("Failed to connect to {}:{}", destination.host, port));
+    let address = (destination.host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(connect_err)?
+        .next()
+        .ok_or_else(|| AppError::io(format!("Failed to resolve {}", destination.host)))?;
+    let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(connect_err)?;
+    let mut session = ssh2::Session::new().map_err(sftp_err)?;
+    session.set_tcp_stream(tcp);
+    session.handshake().map_err(sftp_err)?;
+    check_host_key(&session, destination, port)?;
+
+    match (&destination.private_key_path, &destination.password) {
+        (Some(key_path), _) => session.userauth_pubkey_file(
+            &destination.username,
+            None,
+            Path::new(key_path),
+            destination.passphrase.as_deref(),
+        ),
+        (None, Some(password)) => session.userauth_password(&destination.username, password),
+        (None, None) => session.userauth_agent(&destination.username),
+    }
+    .map_err(|e| AppError::io(format!("SFTP authentication failed: {}", e)))?;
+
+    let sftp = session.sftp().map_err(sftp_err)?;
+    let mut remote = sftp
+        .create(Path::new(&destination.remote_path))
+        .map_err(|e| AppError::io(format!("Failed to create {}: {}", destination.remote_path, e)))?;
+    let mut file = std::fs::File::open(local)?;
+    std::io::copy(&mut file, &mut remote)
+        .with_context(|| format!("Failed to upload to {}", destination.remote_path))?;
+    Ok(())
+}
@@ -0,0 +1,560 @@
+// Adds a sheet to, or appends rows to a sheet of, an existing .xlsx file by
+// editing only the parts involved. Every other part of the package (charts,
+// formulas, pivot caches, ...) is copied through byte for byte.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use crate::error::{AppError, ErrorContext};
+
+const WORKSHEET_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet";
+const WORKSHEET_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml";
+const RELATIONSHIPS_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const MAIN_NS: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+
+const WORKBOOK_PART: &str = "xl/workbook.xml";
+const WORKBOOK_RELS_PART: &str = "xl/_rels/workbook.xml.rels";
+const STYLES_PART: &str = "xl/styles.xml";
+const CONTENT_TYPES_PART: &str = "[Content_Types].xml";
+
+/// First id Excel leaves free for custom number formats
+const FIRST_CUSTOM_NUM_FMT: u32 = 164;
+
+/// A value written to the workbook
+pub(super) enum XlsxCell {
+    Empty,
+    Number(f64),
+    Boolean(bool),
+    Date(f64),     // Excel serial date
+    DateTime(f64), // Excel serial date and time
+    Text(String),
+}
+
+/// How the rows go into the existing workbook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum WorkbookEdit {
+    AddSheet,   // New sheet with a header row; fails if the name is taken
+    AppendRows, // Rows go below the last used row of an existing sheet
+}
+
+/// Excel serial number for DuckDB's `YYYY-MM-DD[ HH:MM:SS[.ffffff]]` text.
+/// Excel can't show dates before 1900, so those return `None`.
+pub(super) fn excel_serial(text: &str) -> Option<f64> {
+    let (date, time) = text.split_once(' ').unwrap_or((text, "00:00:00"));
+    let mut date_parts = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+    if year < 1900 {
+        return None;
+    }
+
+    let mut time_parts = time.splitn(3, ':');
+    let hour = time_parts.next()?.parse::<f64>().ok()?;
+    let minute = time_parts.next()?.parse::<f64>().ok()?;
+    let second = time_parts.next()?.parse::<f64>().ok()?;
+
+    let days = days_from_civil(year, month, day) - days_from_civil(1899, 12, 30);
+    Some(days as f64 + (hour * 3600.0 + minute * 60.0 + second) / 86_400.0)
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Column letters for a 0-based index: 0 -> A, 26 -> AA
+fn column_letters(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// 0-based column index of a cell reference such as `AB12`
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: String = reference.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    Some(letters.bytes().fold(0, |acc, b| acc * 26 + (b.to_ascii_uppercase() - b'A') as usize + 1) - 1)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // Control characters other than tab and line breaks aren't valid XML
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Style indexes for the date and datetime formats added to styles.xml
+struct DateStyles {
+    date: usize,
+    datetime: usize,
+}
+
+fn push_row(xml: &mut String, row_number: usize, cells: &[XlsxCell], styles: &DateStyles) {
+    xml.push_str(&format!("<row r=\"{}\">", row_number));
+    for (col_idx, cell) in cells.iter().enumerate() {
+        let reference = format!("{}{}", column_letters(col_idx), row_number);
+        match cell {
+            XlsxCell::Empty => {}
+            XlsxCell::Number(value) if value.is_finite() => {
+                xml.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, value));
+            }
+            XlsxCell::Number(value) => {
+                xml.push_str(&format!("<c r=\"{}\" t=\"inlineStr\"><is><t>{}</t></is></c>", reference, value));
+            }
+            XlsxCell::Boolean(value) => {
+                xml.push_str(&format!("<c r=\"{}\" t=\"b\"><v>{}</v></c>", reference, *value as u8));
+            }
+            XlsxCell::Date(serial) => {
+                xml.push_str(&format!("<c r=\"{}\" s=\"{}\"><v>{}</v></c>", reference, styles.date, serial));
+            }
+            XlsxCell::DateTime(serial) => {
+                xml.push_str(&format!("<c r=\"{}\" s=\"{}\"><v>{}</v></c>", reference, styles.datetime, serial));
+            }
+            XlsxCell::Text(text) => {
+                xml.push_str(&format!(
+                    "<c r=\"{}\" t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+                    reference,
+                    escape_xml(text)
+                ));
+            }
+        }
+    }
+    xml.push_str("</row>");
+}
+
+/// Replace byte ranges of `xml`; the ranges must not overlap
+fn apply_edits(xml: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut edited = xml.to_string();
+    for (range, text) in edits {
+        edited.replace_range(range, &text);
+    }
+    edited
+}
+
+/// The element name as written, including any namespace prefix
+fn qualified_name(xml: &str, node: roxmltree::Node) -> String {
+    let tag = &xml[node.range().start + 1..];
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    tag[..end].to_string()
+}
+
+/// Namespace prefix (with its colon) to give new children of `node`
+fn child_prefix(xml: &str, node: roxmltree::Node) -> String {
+    qualified_name(xml, node)
+        .rsplit_once(':')
+        .map(|(prefix, _)| format!("{}:", prefix))
+        .unwrap_or_default()
+}
+
+/// Edit inserting `markup` as the last child of `node`
+fn append_child(xml: &str, node: roxmltree::Node, markup: &str) -> (Range<usize>, String) {
+    let range = node.range();
+    let element = &xml[range.clone()];
+    if element.ends_with("/>") {
+        (range.end - 2..range.end, format!(">{}</{}>", markup, qualified_name(xml, node)))
+    } else {
+        let close = range.start + element.rfind("</").unwrap_or(element.len());
+        (close..close, markup.to_string())
+    }
+}
+
+/// Edit setting an attribute in the start tag of `node`
+fn set_attribute(xml: &str, node: roxmltree::Node, name: &str, value: &str) -> (Range<usize>, String) {
+    let start = node.range().start;
+    let tag_end = start + xml[start..].find('>').unwrap_or(0);
+    let tag = &xml[start..tag_end];
+    let pattern = format!(" {}=\"", name);
+    match tag.find(&pattern) {
+        Some(attr) => {
+            let value_start = start + attr + pattern.len();
+            let value_end = value_start + xml[value_start..].find('"').unwrap_or(0);
+            (value_start..value_end, value.to_string())
+        }
+        None => {
+            let name_end = start + 1 + qualified_name(xml, node).len();
+            (name_end..name_end, format!(" {}=\"{}\"", name, value))
+        }
+    }
+}
+
+fn parse<'a>(part: &str, xml: &'a str) -> Result<roxmltree::Document<'a>, AppError> {
+    roxmltree::Document::parse(xml).map_err(|e| AppError::invalid_input(format!("Invalid {} in workbook: {}", part, e)))
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// Add number formats for dates and datetimes to styles.xml and a cell
+/// style for each
+fn add_date_styles(styles: &str, date_format: &str, datetime_format: &str) -> Result<(String, DateStyles), AppError> {
+    let doc = parse(STYLES_PART, styles)?;
+    let root = doc.root_element();
+    let prefix = child_prefix(styles, root);
+
+    let num_fmts = child(root, "numFmts");
+    let next_id = num_fmts
+        .map(|node| {
+            node.children()
+                .filter_map(|n| n.attribute("numFmtId")?.parse::<u32>().ok())
+                .max()
+                .map_or(FIRST_CUSTOM_NUM_FMT, |max| (max + 1).max(FIRST_CUSTOM_NUM_FMT))
+        })
+        .unwrap_or(FIRST_CUSTOM_NUM_FMT);
+    let (date_id, datetime_id) = (next_id, next_id + 1);
+    let num_fmt_markup = format!(
+        "<{p}numFmt numFmtId=\"{}\" formatCode=\"{}\"/><{p}numFmt numFmtId=\"{}\" formatCode=\"{}\"/>",
+        date_id,
+        escape_xml(date_format),
+        datetime_id,
+        escape_xml(datetime_format),
+        p = prefix
+    );
+
+    let cell_xfs = child(root, "cellXfs").ok_or_else(|| AppError::invalid_input("Workbook styles have no cellXfs"))?;
+    let style_count = cell_xfs.children().filter(|n| n.is_element()).count();
+    let xf_markup = format!(
+        "<{p}xf numFmtId=\"{}\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\"/>\
+         <{p}xf numFmtId=\"{}\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\"/>",
+        date_id,
+        datetime_id,
+        p = prefix
+    );
+
+    let mut edits = vec![
+        append_child(styles, cell_xfs, &xf_markup),
+        set_attribute(styles, cell_xfs, "count", &(style_count + 2).to_string()),
+    ];
+    match num_fmts {
+        Some(node) => {
+            let count = node.children().filter(|n| n.is_element()).count();
+            edits.push(append_child(styles, node, &num_fmt_markup));
+            edits.push(set_attribute(styles, node, "count", &(count + 2).to_string()));
+        }
+        None => {
+            // numFmts has to be the first child of styleSheet
+            let first = root
+                .children()
+                .find(|n| n.is_element())
+                .map(|n| n.range().start)
+                .ok_or_else(|| AppError::invalid_input("Workbook styles are empty"))?;
+            edits.push((first..first, format!("<{p}numFmts count=\"2\">{}</{p}numFmts>", num_fmt_markup, p = prefix)));
+        }
+    }
+
+    Ok((
+        apply_edits(styles, edits),
+        DateStyles {
+            date: style_count,
+            datetime: style_count + 1,
+        },
+    ))
+}
+
+/// Resolve a relationship target against the `xl/` folder
+fn part_path(target: &str) -> String {
+    match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("xl/{}", target),
+    }
+}
+
+fn read_part(archive: &mut zip::ZipArchive<BufReader<File>>, name: &str) -> Result<String, AppError> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|_| AppError::invalid_input(format!("Not an Excel workbook: {} is missing", name)))?;
+    let mut text = String::new();
+    entry
+        .read_to_string(&mut text)
+        .with_context(|| format!("Failed to read {}", name))?;
+    Ok(text)
+}
+
+/// Parts to replace or add, keyed by their path in the package
+type PartChanges = HashMap<String, String>;
+
+/// Changes adding a new sheet holding `header` and `rows`
+fn add_sheet(
+    archive: &mut zip::ZipArchive<BufReader<File>>,
+    sheet_name: &str,
+    header: &[String],
+    rows: &[Vec<XlsxCell>],
+    styles: &DateStyles,
+) -> Result<PartChanges, AppError> {
+    let workbook = read_part(archive, WORKBOOK_PART)?;
+    let rels = read_part(archive, WORKBOOK_RELS_PART)?;
+    let content_types = read_part(archive, CONTENT_TYPES_PART)?;
+
+    let workbook_doc = parse(WORKBOOK_PART, &workbook)?;
+    let sheets = child(workbook_doc.root_element(), "sheets")
+        .ok_or_else(|| AppError::invalid_input("Workbook has no sheet list"))?;
+    let sheet_nodes: Vec<_> = sheets.children().filter(|n| n.is_element()).collect();
+    if sheet_nodes
+        .iter()
+        .any(|n| n.attribute("name").is_some_and(|name| name.eq_ignore_ascii_case(sheet_name)))
+    {
+        return Err(AppError::invalid_input(format!("The workbook already has a sheet named {}", sheet_name)));
+    }
+    let sheet_id = sheet_nodes
+        .iter()
+        .filter_map(|n| n.attribute("sheetId")?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let rels_doc = parse(WORKBOOK_RELS_PART, &rels)?;
+    let rel_ids: Vec<&str> = rels_doc
+        .root_element()
+        .children()
+        .filter_map(|n| n.attribute("Id"))
+        .collect();
+    let rel_id = (1..)
+        .map(|i| format!("rIdRats{}", i))
+        .find(|id| !rel_ids.contains(&id.as_str()))
+        .unwrap_or_default();
+    let sheet_file = (1..)
+        .map(|i| format!("worksheets/sheet{}.xml", i))
+        .find(|file| archive.by_name(&part_path(file)).is_err())
+        .unwrap_or_default();
+
+    let mut sheet_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<worksheet xmlns=\"{}\" xmlns:r=\"{}\"><sheetData>",
+        MAIN_NS, RELATIONSHIPS_NS
+    );
+    let header_cells: Vec<XlsxCell> = header.iter().map(|name| XlsxCell::Text(name.clone())).collect();
+    push_row(&mut sheet_xml, 1, &header_cells, styles);
+    for (idx, row) in rows.iter().enumerate() {
+        push_row(&mut sheet_xml, idx + 2, row, styles);
+    }
+    sheet_xml.push_str("</sheetData></worksheet>");
+
+    let content_doc = parse(CONTENT_TYPES_PART, &content_types)?;
+    let content_root = content_doc.root_element();
+
+    let mut changes = PartChanges::new();
+    changes.insert(
+        WORKBOOK_PART.to_string(),
+        apply_edits(
+            &workbook,
+            vec![append_child(
+                &workbook,
+                sheets,
+                &format!(
+                    "<{p}sheet xmlns:r=\"{}\" name=\"{}\" sheetId=\"{}\" r:id=\"{}\"/>",
+                    RELATIONSHIPS_NS,
+                    escape_xml(sheet_name),
+                    sheet_id,
+                    rel_id,
+                    p = child_prefix(&workbook, sheets)
+                ),
+            )],
+        ),
+    );
+    changes.insert(
+        WORKBOOK_RELS_PART.to_string(),
+        apply_edits(
+            &rels,
+            vec![append_child(
+                &rels,
+                rels_doc.root_element(),
+                &format!(
+                    "<{p}Relationship Id=\"{}\" Type=\"{}\" Target=\"{}\"/>",
+                    rel_id,
+                    WORKSHEET_REL_TYPE,
+                    sheet_file,
+                    p = child_prefix(&rels, rels_doc.root_element())
+                ),
+            )],
+        ),
+    );
+    changes.insert(
+        CONTENT_TYPES_PART.to_string(),
+        apply_edits(
+            &content_types,
+            vec![append_child(
+                &content_types,
+                content_root,
+                &format!(
+                    "<{p}Override PartName=\"/{}\" ContentType=\"{}\"/>",
+                    part_path(&sheet_file),
+                    WORKSHEET_CONTENT_TYPE,
+                    p = child_prefix(&content_types, content_root)
+                ),
+            )],
+        ),
+    );
+    changes.insert(part_path(&sheet_file), sheet_xml);
+    Ok(changes)
+}
+
+/// Changes appending `rows` below the last used row of an existing sheet
+fn append_rows(
+    archive: &mut zip::ZipArchive<BufReader<File>>,
+    sheet_name: &str,
+    rows: &[Vec<XlsxCell>],
+    styles: &DateStyles,
+) -> Result<PartChanges, AppError> {
+    let workbook = read_part(archive, WORKBOOK_PART)?;
+    let rels = read_part(archive, WORKBOOK_RELS_PART)?;
+
+    let workbook_doc = parse(WORKBOOK_PART, &workbook)?;
+    let rel_id = child(workbook_doc.root_element(), "sheets")
+        .and_then(|sheets| {
+            sheets
+                .children()
+                .find(|n| n.is_element() && n.attribute("name") == Some(sheet_name))
+        })
+        .and_then(|sheet| sheet.attribute((RELATIONSHIPS_NS, "id")))
+        .ok_or_else(|| AppError::not_found(format!("The workbook has no sheet named {}", sheet_name)))?;
+
+    let rels_doc = parse(WORKBOOK_RELS_PART, &rels)?;
+    let target = rels_doc
+        .root_element()
+        .children()
+        .find(|n| n.attribute("Id") == Some(rel_id))
+        .and_then(|n| n.attribute("Target"))
+        .ok_or_else(|| AppError::invalid_input(format!("Sheet {} has no worksheet part", sheet_name)))?;
+    let sheet_part = part_path(target);
+
+    let sheet = read_part(archive, &sheet_part)?;
+    let sheet_doc = parse(&sheet_part, &sheet)?;
+    let root = sheet_doc.root_element();
+    let sheet_data = child(root, "sheetData")
+        .ok_or_else(|| AppError::invalid_input(format!("{} is not a worksheet", sheet_name)))?;
+
+    // Rows may leave out their number, in which case they follow the previous one
+    let mut last_row = 0;
+    let mut last_column = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    for row in sheet_data.children().filter(|n| n.is_element()) {
+        last_row = row.attribute("r").and_then(|r| r.parse().ok()).unwrap_or(last_row + 1);
+        for cell in row.children().filter(|n| n.is_element()) {
+            if let Some(column) = cell.attribute("r").and_then(column_index) {
+                last_column = last_column.max(column + 1);
+            }
+        }
+    }
+    if last_row + rows.len() > super::EXCEL_MAX_ROWS {
+        return Err(AppError::invalid_input(format!(
+            "Sheet {} has {} rows; appending {} more would pass Excel's limit of {}",
+            sheet_name,
+            last_row,
+            rows.len(),
+            super::EXCEL_MAX_ROWS
+        )));
+    }
+
+    let mut rows_xml = String::new();
+    for (idx, row) in rows.iter().enumerate() {
+        push_row(&mut rows_xml, last_row + idx + 1, row, styles);
+    }
+
+    let mut edits = vec![append_child(&sheet, sheet_data, &rows_xml)];
+    if let Some(dimension) = child(root, "dimension") {
+        let reference = format!("A1:{}{}", column_letters(last_column.max(1) - 1), last_row + rows.len());
+        edits.push(set_attribute(&sheet, dimension, "ref", &reference));
+    }
+
+    let mut changes = PartChanges::new();
+    changes.insert(sheet_part, apply_edits(&sheet, edits));
+    Ok(changes)
+}
+
+/// Write `rows` into the existing workbook at `path`. The edited package is
+/// written next to it and then moved over the original, so a failure leaves
+/// the original untouched.
+pub(super) fn write_into_workbook(
+    path: &Path,
+    sheet_name: &str,
+    edit: WorkbookEdit,
+    header: &[String],
+    rows: &[Vec<XlsxCell>],
+    date_format: &str,
+    datetime_format: &str,
+) -> Result<(), AppError> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| AppError::invalid_input(format!("Not an Excel workbook: {}", e)))?;
+
+    let styles = read_part(&mut archive, STYLES_PART)?;
+    let (styles, date_styles) = add_date_styles(&styles, date_format, datetime_format)?;
+    let mut changes = match edit {
+        WorkbookEdit::AddSheet => add_sheet(&mut archive, sheet_name, header, rows, &date_styles)?,
+        WorkbookEdit::AppendRows => append_rows(&mut archive, sheet_name, rows, &date_styles)?,
+    };
+    changes.insert(STYLES_PART.to_string(), styles);
+
+    let staged = path.with_extension("xlsx.rats-tmp");
+    let result = write_package(&mut archive, &staged, &mut changes);
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+    drop(archive);
+    std::fs::rename(&staged, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Copy every entry of `archive` to `output`, substituting changed parts and
+/// adding new ones at the end
+fn write_package(
+    archive: &mut zip::ZipArchive<BufReader<File>>,
+    output: &Path,
+    changes: &mut PartChanges,
+) -> Result<(), AppError> {
+    let zip_err = |e: zip::result::ZipError| AppError::io(format!("Failed to write workbook: {}", e));
+    let io_err = |e: std::io::Error| AppError::from(e).context("Failed to write workbook");
+
+    let file = File::create(output).map_err(io_err)?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(zip_err)?;
+        match changes.remove(entry.name()) {
+            Some(content) => {
+                let name = entry.name().to_string();
+                drop(entry);
+                writer.start_file(name, options).map_err(zip_err)?;
+                writer.write_all(content.as_bytes()).map_err(io_err)?;
+            }
+            None => writer.raw_copy_file(entry).map_err(zip_err)?,
+        }
+    }
+    for (name, content) in changes.drain() {
+        writer.start_file(name, options).map_err(zip_err)?;
+        writer.write_all(content.as_bytes()).map_err(io_err)?;
+    }
+
+    writer.finish().map_err(zip_err)?;
+    Ok(())
+}
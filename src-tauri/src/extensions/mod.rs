@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use duckdb::Result as DuckResult;
+use crate::duckdb_core;
+use crate::error::{AppError, ErrorContext};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionInfo {
+    pub name: String,
+    pub description: String,
+    pub installed: bool,
+    pub loaded: bool,
+    pub bundled: bool, // Ships with the app, so it installs without network access
+}
+
+/// Extension and repository names go into SQL unquoted
+fn check_name(kind: &str, name: &str) -> Result<(), AppError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(AppError::invalid_input(format!("Invalid {} name: {}", kind, name)));
+    }
+    Ok(())
+}
+
+fn extensions(conn: &duckdb::Connection) -> DuckResult<Vec<ExtensionInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT extension_name, COALESCE(description, ''), installed, loaded FROM duckdb_extensions()
+         ORDER BY extension_name",
+    )?;
+    let extensions = stmt
+        .query_map([], |row| {
+            let name: String = row.get(0)?;
+            Ok(ExtensionInfo {
+                bundled: duckdb_core::bundled_extension(&name).is_some(),
+                name,
+                description: row.get(1)?,
+                installed: row.get(2)?,
+                loaded: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(extensions)
+}
+
+fn extension(conn: &duckdb::Connection, name: &str) -> Result<ExtensionInfo, AppError> {
+    extensions(conn)?
+        .into_iter()
+        .find(|extension| extension.name == name)
+        .ok_or_else(|| AppError::not_found(format!("Unknown extension {}", name)))
+}
+
+/// DuckDB extensions known to this build (e.g. httpfs, spatial, sqlite,
+/// json, excel) and whether each is installed, loaded or bundled
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_extensions(app: AppHandle) -> Result<Vec<ExtensionInfo>, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        extensions(reader.get_connection()).map_err(AppError::from)
+    })
+    .await
+}
+
+/// Download an extension from `repository` ("core" by default, or
+/// "community"). Without network access the bundled copy is installed
+/// instead, when the app ships one.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn install_extension(
+    app: AppHandle,
+    name: String,
+    repository: Option<String>,
+) -> Result<ExtensionInfo, AppError> {
+    check_name("extension", &name)?;
+    if let Some(repository) = &repository {
+        check_name("repository", repository)?;
+    }
+
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        duckdb_core::install_extension(conn, &name, repository.as_deref())
+            .with_context(|| format!("Failed to install {}", name))?;
+        extension(conn, &name)
+    })
+    .await
+}
+
+/// Load an extension into the running database, installing it first if
+/// needed
+#[tauri::command(rename_all = "camelCase")]
+pub async fn load_extension(
+    app: AppHandle,
+    name: String,
+) -> Result<ExtensionInfo, AppError> {
+    check_name("extension", &name)?;
+
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        duckdb_core::load_extension(conn, &name)
+            .with_context(|| format!("Failed to load {}", name))?;
+        extension(conn, &name)
+    })
+    .await
+}
@@ -0,0 +1,118 @@
+use encoding_rs::{Encoding, UTF_8};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use crate::error::AppError;
+use super::ImportError;
+
+/// Bytes inspected when guessing the encoding of a file
+const SAMPLE_BYTES: usize = 1024 * 1024;
+
+/// Guess a file's encoding from its byte-order mark or, failing that, from
+/// the byte statistics of its first megabyte
+pub(super) fn detect_encoding(path: &PathBuf) -> Result<&'static Encoding, ImportError> {
+    let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+    File::open(path)?
+        .take(SAMPLE_BYTES as u64)
+        .read_to_end(&mut sample)?;
+
+    if let Some((encoding, _)) = Encoding::for_bom(&sample) {
+        return Ok(encoding);
+    }
+
+    // A multi-byte character cut off by the end of the sample is still UTF-8
+    let is_last = sample.len() < SAMPLE_BYTES;
+    match std::str::from_utf8(&sample) {
+        Ok(_) => return Ok(UTF_8),
+        Err(e) if e.error_len().is_none() && !is_last => return Ok(UTF_8),
+        Err(_) => {}
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(&sample, is_last);
+    Ok(detector.guess(None, true))
+}
+
+/// Resolve an encoding option: a WHATWG label such as `latin1`,
+/// `windows-1252` or `shift_jis`, or `auto`/unset to detect it
+pub(super) fn resolve_encoding(
+    path: &PathBuf,
+    requested: Option<&str>,
+) -> Result<&'static Encoding, ImportError> {
+    match requested.map(str::trim) {
+        None | Some("") | Some("auto") => detect_encoding(path),
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| AppError::invalid_input(format!("Unknown encoding: {}", label)).into()),
+    }
+}
+
+/// Prefix of converted copies in the temp directory
+const UTF8_COPY_PREFIX: &str = "rats_utf8_";
+
+/// Whether `path` is a converted copy made by `utf8_copy`
+pub(super) fn is_utf8_copy(path: &Path) -> bool {
+    path.starts_with(std::env::temp_dir())
+        && path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.starts_with(UTF8_COPY_PREFIX))
+            .unwrap_or(false)
+}
+
+/// Write a UTF-8 copy of `path` to the temp directory when it is in another
+/// encoding. Returns `None` when the file is already UTF-8.
+pub(super) fn utf8_copy(
+    path: &PathBuf,
+    requested: Option<&str>,
+) -> Result<Option<PathBuf>, ImportError> {
+    let encoding = resolve_encoding(path, requested)?;
+    if encoding == UTF_8 {
+        return Ok(None);
+    }
+
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("data.csv");
+    let output = std::env::temp_dir().join(format!("{}{}_{}", UTF8_COPY_PREFIX, std::process::id(), file_name));
+
+    let result = transcode(path, encoding, &output);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&output);
+    }
+    result.map(|_| Some(output))
+}
+
+fn transcode(path: &PathBuf, encoding: &'static Encoding, output: &PathBuf) -> Result<(), ImportError> {
+    const BUFFER_BYTES: usize = 64 * 1024;
+
+    let mut input = File::open(path)?;
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut decoder = encoding.new_decoder_with_bom_removal();
+
+    let mut buffer = vec![0u8; BUFFER_BYTES];
+    let mut decoded = String::with_capacity(BUFFER_BYTES * 3);
+    loop {
+        let read = input.read(&mut buffer)?;
+        let last = read == 0;
+
+        let mut consumed = 0;
+        loop {
+            let (result, used, _) = decoder.decode_to_string(&buffer[consumed..read], &mut decoded, last);
+            consumed += used;
+            writer.write_all(decoded.as_bytes())?;
+            decoded.clear();
+            if result == encoding_rs::CoderResult::InputEmpty {
+                break;
+            }
+            decoded.reserve(BUFFER_BYTES * 3);
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
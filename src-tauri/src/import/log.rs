@@ -0,0 +1,104 @@
+use regex::Regex;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use crate::error::AppError;
+use super::{ImportControl, ImportError};
+
+/// Apache/nginx access log timestamp, e.g. `10/Oct/2000:13:55:36 -0700`
+const ACCESS_LOG_TIMESTAMP: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+const COMMON_LOG: &str = r#"^(?P<client_ip>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<timestamp>[^\]]+)\] "(?P<method>\S+) (?P<path>\S+) ?(?P<protocol>[^"]*)" (?P<status>\d{3}) (?P<bytes>\d+|-)"#;
+const COMBINED_LOG: &str = r#"^(?P<client_ip>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<timestamp>[^\]]+)\] "(?P<method>\S+) (?P<path>\S+) ?(?P<protocol>[^"]*)" (?P<status>\d{3}) (?P<bytes>\d+|-) "(?P<referrer>[^"]*)" "(?P<user_agent>[^"]*)""#;
+const SYSLOG: &str = r"^(?P<timestamp>\w{3} +\d{1,2} \d{2}:\d{2}:\d{2}) (?P<host>\S+) (?P<process>[^:\[]+)(?:\[(?P<pid>\d+)\])?: (?P<message>.*)$";
+
+/// A built-in pattern plus the timestamp format its `timestamp` column uses
+pub(super) struct LogPreset {
+    pub pattern: &'static str,
+    pub timestamp_format: Option<&'static str>,
+}
+
+pub(super) fn preset(name: &str) -> Option<LogPreset> {
+    match name.to_lowercase().as_str() {
+        "apache_common" | "common" => Some(LogPreset {
+            pattern: COMMON_LOG,
+            timestamp_format: Some(ACCESS_LOG_TIMESTAMP),
+        }),
+        // nginx's default `combined` format is the same as Apache's
+        "apache_combined" | "combined" | "nginx" => Some(LogPreset {
+            pattern: COMBINED_LOG,
+            timestamp_format: Some(ACCESS_LOG_TIMESTAMP),
+        }),
+        // Classic syslog lines carry no year, so timestamps stay text
+        "syslog" => Some(LogPreset {
+            pattern: SYSLOG,
+            timestamp_format: None,
+        }),
+        _ => None,
+    }
+}
+
+/// Compile a pattern and check it names at least one capture group
+pub(super) fn compile_pattern(pattern: &str) -> Result<Regex, ImportError> {
+    let regex = Regex::new(pattern)
+        .map_err(|e| AppError::invalid_input(format!("Invalid log pattern: {}", e)))?;
+    if regex.capture_names().flatten().next().is_none() {
+        return Err(AppError::invalid_input(
+            "Log pattern needs at least one named capture group, e.g. (?P<level>\\w+)",
+        ).into());
+    }
+    Ok(regex)
+}
+
+/// Lines parsed and lines that did not match the pattern
+pub(super) struct ParsedLog {
+    pub rows: usize,
+    pub unmatched: usize,
+}
+
+/// Parse every line of `path` with `regex` and write the named groups as
+/// CSV columns (with a header) to `output`. Unmatched lines are counted.
+pub(super) fn write_log_csv(
+    path: &PathBuf,
+    regex: &Regex,
+    output: &Path,
+    control: &ImportControl,
+) -> Result<ParsedLog, ImportError> {
+    const BATCH_SIZE: usize = 10_000;
+
+    let names: Vec<&str> = regex.capture_names().flatten().collect();
+    let reader = BufReader::new(File::open(path)?);
+    let mut writer = csv::WriterBuilder::new().from_path(output)?;
+    writer.write_record(&names)?;
+
+    let mut parsed = ParsedLog { rows: 0, unmatched: 0 };
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match regex.captures(&line) {
+            Some(captures) => {
+                let record: Vec<&str> = names
+                    .iter()
+                    .map(|name| match captures.name(name).map(|m| m.as_str()) {
+                        // Access logs write "-" for missing values
+                        Some("-") | None => "",
+                        Some(value) => value,
+                    })
+                    .collect();
+                writer.write_record(&record)?;
+                parsed.rows += 1;
+            }
+            None => parsed.unmatched += 1,
+        }
+
+        if index % BATCH_SIZE == 0 && control.is_cancelled() {
+            return Err(ImportError::Cancelled);
+        }
+    }
+    writer.flush()?;
+
+    Ok(parsed)
+}
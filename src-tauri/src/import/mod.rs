@@ -1,11 +1,25 @@
-use calamine::{open_workbook, Reader, Xlsx};
+use calamine::{open_workbook_auto, DataType, Reader};
 use csv::ReaderBuilder;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
-use tauri::{Emitter, State};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, State};
 use crate::AppState;
+use crate::catalog::TableSource;
+use crate::duckdb_core::{in_transaction, quote_ident, table_exists, ColumnInfo, ROW_ID_COLUMN};
+use crate::error::{AppError, ErrorContext};
+
+mod encoding;
+mod log;
+mod orc;
+pub mod resume;
+mod validate;
+mod xml;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportResult {
@@ -13,6 +27,42 @@ pub struct ImportResult {
     pub message: String,
     pub table_name: String,
     pub rows_imported: usize,
+    pub tables: Vec<ImportedTable>,
+    pub rows_rejected: usize,
+    pub rejects_table: Option<String>, // Report of rows skipped with `on_error: skip_row`
+    pub rows_skipped: usize,           // Duplicates left out by `append_new`
+    pub schema_changes: Option<SchemaChanges>, // How the file's columns were matched when appending
+}
+
+/// Differences between an appended file and its target table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaChanges {
+    pub added_columns: Vec<String>,   // New in the file; added to the table as nullable columns
+    pub missing_columns: Vec<String>, // Absent from the file; filled with NULL
+    pub type_conflicts: Vec<TypeConflict>,
+}
+
+impl SchemaChanges {
+    fn is_empty(&self) -> bool {
+        self.added_columns.is_empty() && self.missing_columns.is_empty() && self.type_conflicts.is_empty()
+    }
+}
+
+/// A column whose type in the file differs from the table. Values are cast to
+/// the table type; the append fails if any of them can't be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeConflict {
+    pub column: String,
+    pub table_type: String,
+    pub file_type: String,
+    pub invalid_values: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedTable {
+    pub table_name: String,
+    pub source: String, // File path or sheet name the table was loaded from
+    pub rows_imported: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +70,160 @@ pub struct PreviewData {
     pub columns: Vec<String>,
     pub rows: Vec<Vec<String>>,
     pub total_rows: usize,
+    pub schema: Vec<ColumnInfo>,   // Column names and types DuckDB would create
+    pub parse_errors: Vec<String>, // Sample of rows DuckDB could not parse
+    pub encoding: Option<String>,  // Detected or requested text encoding (CSV only)
+    pub sheets: Vec<SheetInfo>,    // Every sheet in the workbook (Excel only)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetInfo {
+    pub name: String,
+    pub rows: usize, // Rows in the sheet's used range, headers included
+}
+
+/// Result of checking a file without importing it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub format: String,
+    pub total_rows: usize,
+    pub columns: Vec<ColumnValidation>,
+    pub parse_errors: Vec<String>, // Sample of rows that could not be parsed (CSV only)
+    pub rows_rejected: usize,
+    pub encoding: Option<String>,
+    pub error: Option<String>, // Why the file could not be read at all
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnValidation {
+    pub name: String,
+    pub data_type: String, // Type the import would create
+    pub non_null: usize,
+    pub nulls: usize,
+    pub invalid_values: usize,       // Values that don't convert to `data_type`
+    pub invalid_samples: Vec<String>,
+}
+
+/// What to do when the target table already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    #[default]
+    Replace,
+    Append,
+    AppendNew, // Append, skipping rows whose key columns match an existing row
+    FailIfExists,
+}
+
+/// A source column to keep, optionally renamed in the target table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnSelection {
+    pub source: String,
+    pub target: Option<String>, // Defaults to the source name
+}
+
+impl ColumnSelection {
+    pub fn target_name(&self) -> &str {
+        self.target.as_deref().unwrap_or(&self.source)
+    }
+}
+
+/// Load only part of a file for quick exploration. Set one of `sample_rows`
+/// or `sample_percent`; without `random` the first rows are taken.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SampleOptions {
+    pub sample_rows: Option<usize>,
+    pub sample_percent: Option<f64>,
+    pub random: bool,
+    pub seed: Option<u64>, // Makes random samples repeatable
+}
+
+/// How malformed CSV rows are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    #[default]
+    Fail,      // Abort the import on the first bad row
+    SkipRow,   // Drop bad rows and record them in a `<table>_rejects` table
+    NullValue, // Keep the row, replacing unparseable values with NULL
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvImportOptions {
+    pub delimiter: Option<String>,
+    pub quote: Option<String>,
+    pub escape: Option<String>,
+    pub has_header: Option<bool>,
+    pub skip_rows: Option<usize>,
+    pub null_string: Option<String>,
+    pub date_format: Option<String>,      // strptime format, e.g. "%d/%m/%Y" or "%Y%m%d"
+    pub timestamp_format: Option<String>, // strptime format, e.g. "%d/%m/%Y %H:%M"
+    pub on_error: OnError,
+    pub resumable: bool, // Commit in chunks so `resume_import` can continue after a cancel or crash
+    pub encoding: Option<String>, // e.g. "latin1", "shift_jis"; detected when unset or "auto"
+    pub decimal_separator: Option<String>,   // e.g. "," for "1234,5"
+    pub thousands_separator: Option<String>, // e.g. "." for "1.234,5"
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcelImportOptions {
+    pub sheet_name: Option<String>,        // Defaults to the first sheet
+    pub named_range: Option<String>,       // Workbook-level defined name, e.g. "SalesData"
+    pub excel_table: Option<String>,       // Excel Table (ListObject) name; xlsx only
+    pub header_row: Option<usize>,         // 1-based row number of the first header row
+    pub merge_header_rows: Option<usize>,  // Number of stacked header rows to combine
+    pub bulk_load_via_csv: bool,           // Stage rows in a temp CSV and COPY them in
+    pub text_only: bool,                   // Keep every column as VARCHAR (no type detection)
+    pub decimal_separator: Option<String>, // For numbers stored as text, e.g. "1.234,5"
+    pub thousands_separator: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonImportOptions {
+    pub format: String,          // "auto", "newline_delimited", "array"
+    pub flatten_nested: bool,    // Expand nested objects into parent_child columns
+    pub max_depth: Option<usize>, // Limit on nested object levels to flatten
+    pub separator: String,       // Joins parent and child names when flattening
+}
+
+impl Default for JsonImportOptions {
+    fn default() -> Self {
+        Self {
+            format: "auto".to_string(),
+            flatten_nested: false,
+            max_depth: None,
+            separator: "_".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct XmlImportOptions {
+    pub record_path: Option<String>, // e.g. "//record" or "/feed/entry"; defaults to the root's children
+    pub separator: String,           // Joins parent and child names for nested elements
+}
+
+impl Default for XmlImportOptions {
+    fn default() -> Self {
+        Self {
+            record_path: None,
+            separator: "_".to_string(),
+        }
+    }
+}
+
+/// Importable regions of a workbook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcelRegions {
+    pub sheets: Vec<String>,
+    pub named_ranges: Vec<String>,
+    pub tables: Vec<String>, // Excel Tables (ListObjects); xlsx only
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -34,8 +238,90 @@ pub enum ImportError {
     DuckDB(#[from] duckdb::Error),
     #[error("Unsupported file format")]
     UnsupportedFormat,
+    #[error("Import cancelled")]
+    Cancelled,
     #[error("{0}")]
     Custom(String),
+    #[error("{0}")]
+    App(#[from] AppError),
+}
+
+impl From<ImportError> for AppError {
+    fn from(error: ImportError) -> Self {
+        match error {
+            ImportError::App(error) => error,
+            ImportError::DuckDB(error) => AppError::from(error).context("DuckDB error"),
+            ImportError::Io(error) => AppError::from(error).context("IO error"),
+            ImportError::Cancelled => AppError::cancelled(error.to_string()),
+            ImportError::Csv(_) | ImportError::Excel(_) | ImportError::UnsupportedFormat => {
+                AppError::invalid_input(error.to_string())
+            }
+            ImportError::Custom(message) => AppError::internal(message),
+        }
+    }
+}
+
+/// Tracks the running import so `cancel_import` can stop it
+#[derive(Debug, Default)]
+pub struct ImportControl {
+    active: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+/// Marks an import as running until dropped
+pub struct ImportGuard<'a> {
+    control: &'a ImportControl,
+}
+
+impl ImportControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin(&self) -> ImportGuard<'_> {
+        self.cancelled.store(false, Ordering::SeqCst);
+        self.active.store(true, Ordering::SeqCst);
+        ImportGuard { control: self }
+    }
+
+    /// Request cancellation; returns false when no import is running
+    pub fn cancel(&self) -> bool {
+        if !self.active.load(Ordering::SeqCst) {
+            return false;
+        }
+        self.cancelled.store(true, Ordering::SeqCst);
+        true
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for ImportGuard<'_> {
+    fn drop(&mut self) {
+        self.control.active.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Turn a failed import into the error reported to the frontend, emitting
+/// the final `cancelled` progress event when the user stopped it
+fn import_failure(
+    control: &ImportControl,
+    window: &tauri::Window,
+    context: &str,
+    error: ImportError,
+) -> AppError {
+    if control.is_cancelled() || matches!(error, ImportError::Cancelled) {
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: None,
+            status: "cancelled".to_string(),
+            ..Default::default()
+        });
+        return ImportError::Cancelled.into();
+    }
+    AppError::from(error).context(context)
 }
 
 fn detect_file_format(path: &PathBuf) -> Result<String, ImportError> {
@@ -46,12 +332,67 @@ fn detect_file_format(path: &PathBuf) -> Result<String, ImportError> {
         .to_lowercase();
 
     match extension.as_str() {
-        "csv" => Ok("csv".to_string()),
-        "xlsx" | "xlsm" | "xlsb" | "xls" => Ok("excel".to_string()),
+        // DuckDB decompresses gzip transparently, so the inner extension decides
+        "gz" => {
+            let inner = path
+                .file_stem()
+                .map(std::path::Path::new)
+                .and_then(|stem| stem.extension())
+                .and_then(|s| s.to_str())
+                .ok_or(ImportError::UnsupportedFormat)?;
+            match format_from_extension(inner)?.as_str() {
+                format @ ("csv" | "json") => Ok(format.to_string()),
+                _ => Err(ImportError::UnsupportedFormat),
+            }
+        }
+        "zip" => Ok("zip".to_string()),
+        _ => format_from_extension(&extension),
+    }
+}
+
+/// Whether `path` has an extension the importer understands
+pub(crate) fn is_importable(path: &PathBuf) -> bool {
+    detect_file_format(path).is_ok()
+}
+
+/// File name without directory and without data/compression extensions,
+/// e.g. `sales.csv.gz` -> `sales`
+fn default_table_name(path: &Path) -> Option<String> {
+    let mut stem = path.file_stem()?.to_str()?;
+    if is_gzipped(path) {
+        stem = stem.rsplit_once('.').map(|(s, _)| s).unwrap_or(stem);
+    }
+    Some(stem.to_string())
+}
+
+fn format_from_extension(extension: &str) -> Result<String, ImportError> {
+    match extension.to_lowercase().as_str() {
+        "csv" | "tsv" => Ok("csv".to_string()),
+        // calamine reads OpenDocument spreadsheets through the same code path
+        "xlsx" | "xlsm" | "xlsb" | "xls" | "ods" => Ok("excel".to_string()),
+        "json" | "ndjson" | "jsonl" => Ok("json".to_string()),
+        "parquet" => Ok("parquet".to_string()),
+        "arrow" | "feather" | "ipc" => Ok("arrow".to_string()),
+        "avro" => Ok("avro".to_string()),
+        "orc" => Ok("orc".to_string()),
+        "xml" => Ok("xml".to_string()),
         _ => Err(ImportError::UnsupportedFormat),
     }
 }
 
+/// Detect the format of a remote file from the last path segment of its URL,
+/// ignoring any query string (signed URLs carry their signature there)
+fn detect_url_format(url: &str) -> Result<String, ImportError> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .ok_or(ImportError::UnsupportedFormat)?;
+
+    format_from_extension(extension)
+}
+
 fn sanitize_table_name(name: &str) -> String {
     name.chars()
         .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
@@ -66,23 +407,90 @@ fn import_csv_with_duckdb(
     table_name: &str,
     db_conn: &duckdb::Connection,
     window: tauri::Window,
+    load_options: &LoadOptions,
 ) -> Result<usize, ImportError> {
+    let options = &load_options.csv;
+    let projection = &load_options.projection;
+    let column_types = source_column_types(&load_options.column_types, projection);
+    let sample = sample_clause(load_options.sample.as_ref())?;
+
     let path_str = path.to_str().ok_or_else(|| {
-        ImportError::Custom("Invalid file path".to_string())
+        AppError::invalid_input("Invalid file path")
     })?;
 
     println!("Starting CSV import from: {}", path_str);
     println!("Target table: {}", table_name);
 
+    let started = Instant::now();
+    let total_bytes = std::fs::metadata(path).map(|m| m.len()).ok();
     let _ = window.emit("import-progress", ImportProgress {
         rows_imported: 0,
         total_rows: None,
         status: "Starting CSV import...".to_string(),
+        bytes_read: Some(0),
+        total_bytes,
+        ..Default::default()
     });
 
-    // Use DuckDB's simple recommended approach - it auto-detects everything
+    // DuckDB auto-detects anything not set explicitly in the options
     // https://duckdb.org/docs/stable/data/csv/overview
-    let query = format!("CREATE TABLE {} AS FROM '{}'", table_name, path_str);
+    let source = read_csv_source(path_str, options, &column_types)?;
+    let query = match options.on_error {
+        OnError::NullValue => {
+            // Read everything as text, then cast to the sniffed types so values
+            // that don't parse become NULL instead of failing the import
+            let columns = describe_columns(db_conn, &format!("SELECT * FROM {}", source))?;
+            let mut text_args = read_csv_args(path_str, options, &HashMap::new())?;
+            text_args.push("all_varchar = true".to_string());
+            let selected: Vec<(&str, &str)> = if projection.is_empty() {
+                columns.iter().map(|(name, _)| (name.as_str(), name.as_str())).collect()
+            } else {
+                projection.iter().map(|c| (c.source.as_str(), c.target_name())).collect()
+            };
+            let select_list = selected
+                .iter()
+                .map(|(source_name, target_name)| {
+                    let data_type = columns
+                        .iter()
+                        .find(|(name, _)| name == source_name)
+                        .map(|(_, data_type)| data_type.as_str())
+                        .ok_or_else(|| {
+                            AppError::not_found(format!("Column not found: {}", source_name))
+                        })?;
+                    Ok(format!(
+                        "{} AS {}",
+                        try_cast_text(quote_ident(source_name), data_type, options),
+                        quote_ident(target_name)
+                    ))
+                })
+                .collect::<Result<Vec<_>, ImportError>>()?;
+            format!(
+                "CREATE TABLE {} AS SELECT {} FROM read_csv({}){}",
+                quote_ident(table_name),
+                select_list.join(", "),
+                text_args.join(", "),
+                sample
+            )
+        }
+        OnError::SkipRow => {
+            // Start from empty reject tables so only this scan is reported
+            db_conn.execute_batch("DROP TABLE IF EXISTS reject_errors; DROP TABLE IF EXISTS reject_scans;")?;
+            format!(
+                "CREATE TABLE {} AS SELECT {} FROM {}{}",
+                quote_ident(table_name),
+                projection_sql(projection),
+                source,
+                sample
+            )
+        }
+        OnError::Fail => format!(
+            "CREATE TABLE {} AS SELECT {} FROM {}{}",
+            quote_ident(table_name),
+            projection_sql(projection),
+            source,
+            sample
+        ),
+    };
 
     println!("Executing query: {}", query);
 
@@ -96,7 +504,7 @@ fn import_csv_with_duckdb(
     }
 
     // Get row count using DuckDB's efficient count
-    let count_query = format!("SELECT COUNT(*) FROM {}", table_name);
+    let count_query = format!("SELECT COUNT(*) FROM {}", quote_ident(table_name));
     let row_count: usize = match db_conn.query_row(&count_query, [], |row| row.get(0)) {
         Ok(count) => {
             println!("CSV import completed: {} rows", count);
@@ -108,227 +516,3205 @@ fn import_csv_with_duckdb(
         }
     };
 
-    let _ = window.emit("import-progress", ImportProgress {
+    if let (OnError::SkipRow, Some(report)) = (options.on_error, &load_options.rejects_table) {
+        record_csv_rejects(db_conn, report, path_str)?;
+    }
+
+    let progress = ImportProgress {
         rows_imported: row_count,
         total_rows: Some(row_count),
         status: "Import complete!".to_string(),
-    });
+        bytes_read: total_bytes,
+        total_bytes,
+        ..Default::default()
+    };
+    let _ = window.emit("import-progress", progress.with_rate(started, row_count, 1, 0));
 
     Ok(row_count)
 }
 
-// For Excel, we still need to handle it manually but create proper typed table
-fn import_excel_with_duckdb(
-    path: &PathBuf,
-    table_name: &str,
+/// Cast a text column to `data_type`, yielding NULL for values that don't
+/// parse. Dates and timestamps honour the configured formats.
+fn try_cast_text(column: String, data_type: &str, options: &CsvImportOptions) -> String {
+    let format = match data_type {
+        "DATE" => options.date_format.as_ref(),
+        "TIMESTAMP" => options.timestamp_format.as_ref(),
+        _ => None,
+    };
+
+    match format {
+        Some(format) => format!(
+            "TRY_STRPTIME({}, '{}')::{}",
+            column,
+            format.replace('\'', "''"),
+            data_type
+        ),
+        None => format!("TRY_CAST({} AS {})", column, data_type),
+    }
+}
+
+/// Copy DuckDB's rejected-line details for the last scan into a report table
+fn record_csv_rejects(
     db_conn: &duckdb::Connection,
-    window: tauri::Window,
-) -> Result<usize, ImportError> {
-    let mut workbook: Xlsx<_> = open_workbook(path)
-        .map_err(|e| ImportError::Custom(format!("Excel error: {}", e)))?;
+    report_table: &str,
+    source: &str,
+) -> Result<(), ImportError> {
+    db_conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                source VARCHAR,
+                line BIGINT,
+                column_name VARCHAR,
+                error_type VARCHAR,
+                csv_line VARCHAR,
+                error_message VARCHAR
+            )",
+            quote_ident(report_table)
+        ),
+        [],
+    )?;
+    db_conn.execute(
+        &format!(
+            "INSERT INTO {} SELECT ?, line, column_name, error_type::VARCHAR, csv_line, error_message FROM reject_errors",
+            quote_ident(report_table)
+        ),
+        [source],
+    )?;
+    Ok(())
+}
 
-    let sheet_names = workbook.sheet_names().to_owned();
-    if sheet_names.is_empty() {
-        return Err(ImportError::Custom("No sheets found in Excel file".to_string()));
+/// SELECT list keeping and renaming the projected columns, or `*` for all
+fn projection_sql(projection: &[ColumnSelection]) -> String {
+    if projection.is_empty() {
+        return "*".to_string();
     }
+    projection
+        .iter()
+        .map(|c| format!("{} AS {}", quote_ident(&c.source), quote_ident(c.target_name())))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    let range = workbook
-        .worksheet_range(&sheet_names[0])
-        .map_err(|_| ImportError::Custom("Failed to read sheet".to_string()))?;
+/// Column type overrides are keyed by target name; the CSV reader needs source names
+fn source_column_types(
+    column_types: &HashMap<String, String>,
+    projection: &[ColumnSelection],
+) -> HashMap<String, String> {
+    column_types
+        .iter()
+        .map(|(column, data_type)| {
+            let source = projection
+                .iter()
+                .find(|c| c.target_name() == column)
+                .map(|c| c.source.clone())
+                .unwrap_or_else(|| column.clone());
+            (source, data_type.clone())
+        })
+        .collect()
+}
 
-    let mut all_rows = range.rows();
+/// Clause appended after the FROM source to load only a sample:
+/// `LIMIT` for head samples, `USING SAMPLE` for random ones
+fn sample_clause(sample: Option<&SampleOptions>) -> Result<String, ImportError> {
+    let Some(sample) = sample else {
+        return Ok(String::new());
+    };
 
-    // Get headers
-    let headers: Vec<String> = if let Some(header_row) = all_rows.next() {
-        header_row
-            .iter()
-            .enumerate()
-            .map(|(i, cell)| {
-                let header = cell.to_string().trim().to_string();
-                if header.is_empty() {
-                    format!("Column{}", i + 1)
-                } else {
-                    sanitize_table_name(&header)
-                }
-            })
-            .collect()
-    } else {
-        return Err(ImportError::Custom("Empty Excel file".to_string()));
+    let mut clause = match (sample.sample_rows, sample.sample_percent) {
+        (Some(rows), None) if sample.random => format!(" USING SAMPLE reservoir({} ROWS)", rows),
+        (Some(rows), None) => format!(" LIMIT {}", rows),
+        (None, Some(percent)) if !(percent > 0.0 && percent <= 100.0) => {
+            return Err(AppError::invalid_input(format!(
+                "Sample percent must be between 0 and 100, got {}",
+                percent
+            )).into());
+        }
+        (None, Some(percent)) if sample.random => {
+            format!(" USING SAMPLE bernoulli({} PERCENT)", percent)
+        }
+        (None, Some(percent)) => format!(" LIMIT {}%", percent),
+        _ => {
+            return Err(AppError::invalid_input(
+                "Specify exactly one of sampleRows or samplePercent",
+            ).into());
+        }
     };
 
-    // Create table with VARCHAR columns (DuckDB will optimize types)
-    let columns_def: Vec<String> = headers
-        .iter()
-        .map(|h| format!("\"{}\" VARCHAR", h))
-        .collect();
+    if let (true, Some(seed)) = (sample.random, sample.seed) {
+        clause.push_str(&format!(" REPEATABLE ({})", seed));
+    }
+    Ok(clause)
+}
 
-    let create_table_query = format!(
-        "CREATE TABLE {} ({})",
-        table_name,
-        columns_def.join(", ")
-    );
+/// Rewrite an already-loaded table to apply the projection and sample.
+/// Returns the new row count, or `None` when there was nothing to do.
+fn rewrite_loaded_table(
+    db_conn: &duckdb::Connection,
+    table_name: &str,
+    options: &LoadOptions,
+) -> Result<Option<usize>, ImportError> {
+    if options.projection.is_empty() && options.sample.is_none() {
+        return Ok(None);
+    }
 
-    db_conn.execute(&create_table_query, [])?;
+    let table = quote_ident(table_name);
+    let temp_table = quote_ident(&format!("{}_projection_temp", table_name));
+    db_conn.execute(&format!("DROP TABLE IF EXISTS {}", temp_table), [])?;
+    let sample = sample_clause(options.sample.as_ref())?;
+    in_transaction(db_conn, || {
+        db_conn.execute(
+            &format!(
+                "CREATE TABLE {} AS SELECT {} FROM {}{}",
+                temp_table,
+                projection_sql(&options.projection),
+                table,
+                sample
+            ),
+            [],
+        )?;
+        db_conn.execute(&format!("DROP TABLE {}", table), [])?;
+        db_conn.execute(&format!("ALTER TABLE {} RENAME TO {}", temp_table, table), [])
+    })?;
 
-    // Start transaction for better performance
-    db_conn.execute("BEGIN TRANSACTION", [])?;
+    let row_count: usize = db_conn.query_row(
+        &format!("SELECT COUNT(*) FROM {}", table),
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(Some(row_count))
+}
 
-    // Prepare INSERT statement
-    let placeholders = vec!["?"; headers.len()].join(", ");
-    let insert_query = format!("INSERT INTO {} VALUES ({})", table_name, placeholders);
+/// Reject anything that is not a plain SQL type name such as `VARCHAR`,
+/// `DATE` or `DECIMAL(18, 2)`, since type names are spliced into SQL
+fn validate_type_name(data_type: &str) -> Result<(), ImportError> {
+    if crate::duckdb_core::is_type_name(data_type) {
+        Ok(())
+    } else {
+        Err(AppError::invalid_input(format!("Invalid column type: {}", data_type)).into())
+    }
+}
 
-    let mut total_rows = 0;
-    let mut batch_count = 0;
-    const BATCH_SIZE: usize = 1000;
+/// Convert columns of an already-imported table to the requested types
+fn apply_column_types(
+    db_conn: &duckdb::Connection,
+    table_name: &str,
+    column_types: &HashMap<String, String>,
+) -> Result<(), ImportError> {
+    for (column, data_type) in column_types {
+        validate_type_name(data_type)?;
+        db_conn
+            .execute(
+                &format!(
+                    "ALTER TABLE {} ALTER COLUMN {} TYPE {}",
+                    quote_ident(table_name),
+                    quote_ident(column),
+                    data_type
+                ),
+                [],
+            )
+            .map_err(|e| {
+                AppError::from(e).context(format!("Failed to convert column {} to {}", column, data_type))
+            })?;
+    }
+    Ok(())
+}
 
-    for row in all_rows {
-        let cell_strings: Vec<String> = row
-            .iter()
-            .map(|cell| {
-                let s = cell.to_string();
-                if s.is_empty() {
-                    String::new()
-                } else {
-                    s
-                }
-            })
-            .collect();
+/// Check locale separators and fill in the default decimal point. Returns
+/// `None` when neither separator was set.
+fn number_separators(
+    decimal: &Option<String>,
+    thousands: &Option<String>,
+) -> Result<Option<(String, Option<String>)>, ImportError> {
+    if decimal.is_none() && thousands.is_none() {
+        return Ok(None);
+    }
+    for (name, value) in [("decimal separator", decimal), ("thousands separator", thousands)] {
+        if value.as_ref().is_some_and(|v| v.chars().count() != 1) {
+            return Err(AppError::invalid_input(format!("The {} must be a single character", name)).into());
+        }
+    }
 
-        let values: Vec<&dyn duckdb::ToSql> = cell_strings
-            .iter()
-            .map(|s| {
-                if s.is_empty() {
-                    &duckdb::types::Null as &dyn duckdb::ToSql
-                } else {
-                    s as &dyn duckdb::ToSql
-                }
-            })
-            .collect();
+    let decimal = decimal.clone().unwrap_or_else(|| ".".to_string());
+    if thousands.as_ref() == Some(&decimal) {
+        return Err(AppError::invalid_input(
+            "Decimal and thousands separators must differ",
+        ).into());
+    }
+    Ok(Some((decimal, thousands.clone())))
+}
 
-        db_conn.execute(&insert_query, duckdb::params_from_iter(values.iter()))?;
-        total_rows += 1;
-        batch_count += 1;
+/// Convert text columns holding locale-formatted numbers (e.g. "1.234,5")
+/// to DOUBLE. A column is only converted when every value parses and at
+/// least one uses a separator; columns with explicit types are left alone.
+fn convert_locale_numbers(
+    db_conn: &duckdb::Connection,
+    table_name: &str,
+    decimal: &str,
+    thousands: Option<&str>,
+    column_types: &HashMap<String, String>,
+) -> Result<(), ImportError> {
+    let digits = match thousands {
+        Some(thousands) => format!(r"(\d{{1,3}}({}\d{{3}})+|\d+)", regex::escape(thousands)),
+        None => r"\d+".to_string(),
+    };
+    let pattern = format!(r"[+-]?{}({}\d+)?", digits, regex::escape(decimal));
+    let literal = |value: &str| format!("'{}'", value.replace('\'', "''"));
 
-        // Emit progress every 1000 rows
-        if batch_count >= BATCH_SIZE {
-            let _ = window.emit("import-progress", ImportProgress {
-                rows_imported: total_rows,
-                total_rows: None,
-                status: format!("Importing... {} rows", total_rows),
-            });
-            batch_count = 0;
+    let normalized = |column: &str| {
+        let mut value = format!("TRIM({})", column);
+        if let Some(thousands) = thousands {
+            value = format!("REPLACE({}, {}, '')", value, literal(thousands));
         }
-    }
+        format!("REPLACE({}, {}, '.')", value, literal(decimal))
+    };
+    let uses_separator = match thousands {
+        Some(thousands) => format!("contains(v, {}) OR contains(v, {})", literal(decimal), literal(thousands)),
+        None => format!("contains(v, {})", literal(decimal)),
+    };
 
-    // Commit transaction
-    db_conn.execute("COMMIT", [])?;
+    let table = quote_ident(table_name);
+    for (name, data_type) in describe_columns(db_conn, &table)? {
+        if data_type != "VARCHAR" || column_types.contains_key(&name) {
+            continue;
+        }
+        let column = quote_ident(&name);
+        let (unparsed, separated): (usize, usize) = db_conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FILTER (WHERE v <> '' AND NOT regexp_full_match(v, {})),
+                        COUNT(*) FILTER (WHERE {})
+                 FROM (SELECT TRIM({}) AS v FROM {} WHERE {} IS NOT NULL)",
+                literal(&pattern),
+                uses_separator,
+                column,
+                table,
+                column
+            ),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if unparsed > 0 || separated == 0 {
+            continue;
+        }
 
-    let _ = window.emit("import-progress", ImportProgress {
-        rows_imported: total_rows,
-        total_rows: Some(total_rows),
-        status: "Finalizing import...".to_string(),
-    });
+        db_conn.execute(
+            &format!(
+                "ALTER TABLE {} ALTER COLUMN {} TYPE DOUBLE USING CAST(NULLIF({}, '') AS DOUBLE)",
+                table,
+                column,
+                normalized(&column)
+            ),
+            [],
+        )?;
+    }
+    Ok(())
+}
 
-    Ok(total_rows)
+fn single_char_option(name: &str, value: &Option<String>) -> Result<Option<String>, ImportError> {
+    match value {
+        None => Ok(None),
+        Some(v) if v == "\\t" || v == "\t" => Ok(Some("\\t".to_string())),
+        Some(v) if v.chars().count() == 1 => Ok(Some(v.replace('\'', "''"))),
+        Some(v) => Err(AppError::invalid_input(format!(
+            "CSV {} must be a single character, got '{}'",
+            name, v
+        )).into()),
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ImportProgress {
-    pub rows_imported: usize,
-    pub total_rows: Option<usize>,
-    pub status: String,
+/// Build a `read_csv(...)` table function call from the import options
+fn read_csv_source(
+    path_str: &str,
+    options: &CsvImportOptions,
+    column_types: &HashMap<String, String>,
+) -> Result<String, ImportError> {
+    let args = read_csv_args(path_str, options, column_types)?;
+    Ok(format!("read_csv({})", args.join(", ")))
 }
 
-#[tauri::command(rename_all = "camelCase")]
-pub async fn import_file(
-    state: State<'_, AppState>,
-    window: tauri::Window,
-    file_path: String,
-    table_name: Option<String>,
-) -> Result<ImportResult, String> {
-    let path = PathBuf::from(&file_path);
-    let format = detect_file_format(&path).map_err(|e| e.to_string())?;
+fn read_csv_args(
+    path_str: &str,
+    options: &CsvImportOptions,
+    column_types: &HashMap<String, String>,
+) -> Result<Vec<String>, ImportError> {
+    let mut args = vec![
+        format!("'{}'", path_str.replace('\'', "''")),
+        "auto_detect = true".to_string(),
+    ];
 
-    let table_name = table_name.unwrap_or_else(|| {
-        path.file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("imported_data")
-            .to_string()
-    });
+    if let Some(delim) = single_char_option("delimiter", &options.delimiter)? {
+        args.push(format!("delim = '{}'", delim));
+    }
+    if let Some(quote) = single_char_option("quote", &options.quote)? {
+        args.push(format!("quote = '{}'", quote));
+    }
+    if let Some(escape) = single_char_option("escape", &options.escape)? {
+        args.push(format!("escape = '{}'", escape));
+    }
+    if let Some(has_header) = options.has_header {
+        args.push(format!("header = {}", has_header));
+    }
+    if let Some(skip) = options.skip_rows {
+        args.push(format!("skip = {}", skip));
+    }
+    if let Some(null_string) = &options.null_string {
+        args.push(format!("nullstr = '{}'", null_string.replace('\'', "''")));
+    }
+    if let Some(date_format) = &options.date_format {
+        args.push(format!("dateformat = '{}'", date_format.replace('\'', "''")));
+    }
+    if let Some(timestamp_format) = &options.timestamp_format {
+        args.push(format!("timestampformat = '{}'", timestamp_format.replace('\'', "''")));
+    }
+    if !column_types.is_empty() {
+        let mut entries = Vec::new();
+        for (column, data_type) in column_types {
+            validate_type_name(data_type)?;
+            entries.push(format!("'{}': '{}'", column.replace('\'', "''"), data_type));
+        }
+        entries.sort();
+        args.push(format!("types = {{{}}}", entries.join(", ")));
+    }
+    match options.on_error {
+        OnError::Fail => {}
+        OnError::SkipRow => {
+            args.push("ignore_errors = true".to_string());
+            args.push("store_rejects = true".to_string());
+        }
+        OnError::NullValue => args.push("null_padding = true".to_string()),
+    }
+    // Keep locale-formatted numbers as text so they can be converted after
+    // loading; DuckDB would otherwise read "1.234" as 1.234
+    if number_separators(&options.decimal_separator, &options.thousands_separator)?.is_some() {
+        args.push(
+            "auto_type_candidates = ['BOOLEAN', 'BIGINT', 'TIME', 'DATE', 'TIMESTAMP', 'VARCHAR']".to_string(),
+        );
+    }
+
+    Ok(args)
+}
 
-    let sanitized_table_name = sanitize_table_name(&table_name);
+fn read_json_source(path_str: &str, options: &JsonImportOptions) -> Result<String, ImportError> {
+    let format = match options.format.as_str() {
+        "auto" | "newline_delimited" | "array" => options.format.as_str(),
+        other => {
+            return Err(AppError::invalid_input(format!("Unknown JSON format: {}", other)).into());
+        }
+    };
+
+    Ok(format!(
+        "read_json_auto('{}', format = '{}')",
+        path_str.replace('\'', "''"),
+        format
+    ))
+}
+
+// Let DuckDB handle JSON/NDJSON import, optionally flattening nested objects
+fn import_json_with_duckdb(
+    path: &Path,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: tauri::Window,
+    options: &JsonImportOptions,
+) -> Result<usize, ImportError> {
+    let path_str = path.to_str().ok_or_else(|| {
+        AppError::invalid_input("Invalid file path")
+    })?;
 
-    // Emit start event with clearer messaging
     let _ = window.emit("import-progress", ImportProgress {
         rows_imported: 0,
         total_rows: None,
-        status: "Starting import... Large files may take 1-2 minutes".to_string(),
+        status: "Starting JSON import...".to_string(),
+        ..Default::default()
     });
 
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
-
-    // Drop table if exists
-    let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", sanitized_table_name), []);
+    let source = read_json_source(path_str, options)?;
+    db_conn.execute(&format!("CREATE TABLE {} AS SELECT * FROM {}", quote_ident(table_name), source), [])?;
 
-    // Perform import (Tauri's async runtime keeps this from blocking UI)
-    let rows_imported = match format.as_str() {
-        "csv" => import_csv_with_duckdb(&path, &sanitized_table_name, conn, window.clone()),
-        "excel" => import_excel_with_duckdb(&path, &sanitized_table_name, conn, window.clone()),
-        _ => Err(ImportError::UnsupportedFormat),
+    if options.flatten_nested {
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: None,
+            status: "Flattening nested objects...".to_string(),
+            ..Default::default()
+        });
+        flatten_struct_columns(db_conn, table_name, options)?;
     }
-    .map_err(|e| e.to_string())?;
 
-    // Emit completion event
-    let _ = window.emit("import-progress", ImportProgress {
-        rows_imported,
-        total_rows: Some(rows_imported),
-        status: "Import complete!".to_string(),
-    });
+    let count_query = format!("SELECT COUNT(*) FROM {}", quote_ident(table_name));
+    let row_count: usize = db_conn.query_row(&count_query, [], |row| row.get(0))?;
 
-    Ok(ImportResult {
-        success: true,
-        message: format!("Successfully imported {} rows", rows_imported),
-        table_name: sanitized_table_name,
-        rows_imported,
-    })
+    Ok(row_count)
 }
 
-#[tauri::command(rename_all = "camelCase")]
-pub async fn preview_file(
-    file_path: String,
-    rows: Option<usize>,
-) -> Result<PreviewData, String> {
-    let path = PathBuf::from(&file_path);
-    let format = detect_file_format(&path).map_err(|e| e.to_string())?;
-    let preview_rows = rows.unwrap_or(10);
+/// Repeatedly expand STRUCT columns into `parent<sep>child` columns until no
+/// structs remain or `max_depth` levels have been flattened
+fn flatten_struct_columns(
+    db_conn: &duckdb::Connection,
+    table_name: &str,
+    options: &JsonImportOptions,
+) -> Result<(), ImportError> {
+    let max_depth = options.max_depth.unwrap_or(usize::MAX);
+    let table = quote_ident(table_name);
+    let temp_table = quote_ident(&format!("{}_flatten_temp", table_name));
+    let mut depth = 0;
 
-    match format.as_str() {
-        "csv" => preview_csv(&path, preview_rows),
-        "excel" => preview_excel(&path, preview_rows),
-        _ => Err("Unsupported format".to_string()),
-    }
-}
+    while depth < max_depth {
+        let columns = describe_columns(db_conn, &format!("SELECT * FROM {}", table))?;
+        if !columns.iter().any(|(_, t)| t.starts_with("STRUCT(")) {
+            break;
+        }
 
-fn preview_csv(path: &PathBuf, rows: usize) -> Result<PreviewData, String> {
-    let file = File::open(path).map_err(|e| e.to_string())?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = ReaderBuilder::new().from_reader(buf_reader);
+        let mut select_parts = Vec::new();
+        for (name, data_type) in &columns {
+            if data_type.starts_with("STRUCT(") {
+                let fields = describe_columns(
+                    db_conn,
+                    &format!("SELECT {}.* FROM {}", quote_ident(name), table),
+                )?;
+                for (field, _) in fields {
+                    select_parts.push(format!(
+                        "{}.{} AS {}",
+                        quote_ident(name),
+                        quote_ident(&field),
+                        quote_ident(&format!("{}{}{}", name, options.separator, field))
+                    ));
+                }
+            } else {
+                select_parts.push(quote_ident(name));
+            }
+        }
 
-    let headers: Vec<String> = rdr
-        .headers()
-        .map_err(|e| e.to_string())?
+        db_conn.execute(&format!("DROP TABLE IF EXISTS {}", temp_table), [])?;
+        in_transaction(db_conn, || {
+            db_conn.execute(
+                &format!(
+                    "CREATE TABLE {} AS SELECT {} FROM {}",
+                    temp_table,
+                    select_parts.join(", "),
+                    table
+                ),
+                [],
+            )?;
+            db_conn.execute(&format!("DROP TABLE {}", table), [])?;
+            db_conn.execute(&format!("ALTER TABLE {} RENAME TO {}", temp_table, table), [])
+        })?;
+
+        depth += 1;
+    }
+
+    Ok(())
+}
+
+/// Column names and types a query would produce
+fn describe_columns(
+    db_conn: &duckdb::Connection,
+    query: &str,
+) -> Result<Vec<(String, String)>, ImportError> {
+    let mut stmt = db_conn.prepare(&format!("DESCRIBE {}", query))?;
+    let columns = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns)
+}
+
+// Parquet files carry their own schema, so DuckDB reads them directly
+fn import_parquet_with_duckdb(
+    source: &str,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    options: &LoadOptions,
+) -> Result<usize, ImportError> {
+    let source_sql = format!(
+        "read_parquet('{}'){}",
+        source.replace('\'', "''"),
+        sample_clause(options.sample.as_ref())?
+    );
+    create_table_from_query(db_conn, table_name, &projection_sql(&options.projection), &source_sql)
+}
+
+/// Arrow IPC, Feather and ORC files are read through DuckDB's community
+/// `arrow` extension, which is downloaded on first use unless the app ships it
+fn read_arrow_source(db_conn: &duckdb::Connection, source: &str) -> Result<String, ImportError> {
+    crate::duckdb_core::load_community_extension(db_conn, "arrow").map_err(|e| {
+        match crate::duckdb_core::bundled_extension("arrow") {
+            Some(_) => AppError::from(e).context("Failed to load arrow extension"),
+            None => AppError::io(format!(
+                "Reading Arrow, Feather and ORC files needs DuckDB's arrow extension, which could not be \
+                downloaded from the community repository. Connect to the internet once to install it. ({})",
+                e
+            )),
+        }
+    })?;
+    Ok(format!("read_arrow('{}')", source.replace('\'', "''")))
+}
+
+// Arrow IPC / Feather v2 files keep their exact column types
+fn import_arrow_with_duckdb(
+    source: &str,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    options: &LoadOptions,
+) -> Result<usize, ImportError> {
+    let source_sql = format!(
+        "{}{}",
+        read_arrow_source(db_conn, source)?,
+        sample_clause(options.sample.as_ref())?
+    );
+    create_table_from_query(db_conn, table_name, &projection_sql(&options.projection), &source_sql)
+}
+
+fn read_avro_source(db_conn: &duckdb::Connection, source: &str) -> Result<String, ImportError> {
+    crate::duckdb_core::load_extension(db_conn, "avro").map_err(|e| {
+        AppError::from(e).context("Failed to load avro extension")
+    })?;
+    Ok(format!("read_avro('{}')", source.replace('\'', "''")))
+}
+
+// Avro files carry their writer schema, so column types come from the file
+fn import_avro_with_duckdb(
+    source: &str,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    options: &LoadOptions,
+) -> Result<usize, ImportError> {
+    let source_sql = format!(
+        "{}{}",
+        read_avro_source(db_conn, source)?,
+        sample_clause(options.sample.as_ref())?
+    );
+    create_table_from_query(db_conn, table_name, &projection_sql(&options.projection), &source_sql)
+}
+
+fn orc_temp_path(path: &Path, purpose: &str) -> PathBuf {
+    let file_name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("data");
+    std::env::temp_dir().join(format!("rats_orc_{}_{}_{}.arrow", purpose, std::process::id(), file_name))
+}
+
+/// DuckDB has no ORC reader, so ORC files are converted to Arrow IPC first
+fn import_orc_with_duckdb(
+    path: &PathBuf,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: tauri::Window,
+    options: &LoadOptions,
+    control: &ImportControl,
+) -> Result<usize, ImportError> {
+    let _ = window.emit("import-progress", ImportProgress {
+        rows_imported: 0,
+        total_rows: None,
+        status: "Reading ORC stripes...".to_string(),
+        ..Default::default()
+    });
+
+    let temp_path = orc_temp_path(path, "import");
+    let result = orc::write_arrow_ipc(path, &temp_path, None, control).and_then(|_| {
+        let path_str = temp_path.to_str().ok_or_else(|| {
+            ImportError::Custom("Invalid temp file path".to_string())
+        })?;
+        import_arrow_with_duckdb(path_str, table_name, db_conn, options)
+    });
+    let _ = std::fs::remove_file(&temp_path);
+    result
+}
+
+/// Flatten XML records into a temp CSV so DuckDB can detect column types
+fn import_xml_with_duckdb(
+    path: &PathBuf,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: tauri::Window,
+    options: &XmlImportOptions,
+) -> Result<usize, ImportError> {
+    let _ = window.emit("import-progress", ImportProgress {
+        rows_imported: 0,
+        total_rows: None,
+        status: "Parsing XML records...".to_string(),
+        ..Default::default()
+    });
+
+    let records = xml::read_xml_records(path, options)?;
+    let temp_path = std::env::temp_dir().join(format!(
+        "rats_xml_{}_{}.csv",
+        std::process::id(),
+        table_name
+    ));
+
+    let result = records.write_csv(&temp_path).and_then(|_| {
+        let path_str = temp_path.to_str().ok_or_else(|| {
+            ImportError::Custom("Invalid temp file path".to_string())
+        })?;
+        create_table_from_source(
+            db_conn,
+            table_name,
+            &format!("read_csv('{}', header = true)", path_str.replace('\'', "''")),
+        )
+    });
+
+    let _ = std::fs::remove_file(&temp_path);
+    result
+}
+
+/// Materialize a table function call into a new table and return its row count
+fn create_table_from_source(
+    db_conn: &duckdb::Connection,
+    table_name: &str,
+    source_sql: &str,
+) -> Result<usize, ImportError> {
+    create_table_from_query(db_conn, table_name, "*", source_sql)
+}
+
+/// Like `create_table_from_source`, selecting only `select_list` from the source
+fn create_table_from_query(
+    db_conn: &duckdb::Connection,
+    table_name: &str,
+    select_list: &str,
+    source_sql: &str,
+) -> Result<usize, ImportError> {
+    db_conn.execute(
+        &format!("CREATE TABLE {} AS SELECT {} FROM {}", quote_ident(table_name), select_list, source_sql),
+        [],
+    )?;
+
+    let count_query = format!("SELECT COUNT(*) FROM {}", quote_ident(table_name));
+    let row_count: usize = db_conn.query_row(&count_query, [], |row| row.get(0))?;
+    Ok(row_count)
+}
+
+// For Excel, we still need to handle it manually but create proper typed table
+fn import_excel_with_duckdb(
+    path: &PathBuf,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: tauri::Window,
+    options: &ExcelImportOptions,
+    control: &ImportControl,
+) -> Result<usize, ImportError> {
+    let range = read_excel_data(path, options)?;
+
+    import_excel_range(&range, table_name, db_conn, window, options, control)
+}
+
+/// Read the cells selected by the options: an Excel Table, a named range,
+/// or the used range of a sheet
+fn read_excel_data(
+    path: &PathBuf,
+    options: &ExcelImportOptions,
+) -> Result<calamine::Range<calamine::Data>, ImportError> {
+    match (&options.excel_table, &options.named_range) {
+        (Some(_), Some(_)) => Err(AppError::invalid_input(
+            "Specify either an Excel table or a named range, not both",
+        ).into()),
+        (Some(table), None) => read_excel_table(path, table),
+        (None, Some(name)) => read_named_range(path, name),
+        (None, None) => read_excel_sheet(path, options.sheet_name.as_deref()),
+    }
+}
+
+/// Read an Excel Table (ListObject) including its header row
+fn read_excel_table(
+    path: &PathBuf,
+    table_name: &str,
+) -> Result<calamine::Range<calamine::Data>, ImportError> {
+    let mut workbook: calamine::Xlsx<_> = calamine::open_workbook(path)
+        .map_err(|e| AppError::invalid_input(format!("Excel tables are only supported in .xlsx files: {}", e)))?;
+    workbook
+        .load_tables()
+        .map_err(|e| ImportError::Custom(format!("Failed to read Excel tables: {}", e)))?;
+
+    let table = workbook
+        .table_by_name(table_name)
+        .map_err(|_| AppError::not_found(format!("Excel table {} not found", table_name)))?;
+    let (start, end) = match (table.data().start(), table.data().end()) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Err(AppError::invalid_input(format!("Excel table {} has no rows", table_name)).into()),
+    };
+
+    // Table data excludes the header row, which sits directly above it
+    let sheet = workbook
+        .worksheet_range(table.sheet_name())
+        .map_err(|e| ImportError::Custom(format!("Failed to read sheet: {}", e)))?;
+    Ok(sheet.range((start.0.saturating_sub(1), start.1), end))
+}
+
+/// Read a workbook-level defined name such as `'Q1 Sales'!$A$3:$F$120`
+fn read_named_range(
+    path: &PathBuf,
+    name: &str,
+) -> Result<calamine::Range<calamine::Data>, ImportError> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| ImportError::Custom(format!("Excel error: {}", e)))?;
+
+    let reference = workbook
+        .defined_names()
+        .iter()
+        .find(|(defined, _)| defined.eq_ignore_ascii_case(name))
+        .map(|(_, reference)| reference.clone())
+        .ok_or_else(|| AppError::not_found(format!("Named range {} not found", name)))?;
+
+    let (sheet, start, end) = parse_range_reference(&reference).ok_or_else(|| {
+        AppError::invalid_input(format!(
+            "Named range {} must refer to a single block of cells, got {}",
+            name, reference
+        ))
+    })?;
+
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|e| ImportError::Custom(format!("Failed to read sheet {}: {}", sheet, e)))?;
+    Ok(range.range(start, end))
+}
+
+/// A 0-based (row, column) cell position
+type Cell = (u32, u32);
+
+/// Split `Sheet!$A$1:$D$20` into the sheet name and 0-based (row, column)
+/// corners. Single cells and quoted sheet names are accepted.
+fn parse_range_reference(reference: &str) -> Option<(String, Cell, Cell)> {
+    let reference = reference.trim().trim_start_matches('=');
+    let (sheet, cells) = reference.rsplit_once('!')?;
+    let sheet = sheet
+        .strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .map(|s| s.replace("''", "'"))
+        .unwrap_or_else(|| sheet.to_string());
+
+    let (first, last) = cells.split_once(':').unwrap_or((cells, cells));
+    Some((sheet, parse_cell_reference(first)?, parse_cell_reference(last)?))
+}
+
+/// `$B$7` -> (6, 1)
+fn parse_cell_reference(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.replace('$', "");
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() {
+        return None;
+    }
+
+    let mut column: u32 = 0;
+    for c in letters.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        column = column * 26 + (c.to_ascii_uppercase() as u32 - 'A' as u32 + 1);
+    }
+    let row: u32 = digits.parse().ok()?;
+    Some((row.checked_sub(1)?, column - 1))
+}
+
+/// Open a workbook and read the requested sheet, or the first one
+fn read_excel_sheet(
+    path: &PathBuf,
+    sheet_name: Option<&str>,
+) -> Result<calamine::Range<calamine::Data>, ImportError> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| ImportError::Custom(format!("Excel error: {}", e)))?;
+
+    let sheet_names = workbook.sheet_names().to_owned();
+    if sheet_names.is_empty() {
+        return Err(AppError::invalid_input("No sheets found in Excel file").into());
+    }
+
+    let sheet = match sheet_name {
+        Some(name) if sheet_names.iter().any(|s| s == name) => name.to_string(),
+        Some(name) => {
+            return Err(AppError::not_found(format!("Sheet {} not found", name)).into());
+        }
+        None => sheet_names[0].clone(),
+    };
+
+    workbook
+        .worksheet_range(&sheet)
+        .map_err(|_| ImportError::Custom("Failed to read sheet".to_string()))
+}
+
+/// Name and used-range height of every sheet in a workbook
+fn excel_sheet_info(path: &PathBuf) -> Result<Vec<SheetInfo>, ImportError> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| ImportError::Custom(format!("Excel error: {}", e)))?;
+
+    let sheet_names = workbook.sheet_names().to_owned();
+    Ok(sheet_names
+        .into_iter()
+        .map(|name| {
+            let rows = workbook.worksheet_range(&name).map(|range| range.height()).unwrap_or(0);
+            SheetInfo { name, rows }
+        })
+        .collect())
+}
+
+/// Iterate the sheet rows starting at the configured header row, skipping
+/// title and blank rows above it
+fn excel_rows_from_header<'a>(
+    range: &'a calamine::Range<calamine::Data>,
+    options: &ExcelImportOptions,
+) -> impl Iterator<Item = &'a [calamine::Data]> {
+    // calamine ranges start at the first non-empty row, not at row 1
+    let first_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+    let skip = options
+        .header_row
+        .unwrap_or(first_row + 1)
+        .saturating_sub(1)
+        .saturating_sub(first_row);
+
+    range.rows().skip(skip)
+}
+
+/// Build column names from one or more header rows. Upper rows are
+/// forward-filled so merged cells spanning several columns apply to each,
+/// then the parts are joined with `_`. Duplicate names get a numeric suffix.
+fn excel_headers<'a, I>(rows: &mut I, options: &ExcelImportOptions) -> Option<Vec<String>>
+where
+    I: Iterator<Item = &'a [calamine::Data]>,
+{
+    let header_count = options.merge_header_rows.unwrap_or(1).max(1);
+    let header_rows: Vec<Vec<String>> = rows
+        .take(header_count)
+        .map(|row| row.iter().map(|c| c.to_string().trim().to_string()).collect())
+        .collect();
+
+    if header_rows.is_empty() {
+        return None;
+    }
+
+    let width = header_rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut filled: Vec<Vec<String>> = Vec::new();
+    for (row_idx, row) in header_rows.iter().enumerate() {
+        let is_last = row_idx == header_rows.len() - 1;
+        let mut current = String::new();
+        let mut filled_row = Vec::with_capacity(width);
+        for col in 0..width {
+            let cell = row.get(col).cloned().unwrap_or_default();
+            if !cell.is_empty() {
+                current = cell.clone();
+            }
+            filled_row.push(if is_last { cell } else { current.clone() });
+        }
+        filled.push(filled_row);
+    }
+
+    let mut headers: Vec<String> = Vec::with_capacity(width);
+    for col in 0..width {
+        let mut parts: Vec<&str> = Vec::new();
+        for row in &filled {
+            let part = row[col].as_str();
+            if !part.is_empty() && parts.last() != Some(&part) {
+                parts.push(part);
+            }
+        }
+
+        let header = sanitize_table_name(&parts.join("_"));
+        let header = if header.is_empty() {
+            format!("Column{}", col + 1)
+        } else {
+            header
+        };
+
+        let mut unique = header.clone();
+        let mut suffix = 2;
+        while headers.contains(&unique) {
+            unique = format!("{}_{}", header, suffix);
+            suffix += 1;
+        }
+        headers.push(unique);
+    }
+
+    Some(headers)
+}
+
+// Load one worksheet range into a new VARCHAR table
+fn import_excel_range(
+    range: &calamine::Range<calamine::Data>,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: tauri::Window,
+    options: &ExcelImportOptions,
+    control: &ImportControl,
+) -> Result<usize, ImportError> {
+    let mut all_rows = excel_rows_from_header(range, options);
+
+    // Get headers
+    let headers = excel_headers(&mut all_rows, options)
+        .ok_or_else(|| AppError::invalid_input("Empty Excel file"))?;
+
+    // Detect column types from the cell types calamine reports
+    let column_types = if options.text_only {
+        vec!["VARCHAR"; headers.len()]
+    } else {
+        let header_count = options.merge_header_rows.unwrap_or(1).max(1);
+        infer_excel_column_types(
+            excel_rows_from_header(range, options).skip(header_count),
+            headers.len(),
+        )
+    };
+
+    // Load as VARCHAR first, then convert the typed columns in one pass
+    let columns_def: Vec<String> = headers
+        .iter()
+        .map(|h| format!("{} VARCHAR", quote_ident(h)))
+        .collect();
+
+    let create_table_query = format!(
+        "CREATE TABLE {} ({})",
+        quote_ident(table_name),
+        columns_def.join(", ")
+    );
+
+    db_conn.execute(&create_table_query, [])?;
+
+    // Rows below the header, known up front since calamine holds the range
+    let expected_rows = all_rows.size_hint().1;
+
+    let result = if options.bulk_load_via_csv {
+        load_excel_rows_via_csv(all_rows, headers.len(), table_name, db_conn, &window, control)
+    } else {
+        append_excel_rows(all_rows, headers.len(), table_name, db_conn, &window, control, expected_rows)
+    };
+
+    let total_rows = match result {
+        Ok(rows) => rows,
+        Err(e) => {
+            let _ = db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(table_name)), []);
+            return Err(e);
+        }
+    };
+
+    let _ = window.emit("import-progress", ImportProgress {
+        rows_imported: total_rows,
+        total_rows: Some(total_rows),
+        status: "Finalizing import...".to_string(),
+        ..Default::default()
+    });
+
+    for (header, data_type) in headers.iter().zip(column_types.iter()) {
+        if *data_type == "VARCHAR" {
+            continue;
+        }
+        // Cells were written in a canonical text form, so this cast only fails
+        // on unexpected data
+        db_conn
+            .execute(
+                &format!(
+                    "ALTER TABLE {} ALTER COLUMN {} TYPE {}",
+                    quote_ident(table_name), quote_ident(header), data_type
+                ),
+                [],
+            )
+            .map_err(|e| {
+                let _ = db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(table_name)), []);
+                AppError::from(e).context(format!("Failed to convert column {} to {}", header, data_type))
+            })?;
+    }
+
+    Ok(total_rows)
+}
+
+/// Pick a DuckDB type per column from the calamine cell types. Columns mixing
+/// incompatible kinds fall back to VARCHAR; empty and error cells are ignored.
+fn infer_excel_column_types<'a, I>(rows: I, column_count: usize) -> Vec<&'static str>
+where
+    I: Iterator<Item = &'a [calamine::Data]>,
+{
+    use calamine::Data;
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Kind {
+        Unknown,
+        Integer,
+        Float,
+        Boolean,
+        Date,
+        Timestamp,
+        Text,
+    }
+
+    fn merge(current: Kind, next: Kind) -> Kind {
+        match (current, next) {
+            (Kind::Unknown, k) => k,
+            (a, b) if a == b => a,
+            (Kind::Integer, Kind::Float) | (Kind::Float, Kind::Integer) => Kind::Float,
+            (Kind::Date, Kind::Timestamp) | (Kind::Timestamp, Kind::Date) => Kind::Timestamp,
+            _ => Kind::Text,
+        }
+    }
+
+    let mut kinds = vec![Kind::Unknown; column_count];
+
+    for row in rows {
+        for (i, kind) in kinds.iter_mut().enumerate() {
+            if *kind == Kind::Text {
+                continue;
+            }
+            let cell_kind = match row.get(i) {
+                None | Some(Data::Empty) | Some(Data::Error(_)) => continue,
+                Some(Data::Int(_)) => Kind::Integer,
+                Some(Data::Float(_)) => Kind::Float,
+                Some(Data::Bool(_)) => Kind::Boolean,
+                Some(cell @ Data::DateTime(_)) => match cell.as_datetime() {
+                    Some(dt) if dt.time() == Default::default() => Kind::Date,
+                    Some(_) => Kind::Timestamp,
+                    None => Kind::Text, // Durations
+                },
+                Some(Data::DateTimeIso(s)) if s.len() == 10 => Kind::Date,
+                Some(Data::DateTimeIso(_)) => Kind::Timestamp,
+                Some(Data::String(s)) if s.is_empty() => continue,
+                Some(_) => Kind::Text,
+            };
+            *kind = merge(*kind, cell_kind);
+        }
+    }
+
+    kinds
+        .into_iter()
+        .map(|kind| match kind {
+            Kind::Integer => "BIGINT",
+            Kind::Float => "DOUBLE",
+            Kind::Boolean => "BOOLEAN",
+            Kind::Date => "DATE",
+            Kind::Timestamp => "TIMESTAMP",
+            Kind::Unknown | Kind::Text => "VARCHAR",
+        })
+        .collect()
+}
+
+/// Text value of a cell, or `None` for empty and error cells. Dates are
+/// rendered as ISO timestamps so typed columns can be cast afterwards.
+fn excel_cell_text(row: &[calamine::Data], index: usize) -> Option<String> {
+    use calamine::Data;
+
+    let cell = row.get(index)?;
+    let text = match cell {
+        Data::Empty | Data::Error(_) => return None,
+        Data::DateTime(_) => match cell.as_datetime() {
+            Some(dt) => dt.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+            None => cell.to_string(),
+        },
+        _ => cell.to_string(),
+    };
+
+    if text.is_empty() { None } else { Some(text) }
+}
+
+// Stream rows into the table through DuckDB's Appender, which avoids
+// per-row statement overhead
+fn append_excel_rows<'a, I>(
+    rows: I,
+    column_count: usize,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: &tauri::Window,
+    control: &ImportControl,
+    expected_rows: Option<usize>,
+) -> Result<usize, ImportError>
+where
+    I: Iterator<Item = &'a [calamine::Data]>,
+{
+    const BATCH_SIZE: usize = 10_000;
+
+    let started = Instant::now();
+    let mut appender = db_conn.appender(table_name)?;
+    let mut total_rows = 0;
+
+    for row in rows {
+        let values: Vec<Option<String>> = (0..column_count)
+            .map(|i| excel_cell_text(row, i))
+            .collect();
+
+        appender.append_row(duckdb::appender_params_from_iter(values.iter()))?;
+        total_rows += 1;
+
+        // Flush, emit progress and check for cancellation every batch
+        if total_rows % BATCH_SIZE == 0 {
+            if control.is_cancelled() {
+                return Err(ImportError::Cancelled);
+            }
+            appender.flush()?;
+
+            let remaining = expected_rows.unwrap_or(0).saturating_sub(total_rows);
+            let progress = ImportProgress {
+                rows_imported: total_rows,
+                total_rows: expected_rows,
+                status: format!("Importing... {} rows", total_rows),
+                ..Default::default()
+            };
+            let _ = window.emit(
+                "import-progress",
+                progress.with_rate(started, total_rows, total_rows as u64, remaining as u64),
+            );
+        }
+    }
+
+    appender.flush()?;
+    Ok(total_rows)
+}
+
+// Write rows to a temporary CSV and let DuckDB bulk-load it in parallel
+fn load_excel_rows_via_csv<'a, I>(
+    rows: I,
+    column_count: usize,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: &tauri::Window,
+    control: &ImportControl,
+) -> Result<usize, ImportError>
+where
+    I: Iterator<Item = &'a [calamine::Data]>,
+{
+    const BATCH_SIZE: usize = 10_000;
+
+    let temp_path = std::env::temp_dir().join(format!(
+        "rats_excel_{}_{}.csv",
+        std::process::id(),
+        table_name
+    ));
+
+    let result = (|| {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_path(&temp_path)?;
+
+        let mut written = 0;
+        for row in rows {
+            let record: Vec<String> = (0..column_count)
+                .map(|i| excel_cell_text(row, i).unwrap_or_default())
+                .collect();
+            writer.write_record(&record)?;
+            written += 1;
+
+            if written % BATCH_SIZE == 0 && control.is_cancelled() {
+                return Err(ImportError::Cancelled);
+            }
+        }
+        writer.flush()?;
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: Some(written),
+            status: format!("Bulk loading {} rows...", written),
+            ..Default::default()
+        });
+
+        let path_str = temp_path.to_str().ok_or_else(|| {
+            ImportError::Custom("Invalid temp file path".to_string())
+        })?;
+        // Every column is VARCHAR, so empty fields are loaded as NULL
+        db_conn.execute(
+            &format!(
+                "COPY {} FROM '{}' (FORMAT CSV, HEADER false)",
+                quote_ident(table_name),
+                path_str.replace('\'', "''")
+            ),
+            [],
+        )?;
+
+        Ok(written)
+    })();
+
+    let _ = std::fs::remove_file(&temp_path);
+    result
+}
+
+/// Decide which table the data should be loaded into. Appends go through a
+/// staging table so a failed load never leaves the target half-written.
+fn prepare_import_target(
+    db_conn: &duckdb::Connection,
+    table_name: &str,
+    mode: ImportMode,
+) -> Result<String, ImportError> {
+    let exists = table_exists(db_conn, table_name)?;
+
+    match mode {
+        ImportMode::FailIfExists if exists => Err(AppError::invalid_input(format!(
+            "Table {} already exists",
+            table_name
+        )).into()),
+        ImportMode::Append | ImportMode::AppendNew if exists => {
+            let staging = format!("{}_import_staging", table_name);
+            db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&staging)), [])?;
+            Ok(staging)
+        }
+        _ => {
+            db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(table_name)), [])?;
+            Ok(table_name.to_string())
+        }
+    }
+}
+
+/// Line the staged columns up with the target table before appending: new
+/// columns are added to the table, missing ones are left to fill with NULL,
+/// and type differences are checked by casting every staged value
+fn reconcile_append_schema(
+    db_conn: &duckdb::Connection,
+    load_target: &str,
+    table_name: &str,
+) -> Result<SchemaChanges, ImportError> {
+    let table_columns = describe_columns(db_conn, &quote_ident(table_name))?;
+    let staged_columns = describe_columns(db_conn, &quote_ident(load_target))?;
+    // DuckDB matches identifiers case-insensitively
+    let table_types: HashMap<String, &str> = table_columns
+        .iter()
+        .map(|(name, data_type)| (name.to_lowercase(), data_type.as_str()))
+        .collect();
+    let staged_names: Vec<String> = staged_columns.iter().map(|(name, _)| name.to_lowercase()).collect();
+
+    let mut changes = SchemaChanges::default();
+    for (name, file_type) in &staged_columns {
+        match table_types.get(&name.to_lowercase()) {
+            None => changes.added_columns.push(name.clone()),
+            Some(table_type) if !table_type.eq_ignore_ascii_case(file_type) => {
+                let column = quote_ident(name);
+                let invalid_values: usize = db_conn.query_row(
+                    &format!(
+                        "SELECT COUNT(*) FROM {} WHERE {} IS NOT NULL AND TRY_CAST({} AS {}) IS NULL",
+                        quote_ident(load_target), column, column, table_type
+                    ),
+                    [],
+                    |row| row.get(0),
+                )?;
+                changes.type_conflicts.push(TypeConflict {
+                    column: name.clone(),
+                    table_type: table_type.to_string(),
+                    file_type: file_type.clone(),
+                    invalid_values,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    // Appended rows get their row ids after the import
+    changes.missing_columns = table_columns
+        .iter()
+        .filter(|(name, _)| name != ROW_ID_COLUMN && !staged_names.contains(&name.to_lowercase()))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    let unconvertible: Vec<String> = changes
+        .type_conflicts
+        .iter()
+        .filter(|c| c.invalid_values > 0)
+        .map(|c| format!(
+            "{} ({} values of type {} don't fit {})",
+            c.column, c.invalid_values, c.file_type, c.table_type
+        ))
+        .collect();
+    if !unconvertible.is_empty() {
+        return Err(AppError::invalid_input(format!(
+            "Column types conflict with {}: {}",
+            table_name,
+            unconvertible.join(", ")
+        )).into());
+    }
+
+    for (name, file_type) in &staged_columns {
+        if changes.added_columns.contains(name) {
+            db_conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", quote_ident(table_name), quote_ident(name), file_type),
+                [],
+            )?;
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Move staged rows into the target table when appending
+fn finish_import_target(
+    db_conn: &duckdb::Connection,
+    load_target: &str,
+    table_name: &str,
+) -> Result<SchemaChanges, ImportError> {
+    if load_target == table_name {
+        return Ok(SchemaChanges::default());
+    }
+
+    // Added columns roll back with the rows if the append fails
+    in_transaction(db_conn, || {
+        let changes = reconcile_append_schema(db_conn, load_target, table_name)?;
+        db_conn
+            .execute(
+                &format!("INSERT INTO {} BY NAME SELECT * FROM {}", quote_ident(table_name), quote_ident(load_target)),
+                [],
+            )
+            .map_err(|e| AppError::from(e).context(format!("Failed to append to {}", table_name)))?;
+        db_conn.execute(&format!("DROP TABLE {}", quote_ident(load_target)), [])?;
+        Ok(changes)
+    })
+}
+
+/// Reject `append_new` for commands that have no key columns to compare on
+fn require_plain_mode(mode: Option<ImportMode>) -> Result<ImportMode, AppError> {
+    match mode.unwrap_or_default() {
+        ImportMode::AppendNew => Err(AppError::invalid_input("append_new mode is only supported when importing files")),
+        mode => Ok(mode),
+    }
+}
+
+/// Move staged rows into the target table, leaving out rows whose key
+/// columns already appear there. Returns the number of rows skipped.
+fn finish_import_skipping_duplicates(
+    db_conn: &duckdb::Connection,
+    load_target: &str,
+    table_name: &str,
+    key_columns: &[String],
+) -> Result<(usize, SchemaChanges), ImportError> {
+    if load_target == table_name {
+        return Ok((0, SchemaChanges::default()));
+    }
+
+    // Added columns roll back with the rows if the append fails
+    in_transaction(db_conn, || {
+        let changes = reconcile_append_schema(db_conn, load_target, table_name)?;
+        let staged: usize = db_conn.query_row(
+            &format!("SELECT COUNT(*) FROM {}", quote_ident(load_target)),
+            [],
+            |row| row.get(0),
+        )?;
+
+        // NULL keys compare equal so re-imported rows with missing keys are skipped too
+        let key_match = key_columns
+            .iter()
+            .map(|column| {
+                let column = quote_ident(column);
+                format!("existing.{} IS NOT DISTINCT FROM staged.{}", column, column)
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        let inserted = db_conn
+            .execute(
+                &format!(
+                    "INSERT INTO {} BY NAME SELECT staged.* FROM {} staged
+                    WHERE NOT EXISTS (SELECT 1 FROM {} existing WHERE {})",
+                    quote_ident(table_name), quote_ident(load_target), quote_ident(table_name), key_match
+                ),
+                [],
+            )
+            .map_err(|e| AppError::from(e).context(format!("Failed to append to {}", table_name)))?;
+        db_conn.execute(&format!("DROP TABLE {}", quote_ident(load_target)), [])?;
+        Ok((staged.saturating_sub(inserted), changes))
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub rows_imported: usize,
+    pub total_rows: Option<usize>,
+    pub status: String,
+    pub bytes_read: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub rows_per_sec: Option<f64>,
+    pub eta_secs: Option<f64>, // Estimated seconds until the load finishes
+}
+
+impl ImportProgress {
+    /// Add throughput and an ETA. `done` and `remaining` are in whatever
+    /// unit the caller tracks (rows or bytes), counted since `started`.
+    fn with_rate(mut self, started: Instant, rows: usize, done: u64, remaining: u64) -> Self {
+        let elapsed = started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.rows_per_sec = Some(rows as f64 / elapsed);
+            if done > 0 {
+                self.eta_secs = Some(remaining as f64 * elapsed / done as f64);
+            }
+        }
+        self
+    }
+}
+
+/// Options shared by every file loaded in one import call
+#[derive(Debug, Clone, Default)]
+struct LoadOptions {
+    csv: CsvImportOptions,
+    excel: ExcelImportOptions,
+    json: JsonImportOptions,
+    xml: XmlImportOptions,
+    column_types: HashMap<String, String>, // Keyed by target column name
+    projection: Vec<ColumnSelection>,      // Empty keeps every column
+    sample: Option<SampleOptions>,
+    rejects_table: Option<String>,
+    transpose: bool, // Rows become columns; the first column holds the new column names
+}
+
+/// Files at least this large are loaded in chunks to report progress;
+/// smaller ones load in about a second, too fast to need it
+const CHUNKED_CSV_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Chunked loading fixes types up front and can't sample or report rejected
+/// rows, so only plain CSV loads use it
+fn use_chunked_csv_load(path: &PathBuf, options: &LoadOptions) -> bool {
+    options.csv.on_error == OnError::Fail
+        && options.sample.is_none()
+        && !is_gzipped(path)
+        && std::fs::metadata(path).map(|m| m.len() >= CHUNKED_CSV_BYTES).unwrap_or(false)
+}
+
+/// Prefix of the scratch tables transposed files are loaded into first
+const UNTRANSPOSED_TABLE_PREFIX: &str = "_rats_untransposed_";
+
+/// Numbers the scratch tables, so no load clears another's
+static NEXT_UNTRANSPOSED: AtomicU64 = AtomicU64::new(0);
+
+/// Load a single file into a new table, dispatching on its format
+fn load_file(
+    path: &PathBuf,
+    table_name: &str,
+    db_conn: &duckdb::Connection,
+    window: tauri::Window,
+    options: &LoadOptions,
+    control: &ImportControl,
+) -> Result<usize, ImportError> {
+    let format = detect_file_format(path)?;
+
+    // DuckDB reads UTF-8 only, so other encodings are converted first
+    if format == "csv" && !is_gzipped(path) {
+        if let Some(utf8_path) = encoding::utf8_copy(path, options.csv.encoding.as_deref())? {
+            let mut utf8_options = options.clone();
+            utf8_options.csv.encoding = Some("utf-8".to_string());
+            let result = load_file(&utf8_path, table_name, db_conn, window, &utf8_options, control);
+            // An interrupted resumable import continues from the converted copy
+            if result.is_ok() || !options.csv.resumable {
+                let _ = std::fs::remove_file(&utf8_path);
+            }
+            return result;
+        }
+    }
+
+    // Load as-is first; column type overrides name the transposed columns
+    if options.transpose && format != "zip" {
+        let staging = format!(
+            "{}{}",
+            UNTRANSPOSED_TABLE_PREFIX,
+            NEXT_UNTRANSPOSED.fetch_add(1, Ordering::Relaxed)
+        );
+        let raw_options = LoadOptions {
+            column_types: HashMap::new(),
+            transpose: false,
+            ..options.clone()
+        };
+        db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&staging)), [])?;
+        let result = load_file(path, &staging, db_conn, window, &raw_options, control)
+            .and_then(|_| transpose_table(db_conn, &staging, table_name, &options.column_types));
+        let _ = db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&staging)), []);
+        return result;
+    }
+
+    let rows = match format.as_str() {
+        "csv" if options.csv.resumable => {
+            resume::start_chunked_csv(path, table_name, db_conn, &window, options, control, true)
+        }
+        // Plain loads go in chunks so progress can be reported
+        "csv" if use_chunked_csv_load(path, options) => {
+            resume::start_chunked_csv(path, table_name, db_conn, &window, options, control, false)
+        }
+        "csv" => import_csv_with_duckdb(path, table_name, db_conn, window, options),
+        "excel" => import_excel_with_duckdb(path, table_name, db_conn, window, &options.excel, control),
+        "json" => import_json_with_duckdb(path, table_name, db_conn, window, &options.json),
+        "xml" => import_xml_with_duckdb(path, table_name, db_conn, window, &options.xml),
+        "parquet" => {
+            let path_str = path.to_str().ok_or_else(|| {
+                AppError::invalid_input("Invalid file path")
+            })?;
+            import_parquet_with_duckdb(path_str, table_name, db_conn, options)
+        }
+        "arrow" => {
+            let path_str = path.to_str().ok_or_else(|| {
+                AppError::invalid_input("Invalid file path")
+            })?;
+            import_arrow_with_duckdb(path_str, table_name, db_conn, options)
+        }
+        "avro" => {
+            let path_str = path.to_str().ok_or_else(|| {
+                AppError::invalid_input("Invalid file path")
+            })?;
+            import_avro_with_duckdb(path_str, table_name, db_conn, options)
+        }
+        "orc" => import_orc_with_duckdb(path, table_name, db_conn, window, options, control),
+        "zip" => {
+            let extracted = extract_single_file_zip(path)?;
+            let result = load_file(&extracted, table_name, db_conn, window, options, control);
+            let _ = std::fs::remove_file(&extracted);
+            // Column types were already applied to the extracted file
+            return result;
+        }
+        _ => Err(ImportError::UnsupportedFormat),
+    }?;
+
+    // Excel, JSON and XML columns are only known once loaded (and flattened)
+    let rows = if matches!(format.as_str(), "excel" | "json" | "xml") {
+        rewrite_loaded_table(db_conn, table_name, options)?.unwrap_or(rows)
+    } else {
+        rows
+    };
+
+    // CSV applies overrides while parsing; other formats convert afterwards
+    if format != "csv" {
+        apply_column_types(db_conn, table_name, &options.column_types)?;
+    }
+
+    let separators = match format.as_str() {
+        "csv" => number_separators(&options.csv.decimal_separator, &options.csv.thousands_separator)?,
+        "excel" => number_separators(&options.excel.decimal_separator, &options.excel.thousands_separator)?,
+        _ => None,
+    };
+    if let Some((decimal, thousands)) = separators {
+        convert_locale_numbers(db_conn, table_name, &decimal, thousands.as_deref(), &options.column_types)?;
+    }
+
+    Ok(rows)
+}
+
+/// Swap rows and columns of a loaded table. The first column's values become
+/// the new column names, and each remaining column becomes a row labelled
+/// with its old name. Values are re-typed after the swap.
+fn transpose_table(
+    db_conn: &duckdb::Connection,
+    source_table: &str,
+    table_name: &str,
+    column_types: &HashMap<String, String>,
+) -> Result<usize, ImportError> {
+    let columns = describe_columns(db_conn, &quote_ident(source_table))?;
+    if columns.len() < 2 {
+        return Err(AppError::invalid_input(
+            "Transposing needs a label column and at least one data column",
+        ).into());
+    }
+
+    let select_list = columns
+        .iter()
+        .map(|(name, _)| format!("CAST({} AS VARCHAR)", quote_ident(name)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = db_conn.prepare(&format!("SELECT {} FROM {}", select_list, quote_ident(source_table)))?;
+    let rows = stmt
+        .query_map([], |row| {
+            (0..columns.len())
+                .map(|i| row.get::<_, Option<String>>(i))
+                .collect::<Result<Vec<_>, _>>()
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let temp_path = std::env::temp_dir().join(format!(
+        "rats_transpose_{}_{}.csv",
+        std::process::id(),
+        table_name
+    ));
+    let result = write_transposed_csv(&temp_path, &columns, &rows).and_then(|_| {
+        let path_str = temp_path.to_str().ok_or_else(|| {
+            ImportError::Custom("Invalid temp file path".to_string())
+        })?;
+        let options = CsvImportOptions {
+            delimiter: Some(",".to_string()),
+            has_header: Some(true),
+            ..Default::default()
+        };
+        let source = read_csv_source(path_str, &options, column_types)?;
+        create_table_from_source(db_conn, table_name, &source)
+    });
+
+    let _ = std::fs::remove_file(&temp_path);
+    result
+}
+
+fn write_transposed_csv(
+    path: &PathBuf,
+    columns: &[(String, String)],
+    rows: &[Vec<Option<String>>],
+) -> Result<(), ImportError> {
+    let mut writer = csv::WriterBuilder::new().from_path(path)?;
+
+    let mut header = vec![columns[0].0.clone()];
+    header.extend(rows.iter().enumerate().map(|(i, row)| {
+        row[0].clone().unwrap_or_else(|| format!("row_{}", i + 1))
+    }));
+    writer.write_record(&header)?;
+
+    for (index, (name, _)) in columns.iter().enumerate().skip(1) {
+        let mut record = vec![name.as_str()];
+        record.extend(rows.iter().map(|row| row[index].as_deref().unwrap_or_default()));
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Extract the only file in a zip archive to the temp directory
+fn extract_single_file_zip(path: &PathBuf) -> Result<PathBuf, ImportError> {
+    let file = File::open(path)?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| AppError::invalid_input(format!("Invalid zip archive: {}", e)))?;
+
+    let file_indices: Vec<usize> = (0..archive.len())
+        .filter(|&i| archive.by_index(i).map(|f| f.is_file()).unwrap_or(false))
+        .collect();
+    if file_indices.len() != 1 {
+        return Err(AppError::invalid_input(format!(
+            "Zip archives must contain exactly one file, found {}",
+            file_indices.len()
+        )).into());
+    }
+
+    let mut entry = archive
+        .by_index(file_indices[0])
+        .map_err(|e| ImportError::Custom(format!("Failed to read zip entry: {}", e)))?;
+    let entry_name = entry
+        .enclosed_name()
+        .and_then(|p| p.file_name().map(|n| n.to_owned()))
+        .ok_or_else(|| AppError::invalid_input("Zip entry has an invalid name"))?;
+
+    let target = std::env::temp_dir().join(format!(
+        "rats_import_{}_{}",
+        std::process::id(),
+        entry_name.to_string_lossy()
+    ));
+    let mut output = File::create(&target)?;
+    std::io::copy(&mut entry, &mut output)?;
+
+    Ok(target)
+}
+
+fn is_glob_pattern(path: &str) -> bool {
+    path.contains('*') || path.contains('?') || path.contains('[')
+}
+
+/// Expand the requested paths: an explicit list wins, otherwise a glob
+/// pattern is resolved with DuckDB's `glob()`, otherwise the single path
+fn resolve_import_files(
+    db_conn: &duckdb::Connection,
+    file_path: &str,
+    file_paths: Option<Vec<String>>,
+) -> Result<Vec<PathBuf>, ImportError> {
+    if let Some(paths) = file_paths.filter(|p| !p.is_empty()) {
+        return Ok(paths.into_iter().map(PathBuf::from).collect());
+    }
+
+    if !is_glob_pattern(file_path) {
+        return Ok(vec![PathBuf::from(file_path)]);
+    }
+
+    let mut stmt = db_conn.prepare("SELECT file FROM glob(?) ORDER BY file")?;
+    let files: Vec<PathBuf> = stmt
+        .query_map([file_path], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+    if files.is_empty() {
+        return Err(AppError::not_found(format!("No files match {}", file_path)).into());
+    }
+    Ok(files)
+}
+
+/// Load every file into `load_target`, appending files after the first
+fn load_files(
+    files: &[PathBuf],
+    load_target: &str,
+    db_conn: &duckdb::Connection,
+    window: &tauri::Window,
+    options: &LoadOptions,
+    control: &ImportControl,
+) -> Result<Vec<ImportedTable>, ImportError> {
+    let mut loaded = Vec::new();
+
+    for (index, path) in files.iter().enumerate() {
+        if control.is_cancelled() {
+            return Err(ImportError::Cancelled);
+        }
+
+        if files.len() > 1 {
+            let _ = window.emit("import-progress", ImportProgress {
+                rows_imported: loaded.iter().map(|t: &ImportedTable| t.rows_imported).sum(),
+                total_rows: None,
+                status: format!(
+                    "Importing file {} of {}: {}",
+                    index + 1,
+                    files.len(),
+                    path.display()
+                ),
+                ..Default::default()
+            });
+        }
+
+        let file_target = if index == 0 {
+            load_target.to_string()
+        } else {
+            let staging = format!("{}_file_staging", load_target);
+            db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&staging)), [])?;
+            staging
+        };
+
+        let rows = load_file(path, &file_target, db_conn, window.clone(), options, control)
+            .and_then(|rows| {
+                finish_import_target(db_conn, &file_target, load_target)?;
+                Ok(rows)
+            })
+            .map_err(|e| {
+                if file_target != load_target {
+                    let _ = db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&file_target)), []);
+                }
+                match e {
+                    ImportError::Cancelled => e,
+                    e => AppError::from(e).context(path.display()).into(),
+                }
+            })?;
+
+        loaded.push(ImportedTable {
+            table_name: load_target.to_string(),
+            source: path.display().to_string(),
+            rows_imported: rows,
+        });
+    }
+
+    Ok(loaded)
+}
+
+/// Remember where the tables of a finished import came from for the
+/// catalog, with the options the import ran with, and give their rows
+/// stable ids. `container` is the workbook or database file that sheet and
+/// table sources live in. Failing to record them doesn't fail the import.
+fn record_sources(
+    conn: &duckdb::Connection,
+    result: ImportResult,
+    container: Option<&str>,
+    options: &Json,
+) -> ImportResult {
+    for table in &result.tables {
+        let _ = crate::duckdb_core::ensure_row_ids(conn, &table.table_name);
+    }
+    let sources: Vec<TableSource> = result
+        .tables
+        .iter()
+        .map(|t| TableSource {
+            table_name: t.table_name.clone(),
+            source: match container {
+                Some(file) => format!("{}#{}", file, t.source),
+                None => t.source.clone(),
+            },
+            rows_imported: t.rows_imported,
+        })
+        .collect();
+    let _ = crate::catalog::record_table_sources(conn, &sources, options);
+    result
+}
+
+fn import_file_message(
+    rows_imported: usize,
+    files: usize,
+    rows_rejected: usize,
+    rejects_table: Option<&str>,
+    rows_skipped: usize,
+    schema_changes: &SchemaChanges,
+) -> String {
+    let mut message = if files > 1 {
+        format!("Successfully imported {} rows from {} files", rows_imported, files)
+    } else {
+        format!("Successfully imported {} rows", rows_imported)
+    };
+    if rows_skipped > 0 {
+        message.push_str(&format!(", skipped {} duplicate rows", rows_skipped));
+    }
+    if let (true, Some(report)) = (rows_rejected > 0, rejects_table) {
+        message.push_str(&format!(" ({} rejected rows listed in {})", rows_rejected, report));
+    }
+    if !schema_changes.added_columns.is_empty() {
+        message.push_str(&format!("; added columns: {}", schema_changes.added_columns.join(", ")));
+    }
+    if !schema_changes.missing_columns.is_empty() {
+        message.push_str(&format!("; filled with NULL: {}", schema_changes.missing_columns.join(", ")));
+    }
+    message
+}
+
+/// Import a file, an explicit list of files, or a glob pattern
+/// (e.g. `data/2024-*.csv`) into a single table. `columns` keeps only the
+/// listed source columns, optionally renamed. With `append_new` mode, rows
+/// whose `key_columns` already exist in the table are skipped. `sample` loads
+/// only the first or a random subset of rows. `transpose` turns files with
+/// variables as rows into one row per observation.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_file(
+    app: AppHandle,
+    window: tauri::Window,
+    file_path: String,
+    file_paths: Option<Vec<String>>,
+    table_name: Option<String>,
+    csv_options: Option<CsvImportOptions>,
+    excel_options: Option<ExcelImportOptions>,
+    json_options: Option<JsonImportOptions>,
+    xml_options: Option<XmlImportOptions>,
+    column_types: Option<HashMap<String, String>>,
+    columns: Option<Vec<ColumnSelection>>,
+    sample: Option<SampleOptions>,
+    mode: Option<ImportMode>,
+    key_columns: Option<Vec<String>>,
+    transpose: Option<bool>,
+) -> Result<ImportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let import_options = json!({
+            "csv_options": csv_options,
+            "excel_options": excel_options,
+            "json_options": json_options,
+            "xml_options": xml_options,
+            "column_types": column_types,
+            "columns": columns,
+            "sample": sample,
+            "mode": mode,
+            "key_columns": key_columns,
+            "transpose": transpose,
+        });
+        sample_clause(sample.as_ref())?;
+        let transpose = transpose.unwrap_or(false);
+        if transpose && sample.is_some() {
+            return Err(AppError::invalid_input("Sampling is not supported when transposing"));
+        }
+        let mode = mode.unwrap_or_default();
+        let key_columns = key_columns.unwrap_or_default();
+        if mode == ImportMode::AppendNew && key_columns.is_empty() {
+            return Err(AppError::invalid_input("append_new mode requires at least one key column"));
+        }
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let _import = state.imports.begin();
+
+        let files = resolve_import_files(conn, &file_path, file_paths)?;
+        for path in &files {
+            detect_file_format(path).with_context(|| format!("{}", path.display()))?;
+        }
+
+        let table_name = table_name.unwrap_or_else(|| {
+            default_table_name(&files[0]).unwrap_or_else(|| "imported_data".to_string())
+        });
+
+        let sanitized_table_name = sanitize_table_name(&table_name);
+        let csv_options = csv_options.unwrap_or_default();
+
+        // Resumable imports write straight into a new table, one file at a time
+        if csv_options.resumable {
+            if files.len() > 1 || is_gzipped(&files[0]) {
+                return Err(AppError::invalid_input("Resumable imports support a single uncompressed CSV file"));
+            }
+            if matches!(mode, ImportMode::Append | ImportMode::AppendNew) {
+                return Err(AppError::invalid_input("Resumable imports always create a new table"));
+            }
+            if transpose {
+                return Err(AppError::invalid_input("Resumable imports can't be transposed"));
+            }
+        }
+
+        // Rows skipped under `on_error: skip_row` are reported in a side table
+        let rejects_table = if csv_options.on_error == OnError::SkipRow {
+            let report = format!("{}_rejects", sanitized_table_name);
+            conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&report)), [])?;
+            Some(report)
+        } else {
+            None
+        };
+
+        let options = LoadOptions {
+            csv: csv_options,
+            excel: excel_options.unwrap_or_default(),
+            json: json_options.unwrap_or_default(),
+            xml: xml_options.unwrap_or_default(),
+            column_types: column_types.unwrap_or_default(),
+            projection: columns.unwrap_or_default(),
+            sample,
+            rejects_table: rejects_table.clone(),
+            transpose,
+        };
+
+        // Emit start event with clearer messaging
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: None,
+            status: "Starting import... Large files may take 1-2 minutes".to_string(),
+            ..Default::default()
+        });
+
+        let load_target = prepare_import_target(conn, &sanitized_table_name, mode)?;
+
+        // Perform import (Tauri's async runtime keeps this from blocking UI)
+        let import_result = load_files(&files, &load_target, conn, &window, &options, &state.imports)
+            .and_then(|loaded| {
+                let (skipped, changes) = if mode == ImportMode::AppendNew {
+                    finish_import_skipping_duplicates(conn, &load_target, &sanitized_table_name, &key_columns)?
+                } else {
+                    (0, finish_import_target(conn, &load_target, &sanitized_table_name)?)
+                };
+                Ok((loaded, skipped, changes))
+            });
+
+        if import_result.is_err() && load_target != sanitized_table_name {
+            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&load_target)), []);
+        }
+
+        let rows_imported = import_result.as_ref().ok().map(|(loaded, skipped, _)| {
+            loaded.iter().map(|t| t.rows_imported).sum::<usize>() - skipped
+        });
+
+        state.metrics.record(
+            "import_file",
+            started,
+            rows_imported,
+            import_result.is_ok(),
+        );
+
+        let (mut tables, rows_skipped, schema_changes) = import_result
+            .map_err(|e| import_failure(&state.imports, &window, "Import failed", e))?;
+        let rows_imported = rows_imported.unwrap_or_default();
+        for table in &mut tables {
+            table.table_name = sanitized_table_name.clone();
+        }
+
+        let rows_rejected = match &rejects_table {
+            Some(report) if table_exists(conn, report).unwrap_or(false) => conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", quote_ident(report)), [], |row| row.get(0))
+                .unwrap_or(0),
+            _ => 0,
+        };
+        let rejects_table = rejects_table.filter(|_| rows_rejected > 0);
+        if rows_rejected == 0 {
+            if let Some(report) = &options.rejects_table {
+                let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(report)), []);
+            }
+        }
+
+        // Emit completion event
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported,
+            total_rows: Some(rows_imported),
+            status: "Import complete!".to_string(),
+            ..Default::default()
+        });
+
+        Ok(record_sources(conn, ImportResult {
+            success: true,
+            message: import_file_message(
+                rows_imported,
+                tables.len(),
+                rows_rejected,
+                rejects_table.as_deref(),
+                rows_skipped,
+                &schema_changes,
+            ),
+            table_name: sanitized_table_name,
+            rows_imported,
+            tables,
+            rows_rejected,
+            rejects_table,
+            rows_skipped,
+            schema_changes: Some(schema_changes).filter(|c| !c.is_empty()),
+        }, None, &import_options))
+    })
+    .await
+}
+
+/// Load CSV text from any reader, such as stdin, into a table. Used by the
+/// headless `rats-cli` binary, so it needs no window or app state.
+pub fn import_csv_from_reader<R: Read>(
+    db_conn: &duckdb::Connection,
+    reader: &mut R,
+    table_name: &str,
+    csv_options: &CsvImportOptions,
+    mode: ImportMode,
+) -> Result<ImportedTable, ImportError> {
+    if mode == ImportMode::AppendNew {
+        return Err(AppError::invalid_input("append_new mode is only supported when importing files").into());
+    }
+    let table_name = sanitize_table_name(table_name);
+    if table_name.is_empty() {
+        return Err(AppError::invalid_input("Table name is required").into());
+    }
+
+    // DuckDB sniffs the dialect from a seekable file, so the stream is spooled first
+    let temp_path = std::env::temp_dir().join(format!("rats_stdin_{}.csv", std::process::id()));
+    let result = File::create(&temp_path)
+        .and_then(|mut file| std::io::copy(reader, &mut file))
+        .map_err(ImportError::from)
+        .and_then(|_| {
+            match encoding::utf8_copy(&temp_path, csv_options.encoding.as_deref())? {
+                Some(utf8_path) => {
+                    let result = load_csv_stream(db_conn, &utf8_path, &table_name, csv_options, mode);
+                    let _ = std::fs::remove_file(&utf8_path);
+                    result
+                }
+                None => load_csv_stream(db_conn, &temp_path, &table_name, csv_options, mode),
+            }
+        });
+    let _ = std::fs::remove_file(&temp_path);
+
+    Ok(ImportedTable {
+        table_name,
+        source: "stdin".to_string(),
+        rows_imported: result?,
+    })
+}
+
+fn load_csv_stream(
+    db_conn: &duckdb::Connection,
+    path: &Path,
+    table_name: &str,
+    csv_options: &CsvImportOptions,
+    mode: ImportMode,
+) -> Result<usize, ImportError> {
+    let path_str = path.to_str().ok_or_else(|| {
+        ImportError::Custom("Invalid temp file path".to_string())
+    })?;
+    let source = read_csv_source(path_str, csv_options, &HashMap::new())?;
+
+    let load_target = prepare_import_target(db_conn, table_name, mode)?;
+    let result = create_table_from_source(db_conn, &load_target, &source).and_then(|rows| {
+        finish_import_target(db_conn, &load_target, table_name)?;
+        Ok(rows)
+    });
+    if result.is_err() && load_target != table_name {
+        let _ = db_conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&load_target)), []);
+    }
+    result
+}
+
+/// Import a text log by parsing each line with a regex whose named capture
+/// groups become columns, or with a preset: `apache_common`,
+/// `apache_combined`/`nginx` or `syslog`. Lines that don't match are counted
+/// and skipped.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_log_file(
+    app: AppHandle,
+    window: tauri::Window,
+    file_path: String,
+    table_name: Option<String>,
+    pattern: Option<String>,
+    preset: Option<String>,
+    mode: Option<ImportMode>,
+) -> Result<ImportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let import_options = json!({ "pattern": pattern, "preset": preset, "mode": mode });
+        let mode = require_plain_mode(mode)?;
+
+        let (pattern, timestamp_format) = match (pattern, preset) {
+            (Some(pattern), None) => (pattern, None),
+            (None, Some(preset)) => {
+                let preset = log::preset(&preset)
+                    .ok_or_else(|| AppError::invalid_input(format!("Unknown log preset: {}", preset)))?;
+                (preset.pattern.to_string(), preset.timestamp_format.map(|f| f.to_string()))
+            }
+            _ => return Err(AppError::invalid_input("Specify exactly one of pattern or preset")),
+        };
+        let regex = log::compile_pattern(&pattern)?;
+
+        let path = PathBuf::from(&file_path);
+        let table_name = table_name.unwrap_or_else(|| {
+            default_table_name(&path).unwrap_or_else(|| "log_data".to_string())
+        });
+        let sanitized_table_name = sanitize_table_name(&table_name);
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let _import = state.imports.begin();
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: None,
+            status: "Parsing log lines...".to_string(),
+            ..Default::default()
+        });
+
+        let load_target = prepare_import_target(conn, &sanitized_table_name, mode)?;
+
+        // Parsed lines go through a temp CSV so DuckDB detects the column types
+        let temp_path = std::env::temp_dir().join(format!(
+            "rats_log_{}_{}.csv",
+            std::process::id(),
+            sanitized_table_name
+        ));
+        let import_result = log::write_log_csv(&path, &regex, &temp_path, &state.imports)
+            .and_then(|parsed| {
+                if parsed.rows == 0 {
+                    return Err(AppError::invalid_input(format!(
+                        "None of the {} lines matched the pattern",
+                        parsed.unmatched
+                    )).into());
+                }
+                let path_str = temp_path.to_str().ok_or_else(|| {
+                    ImportError::Custom("Invalid temp file path".to_string())
+                })?;
+                let csv_options = CsvImportOptions {
+                    has_header: Some(true),
+                    timestamp_format,
+                    ..Default::default()
+                };
+                let source = read_csv_source(path_str, &csv_options, &HashMap::new())?;
+                let rows = create_table_from_source(conn, &load_target, &source)?;
+                finish_import_target(conn, &load_target, &sanitized_table_name)?;
+                Ok((rows, parsed.unmatched))
+            });
+        let _ = std::fs::remove_file(&temp_path);
+
+        if import_result.is_err() && load_target != sanitized_table_name {
+            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&load_target)), []);
+        }
+
+        state.metrics.record(
+            "import_log_file",
+            started,
+            import_result.as_ref().ok().map(|(rows, _)| *rows),
+            import_result.is_ok(),
+        );
+
+        let (rows_imported, unmatched) = import_result
+            .map_err(|e| import_failure(&state.imports, &window, "Log import failed", e))?;
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported,
+            total_rows: Some(rows_imported),
+            status: "Import complete!".to_string(),
+            ..Default::default()
+        });
+
+        Ok(record_sources(conn, ImportResult {
+            success: true,
+            message: if unmatched > 0 {
+                format!(
+                    "Successfully imported {} lines ({} lines did not match the pattern)",
+                    rows_imported, unmatched
+                )
+            } else {
+                format!("Successfully imported {} lines", rows_imported)
+            },
+            table_name: sanitized_table_name.clone(),
+            rows_imported,
+            tables: vec![ImportedTable {
+                table_name: sanitized_table_name,
+                source: file_path,
+                rows_imported,
+            }],
+            rows_rejected: unmatched,
+            rejects_table: None,
+            rows_skipped: 0,
+            schema_changes: None,
+        }, None, &import_options))
+    })
+    .await
+}
+
+/// Import a remote CSV, Parquet or JSON file over HTTP(S) or from S3
+/// (`s3://bucket/key`) using DuckDB's httpfs extension. S3 access uses the
+/// credentials registered with `set_s3_credentials`.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_from_url(
+    app: AppHandle,
+    window: tauri::Window,
+    url: String,
+    table_name: Option<String>,
+    format: Option<String>,
+    timeout_secs: Option<u64>,
+    csv_options: Option<CsvImportOptions>,
+    mode: Option<ImportMode>,
+) -> Result<ImportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let import_options = json!({ "format": format, "csv_options": csv_options, "mode": mode });
+        let mode = require_plain_mode(mode)?;
+
+        const SUPPORTED_SCHEMES: [&str; 3] = ["http://", "https://", "s3://"];
+        if !SUPPORTED_SCHEMES.iter().any(|scheme| url.starts_with(scheme)) {
+            return Err(AppError::invalid_input("Only http://, https:// and s3:// URLs are supported"));
+        }
+
+        let format = match format {
+            Some(f) => format_from_extension(&f),
+            None => detect_url_format(&url),
+        }
+        .map_err(|_| AppError::invalid_input("Could not determine file format from URL; pass a format explicitly"))?;
+
+        let table_name = table_name.unwrap_or_else(|| {
+            let path = url.split(['?', '#']).next().unwrap_or(&url);
+            let file_name = path.rsplit('/').next().unwrap_or(path);
+            file_name
+                .rsplit_once('.')
+                .map(|(stem, _)| stem)
+                .unwrap_or(file_name)
+                .to_string()
+        });
+        let sanitized_table_name = sanitize_table_name(&table_name);
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let _import = state.imports.begin();
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: None,
+            status: "Loading httpfs extension...".to_string(),
+            ..Default::default()
+        });
+
+        crate::duckdb_core::load_extension(conn, "httpfs")
+            .context("Failed to load httpfs extension")?;
+
+        // The timeout is a global setting, so put back whatever it was afterwards
+        let previous_timeout: Option<u64> = conn
+            .query_row("SELECT current_setting('http_timeout')", [], |row| row.get(0))
+            .ok();
+        let timeout_ms = timeout_secs.unwrap_or(30) * 1000;
+        conn.execute_batch(&format!("SET http_timeout = {}", timeout_ms))
+            .context("Failed to set HTTP timeout")?;
+        let restore_timeout = || {
+            let _ = conn.execute_batch(&match previous_timeout {
+                Some(timeout) => format!("SET http_timeout = {}", timeout),
+                None => "RESET http_timeout".to_string(),
+            });
+        };
+
+        let load_target = prepare_import_target(conn, &sanitized_table_name, mode).map_err(|e| {
+            restore_timeout();
+            AppError::from(e)
+        })?;
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: None,
+            status: format!("Downloading {}...", format),
+            ..Default::default()
+        });
+
+        let import_result = match format.as_str() {
+            "csv" => read_csv_source(&url, &csv_options.unwrap_or_default(), &HashMap::new())
+                .and_then(|source| create_table_from_source(conn, &load_target, &source)),
+            "json" => read_json_source(&url, &JsonImportOptions::default())
+                .and_then(|source| create_table_from_source(conn, &load_target, &source)),
+            "parquet" => import_parquet_with_duckdb(&url, &load_target, conn, &LoadOptions::default()),
+            "avro" => import_avro_with_duckdb(&url, &load_target, conn, &LoadOptions::default()),
+            _ => Err(ImportError::UnsupportedFormat),
+        }
+        .and_then(|rows| {
+            finish_import_target(conn, &load_target, &sanitized_table_name)?;
+            Ok(rows)
+        });
+        restore_timeout();
+
+        if import_result.is_err() && load_target != sanitized_table_name {
+            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&load_target)), []);
+        }
+
+        state.metrics.record(
+            "import_from_url",
+            started,
+            import_result.as_ref().ok().copied(),
+            import_result.is_ok(),
+        );
+
+        let rows_imported = import_result
+            .map_err(|e| import_failure(&state.imports, &window, "URL import failed", e))?;
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported,
+            total_rows: Some(rows_imported),
+            status: "Import complete!".to_string(),
+            ..Default::default()
+        });
+
+        Ok(record_sources(conn, ImportResult {
+            success: true,
+            message: format!("Successfully imported {} rows", rows_imported),
+            table_name: sanitized_table_name.clone(),
+            rows_imported,
+            tables: vec![ImportedTable {
+                table_name: sanitized_table_name,
+                source: url,
+                rows_imported,
+            }],
+            rows_rejected: 0,
+            rejects_table: None,
+            rows_skipped: 0,
+            schema_changes: None,
+        }, None, &import_options))
+    })
+    .await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceTableInfo {
+    pub name: String,
+    pub row_count: usize,
+}
+
+/// Alias used while an external database is attached for import
+const IMPORT_SOURCE_ALIAS: &str = "rats_import_source";
+
+/// Attach an external database read-only for the duration of `f`
+fn with_attached_database<T, F>(
+    db_conn: &duckdb::Connection,
+    location: &str,
+    db_type: &str,
+    f: F,
+) -> Result<T, ImportError>
+where
+    F: FnOnce(&str) -> Result<T, ImportError>,
+{
+    let _ = db_conn.execute_batch(&format!("DETACH DATABASE IF EXISTS {}", IMPORT_SOURCE_ALIAS));
+    db_conn.execute_batch(&format!(
+        "ATTACH '{}' AS {} (TYPE {}, READ_ONLY)",
+        location.replace('\'', "''"),
+        IMPORT_SOURCE_ALIAS,
+        db_type
+    ))?;
+
+    let result = f(IMPORT_SOURCE_ALIAS);
+
+    let _ = db_conn.execute_batch(&format!("DETACH DATABASE IF EXISTS {}", IMPORT_SOURCE_ALIAS));
+    result
+}
+
+fn list_attached_tables(
+    db_conn: &duckdb::Connection,
+    alias: &str,
+) -> Result<Vec<SourceTableInfo>, ImportError> {
+    let mut stmt = db_conn.prepare(
+        "SELECT table_name FROM information_schema.tables WHERE table_catalog = ? ORDER BY table_name",
+    )?;
+    let names: Vec<String> = stmt
+        .query_map([alias], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut tables = Vec::new();
+    for name in names {
+        let row_count: usize = db_conn.query_row(
+            &format!("SELECT COUNT(*) FROM {}.{}", alias, quote_ident(&name)),
+            [],
+            |row| row.get(0),
+        )?;
+        tables.push(SourceTableInfo { name, row_count });
+    }
+    Ok(tables)
+}
+
+/// List the tables inside a SQLite database file
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_sqlite_tables(
+    app: AppHandle,
+    file_path: String,
+) -> Result<Vec<SourceTableInfo>, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        crate::duckdb_core::load_extension(conn, "sqlite")
+            .context("Failed to load sqlite extension")?;
+
+        with_attached_database(conn, &file_path, "SQLITE", |alias| list_attached_tables(conn, alias))
+            .context("Failed to read SQLite file")
+    })
+    .await
+}
+
+/// Import selected tables from a SQLite database file, one workspace
+/// table per source table
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_sqlite_tables(
+    app: AppHandle,
+    window: tauri::Window,
+    file_path: String,
+    tables: Vec<String>,
+    table_prefix: Option<String>,
+    mode: Option<ImportMode>,
+) -> Result<ImportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let import_options = json!({ "table_prefix": table_prefix, "mode": mode });
+        let mode = require_plain_mode(mode)?;
+        if tables.is_empty() {
+            return Err(AppError::invalid_input("No tables selected"));
+        }
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let _import = state.imports.begin();
+
+        crate::duckdb_core::load_extension(conn, "sqlite")
+            .context("Failed to load sqlite extension")?;
+
+        let import_result = with_attached_database(conn, &file_path, "SQLITE", |alias| {
+            let mut imported = Vec::new();
+            for (index, source_table) in tables.iter().enumerate() {
+                if state.imports.is_cancelled() {
+                    return Err(ImportError::Cancelled);
+                }
+
+                let _ = window.emit("import-progress", ImportProgress {
+                    rows_imported: imported.iter().map(|t: &ImportedTable| t.rows_imported).sum(),
+                    total_rows: None,
+                    status: format!(
+                        "Importing table {} of {}: {}",
+                        index + 1,
+                        tables.len(),
+                        source_table
+                    ),
+                    ..Default::default()
+                });
+
+                let table_name = match &table_prefix {
+                    Some(prefix) => sanitize_table_name(&format!("{}_{}", prefix, source_table)),
+                    None => sanitize_table_name(source_table),
+                };
+
+                let load_target = prepare_import_target(conn, &table_name, mode)?;
+                let source_sql = format!("{}.{}", alias, quote_ident(source_table));
+                let rows = create_table_from_source(conn, &load_target, &source_sql)
+                    .and_then(|rows| {
+                        finish_import_target(conn, &load_target, &table_name)?;
+                        Ok(rows)
+                    })
+                    .map_err(|e| {
+                        if load_target != table_name {
+                            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&load_target)), []);
+                        }
+                        match e {
+                            ImportError::Cancelled => e,
+                            e => AppError::from(e).context(source_table).into(),
+                        }
+                    })?;
+
+                imported.push(ImportedTable {
+                    table_name,
+                    source: source_table.clone(),
+                    rows_imported: rows,
+                });
+            }
+            Ok(imported)
+        });
+
+        let rows_imported = import_result
+            .as_ref()
+            .ok()
+            .map(|imported| imported.iter().map(|t| t.rows_imported).sum::<usize>());
+        state.metrics.record("import_sqlite_tables", started, rows_imported, import_result.is_ok());
+
+        let imported = import_result
+            .map_err(|e| import_failure(&state.imports, &window, "SQLite import failed", e))?;
+        let rows_imported = rows_imported.unwrap_or_default();
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported,
+            total_rows: Some(rows_imported),
+            status: "Import complete!".to_string(),
+            ..Default::default()
+        });
+
+        Ok(record_sources(conn, ImportResult {
+            success: true,
+            message: format!(
+                "Successfully imported {} rows from {} table(s)",
+                rows_imported,
+                imported.len()
+            ),
+            table_name: imported[0].table_name.clone(),
+            rows_imported,
+            tables: imported,
+            rows_rejected: 0,
+            rejects_table: None,
+            rows_skipped: 0,
+            schema_changes: None,
+        }, Some(&file_path), &import_options))
+    })
+    .await
+}
+
+/// Import a table or query result from PostgreSQL or MySQL using DuckDB's
+/// scanner extensions. The connection string is never logged or stored.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_from_database(
+    app: AppHandle,
+    window: tauri::Window,
+    database_type: String, // "postgres" or "mysql"
+    connection_string: String,
+    source_table: Option<String>,
+    query: Option<String>,
+    table_name: Option<String>,
+    mode: Option<ImportMode>,
+) -> Result<ImportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        // The connection string can hold a password, so it isn't recorded
+        let import_options = json!({
+            "database_type": database_type,
+            "source_table": source_table,
+            "query": query,
+            "mode": mode,
+        });
+        let mode = require_plain_mode(mode)?;
+
+        let (extension, attach_type, query_function) = match database_type.to_lowercase().as_str() {
+            "postgres" | "postgresql" => ("postgres", "POSTGRES", "postgres_query"),
+            "mysql" => ("mysql", "MYSQL", "mysql_query"),
+            other => return Err(AppError::invalid_input(format!("Unsupported database type: {}", other))),
+        };
+
+        let source_description = match (&source_table, &query) {
+            (Some(table), None) => table.clone(),
+            (None, Some(_)) => "query".to_string(),
+            _ => return Err(AppError::invalid_input("Specify exactly one of sourceTable or query")),
+        };
+
+        let table_name = table_name.unwrap_or_else(|| match &source_table {
+            Some(table) => table.rsplit('.').next().unwrap_or(table).to_string(),
+            None => format!("{}_query", extension),
+        });
+        let sanitized_table_name = sanitize_table_name(&table_name);
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let _import = state.imports.begin();
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: 0,
+            total_rows: None,
+            status: format!("Connecting to {}...", extension),
+            ..Default::default()
+        });
+
+        crate::duckdb_core::load_extension(conn, extension)
+            .with_context(|| format!("Failed to load {} extension", extension))?;
+
+        let load_target = prepare_import_target(conn, &sanitized_table_name, mode)?;
+
+        let import_result = with_attached_database(conn, &connection_string, attach_type, |alias| {
+            let _ = window.emit("import-progress", ImportProgress {
+                rows_imported: 0,
+                total_rows: None,
+                status: format!("Reading {}...", source_description),
+                ..Default::default()
+            });
+
+            let source_sql = match (&source_table, &query) {
+                (Some(table), _) => {
+                    let quoted: Vec<String> = table
+                        .split('.')
+                        .map(quote_ident)
+                        .collect();
+                    format!("{}.{}", alias, quoted.join("."))
+                }
+                (None, Some(query)) => format!(
+                    "{}('{}', '{}')",
+                    query_function,
+                    alias,
+                    query.replace('\'', "''")
+                ),
+                (None, None) => unreachable!("validated above"),
+            };
+
+            create_table_from_source(conn, &load_target, &source_sql)
+        })
+        .and_then(|rows| {
+            finish_import_target(conn, &load_target, &sanitized_table_name)?;
+            Ok(rows)
+        });
+
+        if import_result.is_err() && load_target != sanitized_table_name {
+            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&load_target)), []);
+        }
+
+        state.metrics.record(
+            "import_from_database",
+            started,
+            import_result.as_ref().ok().copied(),
+            import_result.is_ok(),
+        );
+
+        let rows_imported = import_result
+            .map_err(|e| import_failure(&state.imports, &window, "Database import failed", e))?;
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported,
+            total_rows: Some(rows_imported),
+            status: "Import complete!".to_string(),
+            ..Default::default()
+        });
+
+        Ok(record_sources(conn, ImportResult {
+            success: true,
+            message: format!("Successfully imported {} rows", rows_imported),
+            table_name: sanitized_table_name.clone(),
+            rows_imported,
+            tables: vec![ImportedTable {
+                table_name: sanitized_table_name,
+                source: format!("{}:{}", extension, source_description),
+                rows_imported,
+            }],
+            rows_rejected: 0,
+            rejects_table: None,
+            rows_skipped: 0,
+            schema_changes: None,
+        }, None, &import_options))
+    })
+    .await
+}
+
+/// Continue a resumable CSV import from its last committed chunk
+#[tauri::command(rename_all = "camelCase")]
+pub async fn resume_import(
+    app: AppHandle,
+    window: tauri::Window,
+    table_name: String,
+) -> Result<ImportResult, AppError> {
+    let store = resume::checkpoint_store(&app)?;
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let _import = state.imports.begin();
+
+        let checkpoint = resume::load_checkpoint(&store, &table_name)?
+            .ok_or_else(|| AppError::not_found(format!("No interrupted import found for {}", table_name)))?;
+        let import_options = json!({ "csv_options": checkpoint.csv_options(), "resumed": true });
+        if !table_exists(conn, &table_name)? {
+            let _ = resume::remove_checkpoint(&store, &table_name);
+            return Err(AppError::not_found(format!(
+                "Table {} no longer exists; start a new import instead",
+                table_name
+            )));
+        }
+
+        let file_path = checkpoint.file_path().to_string();
+        let separators = number_separators(
+            &checkpoint.csv_options().decimal_separator,
+            &checkpoint.csv_options().thousands_separator,
+        )?;
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported: checkpoint.rows_committed(),
+            total_rows: None,
+            status: format!("Resuming import after {} rows...", checkpoint.rows_committed()),
+            ..Default::default()
+        });
+
+        let import_result = resume::continue_chunked_csv(conn, &window, checkpoint, &state.imports)
+            .and_then(|rows| {
+                if let Some((decimal, thousands)) = &separators {
+                    convert_locale_numbers(conn, &table_name, decimal, thousands.as_deref(), &HashMap::new())?;
+                }
+                Ok(rows)
+            });
+
+        state.metrics.record(
+            "resume_import",
+            started,
+            import_result.as_ref().ok().copied(),
+            import_result.is_ok(),
+        );
+
+        let rows_imported = import_result
+            .map_err(|e| import_failure(&state.imports, &window, "Resume failed", e))?;
+
+        // A converted copy of a non-UTF-8 file is only kept until the import completes
+        let source_path = PathBuf::from(&file_path);
+        if encoding::is_utf8_copy(&source_path) {
+            let _ = std::fs::remove_file(&source_path);
+        }
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported,
+            total_rows: Some(rows_imported),
+            status: "Import complete!".to_string(),
+            ..Default::default()
+        });
+
+        Ok(record_sources(conn, ImportResult {
+            success: true,
+            message: format!("Successfully imported {} rows", rows_imported),
+            table_name: table_name.clone(),
+            rows_imported,
+            tables: vec![ImportedTable {
+                table_name,
+                source: file_path,
+                rows_imported,
+            }],
+            rows_rejected: 0,
+            rejects_table: None,
+            rows_skipped: 0,
+            schema_changes: None,
+        }, None, &import_options))
+    })
+    .await
+}
+
+/// List resumable imports that were cancelled or interrupted
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_interrupted_imports(
+    app: AppHandle,
+) -> Result<Vec<resume::InterruptedImport>, AppError> {
+    let store = resume::checkpoint_store(&app)?;
+    resume::list_checkpoints(&store)
+}
+
+/// Forget an interrupted import, keeping the rows loaded so far
+#[tauri::command(rename_all = "camelCase")]
+pub async fn discard_interrupted_import(
+    app: AppHandle,
+    table_name: String,
+) -> Result<String, AppError> {
+    let store = resume::checkpoint_store(&app)?;
+    resume::remove_checkpoint(&store, &table_name)
+        .context("Failed to discard interrupted import")?;
+    Ok(format!("Discarded interrupted import for {}", table_name))
+}
+
+/// List the sheets, named ranges and Excel Tables a workbook offers
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_excel_regions(file_path: String) -> Result<ExcelRegions, AppError> {
+    let path = PathBuf::from(&file_path);
+    let workbook = open_workbook_auto(&path).map_err(|e| AppError::invalid_input(format!("Excel error: {}", e)))?;
+
+    let sheets = workbook.sheet_names().to_owned();
+    let named_ranges = workbook
+        .defined_names()
         .iter()
-        .map(|h| h.to_string())
+        .filter(|(_, reference)| parse_range_reference(reference).is_some())
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    // Only xlsx files carry table definitions
+    let tables = match calamine::open_workbook::<calamine::Xlsx<_>, _>(&path) {
+        Ok(mut xlsx) => match xlsx.load_tables() {
+            Ok(()) => xlsx.table_names().into_iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        },
+        Err(_) => Vec::new(),
+    };
+
+    Ok(ExcelRegions {
+        sheets,
+        named_ranges,
+        tables,
+    })
+}
+
+/// Stop the running import. DuckDB work is interrupted immediately; Excel
+/// loads stop at the next batch boundary.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn cancel_import(
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    if !state.imports.cancel() {
+        return Ok(false);
+    }
+
+    state.interrupt.interrupt();
+    Ok(true)
+}
+
+/// Import every sheet of an Excel or ODS workbook into its own table
+/// named `<book>_<sheet>`
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_workbook(
+    app: AppHandle,
+    window: tauri::Window,
+    file_path: String,
+    table_prefix: Option<String>,
+    excel_options: Option<ExcelImportOptions>,
+) -> Result<ImportResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let import_options = json!({ "table_prefix": table_prefix, "excel_options": excel_options });
+        // Header options apply to every sheet; sheet_name is ignored here
+        let excel_options = excel_options.unwrap_or_default();
+        let path = PathBuf::from(&file_path);
+        if detect_file_format(&path)? != "excel" {
+            return Err(AppError::invalid_input("import_workbook only supports Excel and ODS files"));
+        }
+
+        let prefix = table_prefix.unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("workbook")
+                .to_string()
+        });
+
+        let mut workbook = open_workbook_auto(&path)
+            .map_err(|e| AppError::invalid_input(format!("Excel error: {}", e)))?;
+        let sheet_names = workbook.sheet_names().to_owned();
+        if sheet_names.is_empty() {
+            return Err(AppError::invalid_input("No sheets found in Excel file"));
+        }
+
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+        let _import = state.imports.begin();
+
+        let mut tables = Vec::new();
+        for (index, sheet_name) in sheet_names.iter().enumerate() {
+            let range = workbook
+                .worksheet_range(sheet_name)
+                .map_err(|e| AppError::invalid_input(format!("Failed to read sheet {}: {}", sheet_name, e)))?;
+
+            // Skip sheets without any content
+            if range.is_empty() {
+                continue;
+            }
+
+            let table_name = sanitize_table_name(&format!("{}_{}", prefix, sheet_name));
+
+            let _ = window.emit("import-progress", ImportProgress {
+                rows_imported: 0,
+                total_rows: None,
+                status: format!(
+                    "Importing sheet {} of {}: {}",
+                    index + 1,
+                    sheet_names.len(),
+                    sheet_name
+                ),
+                ..Default::default()
+            });
+
+            let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(&table_name)), []);
+            let rows_imported = match import_excel_range(
+                &range,
+                &table_name,
+                conn,
+                window.clone(),
+                &excel_options,
+                &state.imports,
+            ) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    state.metrics.record("import_workbook", started, None, false);
+                    let context = format!("Failed to import sheet {}", sheet_name);
+                    return Err(import_failure(&state.imports, &window, &context, e));
+                }
+            };
+
+            tables.push(ImportedTable {
+                table_name,
+                source: sheet_name.clone(),
+                rows_imported,
+            });
+        }
+
+        if tables.is_empty() {
+            return Err(AppError::invalid_input("All sheets in the workbook are empty"));
+        }
+
+        let rows_imported: usize = tables.iter().map(|t| t.rows_imported).sum();
+        state.metrics.record("import_workbook", started, Some(rows_imported), true);
+
+        let _ = window.emit("import-progress", ImportProgress {
+            rows_imported,
+            total_rows: Some(rows_imported),
+            status: "Import complete!".to_string(),
+            ..Default::default()
+        });
+
+        Ok(record_sources(conn, ImportResult {
+            success: true,
+            message: format!(
+                "Successfully imported {} rows from {} sheet(s)",
+                rows_imported,
+                tables.len()
+            ),
+            table_name: tables[0].table_name.clone(),
+            rows_imported,
+            tables,
+            rows_rejected: 0,
+            rejects_table: None,
+            rows_skipped: 0,
+            schema_changes: None,
+        }, Some(&file_path), &import_options))
+    })
+    .await
+}
+
+/// Preview the first rows of a file. For workbooks, `sheet_name` picks the
+/// sheet to show and every sheet is listed with its row count.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn preview_file(
+    file_path: String,
+    rows: Option<usize>,
+    csv_options: Option<CsvImportOptions>,
+    excel_options: Option<ExcelImportOptions>,
+    xml_options: Option<XmlImportOptions>,
+    column_types: Option<HashMap<String, String>>,
+    sheet_name: Option<String>,
+) -> Result<PreviewData, AppError> {
+    let path = PathBuf::from(&file_path);
+    let preview_rows = rows.unwrap_or(10);
+    let mut excel = excel_options.unwrap_or_default();
+    if sheet_name.is_some() {
+        excel.sheet_name = sheet_name;
+    }
+    let options = LoadOptions {
+        csv: csv_options.unwrap_or_default(),
+        excel,
+        xml: xml_options.unwrap_or_default(),
+        column_types: column_types.unwrap_or_default(),
+        ..Default::default()
+    };
+
+    build_preview(&path, preview_rows, &options)
+}
+
+/// Dry-run an import: read the whole file in a scratch database and report
+/// parse errors, values that don't fit the inferred (or requested) column
+/// types, and per-column row counts. No table is created.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn validate_file(
+    window: tauri::Window,
+    file_path: String,
+    csv_options: Option<CsvImportOptions>,
+    excel_options: Option<ExcelImportOptions>,
+    json_options: Option<JsonImportOptions>,
+    xml_options: Option<XmlImportOptions>,
+    column_types: Option<HashMap<String, String>>,
+) -> Result<ValidationReport, AppError> {
+    let path = PathBuf::from(&file_path);
+    let options = LoadOptions {
+        csv: csv_options.unwrap_or_default(),
+        excel: excel_options.unwrap_or_default(),
+        json: json_options.unwrap_or_default(),
+        xml: xml_options.unwrap_or_default(),
+        column_types: column_types.unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let conn = duckdb::Connection::open_in_memory()?;
+    let mut report = validate_path(&conn, &path, window, &options).unwrap_or_else(|e| ValidationReport {
+        error: Some(e.to_string()),
+        ..Default::default()
+    });
+    report.valid = report.error.is_none()
+        && report.rows_rejected == 0
+        && report.columns.iter().all(|c| c.invalid_values == 0);
+
+    Ok(report)
+}
+
+fn validate_path(
+    conn: &duckdb::Connection,
+    path: &PathBuf,
+    window: tauri::Window,
+    options: &LoadOptions,
+) -> Result<ValidationReport, ImportError> {
+    let format = detect_file_format(path)?;
+
+    if format == "zip" {
+        let extracted = extract_single_file_zip(path)?;
+        let report = validate_path(conn, &extracted, window, options);
+        let _ = std::fs::remove_file(&extracted);
+        return report;
+    }
+
+    let mut encoding_name = None;
+    if format == "csv" && !is_gzipped(path) {
+        let detected = encoding::resolve_encoding(path, options.csv.encoding.as_deref())?;
+        if let Some(utf8_path) = encoding::utf8_copy(path, Some(detected.name()))? {
+            let mut utf8_options = options.clone();
+            utf8_options.csv.encoding = Some("utf-8".to_string());
+            let report = validate_path(conn, &utf8_path, window, &utf8_options);
+            let _ = std::fs::remove_file(&utf8_path);
+            return report.map(|report| ValidationReport {
+                encoding: Some(detected.name().to_string()),
+                ..report
+            });
+        }
+        encoding_name = Some(detected.name().to_string());
+    }
+
+    let report = if format == "csv" {
+        validate::validate_csv(conn, path, options)?
+    } else {
+        load_file(path, validate::VALIDATION_TABLE, conn, window, options, &ImportControl::new())?;
+        validate::validate_loaded(conn)?
+    };
+
+    Ok(ValidationReport {
+        format,
+        encoding: encoding_name,
+        ..report
+    })
+}
+
+fn is_gzipped(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("gz"))
+        .unwrap_or(false)
+}
+
+fn build_preview(
+    path: &PathBuf,
+    preview_rows: usize,
+    options: &LoadOptions,
+) -> Result<PreviewData, AppError> {
+    let format = detect_file_format(path)?;
+    let csv_options = &options.csv;
+    let column_types = &options.column_types;
+
+    if format == "zip" {
+        let extracted = extract_single_file_zip(path)?;
+        let preview = build_preview(&extracted, preview_rows, options);
+        let _ = std::fs::remove_file(&extracted);
+        return preview;
+    }
+
+    // Non-UTF-8 files are previewed from a converted copy
+    let mut detected_encoding = None;
+    if format == "csv" && !is_gzipped(path) {
+        let detected = encoding::resolve_encoding(path, csv_options.encoding.as_deref())?;
+        if let Some(utf8_path) = encoding::utf8_copy(path, Some(detected.name()))? {
+            let mut utf8_options = options.clone();
+            utf8_options.csv.encoding = Some("utf-8".to_string());
+            let preview = build_preview(&utf8_path, preview_rows, &utf8_options);
+            let _ = std::fs::remove_file(&utf8_path);
+            return preview.map(|preview| PreviewData {
+                encoding: Some(detected.name().to_string()),
+                ..preview
+            });
+        }
+        detected_encoding = Some(detected.name().to_string());
+    }
+
+    let mut preview = match format.as_str() {
+        // The csv crate can't read gzip, so let DuckDB decompress it
+        "csv" if is_gzipped(path) => {
+            let path_str = path.to_str().ok_or_else(|| AppError::invalid_input("Invalid file path"))?;
+            let source = read_csv_source(path_str, csv_options, column_types)?;
+            preview_duckdb_source(&source, preview_rows)
+        }
+        "csv" => preview_csv(path, preview_rows, csv_options),
+        "excel" => preview_excel(path, preview_rows, &options.excel),
+        "json" => preview_json(path, preview_rows),
+        "parquet" => preview_parquet(path, preview_rows),
+        "arrow" => preview_arrow(path, preview_rows),
+        "avro" => preview_avro(path, preview_rows),
+        "orc" => preview_orc(path, preview_rows),
+        "xml" => preview_xml(path, preview_rows, &options.xml),
+        _ => Err(AppError::invalid_input("Unsupported format")),
+    }?;
+
+    match format.as_str() {
+        "csv" => {
+            let (schema, parse_errors) = infer_csv_schema(path, csv_options, column_types)
+                .context("Schema inference failed")?;
+            preview.schema = schema;
+            preview.parse_errors = parse_errors;
+        }
+        // DuckDB-scanned previews already carry the schema
+        "json" | "parquet" | "arrow" | "avro" | "orc" | "xml" => {}
+        _ => {
+            // Excel sheets are loaded as text columns
+            preview.schema = preview
+                .columns
+                .iter()
+                .map(|name| ColumnInfo {
+                    name: name.clone(),
+                    data_type: column_types
+                        .get(name)
+                        .cloned()
+                        .unwrap_or_else(|| "VARCHAR".to_string()),
+                })
+                .collect();
+        }
+    }
+    preview.encoding = detected_encoding;
+
+    Ok(preview)
+}
+
+/// Ask DuckDB for the schema it would infer for a CSV file, plus a sample
+/// of lines it rejected while sniffing
+fn infer_csv_schema(
+    path: &Path,
+    options: &CsvImportOptions,
+    column_types: &HashMap<String, String>,
+) -> Result<(Vec<ColumnInfo>, Vec<String>), ImportError> {
+    const MAX_PARSE_ERRORS: usize = 20;
+
+    let path_str = path.to_str().ok_or_else(|| {
+        AppError::invalid_input("Invalid file path")
+    })?;
+    let source = read_csv_source(path_str, options, column_types)?;
+
+    // Use a scratch connection so previews never touch the main database
+    let conn = duckdb::Connection::open_in_memory()?;
+
+    let schema = describe_columns(&conn, &format!("SELECT * FROM {}", source))?
+        .into_iter()
+        .map(|(name, data_type)| ColumnInfo { name, data_type })
         .collect();
 
+    // Re-scan with rejects stored so problem lines can be shown before import
+    let mut rejects_args = read_csv_args(path_str, options, column_types)?;
+    if options.on_error != OnError::SkipRow {
+        rejects_args.push("store_rejects = true".to_string());
+    }
+    let rejects_source = format!("read_csv({})", rejects_args.join(", "));
+    let mut parse_errors = Vec::new();
+    if conn
+        .execute(&format!("SELECT COUNT(*) FROM {}", rejects_source), [])
+        .is_ok()
+    {
+        if let Ok(mut stmt) = conn.prepare(&format!(
+            "SELECT line, error_message FROM reject_errors ORDER BY line LIMIT {}",
+            MAX_PARSE_ERRORS
+        )) {
+            if let Ok(rows) = stmt.query_map([], |row| {
+                Ok(format!("Line {}: {}", row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            }) {
+                parse_errors = rows.filter_map(Result::ok).collect();
+            }
+        }
+    }
+
+    Ok((schema, parse_errors))
+}
+
+fn preview_csv(path: &PathBuf, rows: usize, options: &CsvImportOptions) -> Result<PreviewData, AppError> {
+    let file = File::open(path)?;
+    let mut buf_reader = BufReader::new(file);
+
+    // Skip leading lines before the header
+    for _ in 0..options.skip_rows.unwrap_or(0) {
+        let mut line = String::new();
+        if buf_reader.read_line(&mut line)? == 0 {
+            break;
+        }
+    }
+
+    let mut builder = ReaderBuilder::new();
+    builder.has_headers(options.has_header.unwrap_or(true));
+    if let Some(delim) = csv_byte(&options.delimiter)? {
+        builder.delimiter(delim);
+    }
+    if let Some(quote) = csv_byte(&options.quote)? {
+        builder.quote(quote);
+    }
+    if let Some(escape) = csv_byte(&options.escape)? {
+        builder.escape(Some(escape));
+    }
+    let mut rdr = builder.from_reader(buf_reader);
+
+    let headers: Vec<String> = if options.has_header.unwrap_or(true) {
+        rdr.headers()
+            .map_err(|e| AppError::invalid_input(e.to_string()))?
+            .iter()
+            .map(|h| h.to_string())
+            .collect()
+    } else {
+        let width = rdr.headers().map_err(|e| AppError::invalid_input(e.to_string()))?.len();
+        (1..=width).map(|i| format!("column{}", i)).collect()
+    };
+
     let mut preview_rows = Vec::new();
     let mut total_rows = 0;
 
     for result in rdr.records() {
         total_rows += 1;
         if preview_rows.len() < rows {
-            let record = result.map_err(|e| e.to_string())?;
+            let record = result.map_err(|e| AppError::invalid_input(e.to_string()))?;
             let row: Vec<String> = record.iter().map(|f| f.to_string()).collect();
             preview_rows.push(row);
         }
@@ -338,29 +3724,34 @@ fn preview_csv(path: &PathBuf, rows: usize) -> Result<PreviewData, String> {
         columns: headers,
         rows: preview_rows,
         total_rows,
+        schema: Vec::new(),
+        parse_errors: Vec::new(),
+        encoding: None,
+        sheets: Vec::new(),
     })
 }
 
-fn preview_excel(path: &PathBuf, rows: usize) -> Result<PreviewData, String> {
-    let mut workbook: Xlsx<_> = open_workbook(path)
-        .map_err(|e| format!("Excel error: {}", e))?;
-
-    let sheet_names = workbook.sheet_names().to_owned();
-    if sheet_names.is_empty() {
-        return Err("No sheets found".to_string());
+fn csv_byte(value: &Option<String>) -> Result<Option<u8>, AppError> {
+    match value.as_deref() {
+        None => Ok(None),
+        Some("\\t") | Some("\t") => Ok(Some(b'\t')),
+        Some(v) if v.len() == 1 => Ok(Some(v.as_bytes()[0])),
+        Some(v) => Err(AppError::invalid_input(format!("Expected a single ASCII character, got '{}'", v))),
     }
+}
 
-    let range = workbook
-        .worksheet_range(&sheet_names[0])
-        .map_err(|e| e.to_string())?;
+fn preview_excel(
+    path: &PathBuf,
+    rows: usize,
+    options: &ExcelImportOptions,
+) -> Result<PreviewData, AppError> {
+    let range = read_excel_data(path, options)?;
+    let sheets = excel_sheet_info(path)?;
 
-    let mut all_rows = range.rows();
+    let mut all_rows = excel_rows_from_header(&range, options);
 
-    let headers: Vec<String> = if let Some(header_row) = all_rows.next() {
-        header_row.iter().map(|c| c.to_string()).collect()
-    } else {
-        return Err("Empty sheet".to_string());
-    };
+    let headers = excel_headers(&mut all_rows, options)
+        .ok_or_else(|| AppError::invalid_input("Empty sheet"))?;
 
     let mut preview_rows = Vec::new();
     let mut total_rows = 0;
@@ -377,5 +3768,121 @@ fn preview_excel(path: &PathBuf, rows: usize) -> Result<PreviewData, String> {
         columns: headers,
         rows: preview_rows,
         total_rows,
+        schema: Vec::new(),
+        parse_errors: Vec::new(),
+        encoding: None,
+        sheets,
+    })
+}
+
+fn preview_json(path: &Path, rows: usize) -> Result<PreviewData, AppError> {
+    let path_str = path.to_str().ok_or_else(|| AppError::invalid_input("Invalid file path"))?;
+    let source = read_json_source(path_str, &JsonImportOptions::default())?;
+
+    preview_duckdb_source(&source, rows)
+}
+
+fn preview_parquet(path: &Path, rows: usize) -> Result<PreviewData, AppError> {
+    let path_str = path.to_str().ok_or_else(|| AppError::invalid_input("Invalid file path"))?;
+    let source = format!("read_parquet('{}')", path_str.replace('\'', "''"));
+
+    preview_duckdb_source(&source, rows)
+}
+
+fn preview_arrow(path: &Path, rows: usize) -> Result<PreviewData, AppError> {
+    let path_str = path.to_str().ok_or_else(|| AppError::invalid_input("Invalid file path"))?;
+
+    // The arrow extension must be loaded in the scratch connection itself
+    let conn = duckdb::Connection::open_in_memory()?;
+    let source = read_arrow_source(&conn, path_str)?;
+
+    preview_with_connection(&conn, &source, rows)
+}
+
+fn preview_avro(path: &Path, rows: usize) -> Result<PreviewData, AppError> {
+    let path_str = path.to_str().ok_or_else(|| AppError::invalid_input("Invalid file path"))?;
+
+    let conn = duckdb::Connection::open_in_memory()?;
+    let source = read_avro_source(&conn, path_str)?;
+
+    preview_with_connection(&conn, &source, rows)
+}
+
+fn preview_orc(path: &PathBuf, rows: usize) -> Result<PreviewData, AppError> {
+    let temp_path = orc_temp_path(path, "preview");
+    let preview = orc::write_arrow_ipc(path, &temp_path, Some(rows), &ImportControl::new())
+        .map_err(AppError::from)
+        .and_then(|_| preview_arrow(&temp_path, rows));
+    let _ = std::fs::remove_file(&temp_path);
+    preview
+}
+
+/// Preview any source DuckDB can scan, rendering values as text
+fn preview_xml(path: &PathBuf, rows: usize, options: &XmlImportOptions) -> Result<PreviewData, AppError> {
+    let records = xml::read_xml_records(path, options)?;
+    let temp_path = std::env::temp_dir().join(format!("rats_xml_preview_{}.csv", std::process::id()));
+
+    let preview = records.write_csv(&temp_path).map_err(AppError::from).and_then(|_| {
+        let path_str = temp_path.to_str().ok_or_else(|| AppError::internal("Invalid temp file path"))?;
+        preview_duckdb_source(
+            &format!("read_csv('{}', header = true)", path_str.replace('\'', "''")),
+            rows,
+        )
+    });
+
+    let _ = std::fs::remove_file(&temp_path);
+    preview
+}
+
+fn preview_duckdb_source(source: &str, rows: usize) -> Result<PreviewData, AppError> {
+    // Use a scratch connection so previews never touch the main database
+    let conn = duckdb::Connection::open_in_memory()?;
+
+    preview_with_connection(&conn, source, rows)
+}
+
+fn preview_with_connection(
+    conn: &duckdb::Connection,
+    source: &str,
+    rows: usize,
+) -> Result<PreviewData, AppError> {
+    let schema: Vec<ColumnInfo> = describe_columns(conn, &format!("SELECT * FROM {}", source))?
+        .into_iter()
+        .map(|(name, data_type)| ColumnInfo { name, data_type })
+        .collect();
+    let columns: Vec<String> = schema.iter().map(|c| c.name.clone()).collect();
+
+    let total_rows: usize = conn
+        .query_row(&format!("SELECT COUNT(*) FROM {}", source), [], |row| row.get(0))?;
+
+    let cast_columns: Vec<String> = columns
+        .iter()
+        .map(|c| format!("CAST({} AS VARCHAR)", quote_ident(c)))
+        .collect();
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM {} LIMIT {}",
+            cast_columns.join(", "),
+            source,
+            rows
+        ))?;
+
+    let column_count = columns.len();
+    let preview_rows = stmt
+        .query_map([], |row| {
+            (0..column_count)
+                .map(|i| row.get::<_, Option<String>>(i).map(|v| v.unwrap_or_default()))
+                .collect::<Result<Vec<_>, _>>()
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(PreviewData {
+        columns,
+        rows: preview_rows,
+        total_rows,
+        schema,
+        parse_errors: Vec::new(),
+        encoding: None,
+        sheets: Vec::new(),
     })
 }
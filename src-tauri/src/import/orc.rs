@@ -0,0 +1,44 @@
+use arrow_ipc::writer::FileWriter;
+use orc_rust::ArrowReaderBuilder;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use crate::error::AppError;
+use super::{ImportControl, ImportError};
+
+/// Convert an ORC file to an Arrow IPC file so DuckDB's arrow reader can load
+/// it with its column types intact. With a `limit`, conversion stops after
+/// the stripe batch that reaches it. Returns the number of rows written.
+pub(super) fn write_arrow_ipc(
+    path: &PathBuf,
+    output: &Path,
+    limit: Option<usize>,
+    control: &ImportControl,
+) -> Result<usize, ImportError> {
+    let builder = ArrowReaderBuilder::try_new(File::open(path)?)
+        .map_err(|e| AppError::invalid_input(format!("Invalid ORC file: {}", e)))?;
+    let schema = builder.schema();
+    let reader = builder.build();
+
+    let mut writer = FileWriter::try_new(File::create(output)?, &schema)
+        .map_err(|e| ImportError::Custom(format!("Failed to write Arrow file: {}", e)))?;
+
+    let mut rows = 0;
+    for batch in reader {
+        if control.is_cancelled() {
+            return Err(ImportError::Cancelled);
+        }
+        let batch = batch.map_err(|e| ImportError::Custom(format!("Failed to read ORC stripe: {}", e)))?;
+        rows += batch.num_rows();
+        writer
+            .write(&batch)
+            .map_err(|e| ImportError::Custom(format!("Failed to write Arrow file: {}", e)))?;
+        if limit.is_some_and(|limit| rows >= limit) {
+            break;
+        }
+    }
+    writer
+        .finish()
+        .map_err(|e| ImportError::Custom(format!("Failed to write Arrow file: {}", e)))?;
+
+    Ok(rows)
+}
@@ -0,0 +1,507 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use crate::duckdb_core::quote_ident;
+use crate::error::{AppError, ErrorContext};
+use super::{
+    csv_byte, describe_columns, projection_sql, read_csv_source, source_column_types,
+    ColumnSelection, CsvImportOptions, ImportControl, ImportError, ImportProgress, LoadOptions,
+    OnError,
+};
+
+/// File in the app data directory recording how far each resumable import
+/// got, so checkpoints outlive the (possibly in-memory) database session
+const CHECKPOINT_FILE: &str = "import-checkpoints.json";
+
+/// Serializes read-modify-write cycles on the checkpoint file
+static CHECKPOINT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Rows committed per chunk; progress survives in steps of this size
+const CHUNK_ROWS: usize = 100_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterruptedImport {
+    pub table_name: String,
+    pub file_path: String,
+    pub rows_committed: usize,
+    pub bytes_committed: u64,
+    pub file_size: u64,
+}
+
+/// Everything needed to parse the remaining chunks exactly like the first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResumeSettings {
+    csv: CsvImportOptions,
+    projection: Vec<ColumnSelection>,
+    columns: Vec<(String, String)>, // Source columns and types fixed before the first chunk
+    delimiter: u8,
+    quote: u8,
+    escape: Option<u8>,
+}
+
+/// How far an import got: where the next chunk starts in the source file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ChunkMark {
+    byte_offset: u64,
+    rows_committed: usize,
+    table_rows: u64, // Rows in the table at this point, to tell which mark the database reached
+}
+
+/// A checkpoint as stored in the checkpoint file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedCheckpoint {
+    file_path: String,
+    file_size: u64,
+    committed: ChunkMark,
+    pending: Option<ChunkMark>, // Recorded just before a chunk commits
+    settings: ResumeSettings,
+    updated_at: u64, // Seconds since the Unix epoch
+}
+
+pub(super) struct Checkpoint {
+    table_name: String,
+    file_path: String,
+    file_size: u64,
+    mark: ChunkMark,
+    pending: Option<ChunkMark>, // Mark of a chunk that may or may not have committed
+    settings: ResumeSettings,
+    store: Option<PathBuf>, // Checkpoint file; None for plain chunked loads that can't be resumed
+}
+
+impl Checkpoint {
+    pub fn rows_committed(&self) -> usize {
+        self.mark.rows_committed
+    }
+
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    pub fn csv_options(&self) -> &CsvImportOptions {
+        &self.settings.csv
+    }
+
+    /// Settle which mark the table reached: a chunk may have committed after
+    /// its pending mark was saved but before it was confirmed
+    fn reconcile(&mut self, conn: &duckdb::Connection) -> Result<(), ImportError> {
+        let table_rows: u64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {}", quote_ident(&self.table_name)),
+            [],
+            |row| row.get(0),
+        )?;
+        match self.pending.take() {
+            Some(pending) if pending.table_rows == table_rows => self.mark = pending,
+            _ if self.mark.table_rows == table_rows => {}
+            _ => {
+                return Err(AppError::invalid_input(format!(
+                    "{} changed since the import was interrupted; start a new import instead",
+                    self.table_name
+                )).into())
+            }
+        }
+        Ok(())
+    }
+
+    fn saved(&self, pending: Option<ChunkMark>) -> SavedCheckpoint {
+        SavedCheckpoint {
+            file_path: self.file_path.clone(),
+            file_size: self.file_size,
+            committed: self.mark,
+            pending,
+            settings: self.settings.clone(),
+            updated_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Path of the checkpoint file in the app data directory
+pub fn checkpoint_store(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .context("Failed to locate data directory")?;
+    Ok(dir.join(CHECKPOINT_FILE))
+}
+
+fn read_checkpoints(store: &Path) -> Result<HashMap<String, SavedCheckpoint>, AppError> {
+    if !store.exists() {
+        return Ok(HashMap::new());
+    }
+    let contents = std::fs::read_to_string(store).context("Failed to read import checkpoints")?;
+    serde_json::from_str(&contents).context("Invalid import checkpoint file")
+}
+
+/// Change the checkpoints under the lock, replacing the file in one rename
+fn update_checkpoints(
+    store: &Path,
+    change: impl FnOnce(&mut HashMap<String, SavedCheckpoint>),
+) -> Result<(), AppError> {
+    let _lock = CHECKPOINT_LOCK.lock()?;
+    let mut checkpoints = read_checkpoints(store)?;
+    change(&mut checkpoints);
+
+    if let Some(dir) = store.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+    }
+    let contents = serde_json::to_string_pretty(&checkpoints)?;
+    let staged = store.with_extension("json.tmp");
+    std::fs::write(&staged, contents)
+        .and_then(|_| std::fs::rename(&staged, store))
+        .context("Failed to save import checkpoints")
+}
+
+fn save_checkpoint(checkpoint: &Checkpoint, pending: Option<ChunkMark>) -> Result<(), ImportError> {
+    let Some(store) = &checkpoint.store else {
+        return Ok(());
+    };
+    let saved = checkpoint.saved(pending);
+    update_checkpoints(store, |checkpoints| {
+        checkpoints.insert(checkpoint.table_name.clone(), saved);
+    })
+    .map_err(ImportError::from)
+}
+
+pub(super) fn load_checkpoint(store: &Path, table_name: &str) -> Result<Option<Checkpoint>, ImportError> {
+    let mut checkpoints = read_checkpoints(store)?;
+    Ok(checkpoints.remove(table_name).map(|saved| Checkpoint {
+        table_name: table_name.to_string(),
+        file_path: saved.file_path,
+        file_size: saved.file_size,
+        mark: saved.committed,
+        pending: saved.pending,
+        settings: saved.settings,
+        store: Some(store.to_path_buf()),
+    }))
+}
+
+/// Forget the checkpoint for a table (used when the import finishes or the table is dropped)
+pub fn remove_checkpoint(store: &Path, table_name: &str) -> Result<(), AppError> {
+    if !store.exists() {
+        return Ok(());
+    }
+    update_checkpoints(store, |checkpoints| {
+        checkpoints.remove(table_name);
+    })
+}
+
+pub(super) fn list_checkpoints(store: &Path) -> Result<Vec<InterruptedImport>, AppError> {
+    let mut checkpoints: Vec<_> = read_checkpoints(store)?.into_iter().collect();
+    checkpoints.sort_by_key(|(_, saved)| std::cmp::Reverse(saved.updated_at));
+    Ok(checkpoints
+        .into_iter()
+        .map(|(table_name, saved)| InterruptedImport {
+            table_name,
+            file_path: saved.file_path,
+            rows_committed: saved.committed.rows_committed,
+            bytes_committed: saved.committed.byte_offset,
+            file_size: saved.file_size,
+        })
+        .collect())
+}
+
+/// Dialect values not given in the options are taken from DuckDB's sniffer
+fn sniff_dialect(
+    conn: &duckdb::Connection,
+    path_str: &str,
+    options: &CsvImportOptions,
+) -> Result<(u8, u8, Option<u8>, bool, usize), ImportError> {
+    let (sniffed_delimiter, sniffed_quote, sniffed_escape, sniffed_header, sniffed_skip): (
+        String,
+        String,
+        String,
+        bool,
+        u32,
+    ) = conn.query_row(
+        "SELECT Delimiter, Quote, Escape, HasHeader, SkipRows FROM sniff_csv(?)",
+        [path_str],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+    )?;
+
+    let byte = |value: &Option<String>, sniffed: &str| -> Result<Option<u8>, ImportError> {
+        match csv_byte(value)? {
+            Some(b) => Ok(Some(b)),
+            None => Ok(sniffed.bytes().next().filter(|b| *b != 0)),
+        }
+    };
+
+    let delimiter = byte(&options.delimiter, &sniffed_delimiter)?.unwrap_or(b',');
+    let quote = byte(&options.quote, &sniffed_quote)?.unwrap_or(b'"');
+    let escape = byte(&options.escape, &sniffed_escape)?.filter(|e| *e != quote);
+    let has_header = options.has_header.unwrap_or(sniffed_header);
+    let skip_rows = options.skip_rows.unwrap_or(sniffed_skip as usize);
+
+    Ok((delimiter, quote, escape, has_header, skip_rows))
+}
+
+/// Byte offset of the first data row, after skipped lines and the header
+fn data_start_offset(
+    path: &PathBuf,
+    settings: &ResumeSettings,
+    skip_rows: usize,
+    has_header: bool,
+) -> Result<u64, ImportError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut offset = 0u64;
+    let mut line = Vec::new();
+    for _ in 0..skip_rows {
+        line.clear();
+        offset += reader.read_until(b'\n', &mut line)? as u64;
+    }
+
+    if has_header {
+        let mut csv_reader = csv_reader(settings, reader);
+        let mut header = csv::ByteRecord::new();
+        csv_reader.read_byte_record(&mut header)?;
+        offset += csv_reader.position().byte();
+    }
+    Ok(offset)
+}
+
+fn csv_reader<R: std::io::Read>(settings: &ResumeSettings, reader: R) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(settings.delimiter)
+        .quote(settings.quote)
+        .escape(settings.escape)
+        .double_quote(settings.escape.is_none())
+        .from_reader(reader)
+}
+
+/// `read_csv` call for one chunk file, written as plain comma-separated CSV
+/// with the column names and types fixed up front
+fn chunk_source(chunk_path: &str, settings: &ResumeSettings) -> String {
+    let columns: Vec<String> = settings
+        .columns
+        .iter()
+        .map(|(name, data_type)| format!("'{}': '{}'", name.replace('\'', "''"), data_type))
+        .collect();
+
+    let mut args = vec![
+        format!("'{}'", chunk_path.replace('\'', "''")),
+        "header = false".to_string(),
+        "delim = ','".to_string(),
+        "quote = '\"'".to_string(),
+        "escape = '\"'".to_string(),
+        format!("columns = {{{}}}", columns.join(", ")),
+    ];
+    let csv = &settings.csv;
+    if let Some(null_string) = &csv.null_string {
+        args.push(format!("nullstr = '{}'", null_string.replace('\'', "''")));
+    }
+    if let Some(date_format) = &csv.date_format {
+        args.push(format!("dateformat = '{}'", date_format.replace('\'', "''")));
+    }
+    if let Some(timestamp_format) = &csv.timestamp_format {
+        args.push(format!("timestampformat = '{}'", timestamp_format.replace('\'', "''")));
+    }
+    match csv.on_error {
+        OnError::Fail => {}
+        OnError::SkipRow => args.push("ignore_errors = true".to_string()),
+        OnError::NullValue => args.push("null_padding = true".to_string()),
+    }
+
+    format!("read_csv({})", args.join(", "))
+}
+
+/// Load a CSV file in committed chunks, reporting progress after each one.
+/// With `resumable`, a checkpoint is recorded so `resume_import` can continue
+/// after a cancel or crash.
+pub(super) fn start_chunked_csv(
+    path: &PathBuf,
+    table_name: &str,
+    conn: &duckdb::Connection,
+    window: &tauri::Window,
+    options: &LoadOptions,
+    control: &ImportControl,
+    resumable: bool,
+) -> Result<usize, ImportError> {
+    let path_str = path.to_str().ok_or_else(|| {
+        AppError::invalid_input("Invalid file path")
+    })?;
+
+    // Sniff once over the whole file so every chunk gets the same types
+    let column_types = source_column_types(&options.column_types, &options.projection);
+    let source = read_csv_source(path_str, &options.csv, &column_types)?;
+    let columns = describe_columns(conn, &format!("SELECT * FROM {}", source))?;
+    let (delimiter, quote, escape, has_header, skip_rows) = sniff_dialect(conn, path_str, &options.csv)?;
+
+    let settings = ResumeSettings {
+        csv: options.csv.clone(),
+        projection: options.projection.clone(),
+        columns,
+        delimiter,
+        quote,
+        escape,
+    };
+
+    conn.execute(
+        &format!(
+            "CREATE TABLE {} AS SELECT {} FROM {} LIMIT 0",
+            quote_ident(table_name),
+            projection_sql(&settings.projection),
+            source
+        ),
+        [],
+    )?;
+
+    let store = if resumable {
+        Some(checkpoint_store(window.app_handle())?)
+    } else {
+        None
+    };
+    let checkpoint = Checkpoint {
+        table_name: table_name.to_string(),
+        file_path: path_str.to_string(),
+        file_size: std::fs::metadata(path)?.len(),
+        mark: ChunkMark {
+            byte_offset: data_start_offset(path, &settings, skip_rows, has_header)?,
+            rows_committed: 0,
+            table_rows: 0,
+        },
+        pending: None,
+        settings,
+        store,
+    };
+    save_checkpoint(&checkpoint, None)?;
+
+    let result = continue_chunked_csv(conn, window, checkpoint, control);
+    // Without a checkpoint the committed chunks can't be continued
+    if result.is_err() && !resumable {
+        let _ = conn.execute(&format!("DROP TABLE IF EXISTS {}", quote_ident(table_name)), []);
+    }
+    result
+}
+
+/// Load the remaining chunks after a checkpoint. Returns the total number of
+/// rows in the import, including those committed before the checkpoint.
+pub(super) fn continue_chunked_csv(
+    conn: &duckdb::Connection,
+    window: &tauri::Window,
+    mut checkpoint: Checkpoint,
+    control: &ImportControl,
+) -> Result<usize, ImportError> {
+    checkpoint.reconcile(conn)?;
+    let mut file = File::open(&checkpoint.file_path)?;
+    if file.metadata()?.len() != checkpoint.file_size {
+        return Err(AppError::invalid_input(format!(
+            "{} changed since the import started; start a new import instead",
+            checkpoint.file_path
+        )).into());
+    }
+    file.seek(SeekFrom::Start(checkpoint.mark.byte_offset))?;
+
+    let chunk_path = std::env::temp_dir().join(format!(
+        "rats_chunk_{}_{}.csv",
+        std::process::id(),
+        checkpoint.table_name
+    ));
+    let chunk_str = chunk_path.to_str().ok_or_else(|| {
+        ImportError::Custom("Invalid temp file path".to_string())
+    })?;
+    let insert_query = format!(
+        "INSERT INTO {} SELECT {} FROM {}",
+        quote_ident(&checkpoint.table_name),
+        projection_sql(&checkpoint.settings.projection),
+        chunk_source(chunk_str, &checkpoint.settings)
+    );
+
+    let start_offset = checkpoint.mark.byte_offset;
+    let start_rows = checkpoint.mark.rows_committed;
+    let started = Instant::now();
+    let mut reader = csv_reader(&checkpoint.settings, BufReader::new(file));
+    let mut record = csv::ByteRecord::new();
+
+    let result = (|| {
+        loop {
+            if control.is_cancelled() {
+                return Err(ImportError::Cancelled);
+            }
+
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(&chunk_path)?;
+            let mut rows = 0;
+            while rows < CHUNK_ROWS && reader.read_byte_record(&mut record)? {
+                writer.write_byte_record(&record)?;
+                rows += 1;
+            }
+            writer.flush()?;
+            drop(writer);
+
+            if rows == 0 {
+                return Ok(());
+            }
+
+            // The chunk's mark is saved as pending before it commits and
+            // confirmed after; the table's row count settles a crash between
+            conn.execute_batch("BEGIN TRANSACTION")?;
+            let inserted = conn
+                .execute(&insert_query, [])
+                .and_then(|_| {
+                    conn.query_row(
+                        &format!("SELECT COUNT(*) FROM {}", quote_ident(&checkpoint.table_name)),
+                        [],
+                        |row| row.get::<_, u64>(0),
+                    )
+                })
+                .map_err(ImportError::from)
+                .and_then(|table_rows| {
+                    let next = ChunkMark {
+                        byte_offset: start_offset + reader.position().byte(),
+                        rows_committed: checkpoint.mark.rows_committed + rows,
+                        table_rows,
+                    };
+                    save_checkpoint(&checkpoint, Some(next)).map(|_| next)
+                });
+            match inserted {
+                Ok(next) => {
+                    conn.execute_batch("COMMIT")?;
+                    checkpoint.mark = next;
+                    save_checkpoint(&checkpoint, None)?;
+                }
+                Err(e) => {
+                    let _ = conn.execute_batch("ROLLBACK");
+                    return Err(e);
+                }
+            }
+            let mark = checkpoint.mark;
+
+            let progress = ImportProgress {
+                rows_imported: mark.rows_committed,
+                total_rows: None,
+                status: format!(
+                    "Imported {} rows ({:.0}% of file)",
+                    mark.rows_committed,
+                    mark.byte_offset as f64 / checkpoint.file_size.max(1) as f64 * 100.0
+                ),
+                bytes_read: Some(mark.byte_offset),
+                total_bytes: Some(checkpoint.file_size),
+                ..Default::default()
+            };
+            let _ = window.emit(
+                "import-progress",
+                progress.with_rate(
+                    started,
+                    mark.rows_committed - start_rows,
+                    mark.byte_offset - start_offset,
+                    checkpoint.file_size.saturating_sub(mark.byte_offset),
+                ),
+            );
+        }
+    })();
+
+    let _ = std::fs::remove_file(&chunk_path);
+    result?;
+
+    if let Some(store) = &checkpoint.store {
+        remove_checkpoint(store, &checkpoint.table_name)?;
+    }
+    Ok(checkpoint.mark.rows_committed)
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::duckdb_core::quote_ident;
+use crate::error::AppError;
+use super::{
+    describe_columns, read_csv_args, read_csv_source, ColumnValidation,
+    CsvImportOptions, ImportError, LoadOptions, OnError, ValidationReport,
+};
+
+/// Scratch table files are loaded into while validating
+pub(super) const VALIDATION_TABLE: &str = "_rats_validation";
+
+const MAX_PARSE_ERRORS: usize = 20;
+const MAX_INVALID_SAMPLES: usize = 5;
+
+/// Check a CSV file by scanning all of it as text, then casting every column
+/// to the type DuckDB infers (or was asked to use) for it
+pub(super) fn validate_csv(
+    conn: &duckdb::Connection,
+    path: &Path,
+    options: &LoadOptions,
+) -> Result<ValidationReport, ImportError> {
+    let path_str = path.to_str().ok_or_else(|| {
+        AppError::invalid_input("Invalid file path")
+    })?;
+
+    // Types are sniffed from a sample, so later rows may not fit them
+    let typed_source = read_csv_source(path_str, &options.csv, &options.column_types)?;
+    let schema = describe_columns(conn, &format!("SELECT * FROM {}", typed_source))?;
+
+    let mut args = read_csv_args(path_str, &options.csv, &HashMap::new())?;
+    args.push("all_varchar = true".to_string());
+    if options.csv.on_error != OnError::SkipRow {
+        args.push("store_rejects = true".to_string());
+    }
+    conn.execute(
+        &format!("CREATE TABLE {} AS SELECT * FROM read_csv({})", VALIDATION_TABLE, args.join(", ")),
+        [],
+    )?;
+
+    let rows_rejected: usize = conn.query_row("SELECT COUNT(*) FROM reject_errors", [], |row| row.get(0))?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT line, error_message FROM reject_errors ORDER BY line LIMIT {}",
+        MAX_PARSE_ERRORS
+    ))?;
+    let parse_errors = stmt
+        .query_map([], |row| {
+            Ok(format!("Line {}: {}", row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns = schema
+        .into_iter()
+        .map(|(name, data_type)| {
+            let conversion = conversion_sql(&quote_ident(&name), &data_type, &options.csv);
+            column_report(conn, name, data_type, Some(&conversion))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ValidationReport {
+        total_rows: table_rows(conn)?,
+        columns,
+        parse_errors,
+        rows_rejected,
+        ..Default::default()
+    })
+}
+
+/// Report on a file already loaded into the validation table by the regular
+/// importer. Its types come from the file, so only counts are collected.
+pub(super) fn validate_loaded(conn: &duckdb::Connection) -> Result<ValidationReport, ImportError> {
+    let columns = describe_columns(conn, VALIDATION_TABLE)?
+        .into_iter()
+        .map(|(name, data_type)| column_report(conn, name, data_type, None))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ValidationReport {
+        total_rows: table_rows(conn)?,
+        columns,
+        ..Default::default()
+    })
+}
+
+fn table_rows(conn: &duckdb::Connection) -> Result<usize, ImportError> {
+    Ok(conn.query_row(&format!("SELECT COUNT(*) FROM {}", VALIDATION_TABLE), [], |row| row.get(0))?)
+}
+
+/// Convert a text column the way the CSV reader would, yielding NULL for
+/// values that don't fit
+fn conversion_sql(column: &str, data_type: &str, options: &CsvImportOptions) -> String {
+    let format = match data_type.to_uppercase().as_str() {
+        "DATE" => options.date_format.as_ref(),
+        "TIMESTAMP" => options.timestamp_format.as_ref(),
+        _ => None,
+    };
+    match format {
+        Some(format) => format!(
+            "TRY_CAST(TRY_STRPTIME({}, '{}') AS {})",
+            column,
+            format.replace('\'', "''"),
+            data_type
+        ),
+        None => format!("TRY_CAST({} AS {})", column, data_type),
+    }
+}
+
+fn column_report(
+    conn: &duckdb::Connection,
+    name: String,
+    data_type: String,
+    conversion: Option<&str>,
+) -> Result<ColumnValidation, ImportError> {
+    let column = quote_ident(&name);
+    let invalid_filter = conversion
+        .map(|conversion| format!("{} IS NOT NULL AND {} IS NULL", column, conversion))
+        .unwrap_or_else(|| "false".to_string());
+
+    let (non_null, nulls, invalid_values): (usize, usize, usize) = conn.query_row(
+        &format!(
+            "SELECT COUNT({}), COUNT(*) - COUNT({}), COUNT(*) FILTER (WHERE {}) FROM {}",
+            column, column, invalid_filter, VALIDATION_TABLE
+        ),
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let invalid_samples = if invalid_values > 0 {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT DISTINCT CAST({} AS VARCHAR) FROM {} WHERE {} LIMIT {}",
+            column, VALIDATION_TABLE, invalid_filter, MAX_INVALID_SAMPLES
+        ))?;
+        let samples = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        samples.collect::<Result<Vec<_>, _>>()?
+    } else {
+        Vec::new()
+    };
+
+    Ok(ColumnValidation {
+        name,
+        data_type,
+        non_null,
+        nulls,
+        invalid_values,
+        invalid_samples,
+    })
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::error::AppError;
+use super::{ImportError, XmlImportOptions};
+
+/// Records flattened from an XML document: column names in order of first
+/// appearance and one value per column for every record
+pub(super) struct XmlRecords {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+impl XmlRecords {
+    /// Write the records, with a header row, to a CSV file DuckDB can sniff
+    pub fn write_csv(&self, path: &Path) -> Result<(), ImportError> {
+        let mut writer = csv::WriterBuilder::new().from_path(path)?;
+        writer.write_record(&self.columns)?;
+        for row in &self.rows {
+            let record: Vec<&str> = (0..self.columns.len())
+                .map(|i| row.get(i).and_then(|v| v.as_deref()).unwrap_or_default())
+                .collect();
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Parse an XML file and flatten every record element into a row
+pub(super) fn read_xml_records(
+    path: &PathBuf,
+    options: &XmlImportOptions,
+) -> Result<XmlRecords, ImportError> {
+    let text = std::fs::read_to_string(path)?;
+    let document = roxmltree::Document::parse(&text)
+        .map_err(|e| AppError::invalid_input(format!("Invalid XML: {}", e)))?;
+
+    let records = match options.record_path.as_deref().map(str::trim) {
+        Some(selector) if !selector.is_empty() => select_records(&document, selector),
+        _ => document.root_element().children().filter(|n| n.is_element()).collect(),
+    };
+    if records.is_empty() {
+        return Err(AppError::invalid_input("No XML records matched the selector").into());
+    }
+
+    let mut columns: Vec<String> = Vec::new();
+    let mut column_index: HashMap<String, usize> = HashMap::new();
+    let mut rows = Vec::with_capacity(records.len());
+
+    for record in records {
+        let mut fields = Vec::new();
+        flatten_element(record, "", &options.separator, &mut fields);
+
+        let mut row = vec![None; columns.len()];
+        for (name, value) in fields {
+            let index = *column_index.entry(name.clone()).or_insert_with(|| {
+                columns.push(name);
+                columns.len() - 1
+            });
+            if row.len() <= index {
+                row.resize(index + 1, None);
+            }
+            // Repeated child elements are kept together in one cell
+            row[index] = Some(match row[index].take() {
+                Some(existing) => format!("{}; {}", existing, value),
+                None => value,
+            });
+        }
+        rows.push(row);
+    }
+
+    Ok(XmlRecords { columns, rows })
+}
+
+/// Select record elements with a simple XPath-like path: `/feed/entry`
+/// matches from the document root, `//entry` or `//items/entry` match at any
+/// depth, and `*` matches any element name. Namespace prefixes are ignored.
+fn select_records<'a, 'input>(
+    document: &'a roxmltree::Document<'input>,
+    selector: &str,
+) -> Vec<roxmltree::Node<'a, 'input>> {
+    let anywhere = selector.starts_with("//");
+    let segments: Vec<&str> = selector
+        .trim_start_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    document
+        .descendants()
+        .filter(|node| node.is_element())
+        .filter(|node| {
+            let chain: Vec<&str> = node
+                .ancestors()
+                .filter(|n| n.is_element())
+                .map(|n| n.tag_name().name())
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .collect();
+            if chain.len() < segments.len() || (!anywhere && chain.len() != segments.len()) {
+                return false;
+            }
+            chain[chain.len() - segments.len()..]
+                .iter()
+                .zip(&segments)
+                .all(|(name, segment)| *segment == "*" || name == segment)
+        })
+        .collect()
+}
+
+/// Collect attributes and leaf text of an element as `(column, value)` pairs.
+/// Nested elements become `parent<sep>child` columns.
+fn flatten_element(
+    element: roxmltree::Node,
+    prefix: &str,
+    separator: &str,
+    fields: &mut Vec<(String, String)>,
+) {
+    let column = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}{}{}", prefix, separator, name)
+        }
+    };
+
+    for attribute in element.attributes() {
+        fields.push((column(attribute.name()), attribute.value().to_string()));
+    }
+
+    let children: Vec<_> = element.children().filter(|n| n.is_element()).collect();
+    if children.is_empty() {
+        let text = element.text().map(str::trim).unwrap_or_default();
+        if !text.is_empty() {
+            // A text-only record keeps its own tag as the column name
+            let name = if prefix.is_empty() {
+                element.tag_name().name().to_string()
+            } else {
+                prefix.to_string()
+            };
+            fields.push((name, text.to_string()));
+        }
+        return;
+    }
+
+    for child in children {
+        flatten_element(child, &column(child.tag_name().name()), separator, fields);
+    }
+}
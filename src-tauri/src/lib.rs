@@ -1,19 +1,75 @@
+pub mod autosave;
 pub mod duckdb_core;
+pub mod error;
 pub mod import;
 pub mod editor;
 pub mod statistics;
 pub mod export;
+pub mod metrics;
+pub mod environment;
+pub mod extensions;
+pub mod memory;
+pub mod dictionary;
+pub mod catalog;
+pub mod profiles;
+pub mod queue;
+pub mod recipes;
+pub mod session;
+pub mod sql_workbench;
+pub mod watcher;
 
 use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use error::AppError;
 
 pub struct AppState {
     pub db: Mutex<duckdb_core::DatabaseConnection>,
+    // Held outside the mutex so running statements can be interrupted
+    pub interrupt: duckdb_core::ConnectionInterrupt,
+    pub readers: duckdb_core::ReaderPool,
+    pub row_counts: duckdb_core::RowCounts,
+    pub query_cache: duckdb_core::QueryCache,
+    pub metrics: metrics::MetricsCollector,
+    pub queries: duckdb_core::QueryControl,
+    pub imports: import::ImportControl,
+    pub exports: export::ExportControl,
+    pub watchers: watcher::FolderWatchers,
+    pub import_queue: queue::ImportQueue,
+    pub undo: editor::UndoJournal,
 }
 
 impl AppState {
     pub fn new() -> Result<Self, anyhow::Error> {
+        let db = duckdb_core::DatabaseConnection::new()?;
+        let interrupt = duckdb_core::ConnectionInterrupt::new(db.get_connection());
+        let readers = duckdb_core::ReaderPool::new(db.get_connection());
+
         Ok(Self {
-            db: Mutex::new(duckdb_core::DatabaseConnection::new()?),
+            db: Mutex::new(db),
+            interrupt,
+            readers,
+            row_counts: duckdb_core::RowCounts::new(),
+            query_cache: duckdb_core::QueryCache::new(),
+            metrics: metrics::MetricsCollector::new(),
+            queries: duckdb_core::QueryControl::new(),
+            imports: import::ImportControl::new(),
+            exports: export::ExportControl::new(),
+            watchers: watcher::FolderWatchers::new(),
+            import_queue: queue::ImportQueue::new(),
+            undo: editor::UndoJournal::new(),
         })
     }
 }
+
+/// Run a command's DuckDB work on the blocking thread pool. DuckDB calls
+/// are synchronous, and running them on the async runtime's workers would
+/// stall unrelated commands (and the UI waiting on them) during long work.
+pub async fn run_blocking<T, F>(app: AppHandle, work: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce(State<'_, AppState>) -> Result<T, AppError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(move || work(app.state::<AppState>()))
+        .await
+        .map_err(|e| AppError::internal(format!("Background task failed: {}", e)))?
+}
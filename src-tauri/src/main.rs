@@ -1,17 +1,44 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use rats_lib::{AppState, duckdb_core, import, editor, statistics, export};
+use rats_lib::{AppState, autosave, duckdb_core, import, editor, statistics, export, metrics, environment, extensions, memory, dictionary, catalog, profiles, queue, recipes, session, sql_workbench, watcher};
 use tauri::Manager;
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_shell::init())
         .setup(|app| {
             let state = AppState::new().expect("Failed to initialize app state");
             app.manage(state);
+
+            if let Ok(dir) = app.path().resource_dir() {
+                duckdb_core::set_bundled_extension_dir(dir.join("extensions"));
+            }
+
+            if let Err(e) = duckdb_core::restore_database_settings(app.handle()) {
+                eprintln!("{}", e);
+            }
+            if let Err(e) = metrics::restore_metrics_setting(app.handle()) {
+                eprintln!("{}", e);
+            }
+
+            memory::start_monitor(app.handle().clone());
+            metrics::start_writer(app.handle().clone());
+
+            // A database file left open by a crash takes precedence over the saved session
+            match autosave::recover(app.handle()) {
+                Ok(Some(path)) => println!("Recovered database {}", path),
+                Ok(None) => match session::restore_on_launch(app.handle()) {
+                    Ok(Some(session)) => println!("Restored session from {}", session.directory),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("Failed to restore session: {}", e),
+                },
+                Err(e) => eprintln!("{}", e),
+            }
+            autosave::start(app.handle().clone());
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -23,20 +50,107 @@ fn main() {
                         println!("App cleanup completed");
                     }
                 }
+                let _ = autosave::mark_clean_shutdown(window.app_handle());
             }
         })
         .invoke_handler(tauri::generate_handler![
             // Import
             import::import_file,
             import::preview_file,
+            import::validate_file,
+            import::import_workbook,
+            import::list_excel_regions,
+            import::import_from_url,
+            import::import_log_file,
+            import::list_sqlite_tables,
+            import::import_sqlite_tables,
+            import::import_from_database,
+            import::cancel_import,
+            import::resume_import,
+            import::list_interrupted_imports,
+            import::discard_interrupted_import,
+            // Import profiles
+            profiles::list_import_profiles,
+            profiles::save_import_profile,
+            profiles::delete_import_profile,
+            profiles::import_with_profile,
+            recipes::list_export_recipes,
+            recipes::save_export_recipe,
+            recipes::delete_export_recipe,
+            recipes::run_export_recipe,
+            // Folder watch
+            watcher::watch_folder,
+            watcher::unwatch_folder,
+            watcher::list_watched_folders,
+            // Import queue
+            queue::enqueue_import,
+            queue::list_import_jobs,
+            queue::cancel_import_job,
             // Query
             duckdb_core::query_data,
+            duckdb_core::count_rows,
+            duckdb_core::query_arrow,
+            duckdb_core::query_stream,
+            duckdb_core::explain_query,
+            duckdb_core::execute_sql,
+            duckdb_core::cancel_query,
+            duckdb_core::list_query_results,
+            duckdb_core::release_query_result,
             duckdb_core::get_table_info,
             duckdb_core::drop_table,
+            duckdb_core::duplicate_table,
+            duckdb_core::set_s3_credentials,
+            duckdb_core::clear_s3_credentials,
+            duckdb_core::open_database,
+            duckdb_core::save_database_as,
+            duckdb_core::new_in_memory_database,
+            duckdb_core::attach_database,
+            duckdb_core::detach_database,
+            duckdb_core::get_database_settings,
+            duckdb_core::set_database_settings,
+            // SQL workbench
+            sql_workbench::run_script,
+            sql_workbench::run_saved_query,
+            sql_workbench::list_saved_queries,
+            sql_workbench::get_saved_query,
+            sql_workbench::save_query,
+            sql_workbench::delete_saved_query,
+            sql_workbench::get_schema_metadata,
+            // Session
+            session::save_session,
+            session::load_session,
+            session::get_session_settings,
+            session::set_restore_on_launch,
+            // Autosave
+            autosave::get_autosave_settings,
+            autosave::set_autosave_interval,
+            // Catalog
+            catalog::list_tables,
+            catalog::set_table_tags,
+            catalog::set_table_favorite,
+            catalog::set_table_group,
+            catalog::get_table_metadata,
             // Editor
             editor::reorder_rows,
+            editor::insert_rows,
+            editor::delete_rows,
+            editor::add_column,
+            editor::drop_column,
+            editor::rename_column,
+            editor::cast_column,
+            editor::find_replace,
+            editor::add_computed_column,
+            editor::split_column,
+            editor::merge_columns,
+            editor::transform_column,
+            editor::fill_nulls,
+            editor::deduplicate,
+            editor::undo,
+            editor::redo,
+            editor::get_undo_stack,
             // Statistics
             statistics::get_table_statistics,
+            statistics::export_statistics_report,
             statistics::aggregate_column,
             statistics::calculate_correlation,
             statistics::filter_data,
@@ -46,7 +160,38 @@ fn main() {
             export::export_to_csv,
             export::export_to_excel,
             export::export_query_to_csv,
+            export::export_query_result,
+            export::export_to_sqlite,
+            export::cancel_export,
+            export::copy_to_clipboard,
+            export::export_schema,
+            export::export_to_destination,
+            export::export_to_arrow,
+            // Metrics
+            metrics::set_metrics_enabled,
+            metrics::get_usage_metrics,
+            metrics::purge_usage_metrics,
+            // Environment
+            environment::get_environment_info,
+            // Memory
+            memory::get_memory_status,
+            memory::optimize_database,
+            // Extensions
+            extensions::list_extensions,
+            extensions::install_extension,
+            extensions::load_extension,
+            // Data dictionary
+            dictionary::get_data_dictionary,
+            dictionary::set_table_description,
+            dictionary::set_column_metadata,
+            dictionary::delete_column_metadata,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Quitting from the menu or the OS skips CloseRequested
+            if let tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit = event {
+                let _ = autosave::mark_clean_shutdown(app);
+            }
+        });
 }
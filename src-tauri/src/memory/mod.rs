@@ -0,0 +1,287 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use duckdb::Result as DuckResult;
+use crate::AppState;
+use crate::duckdb_core::{quote_ident, INTERNAL_TABLE_PREFIX};
+use crate::error::{AppError, ErrorContext};
+
+/// Share of the memory limit at which `memory-warning` is emitted
+const WARNING_RATIO: f64 = 0.9;
+
+/// Usage has to drop below this share before another warning is emitted
+const RESET_RATIO: f64 = 0.8;
+
+/// How often the background monitor checks memory usage
+const MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryComponent {
+    pub tag: String, // e.g. BASE_TABLE, HASH_TABLE, ORDER_BY
+    pub memory_usage: u64,
+    pub temp_storage: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableMemory {
+    pub name: String,
+    pub row_count: usize,
+    pub estimated_size: u64, // Approximate bytes in memory
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryStatus {
+    pub memory_usage: u64, // Bytes held by DuckDB's buffer manager
+    pub temp_storage: u64, // Bytes spilled to the temp directory
+    pub memory_limit: String,
+    pub memory_limit_bytes: Option<u64>,
+    pub usage_ratio: Option<f64>,
+    pub near_limit: bool,
+    pub database_size: String, // Size of the database file; 0 bytes in memory
+    pub wal_size: String,
+    pub components: Vec<MemoryComponent>, // Largest first
+    pub tables: Vec<TableMemory>,         // Largest first
+}
+
+/// Payload of the `memory-warning` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryWarning {
+    pub memory_usage: u64,
+    pub memory_limit: String,
+    pub usage_ratio: f64,
+}
+
+/// What `optimize_database` freed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizeResult {
+    pub dropped_tables: Vec<String>, // Kept query results and other temporary tables the app made
+    pub memory_before: u64,
+    pub memory_after: u64,
+    pub database_bytes_before: u64, // Size of the database file on disk; 0 in memory
+    pub database_bytes_after: u64,
+    pub wal_bytes_before: u64,
+    pub wal_bytes_after: u64,
+    pub reclaimed_bytes: u64, // How much the database file and WAL shrank together
+}
+
+/// Parse sizes as DuckDB prints them ("3.7 GiB", "512.0 MiB", "4GB")
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let split = size.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(size.len());
+    let value: f64 = size[..split].parse().ok()?;
+    let multiplier: f64 = match size[split..].trim().to_uppercase().as_str() {
+        "" | "B" | "BYTES" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KIB" => 1024.0,
+        "MIB" => 1024.0 * 1024.0,
+        "GIB" => 1024.0 * 1024.0 * 1024.0,
+        "TIB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((value * multiplier) as u64)
+}
+
+fn memory_components(conn: &duckdb::Connection) -> DuckResult<Vec<MemoryComponent>> {
+    let mut stmt = conn.prepare(
+        "SELECT tag, memory_usage_bytes, temporary_storage_bytes FROM duckdb_memory()
+         WHERE memory_usage_bytes > 0 OR temporary_storage_bytes > 0
+         ORDER BY memory_usage_bytes + temporary_storage_bytes DESC",
+    )?;
+    let components = stmt
+        .query_map([], |row| {
+            Ok(MemoryComponent {
+                tag: row.get(0)?,
+                memory_usage: row.get::<_, i64>(1)?.max(0) as u64,
+                temp_storage: row.get::<_, i64>(2)?.max(0) as u64,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(components)
+}
+
+struct Usage {
+    components: Vec<MemoryComponent>,
+    memory_usage: u64,
+    temp_storage: u64,
+    memory_limit: String,
+    usage_ratio: Option<f64>, // None when the limit can't be parsed
+}
+
+/// Memory and temp usage against the configured limit
+fn usage(conn: &duckdb::Connection) -> DuckResult<Usage> {
+    let components = memory_components(conn)?;
+    let memory_usage = components.iter().map(|c| c.memory_usage).sum();
+    let temp_storage = components.iter().map(|c| c.temp_storage).sum();
+    let memory_limit: String =
+        conn.query_row("SELECT current_setting('memory_limit')", [], |row| row.get(0))?;
+    let usage_ratio = parse_size(&memory_limit)
+        .filter(|limit| *limit > 0)
+        .map(|limit| memory_usage as f64 / limit as f64);
+    Ok(Usage { components, memory_usage, temp_storage, memory_limit, usage_ratio })
+}
+
+/// User tables by estimated size, from the row counts `duckdb_tables()` keeps
+fn table_sizes(conn: &duckdb::Connection) -> DuckResult<Vec<TableMemory>> {
+    let mut stmt = conn.prepare(
+        "SELECT table_name, estimated_size FROM duckdb_tables()
+         WHERE database_name = current_database() AND NOT internal AND NOT temporary",
+    )?;
+    let tables = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut sizes = Vec::new();
+    for (name, rows) in tables {
+        if name.starts_with(INTERNAL_TABLE_PREFIX) {
+            continue;
+        }
+        let row_count = rows.max(0) as usize;
+        sizes.push(TableMemory {
+            estimated_size: crate::catalog::estimated_table_size(conn, &name, row_count)?,
+            name,
+            row_count,
+        });
+    }
+    sizes.sort_by(|a, b| b.estimated_size.cmp(&a.estimated_size).then_with(|| a.name.cmp(&b.name)));
+    Ok(sizes)
+}
+
+fn memory_status(conn: &duckdb::Connection) -> DuckResult<MemoryStatus> {
+    let usage = usage(conn)?;
+    let (database_size, wal_size): (String, String) = conn.query_row(
+        "SELECT database_size, wal_size FROM pragma_database_size()
+         WHERE database_name = current_database()",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    Ok(MemoryStatus {
+        memory_usage: usage.memory_usage,
+        temp_storage: usage.temp_storage,
+        memory_limit_bytes: parse_size(&usage.memory_limit),
+        memory_limit: usage.memory_limit,
+        usage_ratio: usage.usage_ratio,
+        near_limit: usage.usage_ratio.is_some_and(|ratio| ratio >= WARNING_RATIO),
+        database_size,
+        wal_size,
+        components: usage.components,
+        tables: table_sizes(conn)?,
+    })
+}
+
+/// DuckDB's memory and temp-disk usage against the memory limit, broken
+/// down by component, with the estimated size of each user table
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_memory_status(app: AppHandle) -> Result<MemoryStatus, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        memory_status(reader.get_connection()).map_err(AppError::from)
+    })
+    .await
+}
+
+/// On-disk size of the database file and of its WAL; both 0 in memory
+fn storage(conn: &duckdb::Connection) -> DuckResult<(u64, u64)> {
+    let path: Option<String> = conn.query_row(
+        "SELECT path FROM duckdb_databases() WHERE database_name = current_database()",
+        [],
+        |row| row.get(0),
+    )?;
+    let Some(path) = path.filter(|p| !p.is_empty()) else {
+        return Ok((0, 0));
+    };
+    let size = |file: &str| std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+    Ok((size(&path), size(&format!("{}.wal", path))))
+}
+
+/// Temporary tables the app made on this connection, like kept query results
+fn app_temp_tables(conn: &duckdb::Connection) -> DuckResult<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT table_name FROM duckdb_tables() WHERE temporary AND starts_with(table_name, ?)
+         ORDER BY table_name",
+    )?;
+    let tables = stmt
+        .query_map([INTERNAL_TABLE_PREFIX], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tables)
+}
+
+fn optimize(conn: &duckdb::Connection) -> DuckResult<OptimizeResult> {
+    let memory_before = usage(conn)?.memory_usage;
+    let (database_bytes_before, wal_bytes_before) = storage(conn)?;
+
+    let dropped_tables = app_temp_tables(conn)?;
+    for table in &dropped_tables {
+        conn.execute_batch(&format!("DROP TABLE temp.main.{}", quote_ident(table)))?;
+    }
+    // VACUUM ANALYZE refreshes the statistics the planner uses; the
+    // checkpoint writes the WAL into the file and frees the blocks of
+    // deleted rows and dropped tables for reuse
+    conn.execute_batch("VACUUM ANALYZE")?;
+    conn.execute_batch("CHECKPOINT")?;
+
+    let memory_after = usage(conn)?.memory_usage;
+    let (database_bytes_after, wal_bytes_after) = storage(conn)?;
+    Ok(OptimizeResult {
+        dropped_tables,
+        memory_before,
+        memory_after,
+        database_bytes_before,
+        database_bytes_after,
+        wal_bytes_before,
+        wal_bytes_after,
+        // Net change, since the checkpoint moves the WAL into the file
+        reclaimed_bytes: (database_bytes_before + wal_bytes_before)
+            .saturating_sub(database_bytes_after + wal_bytes_after),
+    })
+}
+
+/// Free what a long session leaves behind: drop kept query results and
+/// other temporary tables the app made, refresh planner statistics and
+/// checkpoint, so blocks of deleted rows and dropped or swapped tables are
+/// released. Reports memory and file size before and after. Fails inside
+/// a transaction opened with `execute_sql`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn optimize_database(app: AppHandle) -> Result<OptimizeResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        let result = optimize(conn);
+        state.query_cache.clear();
+        state.metrics.record("optimize_database", started, None, result.is_ok());
+        result.context("Failed to optimize database")
+    })
+    .await
+}
+
+/// Check memory usage in the background and emit `memory-warning` when it
+/// reaches 90% of the memory limit. One warning is sent per excursion;
+/// usage has to fall back below 80% before the next.
+pub fn start_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut warned = false;
+        loop {
+            std::thread::sleep(MONITOR_INTERVAL);
+            let state = app.state::<AppState>();
+            let Ok(reader) = state.readers.get() else { continue };
+            let Ok(usage) = usage(reader.get_connection()) else { continue };
+            let Some(usage_ratio) = usage.usage_ratio else { continue };
+
+            if usage_ratio >= WARNING_RATIO && !warned {
+                warned = true;
+                let _ = app.emit("memory-warning", MemoryWarning {
+                    memory_usage: usage.memory_usage,
+                    memory_limit: usage.memory_limit,
+                    usage_ratio,
+                });
+            } else if usage_ratio < RESET_RATIO {
+                warned = false;
+            }
+        }
+    });
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+use crate::AppState;
+use crate::error::{AppError, ErrorContext};
+
+/// Internal table holding local usage metrics. Never transmitted anywhere.
+/// It lives in the open database, so recorded metrics are lost with an
+/// in-memory database; the opt-in itself is kept in `METRICS_FILE`.
+pub const METRICS_TABLE: &str = "_rats_usage_metrics";
+
+/// File in the app config directory remembering whether metrics are enabled
+const METRICS_FILE: &str = "metrics.json";
+
+/// How often recorded invocations are written to `METRICS_TABLE`
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Invocations kept waiting for a write at most; later ones are dropped
+const MAX_PENDING: usize = 10_000;
+
+/// A command invocation waiting to be written
+struct Sample {
+    command: String,
+    recorded_at: f64, // Seconds since the Unix epoch
+    duration_ms: f64,
+    rows: Option<i64>,
+    success: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct MetricsSettings {
+    enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandUsage {
+    pub command: String,
+    pub calls: i64,
+    pub failures: i64,
+    pub total_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub total_rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageMetrics {
+    pub enabled: bool,
+    pub commands: Vec<CommandUsage>,
+}
+
+/// Opt-in collector for command counts, durations and dataset sizes.
+/// Disabled by default; nothing is recorded until the user enables it.
+/// Commands only queue what they measure, as many run on read-only reader
+/// connections; the queue is written through the main connection by
+/// `start_writer` (and before metrics are read or the database changes).
+pub struct MetricsCollector {
+    enabled: AtomicBool,
+    pending: Mutex<Vec<Sample>>,
+}
+
+impl MetricsCollector {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Record a single command invocation. Failures to write metrics are
+    /// ignored so they can never affect the command being measured.
+    pub fn record(&self, command: &str, started: Instant, rows: Option<usize>, success: bool) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let sample = Sample {
+            command: command.to_string(),
+            recorded_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or_default(),
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+            rows: rows.map(|r| r as i64),
+            success,
+        };
+        if let Ok(mut pending) = self.pending.lock() {
+            if pending.len() < MAX_PENDING {
+                pending.push(sample);
+            }
+        }
+    }
+
+    /// Write the queued invocations through `conn`, the main connection
+    pub fn flush(&self, conn: &duckdb::Connection) {
+        let samples = match self.pending.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return,
+        };
+        if samples.is_empty() || ensure_metrics_table(conn).is_err() {
+            return;
+        }
+
+        let insert_query = format!(
+            "INSERT INTO {} (recorded_at, command, duration_ms, row_count, success)
+             VALUES (CAST(to_timestamp(?) AS TIMESTAMP), ?, ?, ?, ?)",
+            METRICS_TABLE
+        );
+        let Ok(mut stmt) = conn.prepare(&insert_query) else { return };
+        for sample in samples {
+            let _ = stmt.execute(duckdb::params![
+                sample.recorded_at,
+                sample.command,
+                sample.duration_ms,
+                sample.rows,
+                sample.success
+            ]);
+        }
+    }
+}
+
+impl Default for MetricsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn ensure_metrics_table(conn: &duckdb::Connection) -> duckdb::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            recorded_at TIMESTAMP DEFAULT current_timestamp,
+            command VARCHAR,
+            duration_ms DOUBLE,
+            row_count BIGINT,
+            success BOOLEAN
+        )",
+        METRICS_TABLE
+    ))
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .context("Failed to locate config directory")?;
+    Ok(dir.join(METRICS_FILE))
+}
+
+fn load_settings(app: &AppHandle) -> Result<MetricsSettings, AppError> {
+    let path = settings_path(app)?;
+    if !path.exists() {
+        return Ok(MetricsSettings::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .context("Failed to read metrics settings")?;
+    serde_json::from_str(&contents).context("Invalid metrics settings file")
+}
+
+fn save_settings(app: &AppHandle, settings: &MetricsSettings) -> Result<(), AppError> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context("Failed to create config directory")?;
+    }
+
+    let contents = serde_json::to_string_pretty(settings)?;
+    std::fs::write(&path, contents).context("Failed to save metrics settings")
+}
+
+/// Write recorded invocations every `FLUSH_INTERVAL`. A database busy
+/// with a long write is left until a later tick.
+pub fn start_writer(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        let state = app.state::<AppState>();
+        let Ok(db) = state.db.try_lock() else { continue };
+        state.metrics.flush(db.get_connection());
+    });
+}
+
+/// Turn metrics collection back on at startup when the user opted in before
+pub fn restore_metrics_setting(app: &AppHandle) -> Result<(), AppError> {
+    let settings = load_settings(app)?;
+    app.state::<AppState>().metrics.set_enabled(settings.enabled);
+    Ok(())
+}
+
+/// Enable or disable local usage metrics collection. The choice is saved
+/// and applies to later runs too.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_metrics_enabled(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<bool, AppError> {
+    save_settings(&app, &MetricsSettings { enabled })?;
+    state.metrics.set_enabled(enabled);
+    Ok(enabled)
+}
+
+/// Summarize recorded usage metrics per command
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_usage_metrics(
+    app: AppHandle,
+) -> Result<UsageMetrics, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        state.metrics.flush(conn);
+        ensure_metrics_table(conn)?;
+
+        let query = format!(
+            "SELECT
+                command,
+                COUNT(*) as calls,
+                COUNT(*) FILTER (WHERE NOT success) as failures,
+                SUM(duration_ms) as total_ms,
+                AVG(duration_ms) as avg_ms,
+                MAX(duration_ms) as max_ms,
+                COALESCE(SUM(row_count), 0)::BIGINT as total_rows
+            FROM {}
+            GROUP BY command
+            ORDER BY calls DESC",
+            METRICS_TABLE
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+        let commands = stmt
+            .query_map([], |row| {
+                Ok(CommandUsage {
+                    command: row.get(0)?,
+                    calls: row.get(1)?,
+                    failures: row.get(2)?,
+                    total_ms: row.get(3)?,
+                    avg_ms: row.get(4)?,
+                    max_ms: row.get(5)?,
+                    total_rows: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(UsageMetrics {
+            enabled: state.metrics.is_enabled(),
+            commands,
+        })
+    })
+    .await
+}
+
+/// Delete all recorded usage metrics
+#[tauri::command(rename_all = "camelCase")]
+pub async fn purge_usage_metrics(
+    app: AppHandle,
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        state.metrics.flush(conn);
+        conn.execute(&format!("DROP TABLE IF EXISTS {}", METRICS_TABLE), [])
+            .context("Failed to purge metrics")?;
+
+        Ok("Usage metrics purged".to_string())
+    })
+    .await
+}
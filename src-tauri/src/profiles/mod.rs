@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use crate::error::{AppError, ErrorContext};
+use crate::import::{
+    self, ColumnSelection, CsvImportOptions, ExcelImportOptions, ImportMode, ImportResult,
+    JsonImportOptions, XmlImportOptions,
+};
+
+/// File in the app config directory holding all saved profiles
+const PROFILES_FILE: &str = "import_profiles.json";
+
+/// A named, reusable import configuration for recurring files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportProfile {
+    pub name: String,
+    pub table_name: Option<String>, // Target table; defaults to the file name
+    pub mode: Option<ImportMode>,
+    pub csv_options: Option<CsvImportOptions>,
+    pub excel_options: Option<ExcelImportOptions>,
+    pub json_options: Option<JsonImportOptions>,
+    pub xml_options: Option<XmlImportOptions>,
+    pub column_types: Option<HashMap<String, String>>,
+    pub columns: Option<Vec<ColumnSelection>>,
+    pub key_columns: Option<Vec<String>>, // Used by the `append_new` mode
+    pub transpose: Option<bool>,
+}
+
+fn profiles_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .context("Failed to locate config directory")?;
+    Ok(dir.join(PROFILES_FILE))
+}
+
+fn load_profiles(app: &AppHandle) -> Result<Vec<ImportProfile>, AppError> {
+    let path = profiles_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .context("Failed to read import profiles")?;
+    serde_json::from_str(&contents).context("Invalid import profiles file")
+}
+
+fn save_profiles(app: &AppHandle, profiles: &[ImportProfile]) -> Result<(), AppError> {
+    let path = profiles_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context("Failed to create config directory")?;
+    }
+
+    let contents = serde_json::to_string_pretty(profiles)?;
+    std::fs::write(&path, contents).context("Failed to save import profiles")
+}
+
+/// List saved import profiles, sorted by name
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_import_profiles(app: AppHandle) -> Result<Vec<ImportProfile>, AppError> {
+    let mut profiles = load_profiles(&app)?;
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+/// Create or replace a profile with the same name
+#[tauri::command(rename_all = "camelCase")]
+pub async fn save_import_profile(
+    app: AppHandle,
+    profile: ImportProfile,
+) -> Result<ImportProfile, AppError> {
+    let name = profile.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Profile name cannot be empty"));
+    }
+    let profile = ImportProfile { name, ..profile };
+
+    let mut profiles = load_profiles(&app)?;
+    profiles.retain(|p| p.name != profile.name);
+    profiles.push(profile.clone());
+    save_profiles(&app, &profiles)?;
+
+    Ok(profile)
+}
+
+/// Delete a saved profile
+#[tauri::command(rename_all = "camelCase")]
+pub async fn delete_import_profile(app: AppHandle, name: String) -> Result<String, AppError> {
+    let mut profiles = load_profiles(&app)?;
+    let before = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == before {
+        return Err(AppError::not_found(format!("Import profile {} not found", name)));
+    }
+
+    save_profiles(&app, &profiles)?;
+    Ok(format!("Import profile {} deleted", name))
+}
+
+/// Import a file using the options stored in a saved profile. An explicit
+/// `table_name` overrides the profile's target table.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_with_profile(
+    app: AppHandle,
+    window: tauri::Window,
+    profile_name: String,
+    file_path: String,
+    table_name: Option<String>,
+) -> Result<ImportResult, AppError> {
+    let profile = load_profiles(&app)?
+        .into_iter()
+        .find(|p| p.name == profile_name)
+        .ok_or_else(|| AppError::not_found(format!("Import profile {} not found", profile_name)))?;
+
+    import::import_file(
+        app,
+        window,
+        file_path,
+        None,
+        table_name.or(profile.table_name),
+        profile.csv_options,
+        profile.excel_options,
+        profile.json_options,
+        profile.xml_options,
+        profile.column_types,
+        profile.columns,
+        None,
+        profile.mode,
+        profile.key_columns,
+        profile.transpose,
+    )
+    .await
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+use crate::AppState;
+use crate::error::AppError;
+use crate::import::{
+    self, CsvImportOptions, ExcelImportOptions, ImportMode, JsonImportOptions, XmlImportOptions,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// One file waiting in, or processed by, the import queue. Status changes
+/// are emitted as `import-job` events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportJob {
+    pub id: u64,
+    pub file_path: String,
+    pub table_name: Option<String>, // Defaults to the file name
+    pub status: JobStatus,
+    pub rows_imported: usize,
+    pub message: Option<String>,
+}
+
+/// Options shared by every job of one `enqueue_import` call
+#[derive(Debug, Clone)]
+struct JobSettings {
+    csv_options: Option<CsvImportOptions>,
+    excel_options: Option<ExcelImportOptions>,
+    json_options: Option<JsonImportOptions>,
+    xml_options: Option<XmlImportOptions>,
+    column_types: Option<HashMap<String, String>>,
+    mode: Option<ImportMode>,
+    key_columns: Option<Vec<String>>,
+}
+
+struct QueuedJob {
+    job: ImportJob,
+    settings: JobSettings,
+    cancel_requested: bool,
+}
+
+#[derive(Default)]
+struct QueueState {
+    jobs: Vec<QueuedJob>,
+    next_id: u64,
+    worker_running: bool,
+}
+
+/// Files waiting to be imported one after another by a background task
+#[derive(Default)]
+pub struct ImportQueue {
+    state: Mutex<QueueState>,
+}
+
+impl ImportQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the next queued job as running, or stop the worker when none is left
+    fn next_job(&self) -> Option<(ImportJob, JobSettings)> {
+        let mut state = self.state.lock().ok()?;
+        match state.jobs.iter_mut().find(|j| j.job.status == JobStatus::Queued) {
+            Some(queued) => {
+                queued.job.status = JobStatus::Running;
+                Some((queued.job.clone(), queued.settings.clone()))
+            }
+            None => {
+                state.worker_running = false;
+                None
+            }
+        }
+    }
+
+    fn finish_job(&self, id: u64, result: Result<(String, usize, String), String>) -> Option<ImportJob> {
+        let mut state = self.state.lock().ok()?;
+        let queued = state.jobs.iter_mut().find(|j| j.job.id == id)?;
+        match result {
+            Ok((table_name, rows_imported, message)) => {
+                queued.job.status = JobStatus::Completed;
+                queued.job.table_name = Some(table_name);
+                queued.job.rows_imported = rows_imported;
+                queued.job.message = Some(message);
+            }
+            Err(e) if queued.cancel_requested => {
+                queued.job.status = JobStatus::Cancelled;
+                queued.job.message = Some(e);
+            }
+            Err(e) => {
+                queued.job.status = JobStatus::Failed;
+                queued.job.message = Some(e);
+            }
+        }
+        Some(queued.job.clone())
+    }
+}
+
+async fn run_queue(window: tauri::Window) {
+    let state = window.state::<AppState>();
+    while let Some((job, settings)) = state.import_queue.next_job() {
+        let _ = window.emit("import-job", job.clone());
+
+        let result = import::import_file(
+            window.app_handle().clone(),
+            window.clone(),
+            job.file_path.clone(),
+            None,
+            job.table_name.clone(),
+            settings.csv_options,
+            settings.excel_options,
+            settings.json_options,
+            settings.xml_options,
+            settings.column_types,
+            None,
+            None,
+            settings.mode,
+            settings.key_columns,
+            None,
+        )
+        .await
+        .map(|result| (result.table_name, result.rows_imported, result.message))
+        .map_err(|e| e.message);
+
+        if let Some(finished) = state.import_queue.finish_job(job.id, result) {
+            let _ = window.emit("import-job", finished);
+        }
+    }
+}
+
+/// Queue files to be imported one at a time in the background. Each file
+/// becomes a job; with a `table_name`, every job loads into that table
+/// (appending by default), otherwise each file gets its own table.
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn enqueue_import(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+    file_paths: Vec<String>,
+    table_name: Option<String>,
+    csv_options: Option<CsvImportOptions>,
+    excel_options: Option<ExcelImportOptions>,
+    json_options: Option<JsonImportOptions>,
+    xml_options: Option<XmlImportOptions>,
+    column_types: Option<HashMap<String, String>>,
+    mode: Option<ImportMode>,
+    key_columns: Option<Vec<String>>,
+) -> Result<Vec<ImportJob>, AppError> {
+    if file_paths.is_empty() {
+        return Err(AppError::invalid_input("No files to import"));
+    }
+
+    let mode = mode.or(table_name.as_ref().map(|_| ImportMode::Append));
+    let settings = JobSettings {
+        csv_options,
+        excel_options,
+        json_options,
+        xml_options,
+        column_types,
+        mode,
+        key_columns,
+    };
+
+    let mut queue = state.import_queue.state.lock()?;
+    let mut jobs = Vec::with_capacity(file_paths.len());
+    for file_path in file_paths {
+        queue.next_id += 1;
+        let job = ImportJob {
+            id: queue.next_id,
+            file_path,
+            table_name: table_name.clone(),
+            status: JobStatus::Queued,
+            rows_imported: 0,
+            message: None,
+        };
+        jobs.push(job.clone());
+        queue.jobs.push(QueuedJob {
+            job,
+            settings: settings.clone(),
+            cancel_requested: false,
+        });
+    }
+
+    if !queue.worker_running {
+        queue.worker_running = true;
+        tauri::async_runtime::spawn(run_queue(window));
+    }
+
+    Ok(jobs)
+}
+
+/// List queued, running and finished import jobs in the order they were added
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_import_jobs(
+    state: State<'_, AppState>,
+) -> Result<Vec<ImportJob>, AppError> {
+    let queue = state.import_queue.state.lock()?;
+    Ok(queue.jobs.iter().map(|j| j.job.clone()).collect())
+}
+
+/// Cancel a queued job, or stop it if it is already running
+#[tauri::command(rename_all = "camelCase")]
+pub async fn cancel_import_job(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+    job_id: u64,
+) -> Result<ImportJob, AppError> {
+    let mut queue = state.import_queue.state.lock()?;
+    let queued = queue
+        .jobs
+        .iter_mut()
+        .find(|j| j.job.id == job_id)
+        .ok_or_else(|| AppError::not_found(format!("Import job {} not found", job_id)))?;
+
+    match queued.job.status {
+        JobStatus::Queued => {
+            queued.job.status = JobStatus::Cancelled;
+            let _ = window.emit("import-job", queued.job.clone());
+        }
+        JobStatus::Running => {
+            queued.cancel_requested = true;
+            if state.imports.cancel() {
+                state.interrupt.interrupt();
+            }
+        }
+        _ => return Err(AppError::invalid_input(format!("Import job {} has already finished", job_id))),
+    }
+
+    Ok(queued.job.clone())
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use crate::error::{AppError, ErrorContext};
+use crate::import::ColumnSelection;
+use crate::export::{
+    self, CsvCompression, CsvExportOptions, DestinationFormat, ExcelExportMode, ExcelExportOptions,
+    ExportDestination, ExportResult, SplitOptions,
+};
+
+/// File in the app config directory holding all saved recipes
+const RECIPES_FILE: &str = "export_recipes.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeFormat {
+    Csv,
+    Parquet,
+    Excel,
+    Arrow,
+}
+
+/// A named export that can be re-run to reproduce the same extract. Recipes
+/// never keep credentials: S3 recipes use the session's credentials from
+/// `set_s3_credentials`, and SFTP recipes a private key file or SSH agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRecipe {
+    pub name: String,
+    pub table_name: Option<String>, // Source table or view; set this or `query`
+    pub query: Option<String>,
+    pub columns: Option<Vec<ColumnSelection>>, // Columns to write, in order; defaults to all
+    pub format: RecipeFormat,
+    pub destination: ExportDestination,
+    pub include_header: Option<bool>,
+    pub compression: Option<CsvCompression>, // CSV and Parquet
+    pub csv_options: Option<CsvExportOptions>,
+    pub split: Option<SplitOptions>,
+    pub excel_options: Option<ExcelExportOptions>,
+    pub sheet_name: Option<String>,
+    pub excel_mode: Option<ExcelExportMode>,
+    pub write_metadata: Option<bool>, // Write a `.meta.json` sidecar next to local files
+}
+
+impl ExportRecipe {
+    /// Remove any S3 credentials, SFTP password and key passphrase from the
+    /// destination, returning whether there were any
+    fn strip_secrets(&mut self) -> bool {
+        match &mut self.destination {
+            ExportDestination::Local { .. } => false,
+            ExportDestination::S3 { credentials, .. } => credentials.take().is_some(),
+            ExportDestination::Sftp(destination) => {
+                let password = destination.password.take();
+                let passphrase = destination.passphrase.take();
+                password.is_some() || passphrase.is_some()
+            }
+        }
+    }
+}
+
+fn recipes_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .context("Failed to locate config directory")?;
+    Ok(dir.join(RECIPES_FILE))
+}
+
+fn load_recipes(app: &AppHandle) -> Result<Vec<ExportRecipe>, AppError> {
+    let path = recipes_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .context("Failed to read export recipes")?;
+    let mut recipes: Vec<ExportRecipe> =
+        serde_json::from_str(&contents).context("Invalid export recipes file")?;
+    // Files written before recipes dropped secrets may still hold some
+    let mut stripped = false;
+    for recipe in &mut recipes {
+        stripped |= recipe.strip_secrets();
+    }
+    if stripped {
+        save_recipes(app, &recipes)?;
+    }
+    Ok(recipes)
+}
+
+fn save_recipes(app: &AppHandle, recipes: &[ExportRecipe]) -> Result<(), AppError> {
+    let path = recipes_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context("Failed to create config directory")?;
+    }
+
+    let contents = serde_json::to_string_pretty(recipes)?;
+    std::fs::write(&path, contents).context("Failed to save export recipes")
+}
+
+/// List saved export recipes, sorted by name
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_export_recipes(app: AppHandle) -> Result<Vec<ExportRecipe>, AppError> {
+    let mut recipes = load_recipes(&app)?;
+    recipes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(recipes)
+}
+
+/// Create or replace a recipe with the same name. Credentials in its
+/// destination are dropped rather than saved.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn save_export_recipe(
+    app: AppHandle,
+    recipe: ExportRecipe,
+) -> Result<ExportRecipe, AppError> {
+    let name = recipe.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Recipe name cannot be empty"));
+    }
+    if recipe.table_name.is_some() == recipe.query.is_some() {
+        return Err(AppError::invalid_input("A recipe exports either a table or a query"));
+    }
+    let mut recipe = ExportRecipe { name, ..recipe };
+    recipe.strip_secrets();
+
+    let mut recipes = load_recipes(&app)?;
+    recipes.retain(|r| r.name != recipe.name);
+    recipes.push(recipe.clone());
+    save_recipes(&app, &recipes)?;
+
+    Ok(recipe)
+}
+
+/// Delete a saved recipe
+#[tauri::command(rename_all = "camelCase")]
+pub async fn delete_export_recipe(app: AppHandle, name: String) -> Result<String, AppError> {
+    let mut recipes = load_recipes(&app)?;
+    let before = recipes.len();
+    recipes.retain(|r| r.name != name);
+    if recipes.len() == before {
+        return Err(AppError::not_found(format!("Export recipe {} not found", name)));
+    }
+
+    save_recipes(&app, &recipes)?;
+    Ok(format!("Export recipe {} deleted", name))
+}
+
+/// Run a saved recipe. Local CSV, Excel and Arrow files use the regular
+/// exports with all their options; Parquet and remote destinations go
+/// through `export_to_destination`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn run_export_recipe(
+    app: AppHandle,
+    window: tauri::Window,
+    name: String,
+) -> Result<ExportResult, AppError> {
+    let recipe = load_recipes(&app)?
+        .into_iter()
+        .find(|r| r.name == name)
+        .ok_or_else(|| AppError::not_found(format!("Export recipe {} not found", name)))?;
+
+    let local_path = match &recipe.destination {
+        ExportDestination::Local { file_path } => Some(file_path.clone()),
+        _ => None,
+    };
+
+    match (recipe.format, local_path) {
+        (RecipeFormat::Csv, Some(file_path)) => match (recipe.table_name, recipe.query) {
+            (Some(table_name), _) => {
+                export::export_to_csv(
+                    app,
+                    window,
+                    table_name,
+                    file_path,
+                    recipe.include_header,
+                    None,
+                    recipe.compression,
+                    recipe.split,
+                    recipe.csv_options,
+                    recipe.columns,
+                    recipe.write_metadata,
+                )
+                .await
+            }
+            (None, Some(query)) => {
+                export::export_query_to_csv(
+                    app,
+                    window,
+                    query,
+                    file_path,
+                    recipe.include_header,
+                    recipe.compression,
+                    recipe.split,
+                    recipe.csv_options,
+                    recipe.columns,
+                    recipe.write_metadata,
+                )
+                .await
+            }
+            (None, None) => Err(AppError::invalid_input(format!("Export recipe {} has no table or query", name))),
+        },
+        (RecipeFormat::Csv, None) | (RecipeFormat::Parquet, _) => {
+            if recipe.csv_options.is_some() || recipe.split.is_some() {
+                return Err(AppError::invalid_input("CSV formatting and split options only apply to local CSV files"));
+            }
+            let format = match recipe.format {
+                RecipeFormat::Parquet => DestinationFormat::Parquet,
+                _ => DestinationFormat::Csv,
+            };
+            export::export_to_destination(
+                app,
+                recipe.table_name,
+                recipe.query,
+                format,
+                recipe.destination,
+                recipe.include_header,
+                recipe.compression,
+                recipe.columns,
+                recipe.write_metadata,
+            )
+            .await
+        }
+        (RecipeFormat::Excel | RecipeFormat::Arrow, None) => {
+            Err(AppError::invalid_input("Excel and Arrow recipes can only write local files"))
+        }
+        (RecipeFormat::Excel, Some(file_path)) => {
+            let table_name = recipe
+                .table_name
+                .ok_or_else(|| AppError::invalid_input("Excel recipes export a table or view, not a query"))?;
+            export::export_to_excel(
+                app,
+                window,
+                table_name,
+                file_path,
+                recipe.sheet_name,
+                None,
+                recipe.excel_options,
+                None,
+                recipe.excel_mode,
+                recipe.columns,
+                recipe.write_metadata,
+            )
+            .await
+        }
+        (RecipeFormat::Arrow, Some(file_path)) => {
+            let table_name = recipe
+                .table_name
+                .ok_or_else(|| AppError::invalid_input("Arrow recipes export a table or view, not a query"))?;
+            export::export_to_arrow(app, window, table_name, file_path, None, recipe.columns, recipe.write_metadata).await
+        }
+    }
+}
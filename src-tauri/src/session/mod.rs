@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use crate::AppState;
+use crate::error::{AppError, ErrorContext};
+use crate::duckdb_core::{self, DatabaseConnection, INTERNAL_TABLE_PREFIX};
+
+/// File in the app config directory remembering the last saved session
+const SESSION_FILE: &str = "session.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSettings {
+    pub last_session: Option<String>, // Project directory of the last save
+    pub restore_on_launch: bool,
+}
+
+/// What a saved or restored snapshot contains
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub directory: String,
+    pub tables: Vec<String>,
+    pub views: Vec<String>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .context("Failed to locate config directory")?;
+    Ok(dir.join(SESSION_FILE))
+}
+
+fn load_settings(app: &AppHandle) -> Result<SessionSettings, AppError> {
+    let path = settings_path(app)?;
+    if !path.exists() {
+        return Ok(SessionSettings::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .context("Failed to read session settings")?;
+    serde_json::from_str(&contents).context("Invalid session settings file")
+}
+
+fn save_settings(app: &AppHandle, settings: &SessionSettings) -> Result<(), AppError> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context("Failed to create config directory")?;
+    }
+
+    let contents = serde_json::to_string_pretty(settings)?;
+    std::fs::write(&path, contents).context("Failed to save session settings")
+}
+
+/// User tables and views in the database, app metadata tables excluded
+fn session_contents(conn: &duckdb::Connection, directory: &str) -> Result<SessionInfo, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT table_name, table_type FROM information_schema.tables
+             WHERE table_catalog = current_database() AND table_schema = 'main'
+             ORDER BY table_name",
+        )?;
+    let entries = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (views, tables): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .filter(|(name, _)| !name.starts_with(INTERNAL_TABLE_PREFIX))
+        .partition(|(_, table_type)| table_type == "VIEW");
+
+    Ok(SessionInfo {
+        directory: directory.to_string(),
+        tables: tables.into_iter().map(|(name, _)| name).collect(),
+        views: views.into_iter().map(|(name, _)| name).collect(),
+    })
+}
+
+/// Snapshot all tables and views into `directory` with DuckDB's
+/// `EXPORT DATABASE` (schema SQL plus one Parquet file per table). Data
+/// dictionary and catalog metadata are included. The directory is
+/// remembered for `load_session`; `restore_on_launch` reloads it whenever
+/// the app starts.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn save_session(
+    app: AppHandle,
+    directory: String,
+    restore_on_launch: Option<bool>,
+) -> Result<SessionInfo, AppError> {
+    let target = directory.clone();
+    let session = crate::run_blocking(app.clone(), move |state| {
+        let started = Instant::now();
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
+
+        std::fs::create_dir_all(&target)
+            .with_context(|| format!("Failed to create {}", target))?;
+        let exported = conn.execute_batch(&format!(
+            "EXPORT DATABASE '{}' (FORMAT PARQUET)",
+            target.replace('\'', "''")
+        ));
+        state.metrics.record("save_session", started, None, exported.is_ok());
+        exported.context("Failed to save session")?;
+
+        session_contents(conn, &target)
+    })
+    .await?;
+
+    let mut settings = load_settings(&app)?;
+    settings.last_session = Some(directory);
+    if let Some(restore_on_launch) = restore_on_launch {
+        settings.restore_on_launch = restore_on_launch;
+    }
+    save_settings(&app, &settings)?;
+
+    Ok(session)
+}
+
+/// Replace the open database with a new in-memory one holding the snapshot
+/// in `directory`, or in the last saved session when none is given
+#[tauri::command(rename_all = "camelCase")]
+pub async fn load_session(
+    app: AppHandle,
+    directory: Option<String>,
+) -> Result<SessionInfo, AppError> {
+    let directory = match directory {
+        Some(directory) => directory,
+        None => load_settings(&app)?
+            .last_session
+            .ok_or_else(|| AppError::not_found("No session has been saved yet"))?,
+    };
+    crate::run_blocking(app, move |state| restore(&state, &directory)).await
+}
+
+/// The last saved session and whether it is restored at startup
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_session_settings(app: AppHandle) -> Result<SessionSettings, AppError> {
+    load_settings(&app)
+}
+
+/// Choose whether the last saved session is restored when the app starts
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_restore_on_launch(app: AppHandle, enabled: bool) -> Result<SessionSettings, AppError> {
+    let mut settings = load_settings(&app)?;
+    settings.restore_on_launch = enabled;
+    save_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+/// Reload the last saved session at startup when the user asked for it
+pub fn restore_on_launch(app: &AppHandle) -> Result<Option<SessionInfo>, AppError> {
+    let settings = load_settings(app)?;
+    match settings.last_session {
+        Some(directory) if settings.restore_on_launch => {
+            let state = app.state::<AppState>();
+            restore(&state, &directory).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn restore(state: &AppState, directory: &str) -> Result<SessionInfo, AppError> {
+    let started = Instant::now();
+    if !Path::new(directory).join("schema.sql").exists() {
+        return Err(AppError::not_found(format!("{} does not contain a saved session", directory)));
+    }
+
+    // Import into a fresh database first so a broken snapshot leaves the
+    // current tables untouched
+    let next = DatabaseConnection::new()
+        .context("Failed to create database")?;
+    next.get_connection()
+        .execute_batch(&format!("IMPORT DATABASE '{}'", directory.replace('\'', "''")))
+        .context("Failed to load session")?;
+
+    let mut db = state.db.lock()?;
+    duckdb_core::switch_database(state, &mut db, next);
+    let conn = db.get_connection();
+
+    let session = session_contents(conn, directory)?;
+    state.metrics.record("load_session", started, None, true);
+    Ok(session)
+}
@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use crate::duckdb_core::{self, NumberMode, StatementResult, INTERNAL_TABLE_PREFIX, ROW_ID_COLUMN};
+use crate::error::{AppError, ErrorContext};
+
+/// File in the app config directory holding all saved queries
+const SAVED_QUERIES_FILE: &str = "saved_queries.json";
+
+/// A named SQL script kept for the SQL editor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQuery {
+    pub name: String,
+    pub sql: String,
+    pub description: Option<String>,
+}
+
+/// What running a script in the workbench returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptRun {
+    pub results: Vec<StatementResult>, // One per statement, in order
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaColumn {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaTable {
+    pub database: String,
+    pub schema: String,
+    pub name: String,
+    pub is_view: bool,
+    pub columns: Vec<SchemaColumn>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaFunction {
+    pub name: String,
+    pub function_type: String, // "scalar", "aggregate", "table", "macro", ...
+}
+
+/// Everything the SQL editor needs for autocomplete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaMetadata {
+    pub tables: Vec<SchemaTable>, // Tables and views of every attached database
+    pub functions: Vec<SchemaFunction>,
+    pub keywords: Vec<String>,
+}
+
+fn saved_queries_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .context("Failed to locate config directory")?;
+    Ok(dir.join(SAVED_QUERIES_FILE))
+}
+
+fn load_saved_queries(app: &AppHandle) -> Result<Vec<SavedQuery>, AppError> {
+    let path = saved_queries_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .context("Failed to read saved queries")?;
+    serde_json::from_str(&contents).context("Invalid saved queries file")
+}
+
+fn save_saved_queries(app: &AppHandle, queries: &[SavedQuery]) -> Result<(), AppError> {
+    let path = saved_queries_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context("Failed to create config directory")?;
+    }
+
+    let contents = serde_json::to_string_pretty(queries)?;
+    std::fs::write(&path, contents).context("Failed to save queries")
+}
+
+fn find_saved_query(app: &AppHandle, name: &str) -> Result<SavedQuery, AppError> {
+    load_saved_queries(app)?
+        .into_iter()
+        .find(|q| q.name == name)
+        .ok_or_else(|| AppError::not_found(format!("Saved query {} not found", name)))
+}
+
+/// Run a script of one or more statements and return a result set or
+/// change count for each, as `execute_sql` does, with the time it took
+#[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_script(
+    app: AppHandle,
+    sql: String,
+    max_rows: Option<usize>,
+    query_id: Option<String>,
+    timeout_secs: Option<u64>,
+    read_only: Option<bool>,
+    number_mode: Option<NumberMode>,
+    keep_results: Option<bool>,
+) -> Result<ScriptRun, AppError> {
+    let started = Instant::now();
+    let results = duckdb_core::execute_sql(
+        app,
+        sql,
+        max_rows,
+        query_id,
+        timeout_secs,
+        read_only,
+        number_mode,
+        keep_results,
+    )
+    .await?;
+    Ok(ScriptRun { results, elapsed_ms: started.elapsed().as_millis() as u64 })
+}
+
+/// Run a saved query by name
+#[tauri::command(rename_all = "camelCase")]
+pub async fn run_saved_query(
+    app: AppHandle,
+    name: String,
+    max_rows: Option<usize>,
+    query_id: Option<String>,
+    timeout_secs: Option<u64>,
+    number_mode: Option<NumberMode>,
+    keep_results: Option<bool>,
+) -> Result<ScriptRun, AppError> {
+    let query = find_saved_query(&app, &name)?;
+    run_script(app, query.sql, max_rows, query_id, timeout_secs, None, number_mode, keep_results).await
+}
+
+/// List saved queries, sorted by name
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_saved_queries(app: AppHandle) -> Result<Vec<SavedQuery>, AppError> {
+    let mut queries = load_saved_queries(&app)?;
+    queries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(queries)
+}
+
+/// A saved query by name, to load into the editor
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_saved_query(app: AppHandle, name: String) -> Result<SavedQuery, AppError> {
+    find_saved_query(&app, &name)
+}
+
+/// Create or replace a saved query with the same name
+#[tauri::command(rename_all = "camelCase")]
+pub async fn save_query(app: AppHandle, query: SavedQuery) -> Result<SavedQuery, AppError> {
+    let name = query.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Query name cannot be empty"));
+    }
+    if query.sql.trim().is_empty() {
+        return Err(AppError::invalid_input("Query SQL cannot be empty"));
+    }
+    let query = SavedQuery { name, ..query };
+
+    let mut queries = load_saved_queries(&app)?;
+    queries.retain(|q| q.name != query.name);
+    queries.push(query.clone());
+    save_saved_queries(&app, &queries)?;
+
+    Ok(query)
+}
+
+/// Delete a saved query
+#[tauri::command(rename_all = "camelCase")]
+pub async fn delete_saved_query(app: AppHandle, name: String) -> Result<String, AppError> {
+    let mut queries = load_saved_queries(&app)?;
+    let before = queries.len();
+    queries.retain(|q| q.name != name);
+    if queries.len() == before {
+        return Err(AppError::not_found(format!("Saved query {} not found", name)));
+    }
+
+    save_saved_queries(&app, &queries)?;
+    Ok(format!("Saved query {} deleted", name))
+}
+
+/// User tables and views of every attached database with their columns,
+/// app metadata tables excluded
+fn schema_tables(conn: &duckdb::Connection) -> duckdb::Result<Vec<SchemaTable>> {
+    let mut stmt = conn.prepare(
+        "SELECT c.database_name, c.schema_name, c.table_name, v.view_name IS NOT NULL,
+                c.column_name, c.data_type
+         FROM duckdb_columns() c
+         LEFT JOIN duckdb_views() v
+           ON v.database_name = c.database_name AND v.schema_name = c.schema_name AND v.view_name = c.table_name
+         WHERE NOT c.internal
+         ORDER BY c.database_name, c.schema_name, c.table_name, c.column_index",
+    )?;
+    let mut rows = stmt.query([])?;
+
+    let mut tables: Vec<SchemaTable> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let (database, schema, name): (String, String, String) = (row.get(0)?, row.get(1)?, row.get(2)?);
+        let column = SchemaColumn { name: row.get(4)?, data_type: row.get(5)? };
+        if name.starts_with(INTERNAL_TABLE_PREFIX) || column.name == ROW_ID_COLUMN {
+            continue;
+        }
+        match tables.last_mut() {
+            Some(table) if table.database == database && table.schema == schema && table.name == name => {
+                table.columns.push(column);
+            }
+            _ => tables.push(SchemaTable {
+                database,
+                schema,
+                name,
+                is_view: row.get(3)?,
+                columns: vec![column],
+            }),
+        }
+    }
+    Ok(tables)
+}
+
+fn schema_functions(conn: &duckdb::Connection) -> duckdb::Result<Vec<SchemaFunction>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT function_name, function_type FROM duckdb_functions()
+         WHERE NOT starts_with(function_name, '__')
+         ORDER BY function_name, function_type",
+    )?;
+    let functions = stmt
+        .query_map([], |row| Ok(SchemaFunction { name: row.get(0)?, function_type: row.get(1)? }))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(functions)
+}
+
+fn keywords(conn: &duckdb::Connection) -> duckdb::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT keyword_name FROM duckdb_keywords() ORDER BY keyword_name")?;
+    let keywords = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(keywords)
+}
+
+/// Tables, views and their columns, functions (built-in, from loaded
+/// extensions and user macros) and SQL keywords, for editor autocomplete
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_schema_metadata(app: AppHandle) -> Result<SchemaMetadata, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
+
+        let metadata = schema_tables(conn).and_then(|tables| {
+            Ok(SchemaMetadata {
+                tables,
+                functions: schema_functions(conn)?,
+                keywords: keywords(conn)?,
+            })
+        });
+        metadata.context("Failed to read schema")
+    })
+    .await
+}
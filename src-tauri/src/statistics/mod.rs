@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
-use tauri::State;
-use crate::AppState;
+use std::time::Instant;
+use tauri::AppHandle;
+use crate::dictionary::{self, ColumnMetadata};
+use crate::duckdb_core::{self, quote_ident, sql_literal, NumberMode, ValueEncoding, ROW_ID_COLUMN};
+use crate::error::{AppError, ErrorContext};
+use duckdb::types::Value;
 use duckdb::Result as DuckResult;
 
+mod report;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColumnStatistics {
     pub column_name: String,
@@ -18,6 +24,7 @@ pub struct ColumnStatistics {
     pub q25: Option<f64>,  // 25th percentile
     pub q75: Option<f64>,  // 75th percentile
     pub data_type: String,
+    pub metadata: Option<ColumnMetadata>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +32,7 @@ pub struct TableStatistics {
     pub table_name: String,
     pub total_rows: i64,
     pub total_columns: usize,
+    pub description: Option<String>,
     pub column_stats: Vec<ColumnStatistics>,
 }
 
@@ -33,40 +41,63 @@ pub struct AggregationResult {
     pub column_name: String,
     pub function: String,
     pub result: serde_json::Value,
+    pub encoding: ValueEncoding, // How `result` is encoded, e.g. a SUM of BIGINT as a string
 }
 
 /// Get comprehensive statistics for a table
 #[tauri::command(rename_all = "camelCase")]
 pub async fn get_table_statistics(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
-) -> Result<TableStatistics, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
+) -> Result<TableStatistics, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
+
+        let stats = table_statistics(conn, table_name)?;
+
+        state.metrics.record(
+            "get_table_statistics",
+            started,
+            Some(stats.total_rows as usize),
+            true,
+        );
+
+        Ok(stats)
+    })
+    .await
+}
 
+fn table_statistics(conn: &duckdb::Connection, table_name: String) -> Result<TableStatistics, AppError> {
     // Get total row count
-    let count_query = format!("SELECT COUNT(*) FROM {}", table_name);
+    let count_query = format!("SELECT COUNT(*) FROM {}", quote_ident(&table_name));
     let total_rows: i64 = conn
-        .query_row(&count_query, [], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
+        .query_row(&count_query, [], |row| row.get(0))?;
 
     // Get column information
-    let describe_query = format!("DESCRIBE {}", table_name);
-    let mut stmt = conn.prepare(&describe_query).map_err(|e| e.to_string())?;
-    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+    let describe_query = format!("DESCRIBE {}", quote_ident(&table_name));
+    let mut stmt = conn.prepare(&describe_query)?;
+    let mut rows = stmt.query([])?;
+
+    let dictionary = dictionary::load_dictionary(conn, &table_name)?;
 
     let mut column_stats = Vec::new();
 
-    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let column_name: String = row.get(0).map_err(|e| e.to_string())?;
-        let data_type: String = row.get(1).map_err(|e| e.to_string())?;
+    while let Some(row) = rows.next()? {
+        let column_name: String = row.get(0)?;
+        let data_type: String = row.get(1)?;
+        if column_name == ROW_ID_COLUMN {
+            continue;
+        }
 
-        let stats = calculate_column_statistics(
+        let mut stats = calculate_column_statistics(
             conn,
             &table_name,
             &column_name,
             &data_type,
-        ).map_err(|e| e.to_string())?;
+        )?;
+        stats.metadata = dictionary.column(&column_name).cloned();
 
         column_stats.push(stats);
     }
@@ -75,6 +106,7 @@ pub async fn get_table_statistics(
         table_name,
         total_rows,
         total_columns: column_stats.len(),
+        description: dictionary.description,
         column_stats,
     })
 }
@@ -93,42 +125,39 @@ fn calculate_column_statistics(
         || data_type.contains("NUMERIC");
 
     // Basic statistics query
+    let column = quote_ident(column_name);
+    let table = quote_ident(table_name);
     let stats_query = if is_numeric {
         format!(
             "SELECT
-                COUNT(\"{}\") as count,
-                COUNT(*) - COUNT(\"{}\") as null_count,
-                COUNT(DISTINCT \"{}\") as distinct_count,
-                MIN(\"{}\")::VARCHAR as min_val,
-                MAX(\"{}\")::VARCHAR as max_val,
-                AVG(\"{}\") as mean,
-                MEDIAN(\"{}\") as median,
-                STDDEV_POP(\"{}\") as std_dev,
-                VAR_POP(\"{}\") as variance,
-                PERCENTILE_CONT(0.25) WITHIN GROUP (ORDER BY \"{}\") as q25,
-                PERCENTILE_CONT(0.75) WITHIN GROUP (ORDER BY \"{}\") as q75
-            FROM {}",
-            column_name, column_name, column_name, column_name, column_name,
-            column_name, column_name, column_name, column_name, column_name, column_name,
-            table_name
+                COUNT({column}) as count,
+                COUNT(*) - COUNT({column}) as null_count,
+                COUNT(DISTINCT {column}) as distinct_count,
+                MIN({column})::VARCHAR as min_val,
+                MAX({column})::VARCHAR as max_val,
+                AVG({column}) as mean,
+                MEDIAN({column}) as median,
+                STDDEV_POP({column}) as std_dev,
+                VAR_POP({column}) as variance,
+                PERCENTILE_CONT(0.25) WITHIN GROUP (ORDER BY {column}) as q25,
+                PERCENTILE_CONT(0.75) WITHIN GROUP (ORDER BY {column}) as q75
+            FROM {table}"
         )
     } else {
         format!(
             "SELECT
-                COUNT(\"{}\") as count,
-                COUNT(*) - COUNT(\"{}\") as null_count,
-                COUNT(DISTINCT \"{}\") as distinct_count,
-                MIN(\"{}\")::VARCHAR as min_val,
-                MAX(\"{}\")::VARCHAR as max_val,
+                COUNT({column}) as count,
+                COUNT(*) - COUNT({column}) as null_count,
+                COUNT(DISTINCT {column}) as distinct_count,
+                MIN({column})::VARCHAR as min_val,
+                MAX({column})::VARCHAR as max_val,
                 NULL as mean,
                 NULL as median,
                 NULL as std_dev,
                 NULL as variance,
                 NULL as q25,
                 NULL as q75
-            FROM {}",
-            column_name, column_name, column_name, column_name, column_name,
-            table_name
+            FROM {table}"
         )
     };
 
@@ -166,226 +195,356 @@ fn calculate_column_statistics(
             q25,
             q75,
             data_type: data_type.to_string(),
+            metadata: None,
         })
     } else {
         Err(duckdb::Error::QueryReturnedNoRows)
     }
 }
 
+/// How often one value occurs in a column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueCount {
+    pub value: Option<String>, // None counts the NULLs
+    pub count: i64,
+}
+
+/// The most frequent values of a column, most common first
+fn top_values(
+    conn: &duckdb::Connection,
+    table_name: &str,
+    column_name: &str,
+    limit: usize,
+) -> DuckResult<Vec<ValueCount>> {
+    let query = format!(
+        "SELECT {}::VARCHAR AS value, COUNT(*) AS count FROM {}
+         GROUP BY 1 ORDER BY count DESC, value NULLS LAST LIMIT {}",
+        quote_ident(column_name),
+        quote_ident(table_name),
+        limit
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let values = stmt
+        .query_map([], |row| Ok(ValueCount { value: row.get(0)?, count: row.get(1)? }))?
+        .collect::<DuckResult<Vec<_>>>()?;
+    Ok(values)
+}
+
+/// Render a self-contained HTML profiling report for a table: the statistics
+/// from `get_table_statistics` plus the most frequent values of each column.
+/// With `pdf_path`, a plain-text PDF version is written as well.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_statistics_report(
+    app: AppHandle,
+    table_name: String,
+    file_path: String,
+    pdf_path: Option<String>,
+    top_value_count: Option<usize>,
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let started = Instant::now();
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
+
+        let stats = table_statistics(conn, table_name)?;
+        let value_counts = stats
+            .column_stats
+            .iter()
+            .map(|column| top_values(conn, &stats.table_name, &column.column_name, top_value_count.unwrap_or(10)))
+            .collect::<DuckResult<Vec<_>>>()?;
+        let generated: String = conn
+            .query_row("SELECT strftime(now(), '%Y-%m-%d %H:%M')", [], |row| row.get(0))?;
+
+        std::fs::write(&file_path, report::render_html(&stats, &value_counts, &generated))
+            .with_context(|| format!("Failed to write {}", file_path))?;
+        if let Some(pdf_path) = &pdf_path {
+            std::fs::write(pdf_path, report::render_pdf(&stats, &value_counts, &generated))
+                .with_context(|| format!("Failed to write {}", pdf_path))?;
+        }
+
+        state.metrics.record(
+            "export_statistics_report",
+            started,
+            Some(stats.total_rows as usize),
+            true,
+        );
+
+        Ok(format!("Wrote profiling report for {} to {}", stats.table_name, file_path))
+    })
+    .await
+}
+
 /// Perform aggregation on a column
 #[tauri::command(rename_all = "camelCase")]
 pub async fn aggregate_column(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
     column_name: String,
     function: String, // "SUM", "AVG", "COUNT", "MIN", "MAX"
-) -> Result<AggregationResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
-
-    let func_upper = function.to_uppercase();
-    let query = format!(
-        "SELECT {}(\"{}\") FROM {}",
-        func_upper, column_name, table_name
-    );
-
-    let result: serde_json::Value = conn
-        .query_row(&query, [], |row| {
-            // Try different types
-            if let Ok(val) = row.get::<_, i64>(0) {
-                Ok(serde_json::Value::Number(val.into()))
-            } else if let Ok(val) = row.get::<_, f64>(0) {
-                Ok(serde_json::Number::from_f64(val)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::Null))
-            } else if let Ok(val) = row.get::<_, String>(0) {
-                Ok(serde_json::Value::String(val))
-            } else {
-                Ok(serde_json::Value::Null)
-            }
+    number_mode: Option<NumberMode>,
+) -> Result<AggregationResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
+        let number_mode = number_mode.unwrap_or_default();
+
+        let func_upper = aggregate_function(&function)?;
+        let aggregate = format!("{}({})", func_upper, quote_ident(&column_name));
+        let query = format!(
+            "SELECT {aggregate}, typeof({aggregate}) FROM {}",
+            quote_ident(&table_name)
+        );
+
+        // Sums of BIGINT are HUGEINT and sums of DECIMAL stay DECIMAL, so
+        // the value is converted like query rows instead of through f64
+        let (result, data_type) = conn
+            .query_row(&query, [], |row| {
+                Ok((duckdb_core::json_value(row.get_ref(0)?, number_mode), row.get::<_, String>(1)?))
+            })?;
+
+        Ok(AggregationResult {
+            column_name,
+            function: func_upper,
+            result,
+            encoding: duckdb_core::value_encoding(&data_type, number_mode),
         })
-        .map_err(|e| e.to_string())?;
-
-    Ok(AggregationResult {
-        column_name,
-        function: func_upper,
-        result,
     })
+    .await
 }
 
 /// Get correlation between two numeric columns
 #[tauri::command(rename_all = "camelCase")]
 pub async fn calculate_correlation(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
     column_x: String,
     column_y: String,
-) -> Result<f64, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
+) -> Result<f64, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+        let conn = reader.get_connection();
 
-    let query = format!(
-        "SELECT CORR(\"{}\", \"{}\") FROM {}",
-        column_x, column_y, table_name
-    );
+        let query = format!(
+            "SELECT CORR({}, {}) FROM {}",
+            quote_ident(&column_x), quote_ident(&column_y), quote_ident(&table_name)
+        );
 
-    let correlation: f64 = conn
-        .query_row(&query, [], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
+        let correlation: f64 = conn
+            .query_row(&query, [], |row| row.get(0))?;
 
-    Ok(correlation)
+        Ok(correlation)
+    })
+    .await
 }
 
 /// Create a filtered view for virtual scrolling
 #[tauri::command(rename_all = "camelCase")]
 pub async fn create_filtered_view(
-    state: State<'_, AppState>,
+    app: AppHandle,
     source_table: String,
     view_name: String,
     conditions: Vec<FilterCondition>,
-) -> Result<String, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let conn = db.get_connection();
-
-    // Drop existing view if it exists
-    let drop_query = format!("DROP VIEW IF EXISTS {}", view_name);
-    conn.execute(&drop_query, [])
-        .map_err(|e| format!("Failed to drop view: {}", e))?;
+) -> Result<String, AppError> {
+    crate::run_blocking(app, move |state| {
+        let db = state.db.lock()?;
+        let conn = db.get_connection();
 
-    // Build WHERE clause
-    let where_clauses: Vec<String> = conditions
-        .iter()
-        .map(|c| build_condition_clause(c))
-        .collect();
+        // Drop existing view if it exists
+        let drop_query = format!("DROP VIEW IF EXISTS {}", quote_ident(&view_name));
+        conn.execute(&drop_query, [])
+            .context("Failed to drop view")?;
 
-    let where_clause = if where_clauses.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", where_clauses.join(" AND "))
-    };
+        // Views can't take bound parameters, so values are inlined as quoted literals
+        let where_clause = build_where_clause(&conditions, sql_value)?;
 
-    // Create view
-    let create_query = format!(
-        "CREATE VIEW {} AS SELECT * FROM {} {}",
-        view_name, source_table, where_clause
-    );
+        // Create view
+        let create_query = format!(
+            "CREATE VIEW {} AS SELECT * FROM {} {}",
+            quote_ident(&view_name), quote_ident(&source_table), where_clause
+        );
 
-    conn.execute(&create_query, [])
-        .map_err(|e| format!("Failed to create filtered view: {}", e))?;
+        conn.execute(&create_query, [])
+            .context("Failed to create filtered view")?;
 
-    Ok(view_name)
+        Ok(view_name)
+    })
+    .await
 }
 
 /// Filter data based on conditions (legacy - now creates filtered view)
 #[tauri::command(rename_all = "camelCase")]
 pub async fn filter_data(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
     conditions: Vec<FilterCondition>,
     limit: Option<usize>,
     offset: Option<usize>,
-) -> Result<crate::duckdb_core::QueryResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    number_mode: Option<NumberMode>,
+) -> Result<crate::duckdb_core::QueryResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+
+        let limit = limit.unwrap_or(1000);
+        let offset = offset.unwrap_or(0);
+
+        // Build WHERE clause, binding the filter values
+        let mut params = Vec::new();
+        let where_clause = build_where_clause(&conditions, |value| {
+            params.push(bind_value(value));
+            "?".to_string()
+        })?;
+
+        let table = quote_ident(&table_name);
+        let query = format!(
+            "SELECT * FROM {} {} LIMIT {} OFFSET {}",
+            table, where_clause, limit, offset
+        );
+
+        let number_mode = number_mode.unwrap_or_default();
+        crate::duckdb_core::execute_query(reader.get_connection(), &query, &params, &table, number_mode)
+            .context("Filter error")
+    })
+    .await
+}
 
-    let limit = limit.unwrap_or(1000);
-    let offset = offset.unwrap_or(0);
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterCondition {
+    pub column: String,
+    pub operator: String, // "=", "!=", ">", "<", ">=", "<=", "LIKE", "IN"
+    pub value: serde_json::Value,
+}
 
-    // Build WHERE clause
-    let where_clauses: Vec<String> = conditions
+/// `WHERE` clause joining `conditions` with AND, or nothing without any.
+/// `value` renders each filter value into the SQL, as a placeholder or a
+/// literal.
+pub(crate) fn build_where_clause(
+    conditions: &[FilterCondition],
+    mut value: impl FnMut(&serde_json::Value) -> String,
+) -> Result<String, AppError> {
+    let clauses = conditions
         .iter()
-        .map(|c| build_condition_clause(c))
-        .collect();
+        .map(|c| build_condition_clause(c, &mut value))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let where_clause = if where_clauses.is_empty() {
-        String::new()
+    if clauses.is_empty() {
+        Ok(String::new())
     } else {
-        format!("WHERE {}", where_clauses.join(" AND "))
-    };
-
-    let query = format!(
-        "SELECT * FROM {} {} LIMIT {} OFFSET {}",
-        table_name, where_clause, limit, offset
-    );
+        Ok(format!("WHERE {}", clauses.join(" AND ")))
+    }
+}
 
-    db.execute_query(&query)
-        .map_err(|e| format!("Filter error: {}", e))
+fn build_condition_clause(
+    condition: &FilterCondition,
+    value: &mut impl FnMut(&serde_json::Value) -> String,
+) -> Result<String, AppError> {
+    let column = quote_ident(&condition.column);
+    let operator = condition.operator.to_uppercase();
+
+    match (operator.as_str(), &condition.value) {
+        ("IN", serde_json::Value::Array(items)) if items.is_empty() => Ok("FALSE".to_string()),
+        ("IN", serde_json::Value::Array(items)) => {
+            let items: Vec<String> = items.iter().map(&mut *value).collect();
+            Ok(format!("{} IN ({})", column, items.join(", ")))
+        }
+        ("IN", _) => Err(AppError::invalid_input(format!("IN filter on {} needs a list of values", condition.column))),
+        ("=" | "!=" | ">" | "<" | ">=" | "<=" | "LIKE", filter_value) => {
+            Ok(format!("{} {} {}", column, operator, value(filter_value)))
+        }
+        _ => Err(AppError::invalid_input(format!("Unsupported filter operator: {}", condition.operator))),
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FilterCondition {
-    pub column: String,
-    pub operator: String, // "=", "!=", ">", "<", ">=", "<=", "LIKE", "IN"
-    pub value: serde_json::Value,
+/// A filter value as a DuckDB parameter
+pub(crate) fn bind_value(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Boolean(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::BigInt(i),
+            None => n.as_f64().map(Value::Double).unwrap_or(Value::Null),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
 }
 
-fn build_condition_clause(condition: &FilterCondition) -> String {
-    let value_str = match &condition.value {
-        serde_json::Value::String(s) => format!("'{}'", s.replace("'", "''")),
-        serde_json::Value::Number(n) => n.to_string(),
-        serde_json::Value::Bool(b) => b.to_string(),
+/// A filter value as a quoted SQL literal
+pub(crate) fn sql_value(value: &serde_json::Value) -> String {
+    match value {
         serde_json::Value::Null => "NULL".to_string(),
-        serde_json::Value::Array(arr) => {
-            let items: Vec<String> = arr
-                .iter()
-                .map(|v| match v {
-                    serde_json::Value::String(s) => format!("'{}'", s.replace("'", "''")),
-                    serde_json::Value::Number(n) => n.to_string(),
-                    _ => "NULL".to_string(),
-                })
-                .collect();
-            format!("({})", items.join(", "))
-        }
-        _ => "NULL".to_string(),
-    };
+        serde_json::Value::Bool(b) => b.to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => sql_literal(s),
+        other => sql_literal(&other.to_string()),
+    }
+}
 
-    match condition.operator.to_uppercase().as_str() {
-        "IN" => format!("\"{}\" IN {}", condition.column, value_str),
-        "LIKE" => format!("\"{}\" LIKE {}", condition.column, value_str),
-        _ => format!("\"{}\" {} {}", condition.column, condition.operator, value_str),
+/// The aggregate function to run, from the ones offered in the UI
+fn aggregate_function(function: &str) -> Result<String, AppError> {
+    let upper = function.to_uppercase();
+    match upper.as_str() {
+        "SUM" | "AVG" | "COUNT" | "MIN" | "MAX" | "MEDIAN" | "MODE" | "STDDEV" | "STDDEV_POP"
+        | "STDDEV_SAMP" | "VARIANCE" | "VAR_POP" | "VAR_SAMP" => Ok(upper),
+        // Shorthand used by the aggregation dialog
+        "VAR" => Ok("VARIANCE".to_string()),
+        _ => Err(AppError::invalid_input(format!("Unsupported aggregate function: {}", function))),
     }
 }
 
 /// Group by and aggregate
 #[tauri::command(rename_all = "camelCase")]
 pub async fn group_and_aggregate(
-    state: State<'_, AppState>,
+    app: AppHandle,
     table_name: String,
     group_by_columns: Vec<String>,
     aggregations: Vec<AggregationSpec>,
-) -> Result<crate::duckdb_core::QueryResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-
-    // Build GROUP BY clause
-    let group_cols: Vec<String> = group_by_columns
-        .iter()
-        .map(|c| format!("\"{}\"", c))
-        .collect();
-
-    // Build aggregation SELECT clause
-    let agg_cols: Vec<String> = aggregations
-        .iter()
-        .map(|a| format!("{}(\"{}\") as \"{}\"", a.function, a.column, a.alias))
-        .collect();
-
-    let select_clause = if group_cols.is_empty() {
-        agg_cols.join(", ")
-    } else {
-        format!("{}, {}", group_cols.join(", "), agg_cols.join(", "))
-    };
-
-    let query = if group_cols.is_empty() {
-        format!("SELECT {} FROM {}", select_clause, table_name)
-    } else {
-        format!(
-            "SELECT {} FROM {} GROUP BY {}",
-            select_clause,
-            table_name,
-            group_cols.join(", ")
-        )
-    };
-
-    db.execute_query(&query)
-        .map_err(|e| format!("Aggregation error: {}", e))
+    number_mode: Option<NumberMode>,
+) -> Result<crate::duckdb_core::QueryResult, AppError> {
+    crate::run_blocking(app, move |state| {
+        let reader = state.readers.get()?;
+
+        // Build GROUP BY clause
+        let group_cols: Vec<String> = group_by_columns
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect();
+
+        // Build aggregation SELECT clause
+        let agg_cols: Vec<String> = aggregations
+            .iter()
+            .map(|a| {
+                Ok(format!(
+                    "{}({}) as {}",
+                    aggregate_function(&a.function)?,
+                    quote_ident(&a.column),
+                    quote_ident(&a.alias)
+                ))
+            })
+            .collect::<Result<_, AppError>>()?;
+
+        let select_clause = if group_cols.is_empty() {
+            agg_cols.join(", ")
+        } else {
+            format!("{}, {}", group_cols.join(", "), agg_cols.join(", "))
+        };
+
+        let table = quote_ident(&table_name);
+        let query = if group_cols.is_empty() {
+            format!("SELECT {} FROM {}", select_clause, table)
+        } else {
+            format!(
+                "SELECT {} FROM {} GROUP BY {}",
+                select_clause,
+                table,
+                group_cols.join(", ")
+            )
+        };
+
+        let number_mode = number_mode.unwrap_or_default();
+        crate::duckdb_core::execute_query(reader.get_connection(), &query, &[], &table, number_mode)
+            .context("Aggregation error")
+    })
+    .await
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,264 @@
+use super::{ColumnStatistics, TableStatistics, ValueCount};
+
+/// Characters per line and lines per page of the PDF report (Courier 9pt on A4)
+const PDF_LINE_CHARS: usize = 95;
+const PDF_PAGE_LINES: usize = 66;
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.4}", value)
+    }
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(format_number).unwrap_or_default()
+}
+
+fn format_value(value: &Option<serde_json::Value>) -> String {
+    match value {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    }
+}
+
+fn percent(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Label/value pairs shown for every column
+fn column_facts(column: &ColumnStatistics, total_rows: i64) -> Vec<(&'static str, String)> {
+    let mut facts = vec![
+        ("Type", column.data_type.clone()),
+        ("Non-null", column.count.to_string()),
+        ("Nulls", format!("{} ({:.1}%)", column.null_count, percent(column.null_count, total_rows))),
+        ("Distinct", column.distinct_count.to_string()),
+        ("Min", format_value(&column.min)),
+        ("Max", format_value(&column.max)),
+    ];
+    if column.mean.is_some() {
+        facts.extend([
+            ("Mean", format_optional(column.mean)),
+            ("Median", format_optional(column.median)),
+            ("Std dev", format_optional(column.std_dev)),
+            ("25th percentile", format_optional(column.q25)),
+            ("75th percentile", format_optional(column.q75)),
+        ]);
+    }
+    facts
+}
+
+/// Render the profiling report as a single HTML file with inline styles and
+/// no external resources, so it can be mailed or archived as is
+pub(super) fn render_html(stats: &TableStatistics, value_counts: &[Vec<ValueCount>], generated: &str) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>Data profile: {}</title>\n", escape_html(&stats.table_name)));
+    html.push_str(
+        "<style>
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem; color: #222; }
+h1 { margin-bottom: 0.2rem; }
+.meta { color: #666; margin-top: 0; }
+table { border-collapse: collapse; margin: 0.5rem 0 1.5rem; }
+th, td { border: 1px solid #ddd; padding: 0.3rem 0.6rem; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+section { border-top: 2px solid #eee; padding-top: 0.5rem; }
+.bar { background: #4a90d9; height: 0.8rem; }
+.description { font-style: italic; }
+@media print { section { page-break-inside: avoid; } }
+</style>\n</head>\n<body>\n",
+    );
+
+    html.push_str(&format!("<h1>Data profile: {}</h1>\n", escape_html(&stats.table_name)));
+    html.push_str(&format!(
+        "<p class=\"meta\">{} rows, {} columns &middot; generated {}</p>\n",
+        stats.total_rows,
+        stats.total_columns,
+        escape_html(generated)
+    ));
+    if let Some(description) = &stats.description {
+        html.push_str(&format!("<p class=\"description\">{}</p>\n", escape_html(description)));
+    }
+
+    // Overview of every column
+    html.push_str("<h2>Columns</h2>\n<table>\n<tr><th>Column</th><th>Type</th><th>Nulls</th><th>Distinct</th><th>Min</th><th>Max</th><th>Mean</th></tr>\n");
+    for column in &stats.column_stats {
+        html.push_str(&format!(
+            "<tr><td><a href=\"#col-{}\">{}</a></td><td>{}</td><td class=\"num\">{:.1}%</td><td class=\"num\">{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>\n",
+            escape_html(&column.column_name),
+            escape_html(&column.column_name),
+            escape_html(&column.data_type),
+            percent(column.null_count, stats.total_rows),
+            column.distinct_count,
+            escape_html(&format_value(&column.min)),
+            escape_html(&format_value(&column.max)),
+            format_optional(column.mean)
+        ));
+    }
+    html.push_str("</table>\n");
+
+    // One section per column with its statistics and most frequent values
+    for (column, values) in stats.column_stats.iter().zip(value_counts) {
+        html.push_str(&format!(
+            "<section id=\"col-{}\">\n<h2>{}</h2>\n",
+            escape_html(&column.column_name),
+            escape_html(&column.column_name)
+        ));
+        if let Some(description) = column.metadata.as_ref().and_then(|m| m.description.as_ref()) {
+            html.push_str(&format!("<p class=\"description\">{}</p>\n", escape_html(description)));
+        }
+
+        html.push_str("<table>\n");
+        for (label, value) in column_facts(column, stats.total_rows) {
+            html.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape_html(&value)));
+        }
+        html.push_str("</table>\n");
+
+        if !values.is_empty() {
+            let max_count = values.iter().map(|v| v.count).max().unwrap_or(1).max(1);
+            html.push_str("<h3>Most frequent values</h3>\n<table>\n<tr><th>Value</th><th>Count</th><th>Share</th><th></th></tr>\n");
+            for value in values {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1}%</td><td style=\"width: 12rem\"><div class=\"bar\" style=\"width: {:.0}%\"></div></td></tr>\n",
+                    value.value.as_deref().map(escape_html).unwrap_or_else(|| "<em>NULL</em>".to_string()),
+                    value.count,
+                    percent(value.count, stats.total_rows),
+                    percent(value.count, max_count)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// The report as plain text lines, used for the PDF version
+fn report_lines(stats: &TableStatistics, value_counts: &[Vec<ValueCount>], generated: &str) -> Vec<String> {
+    let mut lines = vec![
+        format!("Data profile: {}", stats.table_name),
+        format!("{} rows, {} columns - generated {}", stats.total_rows, stats.total_columns, generated),
+    ];
+    if let Some(description) = &stats.description {
+        lines.push(description.clone());
+    }
+
+    for (column, values) in stats.column_stats.iter().zip(value_counts) {
+        lines.push(String::new());
+        lines.push(format!("== {} ==", column.column_name));
+        if let Some(description) = column.metadata.as_ref().and_then(|m| m.description.as_ref()) {
+            lines.push(format!("   {}", description));
+        }
+        for (label, value) in column_facts(column, stats.total_rows) {
+            lines.push(format!("   {:<16} {}", label, value));
+        }
+        if !values.is_empty() {
+            lines.push("   Most frequent values:".to_string());
+            for value in values {
+                let text: String = value.value.as_deref().unwrap_or("NULL").chars().take(60).collect();
+                lines.push(format!(
+                    "     {:<60} {:>12} {:>6.1}%",
+                    text,
+                    value.count,
+                    percent(value.count, stats.total_rows)
+                ));
+            }
+        }
+    }
+    lines
+}
+
+/// Encode a line for a PDF string literal in WinAnsi, replacing characters
+/// the built-in fonts can't show
+fn pdf_text(line: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for c in line.chars().take(PDF_LINE_CHARS) {
+        let byte = if (c as u32) < 0x100 && !c.is_control() { c as u8 } else { b'?' };
+        if matches!(byte, b'(' | b')' | b'\\') {
+            bytes.push(b'\\');
+        }
+        bytes.push(byte);
+    }
+    bytes
+}
+
+/// Render the report as a text-only PDF in a monospaced font. Written by
+/// hand since the document needs nothing beyond the built-in Courier font.
+pub(super) fn render_pdf(stats: &TableStatistics, value_counts: &[Vec<ValueCount>], generated: &str) -> Vec<u8> {
+    let lines = report_lines(stats, value_counts, generated);
+    let pages: Vec<&[String]> = lines.chunks(PDF_PAGE_LINES).collect();
+
+    // Objects 1-3 are the catalog, page tree and font; each page then takes
+    // a page object followed by its content stream
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + i * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec());
+
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                5 + i * 2
+            )
+            .into_bytes(),
+        );
+
+        let mut content = b"BT /F1 9 Tf 12 TL 40 800 Td\n".to_vec();
+        for line in page.iter() {
+            content.push(b'(');
+            content.extend(pdf_text(line));
+            content.extend(b") Tj T*\n");
+        }
+        content.extend(b"ET");
+
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref_offset = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .into_bytes(),
+    );
+    pdf
+}
@@ -0,0 +1,222 @@
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use crate::AppState;
+use crate::error::{AppError, ErrorContext};
+use crate::import::{self, ImportMode};
+
+/// How long a new file's size must stay unchanged before it is imported,
+/// so files still being copied into the folder are not read half-written
+const SETTLE_INTERVAL: Duration = Duration::from_millis(500);
+const SETTLE_ATTEMPTS: usize = 120;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedFolder {
+    pub folder: String,
+    pub table_name: Option<String>, // Shared target table; each file gets its own when None
+    pub mode: ImportMode,
+    pub key_columns: Option<Vec<String>>, // Used by the `append_new` mode
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderWatchEvent {
+    pub folder: String,
+    pub file_path: String,
+    pub status: String, // "importing", "imported" or "failed"
+    pub table_name: Option<String>,
+    pub rows_imported: usize,
+    pub message: String,
+}
+
+struct ActiveWatch {
+    config: WatchedFolder,
+    _watcher: RecommendedWatcher, // Stops watching when dropped
+}
+
+/// Folders currently watched for new files, keyed by canonical path
+#[derive(Default)]
+pub struct FolderWatchers {
+    watches: Mutex<HashMap<PathBuf, ActiveWatch>>,
+}
+
+impl FolderWatchers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Wait until the file size stops changing; false if it disappeared
+async fn wait_until_settled(path: &PathBuf) -> bool {
+    let mut last_size = None;
+    for _ in 0..SETTLE_ATTEMPTS {
+        let size = match std::fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return false,
+        };
+        if last_size == Some(size) {
+            return true;
+        }
+        last_size = Some(size);
+        tokio::time::sleep(SETTLE_INTERVAL).await;
+    }
+    false
+}
+
+/// Skip hidden files and editor lock files such as Excel's `~$book.xlsx`
+fn is_candidate(path: &PathBuf) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.starts_with('.') || n.starts_with("~$"))
+        .unwrap_or(true);
+    !hidden && path.is_file() && import::is_importable(path)
+}
+
+async fn import_new_file(window: &tauri::Window, config: &WatchedFolder, path: PathBuf) {
+    let file_path = path.display().to_string();
+    let emit = |status: &str, table_name: Option<String>, rows_imported: usize, message: String| {
+        let _ = window.emit("folder-watch", FolderWatchEvent {
+            folder: config.folder.clone(),
+            file_path: file_path.clone(),
+            status: status.to_string(),
+            table_name,
+            rows_imported,
+            message,
+        });
+    };
+
+    if !wait_until_settled(&path).await {
+        emit("failed", None, 0, "File disappeared or never finished writing".to_string());
+        return;
+    }
+
+    emit("importing", config.table_name.clone(), 0, "Importing new file".to_string());
+
+    let result = import::import_file(
+        window.app_handle().clone(),
+        window.clone(),
+        file_path.clone(),
+        None,
+        config.table_name.clone(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(config.mode),
+        config.key_columns.clone(),
+        None,
+    )
+    .await;
+
+    match result {
+        Ok(result) => emit("imported", Some(result.table_name), result.rows_imported, result.message),
+        Err(e) => emit("failed", config.table_name.clone(), 0, e.message),
+    }
+}
+
+/// Watch a folder and import every supported file dropped into it. With a
+/// `table_name`, files are appended to that table by default; otherwise each
+/// file is imported into its own table. Progress is emitted as `folder-watch`
+/// events.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn watch_folder(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+    folder: String,
+    table_name: Option<String>,
+    mode: Option<ImportMode>,
+    key_columns: Option<Vec<String>>,
+) -> Result<WatchedFolder, AppError> {
+    let path = std::fs::canonicalize(&folder)
+        .with_context(|| format!("Cannot watch {}", folder))?;
+    if !path.is_dir() {
+        return Err(AppError::invalid_input(format!("{} is not a directory", folder)));
+    }
+
+    let mode = mode.unwrap_or(if table_name.is_some() {
+        ImportMode::Append
+    } else {
+        ImportMode::Replace
+    });
+    let config = WatchedFolder {
+        folder: path.display().to_string(),
+        table_name,
+        mode,
+        key_columns,
+    };
+
+    let mut watches = state.watchers.watches.lock()?;
+    if watches.contains_key(&path) {
+        return Err(AppError::invalid_input(format!("{} is already being watched", config.folder)));
+    }
+
+    // New files are imported one at a time on the async runtime, so the
+    // watcher's thread keeps receiving events. The task ends once the
+    // watcher, and with it the sender, is dropped.
+    let (new_files, mut queued) = tokio::sync::mpsc::unbounded_channel();
+    let handler_config = config.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(path) = queued.recv().await {
+            import_new_file(&window, &handler_config, path).await;
+        }
+    });
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else { return };
+        // Files copied in show up as creates; files moved in as renames
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))) {
+            return;
+        }
+        for path in event.paths {
+            if is_candidate(&path) {
+                let _ = new_files.send(path);
+            }
+        }
+    })
+    .map_err(|e| AppError::io(format!("Failed to create folder watcher: {}", e)))?;
+
+    watcher
+        .watch(&path, RecursiveMode::NonRecursive)
+        .map_err(|e| AppError::io(format!("Failed to watch {}: {}", config.folder, e)))?;
+
+    watches.insert(path, ActiveWatch {
+        config: config.clone(),
+        _watcher: watcher,
+    });
+
+    Ok(config)
+}
+
+/// Stop watching a folder
+#[tauri::command(rename_all = "camelCase")]
+pub async fn unwatch_folder(
+    state: State<'_, AppState>,
+    folder: String,
+) -> Result<String, AppError> {
+    let path = std::fs::canonicalize(&folder).unwrap_or_else(|_| PathBuf::from(&folder));
+
+    let mut watches = state.watchers.watches.lock()?;
+    match watches.remove(&path) {
+        Some(_) => Ok(format!("Stopped watching {}", path.display())),
+        None => Err(AppError::not_found(format!("{} is not being watched", folder))),
+    }
+}
+
+/// List folders currently being watched
+#[tauri::command(rename_all = "camelCase")]
+pub async fn list_watched_folders(
+    state: State<'_, AppState>,
+) -> Result<Vec<WatchedFolder>, AppError> {
+    let watches = state.watchers.watches.lock()?;
+    let mut folders: Vec<WatchedFolder> = watches.values().map(|w| w.config.clone()).collect();
+    folders.sort_by(|a, b| a.folder.cmp(&b.folder));
+    Ok(folders)
+}